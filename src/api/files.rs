@@ -11,11 +11,17 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashSet;
 use std::fmt::Debug;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tracing::warn;
 
 /// This redirect needs to be present in the OAuth credential file, or else OAuth will not work.
 const REDIRECT: &str = "http://localhost";
 
+/// Explains how to get a usable `client_secret.json` file.
+const CREDENTIALS_GUIDANCE: &str = "Tiller needs the JSON file of an OAuth client ID of type \
+    'Desktop app'. In the Google Cloud Console, go to APIs & Services > Credentials, create one, \
+    and use its 'Download JSON' button. See the Setup section of the README for details.";
+
 /// Represents a file that we want to `Serialize`, `Deserialize`, and read from memory in-between
 /// serializations and deserialization. Basically we are just holding the `path` and the `data`
 /// here.
@@ -107,7 +113,9 @@ pub(crate) struct SecretFile {
 }
 
 impl SecretFile {
-    /// Loads the OAuth client credentials from client_secret.json
+    /// Loads the OAuth client credentials from client_secret.json and validates that they are
+    /// credentials for a desktop ("installed") application with everything the OAuth workflow
+    /// needs.
     ///
     /// # Arguments
     /// * `path` - Path to the client_secret.json file
@@ -116,18 +124,54 @@ impl SecretFile {
     /// The parsed ClientSecretFile structure
     ///
     /// # Errors
-    /// Returns an error if the file cannot be read or parsed
-    #[cfg(test)]
+    /// Returns an error, with guidance on how to get the right file, if the file cannot be read,
+    /// parsed or validated
     pub(crate) async fn load(path: &Path) -> Result<SecretFile> {
-        utils::deserialize(path)
-            .await
-            .context("Unable to read ClientSecretFile")
-            .pub_result(ErrorType::Internal)
+        let content = utils::read(path).await.pub_result(ErrorType::Config)?;
+        Self::parse(&content)
+            .with_context(|| {
+                format!(
+                    "The OAuth client credentials file {} cannot be used. {CREDENTIALS_GUIDANCE}",
+                    path.display()
+                )
+            })
+            .pub_result(ErrorType::Config)
     }
 
-    /// Get the redirect URI
-    #[cfg(test)]
-    pub(crate) fn redirect_uri(&self) -> &str {
+    /// Parses and validates the contents of a client_secret.json file.
+    fn parse(json: &str) -> Res<SecretFile> {
+        let value: serde_json::Value =
+            serde_json::from_str(json).context("The file is not valid JSON")?;
+        if value.get("installed").is_none() {
+            if value.get("web").is_some() {
+                bail!("The file is for a 'Web application' OAuth client, not a 'Desktop app'");
+            }
+            bail!("The file has no 'installed' credentials");
+        }
+        let secret: SecretFile =
+            serde_json::from_value(value).context("The 'installed' credentials are invalid")?;
+        secret.validate()?;
+        Ok(secret)
+    }
+
+    /// Checks that none of the values needed for the OAuth workflow are empty.
+    fn validate(&self) -> Res<()> {
+        let required = [
+            ("client_id", self.client_id()),
+            ("client_secret", self.client_secret()),
+            ("auth_uri", self.auth_uri()),
+            ("token_uri", self.token_uri()),
+        ];
+        for (name, value) in required {
+            if value.trim().is_empty() {
+                bail!("The '{name}' of the 'installed' credentials is empty");
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the localhost redirect URI that the OAuth callback is sent to, without a port
+    pub(super) fn redirect_uri(&self) -> &str {
         self.installed.redirect_uris.value()
     }
 
@@ -173,13 +217,19 @@ pub(crate) struct InstalledCredentials {
     token_uri: String,
 }
 
-#[derive(Default, Debug, Clone)]
+/// The `redirect_uris` list from `client_secret.json`, kept exactly as it appears in the file.
+/// Deserialization fails unless the list contains a localhost redirect (see `is_valid_redirect`).
+#[derive(Default, Debug, Clone, Eq, PartialEq)]
 struct RedirectUris(Vec<String>);
 
-#[cfg(test)]
 impl RedirectUris {
+    /// Returns the first localhost redirect in the list, or `REDIRECT` if there is none.
     fn value(&self) -> &str {
-        REDIRECT
+        self.0
+            .iter()
+            .find(|s| is_valid_redirect(s))
+            .map(String::as_str)
+            .unwrap_or(REDIRECT)
     }
 }
 
@@ -228,26 +278,45 @@ pub(super) struct TokenFile {
 
 impl TokenFile {
     pub(super) async fn load(p: impl AsRef<Path>) -> Res<Self> {
-        let token_file: Self = utils::deserialize(p.as_ref())
-            .await
-            .context("Unable to deserialize the token JSON file")?;
+        let token_file = Self::read(p).await?;
         token_file.validate_scopes()?;
         Ok(token_file)
     }
 
+    /// Deserializes the token file without checking its scopes.
+    pub(super) async fn read(p: impl AsRef<Path>) -> Res<Self> {
+        utils::deserialize(p.as_ref())
+            .await
+            .context("Unable to deserialize the token JSON file")
+    }
+
     fn validate_scopes(&self) -> Res<()> {
-        let found_scopes: HashSet<&str> = self.scopes.iter().map(|s| s.as_str()).collect();
-        for &required_scope in OAUTH_SCOPES {
-            if !found_scopes.contains(required_scope) {
-                bail!(
-                    "OAuth scope '{required_scope}' is missing from your saved token. \
-                    The required scopes have changed. Please run `tiller auth` to re-authenticate."
-                );
-            }
+        let missing = self.missing_scopes();
+        if !missing.is_empty() {
+            bail!(
+                "OAuth scope '{}' is missing from your saved token. \
+                The required scopes have changed. Please run `tiller auth` to re-authenticate.",
+                missing.join("', '")
+            );
         }
         Ok(())
     }
 
+    /// The scopes in `OAUTH_SCOPES` that were not granted to this token.
+    pub(super) fn missing_scopes(&self) -> Vec<&'static str> {
+        let found_scopes: HashSet<&str> = self.scopes.iter().map(|s| s.as_str()).collect();
+        OAUTH_SCOPES
+            .iter()
+            .copied()
+            .filter(|required_scope| !found_scopes.contains(required_scope))
+            .collect()
+    }
+
+    /// The scopes that were granted to this token.
+    pub(super) fn scopes(&self) -> &[String] {
+        &self.scopes
+    }
+
     /// Create a new TokenFile
     pub(super) fn new(
         scopes: Vec<String>,
@@ -275,11 +344,16 @@ impl TokenFile {
         &self.refresh_token
     }
 
-    /// Check if the token is expired or will expire soon (within 5 minutes)
-    pub(super) fn is_expired(&self) -> bool {
-        let now = Utc::now();
-        let buffer = chrono::Duration::minutes(5);
-        self.expires_at <= now + buffer
+    /// Check if the token is expired or will expire within `buffer`
+    pub(super) fn is_expired(&self, buffer: Duration) -> bool {
+        // A buffer too large to add to the current time means the token always needs refreshing
+        let deadline = chrono::Duration::from_std(buffer)
+            .ok()
+            .and_then(|buffer| Utc::now().checked_add_signed(buffer));
+        match deadline {
+            Some(deadline) => self.expires_at <= deadline,
+            None => true,
+        }
     }
 
     /// Update the token with new values
@@ -344,7 +418,33 @@ async fn test_client_secret_good_redirect_2() {
     let p = temp_dir.path().join("file.json");
     utils::write(&p, json_data).await.unwrap();
     let secret_file = SecretFile::load(&p).await.unwrap();
-    assert_eq!("http://localhost", secret_file.redirect_uri());
+    assert_eq!("http://127.0.0.1", secret_file.redirect_uri());
+}
+
+#[test]
+fn test_redirect_uris_round_trip() {
+    let json = r#"["https://example.com:4040/whatever","http://localhost","http://127.0.0.1"]"#;
+    let uris: RedirectUris = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        uris.0,
+        vec![
+            "https://example.com:4040/whatever",
+            "http://localhost",
+            "http://127.0.0.1"
+        ]
+    );
+    assert_eq!("http://localhost", uris.value());
+
+    // Serializing writes back the same list, in the same order
+    let serialized = serde_json::to_string(&uris).unwrap();
+    assert_eq!(json, serialized);
+    let reparsed: RedirectUris = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(uris, reparsed);
+}
+
+#[test]
+fn test_redirect_uris_fallback() {
+    assert_eq!(REDIRECT, RedirectUris::default().value());
 }
 
 #[tokio::test]
@@ -377,6 +477,60 @@ async fn test_client_secret_bad_redirect() {
     );
 }
 
+#[test]
+fn test_client_secret_installed_is_valid() {
+    let json = r#"
+{
+    "installed": {
+        "client_id": "YOUR_CLIENT_ID.apps.googleusercontent.com",
+        "client_secret": "YOUR_CLIENT_SECRET",
+        "redirect_uris": ["http://localhost"],
+        "auth_uri": "https://accounts.google.com/o/oauth2/auth",
+        "token_uri": "https://oauth2.googleapis.com/token"
+    }
+}
+"#;
+    let secret_file = SecretFile::parse(json).unwrap();
+    assert_eq!(
+        "YOUR_CLIENT_ID.apps.googleusercontent.com",
+        secret_file.client_id()
+    );
+}
+
+#[test]
+fn test_client_secret_web_client_is_rejected() {
+    let json = r#"
+{
+    "web": {
+        "client_id": "YOUR_CLIENT_ID.apps.googleusercontent.com",
+        "client_secret": "YOUR_CLIENT_SECRET",
+        "redirect_uris": ["http://localhost"],
+        "auth_uri": "https://accounts.google.com/o/oauth2/auth",
+        "token_uri": "https://oauth2.googleapis.com/token"
+    }
+}
+"#;
+    let error = SecretFile::parse(json).unwrap_err().to_string();
+    assert!(error.contains("'Web application'"), "{error}");
+}
+
+#[test]
+fn test_client_secret_empty_value_is_rejected() {
+    let json = r#"
+{
+    "installed": {
+        "client_id": "YOUR_CLIENT_ID.apps.googleusercontent.com",
+        "client_secret": "",
+        "redirect_uris": ["http://localhost"],
+        "auth_uri": "https://accounts.google.com/o/oauth2/auth",
+        "token_uri": "https://oauth2.googleapis.com/token"
+    }
+}
+"#;
+    let error = SecretFile::parse(json).unwrap_err().to_string();
+    assert!(error.contains("'client_secret'"), "{error}");
+}
+
 #[tokio::test]
 async fn test_validate_token_file_bad() {
     use tempfile::TempDir;
@@ -432,3 +586,49 @@ async fn test_validate_token_file_good() {
 
     let _ = TokenFile::load(&json_path).await.unwrap();
 }
+
+#[test]
+fn test_missing_scopes() {
+    let token = TokenFile::new(
+        vec![
+            "https://www.googleapis.com/auth/spreadsheets".to_string(),
+            "https://www.googleapis.com/auth/drive.file".to_string(),
+        ],
+        String::from("abc12"),
+        String::from("xyz89"),
+        Utc::now(),
+        None,
+    );
+    assert_eq!(
+        token.missing_scopes(),
+        vec!["https://www.googleapis.com/auth/drive"]
+    );
+    assert!(token.validate_scopes().is_err());
+
+    let token = TokenFile::new(
+        OAUTH_SCOPES.iter().map(|s| s.to_string()).collect(),
+        String::from("abc12"),
+        String::from("xyz89"),
+        Utc::now(),
+        None,
+    );
+    assert!(token.missing_scopes().is_empty());
+    assert_eq!(token.scopes().len(), OAUTH_SCOPES.len());
+}
+
+#[test]
+fn test_is_expired_with_buffer() {
+    let buffer = Duration::from_secs(300);
+    let token = |expires_in: chrono::Duration| {
+        TokenFile::new(
+            Vec::new(),
+            String::from("abc12"),
+            String::from("xyz89"),
+            Utc::now() + expires_in,
+            None,
+        )
+    };
+    assert!(token(chrono::Duration::minutes(2)).is_expired(buffer));
+    assert!(!token(chrono::Duration::minutes(10)).is_expired(buffer));
+    assert!(token(chrono::Duration::minutes(-1)).is_expired(Duration::ZERO));
+}
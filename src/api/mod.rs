@@ -11,16 +11,19 @@ mod tiller;
 
 use crate::api::sheet::GoogleSheet;
 use crate::api::tiller::TillerImpl;
-use crate::model::TillerData;
+use crate::model::{Category, TillerData, Transaction, TransactionColumn, Transactions};
 use crate::Config;
 use crate::Result;
+pub(super) use files::SecretFile;
 pub(super) use oauth::TokenProvider;
 pub(super) use sheet_test_client::TestSheet;
 use std::env::VarError;
+use std::time::Duration;
 
 use crate::error::{ErrorType, IntoResult, Res};
+use anyhow::Context;
 #[cfg(test)]
-pub(super) use sheet_test_client::{SheetCall, TestSheetState};
+pub(super) use sheet_test_client::{SheetCall, TestSheetState, TEST_ACCOUNT_EMAIL};
 
 // OAuth scopes required for Sheets API access and Drive file operations (backup copies)
 // Note: `drive` scope (not `drive.file`) is required because `drive.file` only grants access
@@ -34,20 +37,125 @@ const OAUTH_SCOPES: &[&str] = &[
 pub(crate) const TRANSACTIONS: &str = "Transactions";
 pub(crate) const CATEGORIES: &str = "Categories";
 pub(crate) const AUTO_CAT: &str = "AutoCat";
+pub(crate) const BALANCES: &str = "Balance History";
 
 /// Represents a range of data to write to a sheet.
 #[derive(Debug, Clone, PartialEq)]
 pub struct SheetRange {
-    /// The A1 notation range, e.g., "Transactions!A1:ZZ1"
+    /// The A1 notation range, e.g., "Transactions!A1:Q1"
     pub range: String,
     /// The data to write (rows of cells)
     pub values: Vec<Vec<String>>,
 }
 
+/// The ranges that `Tiller::apply_plan` or `Tiller::append_transactions` clears and writes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WritePlan {
+    /// The A1 notation ranges that are cleared, e.g., "Transactions!A2:Q"
+    pub clear_ranges: Vec<String>,
+    /// The ranges and data that are written
+    pub write_ranges: Vec<SheetRange>,
+    /// When false, `clear_ranges` are cleared before `write_ranges` are written. When true,
+    /// `write_ranges` are written first and `clear_ranges` then trim the rows left below the new
+    /// data, so a tab is never empty in between.
+    pub clear_after_write: bool,
+}
+
+impl SheetRange {
+    /// The A1 notation of the cell of `tab` at the 0-based `row` and `col`, e.g. `Transactions!AB5`
+    /// for row 4 and column 27.
+    pub(crate) fn cell(tab: &str, row: usize, col: usize) -> String {
+        format!("{tab}!{}{}", tiller::col_to_a1(col), row + 1)
+    }
+
+    /// A range that writes `values` to `tab` from column A of the 0-based `start_row`, as wide as
+    /// its longest row, e.g. `Transactions!A22:Q23` for two rows of 17 values starting at row 21.
+    pub(crate) fn for_values(tab: &str, start_row: usize, values: Vec<Vec<String>>) -> Self {
+        let width = values.iter().map(Vec::len).max().unwrap_or_default().max(1);
+        let last = start_row + values.len().max(1);
+        let range = format!(
+            "{tab}!A{}:{}{last}",
+            start_row + 1,
+            tiller::col_to_a1(width - 1)
+        );
+        Self { range, values }
+    }
+}
+
+impl WritePlan {
+    /// The operations that carry out this plan, in the order they are sent to the sheet.
+    pub(crate) fn operations(&self) -> Vec<SheetOperation<'_>> {
+        let mut operations = Vec::new();
+        if !self.clear_ranges.is_empty() {
+            operations.push(SheetOperation::Clear(&self.clear_ranges));
+        }
+        if !self.write_ranges.is_empty() {
+            operations.push(SheetOperation::Write(&self.write_ranges));
+        }
+        if self.clear_after_write {
+            operations.reverse();
+        }
+        operations
+    }
+
+    /// The tabs that this plan clears or writes, in the order they are written.
+    pub(crate) fn tabs(&self) -> Vec<&str> {
+        let mut tabs = Vec::new();
+        let ranges = self
+            .write_ranges
+            .iter()
+            .map(|r| r.range.as_str())
+            .chain(self.clear_ranges.iter().map(String::as_str));
+        for tab in ranges.map(tab_name) {
+            if !tabs.contains(&tab) {
+                tabs.push(tab);
+            }
+        }
+        tabs
+    }
+
+    /// The part of this plan that clears and writes the tabs for which `keep` returns true.
+    pub(crate) fn filter_tabs(&self, keep: impl Fn(&str) -> bool) -> WritePlan {
+        WritePlan {
+            clear_ranges: self
+                .clear_ranges
+                .iter()
+                .filter(|range| keep(tab_name(range)))
+                .cloned()
+                .collect(),
+            write_ranges: self
+                .write_ranges
+                .iter()
+                .filter(|r| keep(tab_name(&r.range)))
+                .cloned()
+                .collect(),
+            clear_after_write: self.clear_after_write,
+        }
+    }
+}
+
+/// The tab that an A1 notation range refers to, e.g. `Transactions` for `Transactions!A1:Q`.
+fn tab_name(range: &str) -> &str {
+    range.split_once('!').map_or(range, |(tab, _)| tab)
+}
+
+/// A single request to the sheet made while carrying out a `WritePlan`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SheetOperation<'a> {
+    /// Clear these A1 notation ranges.
+    Clear(&'a [String]),
+    /// Write these ranges.
+    Write(&'a [SheetRange]),
+}
+
 /// For testing purposes, this can be placed into the environment to cause the application to use
 /// seeded, testing, in-memory data instead of accessing a live Google sheet.
 pub(crate) const MODE_ENV: &str = "TILLER_SYNC_IN_TEST_MODE";
 
+/// For testing purposes, this can be placed into the environment along with `MODE_ENV` to seed the
+/// in-memory sheet from the CSV files in the directory it names, instead of the built-in seed data.
+pub(crate) const SEED_DIR_ENV: &str = "TILLER_SYNC_TEST_SEED_DIR";
+
 /// An enum representing whether the app is in testing mode or using a live Google sheet.
 #[derive(Default, Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum Mode {
@@ -78,36 +186,99 @@ impl Mode {
 pub async fn sheet(config: Config, mode: Mode) -> Result<Box<dyn Sheet>> {
     let sheet_client: Box<dyn Sheet> = match mode {
         Mode::Google => {
-            let token_provider =
-                TokenProvider::load(config.client_secret_path(), config.token_path())
-                    .await
-                    .pub_result(ErrorType::Config)?;
+            let token_provider = TokenProvider::load(
+                config.client_secret_path(),
+                config.token_path(),
+                build_http_client(&config).pub_result(ErrorType::Config)?,
+                config.token_refresh_buffer(),
+            )
+            .await
+            .pub_result(ErrorType::Config)?;
             Box::new(
                 GoogleSheet::new(config.clone(), token_provider)
                     .await
                     .pub_result(ErrorType::Internal)?,
             )
         }
-        Mode::Testing => Box::new(TestSheet::new_with_seed_data(config.spreadsheet_id())),
+        Mode::Testing => Box::new(
+            TestSheet::for_testing_mode(config.spreadsheet_id()).pub_result(ErrorType::Config)?,
+        ),
     };
 
     Ok(sheet_client)
 }
 
 /// Construct a `Tiller` client, which will use `sheet` to communicate with Google sheets (or, in
-/// testing mode, will use in-memory seed data).
-pub async fn tiller(sheet: Box<dyn Sheet>) -> Res<impl Tiller> {
-    TillerImpl::new(sheet).await
+/// testing mode, will use in-memory seed data). The Balance History tab is only read and written
+/// when `sync_balances` is true. When `atomic_write` is true, tabs are written before they are
+/// trimmed rather than cleared before they are written. When `verify_headers` is true, the header
+/// row of each tab is checked after its rows are cleared.
+pub async fn tiller(
+    sheet: Box<dyn Sheet>,
+    sync_balances: bool,
+    atomic_write: bool,
+    verify_headers: bool,
+) -> Res<impl Tiller> {
+    TillerImpl::new(sheet, sync_balances, atomic_write, verify_headers).await
+}
+
+/// Builds the HTTP client used for requests to Google. Requests fail if they take longer than
+/// `request_timeout_secs`. The `proxy` in `config.json` is used if set, otherwise the standard
+/// `HTTPS_PROXY`, `HTTP_PROXY` and `NO_PROXY` environment variables are honored.
+pub(crate) fn build_http_client(config: &Config) -> Res<reqwest::Client> {
+    http_client(config.request_timeout(), config.proxy())
+}
+
+fn http_client(timeout: Duration, proxy: Option<&str>) -> Res<reqwest::Client> {
+    let mut builder = reqwest::Client::builder().timeout(timeout);
+    if let Some(proxy) = proxy {
+        let proxy = reqwest::Proxy::all(proxy)
+            .with_context(|| format!("Invalid proxy URL '{proxy}'"))?
+            .no_proxy(reqwest::NoProxy::from_env());
+        builder = builder.proxy(proxy);
+    }
+    builder.build().context("Failed to build the HTTP client")
+}
+
+/// If `e` was caused by a request timing out, adds an explanation of how to raise the timeout.
+pub(crate) fn explain_timeout(e: anyhow::Error) -> anyhow::Error {
+    let timed_out = e.chain().any(|cause| {
+        cause
+            .downcast_ref::<reqwest::Error>()
+            .is_some_and(reqwest::Error::is_timeout)
+    });
+    if timed_out {
+        e.context(
+            "The request to Google timed out. Check your network connection or increase \
+            request_timeout_secs in config.json",
+        )
+    } else {
+        e
+    }
 }
 
 #[async_trait::async_trait]
 pub trait Sheet: Send {
-    /// Get the data from a Google sheet.
+    /// Get the data from a Google sheet without number formats: numbers are plain, e.g. `-87.43`
+    /// rather than `-$87.43`, and dates are serial numbers, e.g. `45950` rather than `10/20/2025`.
+    /// Use `get_formatted_values` when values must appear as the user sees them.
     async fn get(&mut self, sheet_name: &str) -> Res<Vec<Vec<String>>>;
 
+    /// Get the data from a Google sheet, with numbers and dates rendered as they are displayed in
+    /// the sheet, e.g. `10/20/2025` rather than a date serial number and `-$87.43` rather than
+    /// `-87.43`.
+    async fn get_formatted_values(&mut self, sheet_name: &str) -> Res<Vec<Vec<String>>>;
+
     /// Get the formulas from a Google sheet (returns formulas for formula cells, values for non-formula cells).
     async fn get_formulas(&mut self, sheet_name: &str) -> Res<Vec<Vec<String>>>;
 
+    /// Get the first row of a Google sheet, which holds its column headers. Empty if the sheet has
+    /// no rows.
+    async fn get_header(&mut self, sheet_name: &str) -> Res<Vec<String>>;
+
+    /// Get the names of the tabs in the spreadsheet, in the order they appear.
+    async fn list_tabs(&mut self) -> Res<Vec<String>>;
+
     /// Clear specified ranges in the spreadsheet.
     /// Each range should be in A1 notation, e.g., "Transactions!A2:ZZ".
     async fn clear_ranges(&mut self, ranges: &[&str]) -> Res<()>;
@@ -119,6 +290,18 @@ pub trait Sheet: Send {
     /// Create a copy of the spreadsheet using the Google Drive API.
     /// Returns the file ID of the new copy.
     async fn copy_spreadsheet(&mut self, new_name: &str) -> Res<String>;
+
+    /// Get the Google account that the access token was granted by.
+    async fn account(&mut self) -> Res<Account>;
+}
+
+/// A Google account, as reported by the Google Drive API.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Account {
+    /// The account's email address.
+    pub(crate) email: String,
+    /// The account's display name.
+    pub(crate) name: String,
 }
 
 #[async_trait::async_trait]
@@ -126,13 +309,50 @@ pub trait Tiller {
     /// Get the data from the Tiller Google sheet.
     async fn get_data(&mut self) -> Res<TillerData>;
 
+    /// Check that the sheet has the tabs that are read and written, failing with a list of the tabs
+    /// that it does have otherwise.
+    async fn check_tabs(&mut self) -> Res<()>;
+
     /// Create a backup copy of the spreadsheet.
     /// Returns the file ID of the new copy.
     async fn copy_spreadsheet(&mut self, new_name: &str) -> Res<String>;
 
-    /// Clear and write data to the Google sheet.
-    /// This clears all data rows (preserving headers) and writes new data.
-    async fn clear_and_write_data(&mut self, data: &TillerData) -> Res<()>;
+    /// Compute the ranges that need to be cleared and written to replace the sheet's data with
+    /// `data`, without touching the sheet.
+    fn write_plan(&self, data: &TillerData) -> Res<WritePlan>;
+
+    /// Clear and write the ranges of `plan` to the Google sheet, in the order given by
+    /// `WritePlan::operations`. Clearing must leave the header row of each tab as it was, when
+    /// verifying headers is enabled, otherwise nothing more is written and an error is returned.
+    async fn apply_plan(&mut self, plan: &WritePlan) -> Res<()>;
+
+    /// Compute the ranges that `append_transactions` would write for `categories` and
+    /// `transactions`, without touching the sheet. `current` is the sheet as last fetched.
+    fn append_plan(
+        &self,
+        current: &TillerData,
+        categories: &[Category],
+        transactions: &[Transaction],
+    ) -> Res<WritePlan>;
+
+    /// Compute the ranges that write the `columns` of `transactions` to the Transactions tab, one
+    /// range per column, without touching the sheet. `transactions` must have the rows and headers
+    /// of the tab as last fetched, so that every other column is left as it is.
+    fn columns_plan(
+        &self,
+        transactions: &Transactions,
+        columns: &[TransactionColumn],
+    ) -> Res<WritePlan>;
+
+    /// Write `categories` below the last row of the Categories tab, then `transactions` below the
+    /// last row of the Transactions tab, in the column order of `current`, which is the sheet as
+    /// last fetched. Nothing is cleared, so existing rows and their formulas are left untouched.
+    async fn append_transactions(
+        &mut self,
+        current: &TillerData,
+        categories: &[Category],
+        transactions: &[Transaction],
+    ) -> Res<()>;
 
     /// Verify that the write was successful by re-fetching row counts.
     /// Returns the counts (transactions, categories, autocat) if verification passes.
@@ -145,7 +365,9 @@ async fn test_sync_down_behavior() {
     use std::str::FromStr as _;
 
     let client = Box::new(TestSheet::new_with_seed_data("test_sync_down_behavior"));
-    let mut tiller = crate::api::tiller(client).await.unwrap();
+    let mut tiller = crate::api::tiller(client, false, false, true)
+        .await
+        .unwrap();
     let tiller_data = tiller.get_data().await.unwrap();
 
     // Check that the test data is coming through correctly with an =ABS(E1) formula in
@@ -179,3 +401,76 @@ async fn test_sync_down_behavior() {
     assert_eq!(tiller_data, tiller_data_deserialized);
     assert_eq!(tiller_data_serialized, tiller_data_serialized_again)
 }
+
+#[tokio::test]
+async fn test_sheet_uses_spreadsheet_id_override() {
+    use std::collections::HashMap;
+
+    let env = crate::test::TestEnv::new().await;
+    let configured = TestSheet::new(env.config().spreadsheet_id());
+    let copy_id = format!("{}-copy", env.config().spreadsheet_id());
+    let copy_data = vec![vec!["Copy".to_string()]];
+    TestSheet::new(copy_id.as_str()).set_state(TestSheetState {
+        data: HashMap::from([("Transactions".to_string(), copy_data.clone())]),
+        ..Default::default()
+    });
+
+    let config = env.config().with_spreadsheet_id(&copy_id).unwrap();
+    let mut client = sheet(config, Mode::Testing).await.unwrap();
+    assert_eq!(client.get("Transactions").await.unwrap(), copy_data);
+    assert!(configured.get_state().call_history.borrow().is_empty());
+}
+
+#[tokio::test]
+async fn test_http_client_times_out() {
+    // A server that accepts connections but never responds
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        let mut connections = Vec::new();
+        while let Ok((socket, _)) = listener.accept().await {
+            connections.push(socket);
+        }
+    });
+
+    let client = http_client(Duration::from_millis(200), None).unwrap();
+    let err = client
+        .get(format!("http://{addr}/"))
+        .send()
+        .await
+        .unwrap_err();
+    assert!(err.is_timeout());
+
+    let message = format!("{:#}", explain_timeout(err.into()));
+    assert!(message.contains("request_timeout_secs"), "{message}");
+}
+
+#[tokio::test]
+async fn test_http_client_uses_configured_proxy() {
+    use tokio::io::AsyncReadExt;
+
+    // A proxy that records the first request it receives and never responds
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy = format!("http://{}", listener.local_addr().unwrap());
+    let received = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buf = vec![0; 1024];
+        let n = socket.read(&mut buf).await.unwrap();
+        String::from_utf8_lossy(&buf[..n]).to_string()
+    });
+
+    let client = http_client(Duration::from_millis(500), Some(&proxy)).unwrap();
+    let _ = client.get("http://sheets.example.com/v4").send().await;
+
+    // Requests sent through a proxy use the absolute URL as the request target
+    let request = received.await.unwrap();
+    assert!(
+        request.starts_with("GET http://sheets.example.com/v4 HTTP/1.1"),
+        "{request}"
+    );
+}
+
+#[test]
+fn test_http_client_rejects_invalid_proxy() {
+    assert!(http_client(Duration::from_secs(1), Some("not a url")).is_err());
+}
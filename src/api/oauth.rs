@@ -1,5 +1,5 @@
 use crate::api::files::{File, SecretFile, TokenFile};
-use crate::api::OAUTH_SCOPES;
+use crate::api::{explain_timeout, OAUTH_SCOPES};
 use crate::error::Res;
 use anyhow::{anyhow, bail, Context};
 use chrono::Utc;
@@ -11,8 +11,9 @@ use oauth2::{
 };
 use std::fmt::Debug;
 use std::net::TcpListener;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
 use tracing::{debug, error};
 
@@ -20,6 +21,10 @@ use tracing::{debug, error};
 pub(crate) struct TokenProvider {
     secret: File<SecretFile>,
     token: File<TokenFile>,
+    /// The HTTP client used for requests to Google's OAuth endpoints.
+    http: reqwest::Client,
+    /// How long before the access token expires it is refreshed.
+    refresh_buffer: Duration,
 }
 
 impl TokenProvider {
@@ -42,13 +47,21 @@ impl TokenProvider {
     /// - `secret`: The path to an existing file that contains the client ID and client secret.
     /// - `token`: The path to a file that may or may not already exist. This is the path where we
     ///   will store our OAuth token, refresh token, and other token metadata such as expiration.
+    /// - `http`: The HTTP client to use for requests to Google's OAuth endpoints, see
+    ///   `build_http_client`.
+    /// - `refresh_buffer`: How long before the access token expires it is refreshed.
     ///
     /// # Returns
     /// - A constructed `TokenProvider` object.
     ///
     /// # Errors
     /// - If any of the file operations, network operations, or logical checks fail.
-    pub(crate) async fn initialize<P1, P2>(secret: P1, token: P2) -> Res<Self>
+    pub(crate) async fn initialize<P1, P2>(
+        secret: P1,
+        token: P2,
+        http: reqwest::Client,
+        refresh_buffer: Duration,
+    ) -> Res<Self>
     where
         P1: Into<PathBuf>,
         P2: Into<PathBuf>,
@@ -74,16 +87,14 @@ impl TokenProvider {
 
         // Find an available port and start HTTP server
         let (listener, port) = bind_random_port()?;
-        let redirect_url = format!("http://localhost:{port}");
+        let redirect_url = local_redirect_url(secret_file.data().redirect_uri(), port)?;
 
         // Build authorization URL
-        let redirect_uri_for_auth =
-            RedirectUrl::new(redirect_url.clone()).context("Invalid redirect URL")?;
         let (auth_url, csrf_token) = oauth_client
             .authorize_url(CsrfToken::new_random)
             .add_scopes(OAUTH_SCOPES.iter().map(|s| Scope::new(s.to_string())))
             .set_pkce_challenge(pkce_challenge)
-            .set_redirect_uri(std::borrow::Cow::Borrowed(&redirect_uri_for_auth))
+            .set_redirect_uri(std::borrow::Cow::Borrowed(&redirect_url))
             .url();
 
         // Print instructions to user
@@ -98,13 +109,13 @@ impl TokenProvider {
         let auth_code = receive_oauth_callback(listener, csrf_token).await?;
 
         // Exchange authorization code for token
-        let redirect_uri = RedirectUrl::new(redirect_url).context("Invalid redirect URL")?;
         let token_response = oauth_client
             .exchange_code(AuthorizationCode::new(auth_code))
             .set_pkce_verifier(pkce_verifier)
-            .set_redirect_uri(std::borrow::Cow::Owned(redirect_uri))
-            .request_async(&async_http_client)
+            .set_redirect_uri(std::borrow::Cow::Owned(redirect_url))
+            .request_async(&|request| async_http_client(&http, request))
             .await
+            .map_err(|e| explain_timeout(e.into()))
             .context("Failed to exchange authorization code for token")?;
 
         // Calculate expiration time
@@ -113,9 +124,16 @@ impl TokenProvider {
             .unwrap_or(std::time::Duration::from_secs(3600));
         let expires_at = Utc::now() + chrono::Duration::from_std(expires_in)?;
 
+        // Record the scopes that were actually granted. Google lets the user untick some of them
+        // on the consent screen, and omits the list when everything requested was granted.
+        let scopes = match token_response.scopes() {
+            Some(granted) => granted.iter().map(|s| s.as_str().to_string()).collect(),
+            None => OAUTH_SCOPES.iter().map(|s| s.to_string()).collect(),
+        };
+
         // Create token file
         let token_data = TokenFile::new(
-            OAUTH_SCOPES.iter().map(|s| s.to_string()).collect(),
+            scopes,
             token_response.access_token().secret().clone(),
             token_response
                 .refresh_token()
@@ -125,6 +143,14 @@ impl TokenProvider {
             expires_at,
             None, // id_token not available in BasicTokenResponse
         );
+        let missing = token_data.missing_scopes();
+        if !missing.is_empty() {
+            bail!(
+                "Google did not grant the '{}' scope. Please run `tiller auth` again and allow \
+                every permission that is requested.",
+                missing.join("', '")
+            );
+        }
 
         let token_file = File::new(token_path, token_data);
         token_file.save().await?;
@@ -135,6 +161,8 @@ impl TokenProvider {
         Ok(Self {
             secret: secret_file,
             token: token_file,
+            http,
+            refresh_buffer,
         })
     }
 
@@ -153,13 +181,21 @@ impl TokenProvider {
     /// # Arguments
     /// - `secret`: The path to an existing file that contains the client ID and client secret.
     /// - `token`: The path to an existing file that contains the token and refresh token.
+    /// - `http`: The HTTP client to use for requests to Google's OAuth endpoints, see
+    ///   `build_http_client`.
+    /// - `refresh_buffer`: How long before the access token expires it is refreshed.
     ///
     /// # Returns
     /// - A constructed `TokenProvider` object.
     ///
     /// # Errors
     /// - If any of the file operations, network operations, or logical checks fail.
-    pub(crate) async fn load<P1, P2>(secret: P1, token: P2) -> Res<Self>
+    pub(crate) async fn load<P1, P2>(
+        secret: P1,
+        token: P2,
+        http: reqwest::Client,
+        refresh_buffer: Duration,
+    ) -> Res<Self>
     where
         P1: Into<PathBuf>,
         P2: Into<PathBuf>,
@@ -177,15 +213,37 @@ impl TokenProvider {
         Ok(Self {
             secret: secret_file,
             token: token_file,
+            http,
+            refresh_buffer,
         })
     }
 
+    /// Returns the required scopes that the token file at `token` was not granted, e.g. because
+    /// `OAUTH_SCOPES` changed since it was created. Returns `None` if there is no readable token
+    /// file.
+    pub(crate) async fn missing_scopes(token: impl AsRef<Path>) -> Option<Vec<&'static str>> {
+        let token = token.as_ref();
+        if !token.exists() {
+            return None;
+        }
+        TokenFile::read(token)
+            .await
+            .ok()
+            .map(|token_file| token_file.missing_scopes())
+    }
+
+    /// Returns the scopes that were granted to the current token.
+    pub(crate) fn scopes(&self) -> &[String] {
+        self.token.data().scopes()
+    }
+
     /// Returns the current token without checking its expiration or refreshing it.
     pub(super) fn token(&self) -> &str {
         self.token.data().access_token()
     }
 
-    /// - Checks to see if our token is expired or expiring soon (within `EXPIRATION` minutes)
+    /// - Checks to see if our token is expired or expiring soon (within `refresh_buffer`, see
+    ///   `token_refresh_buffer_secs` in `config.json`)
     /// - If our token needs to be refreshed, does so with the `oauth` library. Note: DOES NOT use
     ///   user interaction, no matter what happens.
     /// - If an error occurs while attempting to refresh the token, the error is returned.
@@ -194,7 +252,7 @@ impl TokenProvider {
     /// - Returns the `token` for the sheets client to use.
     pub(super) async fn token_with_refresh(&mut self) -> Res<&str> {
         // Check if token needs refresh
-        if !self.token.data().is_expired() {
+        if !self.token.data().is_expired(self.refresh_buffer) {
             return Ok(self.token.data().access_token());
         }
 
@@ -213,8 +271,9 @@ impl TokenProvider {
         let refresh_token = RefreshToken::new(self.token.data().refresh_token().to_string());
         let token_response = oauth_client
             .exchange_refresh_token(&refresh_token)
-            .request_async(&async_http_client)
+            .request_async(&|request| async_http_client(&self.http, request))
             .await
+            .map_err(|e| explain_timeout(e.into()))
             .context("Failed to refresh access token")?;
 
         // Calculate new expiration time
@@ -238,6 +297,7 @@ impl TokenProvider {
 
 /// Async HTTP client for OAuth2 requests using reqwest
 async fn async_http_client(
+    client: &reqwest::Client,
     request: oauth2::HttpRequest,
 ) -> std::result::Result<
     oauth2::HttpResponse,
@@ -246,8 +306,6 @@ async fn async_http_client(
         oauth2::StandardErrorResponse<oauth2::basic::BasicErrorResponseType>,
     >,
 > {
-    let client = ::reqwest::Client::new();
-
     let mut req_builder = client
         .request(request.method().clone(), request.uri().to_string())
         .body(request.body().to_vec());
@@ -319,6 +377,16 @@ fn bind_random_port() -> Res<(TcpListener, u16)> {
     Ok((listener, port))
 }
 
+/// Returns `redirect_uri`, the localhost redirect from the client secret file, with the `port` of
+/// our local webserver added, e.g. `http://localhost` becomes `http://localhost:8080/`.
+fn local_redirect_url(redirect_uri: &str, port: u16) -> Res<RedirectUrl> {
+    let mut url = url::Url::parse(redirect_uri)
+        .with_context(|| format!("Invalid redirect URI '{redirect_uri}'"))?;
+    url.set_port(Some(port))
+        .map_err(|_| anyhow!("Cannot add a port to the redirect URI '{redirect_uri}'"))?;
+    Ok(RedirectUrl::from_url(url))
+}
+
 /// Attempt to open the authorization URL in the default browser
 fn open_browser(url: &str) -> Res<()> {
     #[cfg(target_os = "macos")]
@@ -439,3 +507,11 @@ async fn receive_oauth_callback(listener: TcpListener, expected_csrf: CsrfToken)
         None => bail!("Failed to receive authorization code"),
     }
 }
+
+#[test]
+fn test_local_redirect_url_adds_the_port() {
+    let url = local_redirect_url("http://127.0.0.1", 8080).unwrap();
+    assert_eq!("http://127.0.0.1:8080/", url.as_str());
+    let url = local_redirect_url("http://localhost/callback", 4321).unwrap();
+    assert_eq!("http://localhost:4321/callback", url.as_str());
+}
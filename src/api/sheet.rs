@@ -1,99 +1,155 @@
-//! Implements the `Sheet` trait using the `sheets:Client` to interact with a Google sheet.
+//! Implements the `Sheet` trait by calling the Google Sheets API.
 
-use crate::api::{Sheet, SheetRange, TokenProvider};
+use crate::api::{build_http_client, explain_timeout, Account, Sheet, SheetRange, TokenProvider};
 use crate::error::Res;
 use crate::Config;
 use anyhow::Context;
 use sheets::types::{
-    BatchClearValuesRequest, BatchUpdateValuesRequest, DateTimeRenderOption, Dimension,
-    ValueInputOption, ValueRange, ValueRenderOption,
+    BatchClearValuesRequest, BatchUpdateValuesRequest, Dimension, Spreadsheet, ValueInputOption,
+    ValueRange,
 };
-use sheets::ClientError;
-use tracing::trace;
+use std::future::Future;
+use std::time::Duration;
+use tracing::{debug, trace, warn};
 
-/// Implements the `Sheet` trait using the `sheets:Client` to interact with a Google sheet. It takes
-/// a `TokenProvider`, on which it calls refresh to keep the token up-to-date.
+/// The Sheets API endpoint for spreadsheets.
+const SHEETS_API: &str = "https://sheets.googleapis.com/v4/spreadsheets";
+
+/// The Drive API endpoint.
+const DRIVE_API: &str = "https://www.googleapis.com/drive/v3";
+
+/// The `valueRenderOption` that returns values as they are displayed in the sheet.
+const FORMATTED_VALUE: &str = "FORMATTED_VALUE";
+
+/// The `valueRenderOption` that returns values without their number formats applied.
+const UNFORMATTED_VALUE: &str = "UNFORMATTED_VALUE";
+
+/// The `valueRenderOption` that returns formulas instead of their values.
+const FORMULA: &str = "FORMULA";
+
+/// The `dateTimeRenderOption` that returns dates and times as they are displayed in the sheet.
+const FORMATTED_STRING: &str = "FORMATTED_STRING";
+
+/// The `dateTimeRenderOption` that returns dates and times as serial numbers, i.e. days since
+/// 12/30/1899.
+const SERIAL_NUMBER: &str = "SERIAL_NUMBER";
+
+/// How many times a request that Google answered with a transient failure status is retried.
+const TRANSIENT_RETRIES: u32 = 3;
+
+/// How long to wait before the first retry of a transient failure. Each later retry waits twice as
+/// long as the one before.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Implements the `Sheet` trait by calling the Sheets API with the HTTP client from
+/// `build_http_client`. It takes a `TokenProvider`, on which it calls refresh to keep the token
+/// up-to-date.
 pub(super) struct GoogleSheet {
     config: Config,
     token_provider: TokenProvider,
-    client: sheets::Client,
+    http: reqwest::Client,
 }
 
 impl GoogleSheet {
     pub(super) async fn new(config: Config, mut token_provider: TokenProvider) -> Res<Self> {
-        let client = create_sheets_client(&mut token_provider).await?;
+        token_provider.token_with_refresh().await?;
+        let http = build_http_client(&config)?;
         Ok(Self {
             config,
             token_provider,
-            client,
+            http,
         })
     }
-
-    /// Refreshes the sheets client with a new access token if needed
-    async fn refresh_client(&mut self) -> Res<()> {
-        self.client = create_sheets_client(&mut self.token_provider).await?;
-        Ok(())
-    }
 }
 
 #[async_trait::async_trait]
 impl Sheet for GoogleSheet {
     async fn get(&mut self, sheet_name: &str) -> Res<Vec<Vec<String>>> {
         trace!("get for {sheet_name}");
-        self.refresh_client().await?;
         let range = format!("{sheet_name}!A:ZZ"); // Get all columns
-        let response = self
-            .client
-            .spreadsheets()
-            .values_get(
-                self.config.spreadsheet_id(),
-                &range,
-                DateTimeRenderOption::FormattedString,
-                Dimension::Rows,
-                ValueRenderOption::FormattedValue,
-            )
-            .await
-            .map_err(map_client_error)
-            .with_context(|| format!("Failed to fetch {sheet_name} sheet data"))?;
-        Ok(response.body.values)
+        let (id, range) = (self.config.spreadsheet_id(), range.as_str());
+        let response = authorized(&self.http, &mut self.token_provider, |client| async move {
+            client
+                .values_get(id, range, UNFORMATTED_VALUE, SERIAL_NUMBER)
+                .await
+        })
+        .await
+        .with_context(|| format!("Failed to fetch {sheet_name} sheet data"))?;
+        Ok(response)
+    }
+
+    async fn get_formatted_values(&mut self, sheet_name: &str) -> Res<Vec<Vec<String>>> {
+        trace!("get_formatted_values for {sheet_name}");
+        let range = format!("{sheet_name}!A:ZZ"); // Get all columns
+        let (id, range) = (self.config.spreadsheet_id(), range.as_str());
+        let response = authorized(&self.http, &mut self.token_provider, |client| async move {
+            client
+                .values_get(id, range, FORMATTED_VALUE, FORMATTED_STRING)
+                .await
+        })
+        .await
+        .with_context(|| format!("Failed to fetch {sheet_name} sheet formatted values"))?;
+        Ok(response)
     }
 
     async fn get_formulas(&mut self, sheet_name: &str) -> Res<Vec<Vec<String>>> {
         trace!("get_formulas for {sheet_name}");
-        self.refresh_client().await?;
         let range = format!("{sheet_name}!A:ZZ"); // Get all columns
-        let response = self
-            .client
-            .spreadsheets()
-            .values_get(
-                self.config.spreadsheet_id(),
-                &range,
-                DateTimeRenderOption::FormattedString,
-                Dimension::Rows,
-                ValueRenderOption::Formula,
-            )
-            .await
-            .map_err(map_client_error)
-            .with_context(|| format!("Failed to fetch {sheet_name} sheet formulas"))?;
-        Ok(response.body.values)
+        let (id, range) = (self.config.spreadsheet_id(), range.as_str());
+        let response = authorized(&self.http, &mut self.token_provider, |client| async move {
+            client
+                .values_get(id, range, FORMULA, FORMATTED_STRING)
+                .await
+        })
+        .await
+        .with_context(|| format!("Failed to fetch {sheet_name} sheet formulas"))?;
+        Ok(response)
+    }
+
+    async fn get_header(&mut self, sheet_name: &str) -> Res<Vec<String>> {
+        trace!("get_header for {sheet_name}");
+        let range = format!("{sheet_name}!1:1"); // Only the first row
+        let (id, range) = (self.config.spreadsheet_id(), range.as_str());
+        let response = authorized(&self.http, &mut self.token_provider, |client| async move {
+            client
+                .values_get(id, range, FORMATTED_VALUE, FORMATTED_STRING)
+                .await
+        })
+        .await
+        .with_context(|| format!("Failed to fetch the {sheet_name} sheet header row"))?;
+        Ok(response.into_iter().next().unwrap_or_default())
+    }
+
+    async fn list_tabs(&mut self) -> Res<Vec<String>> {
+        trace!("list_tabs");
+        let id = self.config.spreadsheet_id();
+        let response = authorized(&self.http, &mut self.token_provider, |client| async move {
+            client.get_spreadsheet(id).await
+        })
+        .await
+        .context("Failed to fetch the spreadsheet's tabs")?;
+        Ok(response
+            .sheets
+            .into_iter()
+            .filter_map(|sheet| sheet.properties)
+            .map(|properties| properties.title)
+            .collect())
     }
 
     async fn clear_ranges(&mut self, ranges: &[&str]) -> Res<()> {
-        self.refresh_client().await?;
         let request = BatchClearValuesRequest {
             ranges: ranges.iter().map(|s| s.to_string()).collect(),
         };
-        self.client
-            .spreadsheets()
-            .values_batch_clear(self.config.spreadsheet_id(), &request)
-            .await
-            .map_err(map_client_error)
-            .with_context(|| format!("Failed to clear ranges: {:?}", ranges))?;
+        let (id, request) = (self.config.spreadsheet_id(), &request);
+        authorized(&self.http, &mut self.token_provider, |client| async move {
+            client.batch(id, "values:batchClear", request).await
+        })
+        .await
+        .with_context(|| format!("Failed to clear ranges: {:?}", ranges))?;
         Ok(())
     }
 
     async fn write_ranges(&mut self, data: &[SheetRange]) -> Res<()> {
-        self.refresh_client().await?;
         let value_ranges: Vec<ValueRange> = data
             .iter()
             .map(|sr| ValueRange {
@@ -111,93 +167,474 @@ impl Sheet for GoogleSheet {
             value_input_option: Some(ValueInputOption::UserEntered),
         };
 
-        self.client
-            .spreadsheets()
-            .values_batch_update(self.config.spreadsheet_id(), &request)
-            .await
-            .map_err(map_client_error)
-            .with_context(|| "Failed to write ranges")?;
+        let (id, request) = (self.config.spreadsheet_id(), &request);
+        authorized(&self.http, &mut self.token_provider, |client| async move {
+            client.batch(id, "values:batchUpdate", request).await
+        })
+        .await
+        .with_context(|| "Failed to write ranges")?;
         Ok(())
     }
 
     async fn copy_spreadsheet(&mut self, new_name: &str) -> Res<String> {
-        self.refresh_client().await?;
-
-        // Use Google Drive API to copy the spreadsheet
-        // POST https://www.googleapis.com/drive/v3/files/{fileId}/copy
-        let url = format!(
-            "https://www.googleapis.com/drive/v3/files/{}/copy",
-            self.config.spreadsheet_id()
-        );
-
-        let client = reqwest::Client::new();
-        let response = client
-            .post(&url)
-            .bearer_auth(self.token_provider.token())
-            .json(&serde_json::json!({
-                "name": new_name
-            }))
-            .send()
-            .await
-            .context("Failed to send copy request to Google Drive API")?;
+        let id = self.config.spreadsheet_id();
+        authorized(&self.http, &mut self.token_provider, |client| async move {
+            client.copy_file(id, new_name).await
+        })
+        .await
+        .context("Failed to copy the spreadsheet with the Google Drive API")
+    }
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let body = response
-                .text()
+    async fn account(&mut self) -> Res<Account> {
+        let response_json = authorized(&self.http, &mut self.token_provider, |client| async move {
+            client.about_user().await
+        })
+        .await
+        .context("Failed to get the Google account from the Google Drive API")?;
+
+        let user = response_json
+            .get("user")
+            .context("Google Drive API response missing 'user' field")?;
+        let field = |name: &str| {
+            user.get(name)
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        Ok(Account {
+            email: field("emailAddress"),
+            name: field("displayName"),
+        })
+    }
+}
+
+/// The error added to a request's error when Google rejects the access token with
+/// `401 Unauthorized`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Unauthorized;
+
+impl std::fmt::Display for Unauthorized {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Google rejected the OAuth access token (401 Unauthorized)")
+    }
+}
+
+/// Whether `e` was caused by Google rejecting the access token, see [`Unauthorized`].
+fn is_unauthorized(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<Unauthorized>().is_some()
+}
+
+/// Runs `op` with a `SheetsClient` for the current access token, see [`retry_unauthorized`].
+async fn authorized<T, Op, Fut>(
+    http: &reqwest::Client,
+    token_provider: &mut TokenProvider,
+    op: Op,
+) -> Res<T>
+where
+    Op: Fn(SheetsClient) -> Fut,
+    Fut: Future<Output = Res<T>>,
+{
+    let access_token = token_provider.token_with_refresh().await?.to_string();
+    let client = SheetsClient::new(http.clone(), access_token);
+    let refresh = move || async move {
+        let access_token = token_provider.refresh().await;
+        access_token.map(|token| SheetsClient::new(http.clone(), token.to_string()))
+    };
+    retry_unauthorized(client, refresh, op).await
+}
+
+/// Runs `op` with `client`. A token can expire partway through a sync even though it is refreshed
+/// ahead of time, so if `op` fails because Google rejected the access token, `refresh` is called
+/// once for a client with a new token and `op` is run again. If the retry fails too, its error is
+/// returned.
+async fn retry_unauthorized<C, T, Op, Fut, Refresh, RefreshFut>(
+    client: C,
+    refresh: Refresh,
+    op: Op,
+) -> Res<T>
+where
+    Op: Fn(C) -> Fut,
+    Fut: Future<Output = Res<T>>,
+    Refresh: FnOnce() -> RefreshFut,
+    RefreshFut: Future<Output = Res<C>>,
+{
+    match op(client).await {
+        Err(e) if is_unauthorized(&e) => {
+            debug!("{e}, refreshing the token and retrying");
+            let client = refresh()
                 .await
-                .unwrap_or_else(|_| "Unable to read response body".to_string());
-            anyhow::bail!(
-                "Google Drive API copy failed with status {}: {}",
-                status,
-                body
-            );
+                .context("Unable to refresh the token after Google rejected it")?;
+            op(client).await
         }
+        result => result,
+    }
+}
+
+/// Makes Sheets and Drive API requests with an access token. The requests go through the HTTP
+/// client from `build_http_client`, so they honor the configured proxy and request timeout.
+struct SheetsClient {
+    http: reqwest::Client,
+    access_token: String,
+}
 
-        // Parse response to get the new file ID
-        let response_json: serde_json::Value = response
+impl SheetsClient {
+    fn new(http: reqwest::Client, access_token: String) -> Self {
+        Self { http, access_token }
+    }
+
+    /// The URL of the API endpoint `api`, followed by the percent-encoded `segments`.
+    fn url(api: &str, segments: &[&str]) -> Res<url::Url> {
+        let mut url = url::Url::parse(api).with_context(|| format!("Invalid API URL {api}"))?;
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("The API URL {api} cannot have path segments"))?
+            .extend(segments);
+        Ok(url)
+    }
+
+    /// Gets the values of `range` by row. `value_render_option` decides whether formatted values,
+    /// unformatted values or formulas are returned, and `date_time_render_option` whether dates are
+    /// formatted or serial numbers. Unformatted numbers and booleans are returned as text, e.g.
+    /// `-87.43` and `TRUE`.
+    async fn values_get(
+        &self,
+        id: &str,
+        range: &str,
+        value_render_option: &str,
+        date_time_render_option: &str,
+    ) -> Res<Vec<Vec<String>>> {
+        let request = self
+            .http
+            .get(Self::url(SHEETS_API, &[id, "values", range])?)
+            .query(&[
+                ("majorDimension", "ROWS"),
+                ("valueRenderOption", value_render_option),
+                ("dateTimeRenderOption", date_time_render_option),
+            ]);
+        let response: ValuesResponse = self
+            .send(request)
+            .await?
             .json()
             .await
-            .context("Failed to parse Google Drive API response")?;
+            .context("Failed to parse Sheets API response")?;
+        Ok(response
+            .values
+            .into_iter()
+            .map(|row| row.into_iter().map(cell_text).collect())
+            .collect())
+    }
+
+    /// Gets the spreadsheet's properties and tabs, without their data.
+    async fn get_spreadsheet(&self, id: &str) -> Res<Spreadsheet> {
+        let request = self
+            .http
+            .get(Self::url(SHEETS_API, &[id])?)
+            .query(&[("includeGridData", "false")]);
+        self.send(request)
+            .await?
+            .json()
+            .await
+            .context("Failed to parse Sheets API response")
+    }
 
-        let file_id = response_json
+    /// Posts `body` to the batch `method` of the spreadsheet, e.g. `values:batchClear`.
+    async fn batch(&self, id: &str, method: &str, body: &impl serde::Serialize) -> Res<()> {
+        let request = self
+            .http
+            .post(Self::url(SHEETS_API, &[id, method])?)
+            .json(body);
+        self.send(request).await?;
+        Ok(())
+    }
+
+    /// Copies the Drive file `id`, naming the copy `name`, and returns the file ID of the copy.
+    async fn copy_file(&self, id: &str, name: &str) -> Res<String> {
+        let request = self
+            .http
+            .post(Self::url(DRIVE_API, &["files", id, "copy"])?)
+            .json(&serde_json::json!({ "name": name }));
+        let response: serde_json::Value = self
+            .send(request)
+            .await?
+            .json()
+            .await
+            .context("Failed to parse Google Drive API response")?;
+        response
             .get("id")
             .and_then(|v| v.as_str())
-            .context("Google Drive API response missing 'id' field")?
-            .to_string();
-
-        Ok(file_id)
-    }
-}
-
-/// Creates a new sheets client with a refreshed access token.
-async fn create_sheets_client(token_provider: &mut TokenProvider) -> Res<sheets::Client> {
-    // Get the access token (will refresh if needed)
-    let access_token = token_provider.token_with_refresh().await?;
-
-    // Create sheets client
-    // Note: The sheets crate requires client_id, client_secret, and redirect_uri,
-    // but we don't need them for API calls, only the access token
-    Ok(sheets::Client::new(
-        String::new(), // client_id (not needed for API calls with access token)
-        String::new(), // client_secret (not needed for API calls with access token)
-        String::new(), // redirect_uri (not needed for API calls with access token)
-        access_token.to_string(),
-        String::new(), // refresh_token (not needed, we handle refresh ourselves)
-    ))
-}
-
-fn map_client_error(e: sheets::ClientError) -> anyhow::Error {
-    let error_name = match &e {
-        ClientError::EmptyRefreshToken => "EmptyRefreshToken".to_string(),
-        ClientError::FromUtf8Error(inner) => format!("FromUtf8Error {inner}"),
-        ClientError::UrlParserError(inner) => format!("UrlParserError {inner}"),
-        ClientError::SerdeJsonError(inner) => format!("SerdeJsonError {inner}"),
-        ClientError::ReqwestError(inner) => format!("ReqwestError {inner}"),
-        ClientError::InvalidHeaderValue(inner) => format!("InvalidHeaderValue {inner}"),
-        ClientError::ReqwestMiddleWareError(inner) => format!("ReqwestMiddleWareError {inner}"),
-        ClientError::HttpError { .. } => "HttpError".to_string(),
-        ClientError::Other(_) => "Other".to_string(),
-    };
-    Err::<(), ClientError>(e).context(error_name).err().unwrap()
+            .map(str::to_string)
+            .context("Google Drive API response missing 'id' field")
+    }
+
+    /// Gets the Drive API's information about the user that granted the access token.
+    async fn about_user(&self) -> Res<serde_json::Value> {
+        let request = self
+            .http
+            .get(Self::url(DRIVE_API, &["about"])?)
+            .query(&[("fields", "user")]);
+        self.send(request)
+            .await?
+            .json()
+            .await
+            .context("Failed to parse Google Drive API response")
+    }
+
+    /// Sends `request` with the access token. A failure status is returned as an error, which is
+    /// marked [`Unauthorized`] when Google rejected the token.
+    ///
+    /// Google answers with `429 Too Many Requests` when a quota is used up and with a `5xx` status
+    /// when it has a passing problem, so these are retried up to `TRANSIENT_RETRIES` times, waiting
+    /// longer before each retry.
+    async fn send(&self, request: reqwest::RequestBuilder) -> Res<reqwest::Response> {
+        let request = request.bearer_auth(&self.access_token);
+        let mut delay = RETRY_DELAY;
+        let mut retries = 0;
+        let response = loop {
+            let response = request
+                .try_clone()
+                .context("Unable to copy the request to Google API")?
+                .send()
+                .await
+                .map_err(|e| explain_timeout(e.into()))
+                .context("Failed to send request to Google API")?;
+            let status = response.status();
+            if !is_transient(status) || retries == TRANSIENT_RETRIES {
+                break response;
+            }
+            warn!("Google API answered with status {status}, retrying in {delay:?}");
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+            retries += 1;
+        };
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let body = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unable to read response body".to_string());
+        let e = anyhow::anyhow!("Google API request failed with status {}: {}", status, body);
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(e.context(Unauthorized));
+        }
+        Err(e)
+    }
+}
+
+/// Whether a request that Google answered with `status` may succeed if it is sent again.
+fn is_transient(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// The body of a `values.get` response. Unformatted cells can be numbers or booleans rather than
+/// strings, so they are read as JSON values.
+#[derive(serde::Deserialize)]
+struct ValuesResponse {
+    #[serde(default)]
+    values: Vec<Vec<serde_json::Value>>,
+}
+
+/// The text of a cell in a `values.get` response, e.g. `45950` for a date serial number.
+fn cell_text(cell: serde_json::Value) -> String {
+    match cell {
+        serde_json::Value::String(s) => s,
+        serde_json::Value::Bool(b) => if b { "TRUE" } else { "FALSE" }.to_string(),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[test]
+fn test_values_response_cells_are_text() {
+    let response: ValuesResponse = serde_json::from_str(
+        r#"{"range": "Transactions!A1:C2", "values": [["Date", "Amount", "Flag"], [45950, -87.43, true]]}"#,
+    )
+    .unwrap();
+    let rows: Vec<Vec<String>> = response
+        .values
+        .into_iter()
+        .map(|row| row.into_iter().map(cell_text).collect())
+        .collect();
+    assert_eq!(rows[1], vec!["45950", "-87.43", "TRUE"]);
+
+    // An empty range has no values at all
+    let response: ValuesResponse = serde_json::from_str(r#"{"range": "AutoCat!A1:K"}"#).unwrap();
+    assert!(response.values.is_empty());
+}
+
+#[tokio::test]
+async fn test_send_retries_transient_failures() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // Answers each request with the next status, then with 200
+    async fn serve(statuses: Vec<u16>) -> (String, tokio::task::JoinHandle<usize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let mut requests = 0;
+            for status in statuses.into_iter().chain([200]) {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf).await.unwrap();
+                requests += 1;
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok"
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+                if status == 404 {
+                    break;
+                }
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    let http = reqwest::Client::new();
+    let client = SheetsClient::new(http.clone(), "token".to_string());
+
+    let (url, server) = serve(vec![429, 503]).await;
+    let response = client.send(http.get(&url)).await.unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(server.await.unwrap(), 3);
+
+    // Other failures are returned right away
+    let (url, server) = serve(vec![404]).await;
+    let e = client.send(http.get(&url)).await.unwrap_err();
+    assert!(e.to_string().contains("404"), "{e}");
+    assert_eq!(server.await.unwrap(), 1);
+}
+
+#[tokio::test]
+async fn test_retry_unauthorized_refreshes_once() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // The first call is rejected, the retry with the refreshed token succeeds
+    let calls = AtomicUsize::new(0);
+    let refreshes = AtomicUsize::new(0);
+    let result = retry_unauthorized(
+        "old",
+        || async {
+            refreshes.fetch_add(1, Ordering::SeqCst);
+            Ok("new")
+        },
+        |token| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async move {
+                match token {
+                    "new" => Ok(token.to_string()),
+                    _ => Err(anyhow::anyhow!("401").context(Unauthorized)),
+                }
+            }
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(result, "new");
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_retry_unauthorized_fails_when_retry_is_rejected() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let calls = AtomicUsize::new(0);
+    let refreshes = AtomicUsize::new(0);
+    let err = retry_unauthorized(
+        "old",
+        || async {
+            refreshes.fetch_add(1, Ordering::SeqCst);
+            Ok("new")
+        },
+        |_token| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async { Err::<(), _>(anyhow::anyhow!("401").context(Unauthorized)) }
+        },
+    )
+    .await
+    .unwrap_err();
+    assert!(is_unauthorized(&err));
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_retry_unauthorized_does_not_retry_other_errors() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let calls = AtomicUsize::new(0);
+    let err = retry_unauthorized(
+        (),
+        || async { Err::<(), _>(anyhow::anyhow!("should not refresh")) },
+        |_| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async { Err::<(), _>(anyhow::anyhow!("500")) }
+        },
+    )
+    .await
+    .unwrap_err();
+    assert!(!is_unauthorized(&err));
+    assert_eq!(err.to_string(), "500");
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_sheets_url_encodes_ranges() {
+    let url = SheetsClient::url(SHEETS_API, &["abc123", "values", "Balance History!A:ZZ"]).unwrap();
+    assert_eq!(
+        url.as_str(),
+        "https://sheets.googleapis.com/v4/spreadsheets/abc123/values/Balance%20History!A:ZZ"
+    );
+    let url = SheetsClient::url(SHEETS_API, &["abc123", "values:batchClear"]).unwrap();
+    assert_eq!(
+        url.as_str(),
+        "https://sheets.googleapis.com/v4/spreadsheets/abc123/values:batchClear"
+    );
+    let url = SheetsClient::url(DRIVE_API, &["files", "abc123", "copy"]).unwrap();
+    assert_eq!(
+        url.as_str(),
+        "https://www.googleapis.com/drive/v3/files/abc123/copy"
+    );
+}
+
+#[tokio::test]
+async fn test_sheets_requests_use_the_configured_http_client() {
+    use tokio::io::AsyncReadExt;
+
+    // A proxy that records the first request it receives and never responds
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let proxy = format!("http://{}", listener.local_addr().unwrap());
+    let received = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let mut buf = vec![0; 1024];
+        let n = socket.read(&mut buf).await.unwrap();
+        String::from_utf8_lossy(&buf[..n]).to_string()
+    });
+
+    let mut env = crate::test::TestEnv::new().await;
+    env.set_config_value("proxy", proxy.into()).await;
+    env.set_config_value("request_timeout_secs", 1.into()).await;
+    let http = build_http_client(&env.config()).unwrap();
+    let client = SheetsClient::new(http, "token".to_string());
+    let err = client
+        .values_get(
+            "abc123",
+            "Transactions!A:ZZ",
+            FORMATTED_VALUE,
+            FORMATTED_STRING,
+        )
+        .await
+        .unwrap_err();
+
+    // The request went through the configured proxy and timed out by the configured timeout
+    let request = received.await.unwrap();
+    assert!(
+        request.starts_with("CONNECT sheets.googleapis.com:443 HTTP/1.1"),
+        "{request}"
+    );
+    assert!(
+        format!("{err:#}").contains("request_timeout_secs"),
+        "{err:#}"
+    );
 }
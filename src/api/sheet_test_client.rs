@@ -8,17 +8,28 @@
 //! Note: this is compiled even in the "production" version of this app so that we can run the whole
 //! app, top-to-bottom, without using Google Sheets.
 
-use crate::api::{Sheet, SheetRange, AUTO_CAT, CATEGORIES, TRANSACTIONS};
+use crate::api::tiller::{a1_to_rowcol, column_letters};
+use crate::api::{
+    Account, Sheet, SheetRange, AUTO_CAT, BALANCES, CATEGORIES, SEED_DIR_ENV, TRANSACTIONS,
+};
 use crate::error::Res;
-use anyhow::Context;
+use crate::model::RowCol;
+use anyhow::{bail, Context};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::io::Cursor;
+use std::path::Path;
 use std::sync::{Mutex, MutexGuard, OnceLock};
 
 /// TestSheets that persist across multiple sync calls.
 static TEST_SHEETS: OnceLock<Mutex<HashMap<String, TestSheetState>>> = OnceLock::new();
 
+/// The email address of the pretend Google account that `TestSheet` reports.
+pub(crate) const TEST_ACCOUNT_EMAIL: &str = "tiller-test@example.com";
+
+/// The display name of the pretend Google account that `TestSheet` reports.
+const TEST_ACCOUNT_NAME: &str = "Tiller Test";
+
 /// Type alias for sheet data: a 2D grid of strings.
 type SheetData = Vec<Vec<String>>;
 
@@ -36,9 +47,19 @@ pub(crate) struct TestSheetState {
     /// If a sheet is not present here, `get_formulas()` falls back to `data`.
     pub(crate) formulas: SheetDataMap,
 
+    /// Formatted value data for each sheet (what `get_formatted_values()` returns).
+    /// If a sheet is not present here, `get_formatted_values()` falls back to `data`. Writing to
+    /// a sheet removes its entry, since the written data replaces what was displayed.
+    pub(crate) formatted: SheetDataMap,
+
     /// History of all calls made to this sheet. Uses RefCell for interior mutability
     /// so we can record calls even through the `&mut self` trait methods.
     pub(crate) call_history: RefCell<Vec<SheetCall>>,
+
+    /// When set, the number of `write_ranges()` calls that succeed before one fails, e.g. to
+    /// simulate a network error part way through a sync up. Only one call fails, later calls
+    /// succeed again.
+    pub(crate) writes_before_failure: Option<usize>,
 }
 
 /// Records a call made to `TestSheet`, including the data involved.
@@ -48,12 +69,23 @@ pub(crate) enum SheetCall {
     Get { sheet_name: String, data: SheetData },
     /// A get_formulas() call was made, returning the specified data
     GetFormulas { sheet_name: String, data: SheetData },
+    /// A get_formatted_values() call was made, returning the specified data
+    GetFormattedValues { sheet_name: String, data: SheetData },
+    /// A get_header() call was made, returning the specified header row
+    GetHeader {
+        sheet_name: String,
+        header: Vec<String>,
+    },
+    /// A list_tabs() call was made
+    ListTabs,
     /// A clear_ranges() call was made with the specified ranges
     ClearRanges { ranges: Vec<String> },
     /// A write_ranges() call was made with the specified data
     WriteRanges { ranges: Vec<(String, SheetData)> },
     /// A copy_spreadsheet() call was made with the specified name
     CopySpreadsheet { new_name: String },
+    /// An account() call was made
+    Account,
 }
 
 /// An implementation of the `Sheet` trait that does not use Google sheets. It can hold any data in
@@ -106,13 +138,51 @@ impl TestSheet {
             TestSheetState {
                 data,
                 formulas,
-                call_history: RefCell::new(vec![]),
+                ..Default::default()
             }
         });
 
         Self { name }
     }
 
+    /// Creates the `TestSheet` that `Mode::Testing` uses. When `TILLER_SYNC_TEST_SEED_DIR` is set,
+    /// a sheet that doesn't exist yet is seeded from the CSV files in that directory, see
+    /// `new_with_seed_dir`. Otherwise it is seeded with the built-in data.
+    /// - `name` is the `sheet_id` from the `sheet_url` in `Config`.
+    pub(crate) fn for_testing_mode(name: impl Into<String>) -> Res<Self> {
+        match std::env::var_os(SEED_DIR_ENV) {
+            Some(dir) => Self::new_with_seed_dir(name, Path::new(&dir)),
+            None => Ok(Self::new_with_seed_data(name)),
+        }
+    }
+
+    /// Creates a `TestSheet` seeded from the CSV files in `dir` if it doesn't already exist. Each
+    /// tab is read from the file named after it, e.g. `Transactions.csv` or
+    /// `Balance History.csv`, and a tab without a file is left out of the sheet. The seeded sheet
+    /// has no formulas.
+    /// - `name` is the `sheet_id` from the `sheet_url` in `Config`.
+    pub(crate) fn new_with_seed_dir(name: impl Into<String>, dir: &Path) -> Res<Self> {
+        let name = name.into();
+        let mut map = TEST_SHEETS
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .expect("Error obtaining test mutex");
+
+        // Only seed if the entry doesn't exist yet
+        if !map.contains_key(&name) {
+            let data = load_seed_dir(dir)?;
+            map.insert(
+                name.clone(),
+                TestSheetState {
+                    data,
+                    ..Default::default()
+                },
+            );
+        }
+
+        Ok(Self { name })
+    }
+
     #[cfg(test)]
     /// Builder method: add value data for a sheet.
     pub(crate) fn with_sheet(self, sheet_name: &str, data: SheetData) -> Self {
@@ -142,6 +212,64 @@ impl TestSheet {
         self
     }
 
+    /// Builder method: add formatted value data for a sheet, e.g. to simulate `get()` returning
+    /// date serial numbers while the sheet displays formatted dates.
+    /// If not set, `get_formatted_values()` will return the same data as `get()`.
+    #[cfg(test)]
+    pub(crate) fn with_formatted_values(self, sheet_name: &str, formatted: SheetData) -> Self {
+        let mut map = TEST_SHEETS
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .expect("Error obtaining test mutex");
+        map.entry(self.name.clone())
+            .or_default()
+            .formatted
+            .insert(sheet_name.to_string(), formatted);
+        self
+    }
+
+    /// Builder method: remove the empty Column A that the seeded Transactions tab starts with, since
+    /// not every sheet has it, and regenerate the tab's formulas for the shifted columns.
+    #[cfg(test)]
+    pub(crate) fn without_column_a(self) -> Self {
+        let mut map = TEST_SHEETS
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .expect("Error obtaining test mutex");
+        let state = map.entry(self.name.clone()).or_default();
+        if let Some(transactions) = state.data.get_mut(TRANSACTIONS) {
+            if transactions
+                .first()
+                .and_then(|h| h.first())
+                .map(String::as_str)
+                == Some("")
+            {
+                for row in transactions.iter_mut() {
+                    if !row.is_empty() {
+                        row.remove(0);
+                    }
+                }
+            }
+            let formulas = generate_transaction_formulas(transactions);
+            state.formulas.insert(TRANSACTIONS.to_string(), formulas);
+        }
+        state.formatted.remove(TRANSACTIONS);
+        self
+    }
+
+    /// Builder method: make the `write_ranges()` call after the next `writes` calls fail once.
+    #[cfg(test)]
+    pub(crate) fn with_write_failure_after(self, writes: usize) -> Self {
+        let mut map = TEST_SHEETS
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .expect("Error obtaining test mutex");
+        map.entry(self.name.clone())
+            .or_default()
+            .writes_before_failure = Some(writes);
+        self
+    }
+
     /// Get the call history for test assertions.
     #[cfg(test)]
     pub(crate) fn call_history(&self) -> Vec<SheetCall> {
@@ -231,6 +359,33 @@ impl Sheet for TestSheet {
         Ok(data)
     }
 
+    async fn get_header(&mut self, sheet_name: &str) -> Res<Vec<String>> {
+        let mut map = TEST_SHEETS
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .expect("Error obtaining test mutex");
+
+        let header = map
+            .entry(self.name.clone())
+            .or_default()
+            .data
+            .get(sheet_name)
+            .with_context(|| format!("Sheet '{sheet_name}' not found"))?
+            .first()
+            .cloned()
+            .unwrap_or_default();
+
+        self.record_call(
+            SheetCall::GetHeader {
+                sheet_name: sheet_name.to_string(),
+                header: header.clone(),
+            },
+            map,
+        );
+
+        Ok(header)
+    }
+
     async fn get_formulas(&mut self, sheet_name: &str) -> Res<SheetData> {
         let mut map = TEST_SHEETS
             .get_or_init(|| Mutex::new(HashMap::new()))
@@ -264,6 +419,54 @@ impl Sheet for TestSheet {
         Ok(data)
     }
 
+    async fn get_formatted_values(&mut self, sheet_name: &str) -> Res<SheetData> {
+        let mut map = TEST_SHEETS
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .expect("Error obtaining test mutex");
+
+        let state = map.entry(self.name.clone()).or_default();
+        let data = match state.formatted.get(sheet_name) {
+            Some(found) => found.to_owned(),
+            None => state
+                .data
+                .get(sheet_name)
+                .with_context(|| format!("Sheet '{sheet_name}' not found"))?
+                .to_owned(),
+        };
+
+        self.record_call(
+            SheetCall::GetFormattedValues {
+                sheet_name: sheet_name.to_string(),
+                data: data.clone(),
+            },
+            map,
+        );
+
+        Ok(data)
+    }
+
+    async fn list_tabs(&mut self) -> Res<Vec<String>> {
+        let mut map = TEST_SHEETS
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .expect("Error obtaining test mutex");
+
+        // The tabs are held in a map, so there is no order to preserve
+        let mut tabs: Vec<String> = map
+            .entry(self.name.clone())
+            .or_default()
+            .data
+            .keys()
+            .cloned()
+            .collect();
+        tabs.sort();
+
+        self.record_call(SheetCall::ListTabs, map);
+
+        Ok(tabs)
+    }
+
     async fn clear_ranges(&mut self, ranges: &[&str]) -> Res<()> {
         let mut map = TEST_SHEETS
             .get_or_init(|| Mutex::new(HashMap::new()))
@@ -273,21 +476,13 @@ impl Sheet for TestSheet {
         // For each range, parse the sheet name and clear the appropriate rows
         for range in ranges {
             if let Some(sheet_name) = range.split('!').next() {
-                // Clear the data for this sheet (except header if range starts at A2)
-                if let Some(sheet_data) = map
-                    .entry(self.name.clone())
-                    .or_default()
-                    .data
-                    .get_mut(sheet_name)
-                {
-                    // If range contains "A2:", keep header row, clear rest
-                    if range.contains("A2:") || range.contains("!A2") {
-                        if !sheet_data.is_empty() {
-                            sheet_data.truncate(1); // Keep only header
-                        }
-                    } else {
-                        sheet_data.clear();
-                    }
+                let state = map.entry(self.name.clone()).or_default();
+                state.formatted.remove(sheet_name);
+
+                // Clear the rows of this sheet from the range's first row down, e.g. a range starting
+                // at A2 keeps the header row and a range with no row number clears everything
+                if let Some(sheet_data) = state.data.get_mut(sheet_name) {
+                    sheet_data.truncate(first_row(range).saturating_sub(1));
                 }
             }
         }
@@ -308,6 +503,16 @@ impl Sheet for TestSheet {
             .lock()
             .expect("Error obtaining test mutex");
 
+        let state = map.entry(self.name.clone()).or_default();
+        match state.writes_before_failure {
+            Some(0) => {
+                state.writes_before_failure = None;
+                anyhow::bail!("Simulated failure writing to test sheet '{}'", self.name);
+            }
+            Some(writes) => state.writes_before_failure = Some(writes - 1),
+            None => {}
+        }
+
         let mut recorded_ranges = Vec::new();
 
         for sheet_range in data {
@@ -315,6 +520,18 @@ impl Sheet for TestSheet {
                 recorded_ranges.push((sheet_range.range.clone(), sheet_range.values.clone()));
 
                 let state = map.entry(self.name.clone()).or_default();
+                state.formatted.remove(sheet_name);
+
+                // A range that starts right of column A, e.g. one column of `columns_plan`,
+                // overwrites just its own cells, including any formulas in them
+                if let Some(start) = start_cell(&sheet_range.range).filter(|start| start.1 > 0) {
+                    if let Some(formulas) = state.formulas.get_mut(sheet_name) {
+                        write_cells(formulas, start, &sheet_range.values);
+                    }
+                    let sheet_data = state.data.entry(sheet_name.to_string()).or_default();
+                    write_cells(sheet_data, start, &sheet_range.values);
+                    continue;
+                }
 
                 // Get or create the sheet data
                 let sheet_data = state.data.entry(sheet_name.to_string()).or_default();
@@ -373,6 +590,18 @@ impl Sheet for TestSheet {
 
         Ok(fake_file_id)
     }
+
+    async fn account(&mut self) -> Res<Account> {
+        let map = TEST_SHEETS
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .expect("Error obtaining test mutex");
+        self.record_call(SheetCall::Account, map);
+        Ok(Account {
+            email: TEST_ACCOUNT_EMAIL.to_string(),
+            name: TEST_ACCOUNT_NAME.to_string(),
+        })
+    }
 }
 
 /// Provides the seed data and formula data from this module.
@@ -396,10 +625,38 @@ fn default_data() -> (SheetDataMap, SheetDataMap) {
     let auto_cat = load_csv(AUTO_CAT_DATA).expect("Failed to load autocat seed data");
     data.insert(AUTO_CAT.to_string(), auto_cat);
 
+    // Load balance history (no formulas)
+    let balances = load_csv(BALANCE_DATA).expect("Failed to load balance seed data");
+    data.insert(BALANCES.to_string(), balances);
+
     (data, formulas)
 }
 
-/// Generates formula data for transactions, with =ABS(E{row}) formulas in "Custom Column".
+/// Loads the value data of each tab that has a CSV file named after it in `dir`.
+fn load_seed_dir(dir: &Path) -> Res<SheetDataMap> {
+    let mut data = HashMap::new();
+    for tab in [TRANSACTIONS, CATEGORIES, AUTO_CAT, BALANCES] {
+        let path = dir.join(format!("{tab}.csv"));
+        if !path.is_file() {
+            continue;
+        }
+        let csv = std::fs::read_to_string(&path)
+            .with_context(|| format!("Unable to read seed data from '{}'", path.display()))?;
+        let rows = load_csv(&csv)
+            .with_context(|| format!("Unable to parse seed data in '{}'", path.display()))?;
+        data.insert(tab.to_string(), rows);
+    }
+    if data.is_empty() {
+        bail!(
+            "No seed data found in '{}', expected CSV files such as '{TRANSACTIONS}.csv'",
+            dir.display()
+        );
+    }
+    Ok(data)
+}
+
+/// Generates formula data for transactions, with `=ABS(E{row})` formulas in "Custom Column", where
+/// `E` is whichever column holds the Amount.
 fn generate_transaction_formulas(transactions: &[Vec<String>]) -> SheetData {
     if transactions.is_empty() {
         return Vec::new();
@@ -407,6 +664,11 @@ fn generate_transaction_formulas(transactions: &[Vec<String>]) -> SheetData {
 
     let header_row = &transactions[0];
     let custom_col_idx = header_row.iter().position(|h| h == "Custom Column");
+    let amount_col = header_row
+        .iter()
+        .position(|h| h == "Amount")
+        .map(|ix| column_letters(ix + 1))
+        .unwrap_or_default();
 
     transactions
         .iter()
@@ -420,7 +682,7 @@ fn generate_transaction_formulas(transactions: &[Vec<String>]) -> SheetData {
                 let mut formula_row = row.clone();
                 if formula_row.len() > col_idx {
                     let sheet_row_num = row_idx + 1; // 1-indexed sheet row
-                    formula_row[col_idx] = format!("=ABS(E{sheet_row_num})");
+                    formula_row[col_idx] = format!("=ABS({amount_col}{sheet_row_num})");
                 }
                 formula_row
             } else {
@@ -447,6 +709,39 @@ fn load_csv(csv_data: &str) -> Res<SheetData> {
     Ok(rows)
 }
 
+/// Returns the 1-based row number that an A1 notation range starts at, e.g. 22 for
+/// `Transactions!A22:Q`, or 1 if the range has no row number, e.g. `Transactions!A:Q`.
+fn first_row(range: &str) -> usize {
+    let cell = range.split_once('!').map_or(range, |(_, cell)| cell);
+    cell.trim_start_matches(|c: char| c.is_ascii_alphabetic())
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect::<String>()
+        .parse()
+        .unwrap_or(1)
+}
+
+/// The first cell of an A1 `range`, e.g. `(1, 3)` for `Transactions!D2:D21`, or `None` if the
+/// range does not start with a cell.
+fn start_cell(range: &str) -> Option<RowCol> {
+    let start = range.split(':').next().unwrap_or(range);
+    a1_to_rowcol(start).ok()
+}
+
+/// Overwrites the cells of `rows` from `start` with `values`, growing `rows` as needed.
+fn write_cells(rows: &mut Vec<Vec<String>>, start: RowCol, values: &[Vec<String>]) {
+    let RowCol(row, col) = start;
+    if rows.len() < row + values.len() {
+        rows.resize(row + values.len(), Vec::new());
+    }
+    for (cells, values) in rows[row..].iter_mut().zip(values) {
+        if cells.len() < col + values.len() {
+            cells.resize(col + values.len(), String::new());
+        }
+        cells[col..col + values.len()].clone_from_slice(values);
+    }
+}
+
 /// Seed transaction data.
 const TRANSACTION_DATA: &str = r##",Date,Description,Category,Amount,Account,Account #,Institution,Month,Week,Transaction ID,Account ID,Check Number,Full Description,Date Added,Categorized Date,Custom Column
 ,10/20/2025,Whole Foods Market,Groceries,-$87.43,Credit Card 1,xxxx1234,Bank A,10/1/25,10/19/25,tx001a2b3c4d5e6f7g8h9i01,acct001a2b3c4d5e6f7g,,WHOLE FOODS MARKET,10/21/25,10/21/2025 9:15:30 AM,87.43
@@ -487,6 +782,14 @@ Coffee Shops,Starbucks,,,,,,,,,
 Gas & Fuel,Shell,,,,,,,,,
 "##;
 
+/// Seed Balance History data.
+const BALANCE_DATA: &str = r##"Date,Time,Account,Account #,Account ID,Balance ID,Institution,Balance,Month,Week,Type,Class,Account Status,Date Added
+10/20/2025,6:00:00 AM,Credit Card 1,xxxx1234,acct001a2b3c4d5e6f7g,bal001a2b3c4d5e6f7g01,Bank A,"-$1,024.37",10/1/25,10/19/25,Credit Card,Liability,Active,10/20/25
+10/20/2025,6:00:00 AM,Checking 1,xxxx5678,acct002a2b3c4d5e6f7g,bal001a2b3c4d5e6f7g02,Bank A,"$4,310.55",10/1/25,10/19/25,Checking,Asset,Active,10/20/25
+10/13/2025,6:00:00 AM,Credit Card 1,xxxx1234,acct001a2b3c4d5e6f7g,bal001a2b3c4d5e6f7g03,Bank A,-$812.64,10/1/25,10/12/25,Credit Card,Liability,Active,10/13/25
+10/13/2025,6:00:00 AM,Checking 1,xxxx5678,acct002a2b3c4d5e6f7g,bal001a2b3c4d5e6f7g04,Bank A,"$4,453.22",10/1/25,10/12/25,Checking,Asset,Active,10/13/25
+"##;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1,78 +1,254 @@
 //! Implements the `Tiller` trait for interacting with Google sheet data from a tiller sheet.
 
-use crate::api::{Sheet, SheetRange, Tiller, AUTO_CAT, CATEGORIES, TRANSACTIONS};
+use crate::api::{
+    tab_name, Sheet, SheetOperation, SheetRange, Tiller, WritePlan, AUTO_CAT, BALANCES, CATEGORIES,
+    TRANSACTIONS,
+};
 use crate::error::Res;
-use crate::model::{AutoCats, Categories, TillerData, Transactions};
+use crate::model::{
+    AutoCats, Balances, Categories, Category, Item, Items, RowCol, TillerData, Transaction,
+    TransactionColumn, Transactions,
+};
+use anyhow::Context;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::Debug;
+use tracing::info;
 
 /// Implements the `Tiller` trait for interacting with Google sheet data from a tiller sheet.
 pub(super) struct TillerImpl {
     sheet: Box<dyn Sheet + Send>,
+    /// Whether the Balance History tab is included when reading and writing.
+    sync_balances: bool,
+    /// Whether tabs are written before they are trimmed rather than cleared before they are
+    /// written.
+    atomic_write: bool,
+    /// Whether the header row of each tab is checked after its rows are cleared.
+    verify_headers: bool,
 }
 
 impl TillerImpl {
     /// Create a new `TillerImpl` object that will use a dynamically-dispatched `sheet` to get and
     /// send its data.
-    pub(super) async fn new(sheet: Box<dyn Sheet + Send>) -> Res<Self> {
-        Ok(Self { sheet })
+    pub(super) async fn new(
+        sheet: Box<dyn Sheet + Send>,
+        sync_balances: bool,
+        atomic_write: bool,
+        verify_headers: bool,
+    ) -> Res<Self> {
+        Ok(Self {
+            sheet,
+            sync_balances,
+            atomic_write,
+            verify_headers,
+        })
+    }
+
+    /// The header row of each tab that `ranges` refer to, for `check_headers`.
+    async fn get_headers(&mut self, ranges: &[&str]) -> Res<Vec<(String, Vec<String>)>> {
+        let mut headers: Vec<(String, Vec<String>)> = Vec::new();
+        for tab in ranges.iter().copied().map(tab_name) {
+            if !headers.iter().any(|(t, _)| t == tab) {
+                let header = self.sheet.get_header(tab).await?;
+                headers.push((tab.to_string(), header));
+            }
+        }
+        Ok(headers)
+    }
+
+    /// Returns an error if the header row of any tab in `expected`, from `get_headers`, has changed
+    /// since, e.g. because a clear range reached row 1.
+    async fn check_headers(
+        &mut self,
+        expected: &[(String, Vec<String>)],
+        ranges: &[&str],
+    ) -> Res<()> {
+        for (tab, header) in expected {
+            if &self.sheet.get_header(tab).await? != header {
+                anyhow::bail!(
+                    "The header row of the {tab} tab changed when the ranges {} were cleared, so \
+                    nothing more was written. Restore the tab from the copy of the sheet made \
+                    before sync up",
+                    ranges.join(", ")
+                );
+            }
+        }
+        Ok(())
     }
 }
 
 #[async_trait::async_trait]
 impl Tiller for TillerImpl {
     async fn get_data(&mut self) -> Res<TillerData> {
-        // Fetch data from all three tabs
+        // Fetch data from all tabs
         let transactions = fetch_transactions(self.sheet.as_mut()).await?;
         let categories = fetch_categories(self.sheet.as_mut()).await?;
         let auto_cats = fetch_auto_cats(self.sheet.as_mut()).await?;
+        let balances = if self.sync_balances {
+            fetch_balances(self.sheet.as_mut()).await?
+        } else {
+            Balances::default()
+        };
 
         Ok(TillerData {
             transactions,
             categories,
             auto_cats,
+            balances,
         })
     }
 
+    async fn check_tabs(&mut self) -> Res<()> {
+        let mut required = vec![TRANSACTIONS, CATEGORIES, AUTO_CAT];
+        if self.sync_balances {
+            required.push(BALANCES);
+        }
+        let found = self.sheet.list_tabs().await?;
+        let missing: Vec<&str> = required
+            .into_iter()
+            .filter(|tab| !found.iter().any(|f| f == tab))
+            .collect();
+        if !missing.is_empty() {
+            anyhow::bail!(
+                "The sheet has no {} tab{}. Found the tabs: {}. Check that sheet_url points to \
+                your Tiller sheet and that its tabs have not been renamed",
+                missing.join(", "),
+                if missing.len() == 1 { "" } else { "s" },
+                found.join(", ")
+            );
+        }
+        Ok(())
+    }
+
     async fn copy_spreadsheet(&mut self, new_name: &str) -> Res<String> {
         self.sheet.copy_spreadsheet(new_name).await
     }
 
-    async fn clear_and_write_data(&mut self, data: &TillerData) -> Res<()> {
-        // Clear each tab entirely (headers and data)
-        let clear_ranges = [
-            &format!("{TRANSACTIONS}!A1:ZZ"),
-            &format!("{CATEGORIES}!A1:ZZ"),
-            &format!("{AUTO_CAT}!A1:ZZ"),
+    fn write_plan(&self, data: &TillerData) -> Res<WritePlan> {
+        // Balances are only written when enabled and when we have data for them, otherwise a
+        // database that has never synced balances down would wipe the tab.
+        let write_balances = self.sync_balances && !data.balances.mapping().headers().is_empty();
+
+        // All rows (header + data) for each tab. Categories are written first so that if a write
+        // fails part way, the tabs already written never refer to a category the sheet lacks.
+        let mut tabs = vec![
+            (CATEGORIES, data.categories.to_rows()?),
+            (AUTO_CAT, data.auto_cats.to_rows()?),
+            (TRANSACTIONS, data.transactions.to_rows()?),
         ];
-        self.sheet
-            .clear_ranges(&clear_ranges.map(|s| s.as_str()))
-            .await?;
-
-        // Build write data for all three sheets (headers + data in one operation each)
-        let mut write_data = Vec::new();
-
-        // Transactions - all rows (header + data)
-        let txn_data = data.transactions.to_rows()?;
-        write_data.push(SheetRange {
-            range: format!("{TRANSACTIONS}!A1:ZZ"),
-            values: txn_data,
-        });
-
-        // Categories - all rows (header + data)
-        let cat_data = data.categories.to_rows()?;
-        write_data.push(SheetRange {
-            range: format!("{CATEGORIES}!A1:ZZ"),
-            values: cat_data,
-        });
-
-        // AutoCat - all rows (header + data)
-        let aut_data = data.auto_cats.to_rows()?;
-        write_data.push(SheetRange {
-            range: format!("{AUTO_CAT}!A1:ZZ"),
-            values: aut_data,
-        });
-
-        self.sheet.write_ranges(&write_data).await?;
+        if write_balances {
+            tabs.push((BALANCES, data.balances.to_rows()?));
+        }
+
+        // The data rows of each tab are cleared, keeping the header row, and the tab is rewritten
+        // from row 1 in one operation, but only as far right as its headers go so that columns
+        // users have added beyond them survive. With atomic writes, the tab is instead overwritten
+        // in place and only the rows below the new data are cleared afterwards.
+        let mut clear_ranges = Vec::new();
+        let mut write_ranges = Vec::new();
+        for (tab, values) in tabs {
+            let width = values.first().map(Vec::len).unwrap_or_default();
+            let range = tab_range(tab, width);
+            if self.atomic_write {
+                clear_ranges.push(trim_range(tab, width, values.len()));
+            } else {
+                clear_ranges.push(trim_range(tab, width, 1));
+            }
+            write_ranges.push(SheetRange { range, values });
+        }
+
+        Ok(WritePlan {
+            clear_ranges,
+            write_ranges,
+            clear_after_write: self.atomic_write,
+        })
+    }
+
+    async fn apply_plan(&mut self, plan: &WritePlan) -> Res<()> {
+        for operation in plan.operations() {
+            match operation {
+                SheetOperation::Clear(ranges) => {
+                    let ranges: Vec<&str> = ranges.iter().map(String::as_str).collect();
+                    let headers = if self.verify_headers {
+                        self.get_headers(&ranges).await?
+                    } else {
+                        Vec::new()
+                    };
+                    self.sheet.clear_ranges(&ranges).await?;
+                    self.check_headers(&headers, &ranges).await?;
+                }
+                SheetOperation::Write(ranges) => self.sheet.write_ranges(ranges).await?,
+            }
+        }
+        Ok(())
+    }
+
+    fn append_plan(
+        &self,
+        current: &TillerData,
+        categories: &[Category],
+        transactions: &[Transaction],
+    ) -> Res<WritePlan> {
+        // Categories go first so that the new transactions never refer to one the sheet lacks
+        let write_ranges = [
+            append_range(CATEGORIES, &current.categories, categories)?,
+            append_range(TRANSACTIONS, &current.transactions, transactions)?,
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        Ok(WritePlan {
+            write_ranges,
+            ..Default::default()
+        })
+    }
 
+    fn columns_plan(
+        &self,
+        transactions: &Transactions,
+        columns: &[TransactionColumn],
+    ) -> Res<WritePlan> {
+        let headers = transactions.mapping().headers();
+        let mut write_ranges = Vec::new();
+        for column in columns {
+            let header = column.to_header();
+            let Some(col) = headers.iter().position(|h| h.as_ref() == header) else {
+                anyhow::bail!("The {TRANSACTIONS} tab has no '{header}' column to write");
+            };
+            if transactions.data().is_empty() {
+                continue;
+            }
+
+            // The headers take the first row, so the data starts in the second
+            let values: Vec<Vec<String>> = transactions
+                .data()
+                .iter()
+                .map(|t| vec![t.get_by_header(header)])
+                .collect();
+            let range = format!(
+                "{}:{}{}",
+                SheetRange::cell(TRANSACTIONS, 1, col),
+                col_to_a1(col),
+                values.len() + 1
+            );
+            write_ranges.push(SheetRange { range, values });
+        }
+        Ok(WritePlan {
+            write_ranges,
+            ..Default::default()
+        })
+    }
+
+    async fn append_transactions(
+        &mut self,
+        current: &TillerData,
+        categories: &[Category],
+        transactions: &[Transaction],
+    ) -> Res<()> {
+        let plan = self.append_plan(current, categories, transactions)?;
+        if !plan.write_ranges.is_empty() {
+            self.sheet.write_ranges(&plan.write_ranges).await?;
+        }
         Ok(())
     }
 
@@ -114,27 +290,422 @@ impl Tiller for TillerImpl {
             );
         }
 
+        // Balances are only checked if `write_plan` would have written them
+        let wrote_balances =
+            self.sync_balances && !expected.balances.mapping().headers().is_empty();
+        let expected_bal = expected.balances.data().len();
+        let actual_bal = actual.balances.data().len();
+        if wrote_balances && actual_bal != expected_bal {
+            bail!(
+                "Verification failed: expected {} balances, found {}",
+                expected_bal,
+                actual_bal
+            );
+        }
+
         Ok((actual_txn, actual_cat, actual_ac))
     }
 }
 
+/// Returns the A1 range covering the first `width` columns of `tab`, e.g. `Transactions!A1:Q` for
+/// 17 columns.
+fn tab_range(tab: &str, width: usize) -> String {
+    format!("{tab}!A1:{}", column_letters(width.max(1)))
+}
+
+/// Returns the A1 range of the first `width` columns of `tab` below its first `rows` rows, e.g.
+/// `Transactions!A22:Q` for 17 columns and 21 rows.
+fn trim_range(tab: &str, width: usize, rows: usize) -> String {
+    format!("{tab}!A{}:{}", rows + 1, column_letters(width.max(1)))
+}
+
+/// Returns the range that writes `rows` below the last row of `tab`, in the column order of
+/// `current`, which is the tab as last fetched from the sheet. Returns `None` when there are no
+/// rows to write.
+fn append_range<I>(tab: &str, current: &Items<I>, rows: &[I]) -> Res<Option<SheetRange>>
+where
+    I: Default + Debug + Clone + Eq + PartialEq + Serialize + DeserializeOwned + Item,
+{
+    if rows.is_empty() {
+        return Ok(None);
+    }
+    let headers: Vec<String> = current
+        .mapping()
+        .headers()
+        .iter()
+        .map(|h| h.as_ref().to_string())
+        .collect();
+    if headers.is_empty() {
+        anyhow::bail!("The {tab} tab has no header row to append below");
+    }
+
+    // The headers take the first row, so the first free row follows the last data row
+    let values = rows.iter().map(|row| row.to_row(&headers)).collect();
+    Ok(Some(SheetRange::for_values(
+        tab,
+        current.data().len() + 1,
+        values,
+    )))
+}
+
+/// Converts a 1-based column number to its A1 letters, e.g. 1 is `A`, 26 is `Z` and 27 is `AA`.
+pub(super) fn column_letters(mut column: usize) -> String {
+    let mut letters = Vec::new();
+    while column > 0 {
+        let remainder = (column - 1) % 26;
+        letters.push(char::from(b'A' + remainder as u8));
+        column = (column - 1) / 26;
+    }
+    letters.iter().rev().collect()
+}
+
+/// Converts a 0-based column index to its A1 letters, e.g. 0 is `A`, 25 is `Z` and 26 is `AA`.
+pub(super) fn col_to_a1(col: usize) -> String {
+    column_letters(col + 1)
+}
+
+/// Converts A1 column letters to a 0-based column index, e.g. `A` is 0, `Z` is 25 and `AA` is 26.
+/// Lowercase letters are accepted.
+///
+/// # Errors
+/// - Returns an error if `letters` is empty, has anything other than ASCII letters, or is too long
+///   to be a column.
+pub(super) fn a1_to_col(letters: &str) -> Res<usize> {
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        anyhow::bail!("Invalid A1 column '{letters}', expected letters such as 'A' or 'AB'");
+    }
+    // Bijective base 26, where A is 1 and Z is 26
+    let column = letters.bytes().try_fold(0usize, |column, b| {
+        let digit = usize::from(b.to_ascii_uppercase() - b'A') + 1;
+        column.checked_mul(26)?.checked_add(digit)
+    });
+    match column {
+        Some(column) => Ok(column - 1),
+        None => anyhow::bail!("The A1 column '{letters}' is too large"),
+    }
+}
+
+/// Parses an A1 cell reference into 0-based indices, e.g. `A1` is `(0, 0)` and `AB10` is `(9, 27)`.
+/// A tab name, e.g. `Transactions!AB10`, and absolute markers, e.g. `$AB$10`, are allowed and
+/// ignored.
+///
+/// # Errors
+/// - Returns an error if `a1` is not a column of letters followed by a row number of 1 or more.
+pub(super) fn a1_to_rowcol(a1: &str) -> Res<RowCol> {
+    let cell = a1.rsplit_once('!').map_or(a1, |(_, cell)| cell);
+    let cell = cell.strip_prefix('$').unwrap_or(cell);
+    let split = cell
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(cell.len());
+    let (letters, row) = cell.split_at(split);
+    let row = row.strip_prefix('$').unwrap_or(row);
+    let row = match row.parse::<usize>() {
+        Ok(row) if row > 0 && row.bytes().all(|b| b.is_ascii_digit()) => row,
+        _ => anyhow::bail!("Invalid A1 cell reference '{a1}', expected e.g. 'A1' or 'AB10'"),
+    };
+    let col = a1_to_col(letters).with_context(|| format!("Invalid A1 cell reference '{a1}'"))?;
+    Ok(RowCol::new(row - 1, col))
+}
+
 /// Fetches transaction data from the Transactions tab
 async fn fetch_transactions(client: &mut (dyn Sheet + Send)) -> Res<Transactions> {
-    let values = client.get(TRANSACTIONS).await?;
+    let values = client.get_formatted_values(TRANSACTIONS).await?;
     let formulas = client.get_formulas(TRANSACTIONS).await?;
-    Transactions::parse(values, formulas)
+    let transactions = Transactions::parse(values, formulas)?;
+    info!(
+        "Fetched {TRANSACTIONS} ({} rows)",
+        transactions.data().len()
+    );
+    Ok(transactions)
 }
 
 /// Fetches category data from the Categories tab
 async fn fetch_categories(client: &mut (dyn Sheet + Send)) -> Res<Categories> {
-    let values = client.get(CATEGORIES).await?;
+    let values = client.get_formatted_values(CATEGORIES).await?;
     let formulas = client.get_formulas(CATEGORIES).await?;
-    Categories::parse(values, formulas)
+    let categories = Categories::parse(values, formulas)?;
+    info!("Fetched {CATEGORIES} ({} rows)", categories.data().len());
+    Ok(categories)
 }
 
 /// Fetches AutoCat data from the AutoCat tab
 async fn fetch_auto_cats(client: &mut (dyn Sheet + Send)) -> Res<AutoCats> {
-    let values = client.get(AUTO_CAT).await?;
+    let values = client.get_formatted_values(AUTO_CAT).await?;
     let formulas = client.get_formulas(AUTO_CAT).await?;
-    AutoCats::parse(values, formulas)
+    let auto_cats = AutoCats::parse(values, formulas)?;
+    info!("Fetched {AUTO_CAT} ({} rows)", auto_cats.data().len());
+    Ok(auto_cats)
+}
+
+/// Fetches balance data from the Balance History tab
+async fn fetch_balances(client: &mut (dyn Sheet + Send)) -> Res<Balances> {
+    let values = client.get_formatted_values(BALANCES).await?;
+    let formulas = client.get_formulas(BALANCES).await?;
+    let balances = Balances::parse(values, formulas)?;
+    info!("Fetched {BALANCES} ({} rows)", balances.data().len());
+    Ok(balances)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{SheetCall, TestSheet};
+
+    #[test]
+    fn test_col_to_a1() {
+        for (col, letters) in [
+            (0, "A"),
+            (25, "Z"),
+            (26, "AA"),
+            (51, "AZ"),
+            (52, "BA"),
+            (701, "ZZ"),
+            (702, "AAA"),
+        ] {
+            assert_eq!(col_to_a1(col), letters);
+            assert_eq!(a1_to_col(letters).unwrap(), col);
+        }
+    }
+
+    #[test]
+    fn test_a1_to_col_round_trip() {
+        for col in 0..20_000 {
+            assert_eq!(a1_to_col(&col_to_a1(col)).unwrap(), col);
+        }
+        assert_eq!(a1_to_col("ab").unwrap(), 27);
+        for invalid in ["", "1", "A1", "A-", " A"] {
+            assert!(a1_to_col(invalid).is_err(), "{invalid}");
+        }
+        assert!(a1_to_col(&"Z".repeat(20)).is_err());
+    }
+
+    #[test]
+    fn test_a1_to_rowcol() {
+        for (a1, row, col) in [
+            ("A1", 0, 0),
+            ("Z1", 0, 25),
+            ("AA1", 0, 26),
+            ("AB10", 9, 27),
+            ("$AB$10", 9, 27),
+            ("Transactions!AB10", 9, 27),
+        ] {
+            assert_eq!(a1_to_rowcol(a1).unwrap(), RowCol::new(row, col), "{a1}");
+        }
+        let cell = SheetRange::cell(TRANSACTIONS, 41, 702);
+        assert_eq!(a1_to_rowcol(&cell).unwrap(), RowCol::new(41, 702));
+
+        for invalid in ["1A", "", "A", "10", "A0", "A1B", "A-1", "A+1", "A1:B2"] {
+            assert!(a1_to_rowcol(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_sheet_range_helpers() {
+        assert_eq!(SheetRange::cell(TRANSACTIONS, 0, 0), "Transactions!A1");
+        assert_eq!(SheetRange::cell(TRANSACTIONS, 4, 27), "Transactions!AB5");
+
+        let values = vec![vec!["a".to_string(); 17], vec!["b".to_string(); 3]];
+        let range = SheetRange::for_values(TRANSACTIONS, 21, values.clone());
+        assert_eq!(range.range, "Transactions!A22:Q23");
+        assert_eq!(range.values, values);
+    }
+
+    #[test]
+    fn test_column_letters() {
+        assert_eq!(column_letters(1), "A");
+        assert_eq!(column_letters(17), "Q");
+        assert_eq!(column_letters(26), "Z");
+        assert_eq!(column_letters(27), "AA");
+        assert_eq!(column_letters(52), "AZ");
+        assert_eq!(column_letters(702), "ZZ");
+        assert_eq!(column_letters(703), "AAA");
+    }
+
+    #[tokio::test]
+    async fn test_columns_plan_writes_one_range_per_column() {
+        let sheet = Box::new(TestSheet::new_with_seed_data(
+            "test_columns_plan_writes_one_range_per_column",
+        ));
+        let mut tiller = TillerImpl::new(sheet, false, false, true).await.unwrap();
+        let data = tiller.get_data().await.unwrap();
+
+        let columns = [TransactionColumn::Category, TransactionColumn::AccountId];
+        let plan = tiller.columns_plan(&data.transactions, &columns).unwrap();
+        assert!(plan.clear_ranges.is_empty());
+        let ranges: Vec<&str> = plan.write_ranges.iter().map(|r| r.range.as_str()).collect();
+        assert_eq!(ranges, vec!["Transactions!D2:D21", "Transactions!L2:L21"]);
+        assert_eq!(
+            plan.write_ranges[0].values[0],
+            vec!["Groceries".to_string()]
+        );
+        assert!(plan.write_ranges.iter().all(|r| r.values.len() == 20));
+
+        // The seeded sheet has no Tags column
+        assert!(tiller
+            .columns_plan(&data.transactions, &[TransactionColumn::Tags])
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_write_plan_ranges_match_header_width() {
+        let sheet = Box::new(TestSheet::new_with_seed_data(
+            "test_write_plan_ranges_match_header_width",
+        ));
+        let mut tiller = TillerImpl::new(sheet, true, false, true).await.unwrap();
+        let data = tiller.get_data().await.unwrap();
+
+        let plan = tiller.write_plan(&data).unwrap();
+        // The seeded tabs have 16, 11, 17 and 14 columns, and their header rows are not cleared
+        let expected = vec![
+            "Categories!A1:P",
+            "AutoCat!A1:K",
+            "Transactions!A1:Q",
+            "Balance History!A1:N",
+        ];
+        let cleared: Vec<String> = expected.iter().map(|r| r.replace("A1", "A2")).collect();
+        assert_eq!(plan.clear_ranges, cleared);
+        let written: Vec<&str> = plan.write_ranges.iter().map(|r| r.range.as_str()).collect();
+        assert_eq!(written, expected);
+        for range in &plan.write_ranges {
+            assert!(range
+                .values
+                .iter()
+                .all(|row| row.len() <= range.values[0].len()));
+        }
+
+        assert_eq!(
+            plan.tabs(),
+            vec!["Categories", "AutoCat", "Transactions", "Balance History"]
+        );
+        let balances = plan.filter_tabs(|tab| tab == "Balance History");
+        assert_eq!(balances.clear_ranges, vec!["Balance History!A2:N"]);
+        assert_eq!(balances.write_ranges.len(), 1);
+        assert_eq!(balances.write_ranges[0], plan.write_ranges[3]);
+        assert_eq!(balances.clear_after_write, plan.clear_after_write);
+    }
+
+    #[test]
+    fn test_write_plan_operations_order() {
+        let mut plan = WritePlan {
+            clear_ranges: vec!["AutoCat!A4:K".to_string()],
+            write_ranges: vec![SheetRange {
+                range: "AutoCat!A1:K".to_string(),
+                values: vec![vec!["Category".to_string()]],
+            }],
+            clear_after_write: false,
+        };
+        assert_eq!(
+            plan.operations(),
+            vec![
+                SheetOperation::Clear(&plan.clear_ranges),
+                SheetOperation::Write(&plan.write_ranges)
+            ]
+        );
+
+        plan.clear_after_write = true;
+        assert_eq!(
+            plan.operations(),
+            vec![
+                SheetOperation::Write(&plan.write_ranges),
+                SheetOperation::Clear(&plan.clear_ranges)
+            ]
+        );
+
+        // Nothing to clear, e.g. when appending
+        plan.clear_ranges.clear();
+        assert_eq!(
+            plan.operations(),
+            vec![SheetOperation::Write(&plan.write_ranges)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_apply_plan_catches_a_clear_that_reaches_the_header_row() {
+        let name = "test_apply_plan_catches_a_clear_that_reaches_the_header_row";
+        let test_sheet = TestSheet::new_with_seed_data(name);
+        let mut tiller = TillerImpl::new(Box::new(test_sheet.clone()), false, false, true)
+            .await
+            .unwrap();
+        let data = tiller.get_data().await.unwrap();
+        let mut plan = tiller
+            .write_plan(&data)
+            .unwrap()
+            .filter_tabs(|tab| tab == TRANSACTIONS);
+
+        // The planned clear keeps the header row
+        tiller.apply_plan(&plan).await.unwrap();
+        assert_eq!(test_sheet.get_state().data[TRANSACTIONS].len(), 21);
+
+        // A clear range that wrongly starts at row 1 is caught before anything is written
+        plan.clear_ranges = vec!["Transactions!A1:Q".to_string()];
+        test_sheet.clear_history();
+        let err = tiller.apply_plan(&plan).await.unwrap_err().to_string();
+        assert!(
+            err.contains("The header row of the Transactions tab changed"),
+            "{err}"
+        );
+        assert!(!test_sheet
+            .call_history()
+            .iter()
+            .any(|c| matches!(c, SheetCall::WriteRanges { .. })));
+        assert!(test_sheet.get_state().data[TRANSACTIONS].is_empty());
+
+        // Without the verification the same plan goes ahead
+        let mut unverified = TillerImpl::new(Box::new(test_sheet.clone()), false, false, false)
+            .await
+            .unwrap();
+        unverified.apply_plan(&plan).await.unwrap();
+        assert_eq!(test_sheet.get_state().data[TRANSACTIONS].len(), 21);
+    }
+
+    #[tokio::test]
+    async fn test_atomic_write_trims_after_writing() {
+        let name = "test_atomic_write_trims_after_writing";
+        let test_sheet = TestSheet::new_with_seed_data(name);
+        let mut tiller = TillerImpl::new(Box::new(test_sheet.clone()), false, true, true)
+            .await
+            .unwrap();
+        let mut data = tiller.get_data().await.unwrap();
+        // Drop 3 of the 20 seeded transactions so that stale rows are left below the new data
+        data.transactions.data_mut().truncate(17);
+        test_sheet.clear_history();
+
+        let plan = tiller.write_plan(&data).unwrap();
+        tiller.apply_plan(&plan).await.unwrap();
+
+        let calls: Vec<SheetCall> = test_sheet
+            .call_history()
+            .into_iter()
+            .filter(|c| {
+                matches!(
+                    c,
+                    SheetCall::ClearRanges { .. } | SheetCall::WriteRanges { .. }
+                )
+            })
+            .collect();
+        assert_eq!(calls.len(), 2, "{calls:?}");
+        let SheetCall::WriteRanges { ranges } = &calls[0] else {
+            panic!("Expected the tabs to be written first: {calls:?}");
+        };
+        let written: Vec<&str> = ranges.iter().map(|(range, _)| range.as_str()).collect();
+        assert_eq!(
+            written,
+            vec!["Categories!A1:P", "AutoCat!A1:K", "Transactions!A1:Q"]
+        );
+        assert_eq!(
+            calls[1],
+            SheetCall::ClearRanges {
+                ranges: vec![
+                    "Categories!A7:P".to_string(),
+                    "AutoCat!A5:K".to_string(),
+                    "Transactions!A19:Q".to_string(),
+                ]
+            }
+        );
+
+        let state = test_sheet.get_state();
+        assert_eq!(state.data["Transactions"].len(), 18);
+        assert_eq!(state.data["Categories"].len(), 6);
+        assert_eq!(state.data["AutoCat"].len(), 4);
+    }
 }
@@ -1,8 +1,10 @@
 //! These structs provide the CLI interface for the tiller CLI.
 
-use crate::commands::FormulasMode;
+use crate::commands::{FormulasMode, SyncUpStrategy};
 use crate::error::{ErrorType, IntoResult};
-use crate::model::{Amount, AutoCatUpdates, CategoryUpdates, TransactionUpdates};
+use crate::model::{
+    Amount, AutoCatUpdates, CategoryUpdates, TransactionColumn, TransactionUpdates,
+};
 use crate::utils;
 use crate::Result;
 use anyhow::anyhow;
@@ -11,7 +13,9 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::convert::Infallible;
+use std::ffi::OsString;
 use std::fmt::{Display, Formatter};
+use std::io::IsTerminal;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
@@ -73,6 +77,10 @@ pub enum Command {
     Init(InitArgs),
     /// Authenticate with Google Sheets via OAuth.
     Auth(AuthArgs),
+    /// Show which Google account is authorized and which Tiller sheet is synced.
+    ///
+    /// Use this to check that you authorized the right account if you have more than one.
+    Whoami(WhoamiArgs),
     /// Upload or Download Transactions, Categories and AutoCat tabs to/from your Tiller Sheet.
     Sync(SyncArgs),
     /// Run as an MCP (Model Context Protocol) server for AI agent integration.
@@ -95,8 +103,120 @@ pub enum Command {
     ///
     /// Returns tables, columns, types, indexes, foreign keys, column descriptions, and row counts.
     Schema(SchemaArgs),
+    /// Display the summed transaction amounts for each institution.
+    ///
+    /// Transactions with no institution are grouped under "(unknown)".
+    InstitutionTotals(InstitutionTotalsArgs),
+    /// List transactions from the local database, optionally filtered and paginated.
+    ///
+    /// The output includes the total number of matching transactions, so you can tell whether
+    /// there are more pages after the one returned.
+    ListTransactions(ListTransactionsArgs),
+    /// List the AutoCat rules in the order Tiller processes them.
+    ///
+    /// When more than one rule matches a transaction, the first one listed wins. Rules added
+    /// locally are listed after the rules from the sheet.
+    ListAutocats(ListAutocatsArgs),
+    /// Move every transaction from one category to another.
+    ///
+    /// Changes are made locally only. Use `sync up` to upload local changes to the Google Sheet.
+    Recategorize(RecategorizeArgs),
+    /// Merge one or more categories into another category.
+    ///
+    /// The transactions and AutoCat rules of the merged categories are moved to the target
+    /// category, then the merged categories are deleted. Changes are made locally only. Use
+    /// `sync up` to upload local changes to the Google Sheet.
+    MergeCategories(MergeCategoriesArgs),
+    /// Apply the AutoCat rules to every transaction that has no category.
+    ///
+    /// The first rule that matches a transaction gives it its category, as in Tiller. Transactions
+    /// that already have a category are not changed. Changes are made locally only. Use `sync up`
+    /// to upload local changes to the Google Sheet.
+    CategorizeUncategorized(CategorizeUncategorizedArgs),
+    /// Move an AutoCat rule to a new position in the processing order.
+    ///
+    /// Changes are made locally only. Use `sync up` to upload local changes to the Google Sheet.
+    MoveAutocat(MoveAutocatArgs),
+    /// List every formula found in the Transactions tab of the Google sheet.
+    ///
+    /// For each formula, the (row, column) coordinate under which it is tracked, its header, the
+    /// formula itself and the computed value are reported. Use this to debug
+    /// `sync up --formulas preserve`.
+    DiagnoseFormulas(DiagnoseFormulasArgs),
+    /// Back up the local database without syncing.
+    ///
+    /// Copies the SQLite file and writes a JSON snapshot of the database contents to the backups
+    /// directory.
+    CreateBackup(CreateBackupArgs),
+    /// Compare two JSON backup snapshots from the backups directory.
+    ///
+    /// Reports the transactions, categories and AutoCat rules that were added, deleted or
+    /// modified going from the first snapshot to the second.
+    Diff(DiffArgs),
+    /// List transaction fields that were changed in the Google sheet since the last sync down.
+    ///
+    /// Each conflict shows the value at the last sync down, the value now in the sheet, and the
+    /// value in the local database. A `sync up` would overwrite the sheet's value.
+    ListConflicts(ListConflictsArgs),
+    /// Resolve one conflict reported by `list-conflicts` by choosing the local or remote value.
+    ///
+    /// Choosing `remote` copies the sheet's value into the local database. Choosing `local` keeps
+    /// the local value, which the next `sync up` writes to the sheet.
+    ResolveConflict(ResolveConflictArgs),
+    /// Compact the local SQLite database and refresh its query planner statistics.
+    ///
+    /// Repeated syncs delete and re-insert every row, which leaves unused pages in the database
+    /// file. This reclaims them and reports the file size before and after.
+    Maintenance(MaintenanceArgs),
+    /// List the most recent sync ups and sync downs, newest first.
+    ///
+    /// Each entry shows when the sync finished, its direction, the number of rows synced for each
+    /// tab, and whether it succeeded. Dry runs are not recorded.
+    SyncHistory(SyncHistoryArgs),
+    /// Check the local database for problems, without changing anything.
+    ///
+    /// Reports transactions whose category does not exist, amounts that are not numbers, dates
+    /// that cannot be parsed, and transaction IDs that appear more than once in the last sync down.
+    Validate(ValidateArgs),
+    /// Merge transactions inserted locally into the transactions Tiller later added for them.
+    ///
+    /// Run this after a sync down. A locally inserted transaction, with a `user-` ID, is merged
+    /// into the one transaction from Tiller with the same date, amount, description and account.
+    /// The merged transaction keeps Tiller's ID, and the category, note and tags entered locally
+    /// where Tiller left them blank. Changes are made locally only. Use `sync up` to upload them.
+    ReconcileIds(ReconcileIdsArgs),
 }
 
+/// The format in which log lines are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines.
+    #[default]
+    Text,
+    /// One JSON object per line.
+    Json,
+}
+
+serde_plain::derive_display_from_serialize!(LogFormat);
+serde_plain::derive_fromstr_from_deserialize!(LogFormat);
+
+/// The format in which the results of list and query commands are printed to stdout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// Columns aligned for reading in a terminal.
+    #[default]
+    Table,
+    /// Pretty-printed JSON.
+    Json,
+    /// Comma-separated values with a header row.
+    Csv,
+}
+
+serde_plain::derive_display_from_serialize!(Format);
+serde_plain::derive_fromstr_from_deserialize!(Format);
+
 /// Arguments common to all subcommands.
 #[derive(Debug, Parser, Clone)]
 pub struct Common {
@@ -107,25 +227,130 @@ pub struct Common {
     #[arg(long, default_value_t = LevelFilter::INFO)]
     log_level: LevelFilter,
 
-    /// The directory where tiller data and configuration is held. Defaults to ~/tiller
-    #[arg(long, env = "TILLER_HOME", default_value_t = default_tiller_home())]
-    tiller_home: DisplayPath,
+    /// Suppress all output except errors, regardless of --log-level. The results of list and
+    /// query commands are not printed either. Useful when running from cron. The exit code still
+    /// reports whether the command succeeded.
+    #[arg(long)]
+    quiet: bool,
+
+    /// The format of log lines written to stderr: text or json. Use json to emit one JSON object
+    /// per line for ingestion into a log aggregator.
+    #[arg(long, env = "LOG_FORMAT", value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
+    /// Do not color log lines. Color is also disabled when the NO_COLOR environment variable is
+    /// set to a non-empty value, or when stderr is not a terminal, e.g. when piped to a file.
+    #[arg(long)]
+    no_color: bool,
+
+    /// How the results of list and query commands are printed to stdout: table, json or csv.
+    /// Nested fields become columns named `outer.inner` in a table or CSV.
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    output: Format,
+
+    /// The directory where tiller data and configuration is held. In order of precedence, this is
+    /// the value of this flag, the TILLER_HOME environment variable, or ~/tiller.
+    #[arg(long)]
+    tiller_home: Option<DisplayPath>,
+
+    /// The path to a config file to use instead of $TILLER_HOME/config.json. When this is given,
+    /// the directory containing the config file is treated as the tiller home directory, so the
+    /// database, backups and secrets are found alongside it.
+    #[arg(long)]
+    config_file: Option<PathBuf>,
+
+    /// The ID or URL of a Google Sheet to use instead of the one in the config file, for this
+    /// invocation only. Useful for working with a copy of your sheet.
+    #[arg(long)]
+    spreadsheet_id: Option<String>,
+
+    /// Report what a command would change without changing anything. Database changes are rolled
+    /// back, no backups are written and the Google sheet is not modified. This applies to every
+    /// command, including those that have their own --dry-run flag.
+    #[arg(long)]
+    dry_run: bool,
 }
 
 impl Common {
-    pub fn new(log_level: LevelFilter, tiller_home: PathBuf) -> Self {
+    pub fn new(log_level: LevelFilter, tiller_home: PathBuf, config_file: Option<PathBuf>) -> Self {
         Self {
             log_level,
-            tiller_home: tiller_home.into(),
+            quiet: false,
+            log_format: LogFormat::default(),
+            no_color: false,
+            output: Format::default(),
+            tiller_home: Some(tiller_home.into()),
+            config_file,
+            spreadsheet_id: None,
+            dry_run: false,
         }
     }
 
+    /// The level at which to log. This is `error` or quieter when `--quiet` is given.
     pub fn log_level(&self) -> LevelFilter {
-        self.log_level
+        if self.quiet {
+            self.log_level.min(LevelFilter::ERROR)
+        } else {
+            self.log_level
+        }
+    }
+
+    pub fn quiet(&self) -> bool {
+        self.quiet
+    }
+
+    pub fn log_format(&self) -> LogFormat {
+        self.log_format
+    }
+
+    /// The format in which list and query results are printed.
+    pub fn output(&self) -> Format {
+        self.output
+    }
+
+    /// Whether log lines written to stderr may be colored with ANSI escape sequences.
+    pub fn color(&self) -> bool {
+        use_color(
+            self.no_color,
+            std::env::var_os("NO_COLOR"),
+            std::io::stderr().is_terminal(),
+        )
     }
 
-    pub fn tiller_home(&self) -> &DisplayPath {
-        &self.tiller_home
+    /// The tiller home directory given by `--tiller-home`, `TILLER_HOME` or the default.
+    pub fn tiller_home(&self) -> DisplayPath {
+        resolve_tiller_home(self.tiller_home.as_ref(), std::env::var_os("TILLER_HOME"))
+    }
+
+    pub fn config_file(&self) -> Option<&Path> {
+        self.config_file.as_deref()
+    }
+
+    /// The spreadsheet ID or URL that overrides the one in the config file, if any.
+    pub fn spreadsheet_id(&self) -> Option<&str> {
+        self.spreadsheet_id.as_deref()
+    }
+
+    /// Whether every command should only report what it would change.
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+}
+
+/// Color is used only when it was not turned off with `--no-color` or a non-empty `NO_COLOR`
+/// (see <https://no-color.org>), and the output is going to a terminal.
+fn use_color(no_color_flag: bool, no_color_env: Option<OsString>, is_terminal: bool) -> bool {
+    let no_color_env = no_color_env.is_some_and(|v| !v.is_empty());
+    !no_color_flag && !no_color_env && is_terminal
+}
+
+/// The tiller home is the `--tiller-home` flag if it was given, otherwise a non-empty
+/// `TILLER_HOME`, otherwise ~/tiller.
+fn resolve_tiller_home(flag: Option<&DisplayPath>, env: Option<OsString>) -> DisplayPath {
+    match (flag, env) {
+        (Some(flag), _) => flag.clone(),
+        (None, Some(env)) if !env.is_empty() => DisplayPath(PathBuf::from(env)),
+        _ => default_tiller_home(),
     }
 }
 
@@ -141,6 +366,11 @@ pub struct InitArgs {
     /// default secrets location in the main data directory.
     #[arg(long)]
     client_secret: PathBuf,
+
+    /// Print the paths that would be created and the spreadsheet ID extracted from the sheet URL
+    /// without creating anything.
+    #[arg(long)]
+    dry_run: bool,
 }
 
 impl InitArgs {
@@ -148,6 +378,7 @@ impl InitArgs {
         Self {
             sheet_url: sheet_url.into(),
             client_secret: secret.into(),
+            dry_run: false,
         }
     }
 
@@ -158,6 +389,10 @@ impl InitArgs {
     pub fn client_secret(&self) -> &Path {
         &self.client_secret
     }
+
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
 }
 
 #[derive(Debug, Default, Copy, Clone, Serialize, Deserialize)]
@@ -166,6 +401,8 @@ pub enum UpDown {
     Up,
     #[default]
     Down,
+    /// Upload local changes, if there are any, then download the sheet.
+    Both,
 }
 
 serde_plain::derive_display_from_serialize!(UpDown);
@@ -192,7 +429,7 @@ impl AuthArgs {
 /// (Not shown): Args for the `tiller sync` command.
 #[derive(Debug, Parser, Clone)]
 pub struct SyncArgs {
-    /// The direction to sync: "up" or "down"
+    /// The direction to sync: "up", "down" or "both"
     direction: UpDown,
 
     /// The path to the OAuth 2.0 client credentials file, defaults to $TILLER_HOME/.secrets/client_secret.json
@@ -211,6 +448,32 @@ pub struct SyncArgs {
     /// - ignore: Skip all formulas, only write values
     #[arg(long, value_enum, default_value_t = FormulasMode::Unknown)]
     formulas: FormulasMode,
+
+    /// How sync up writes to the sheet: replace or append-only.
+    /// - replace: Clear each tab and rewrite it from the local database (default)
+    /// - append-only: Only add locally created (`user-`) transactions below the last row
+    #[arg(long, value_enum, default_value_t = SyncUpStrategy::Replace)]
+    strategy: SyncUpStrategy,
+
+    /// Only write these columns of the Transactions tab during sync up, e.g. `category,note`. The
+    /// other columns, rows that are not in the local datastore and the other tabs keep what the
+    /// sheet has, and new transactions are not added. If omitted, every column is written.
+    #[arg(long, value_delimiter = ',')]
+    only: Vec<TransactionColumn>,
+
+    /// Show the ranges that sync up would clear and write without changing the sheet
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Stop sync down without saving anything if the Transactions tab has more rows than this.
+    /// Overrides `max_rows` in config.json
+    #[arg(long)]
+    max_rows: Option<u64>,
+
+    /// Do not copy the spreadsheet with the Drive API before sync up. The JSON and SQLite backups
+    /// are still made. Overrides `drive_backup` in config.json
+    #[arg(long)]
+    no_drive_backup: bool,
 }
 
 impl SyncArgs {
@@ -221,6 +484,11 @@ impl SyncArgs {
             oauth_token: oath_token,
             force: false,
             formulas: FormulasMode::Unknown,
+            strategy: SyncUpStrategy::Replace,
+            only: Vec::new(),
+            dry_run: false,
+            max_rows: None,
+            no_drive_backup: false,
         }
     }
 
@@ -243,6 +511,44 @@ impl SyncArgs {
     pub fn formulas(&self) -> FormulasMode {
         self.formulas
     }
+
+    pub fn strategy(&self) -> SyncUpStrategy {
+        self.strategy
+    }
+
+    pub fn only(&self) -> &[TransactionColumn] {
+        &self.only
+    }
+
+    pub fn max_rows(&self) -> Option<u64> {
+        self.max_rows
+    }
+
+    pub fn no_drive_backup(&self) -> bool {
+        self.no_drive_backup
+    }
+
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+}
+
+/// Args for the `tiller validate` command.
+#[derive(Debug, Parser, Clone, Default)]
+pub struct ValidateArgs {
+    // No additional arguments for now.
+}
+
+/// Args for the `tiller reconcile-ids` command.
+#[derive(Debug, Parser, Clone, Default)]
+pub struct ReconcileIdsArgs {
+    // No additional arguments for now.
+}
+
+/// Args for the `tiller whoami` command.
+#[derive(Debug, Parser, Clone, Default)]
+pub struct WhoamiArgs {
+    // No additional arguments for now.
 }
 
 /// Args for the `tiller mcp` command.
@@ -316,6 +622,317 @@ pub struct SchemaArgs {
     pub include_metadata: bool,
 }
 
+// =============================================================================
+// List command structs
+// =============================================================================
+
+/// Args for the `tiller list-transactions` command.
+///
+/// Lists transactions from the local SQLite database in the same order as they appear in the
+/// sheet, one page at a time. The result includes the total number of matching transactions so
+/// that callers can tell whether there are more pages to fetch.
+#[derive(Debug, Clone, Parser, Serialize, Deserialize, JsonSchema, Default)]
+#[schemars(title = "ListTransactionsArgs")]
+pub struct ListTransactionsArgs {
+    /// Only transactions matching all of the given criteria are listed.
+    #[clap(flatten)]
+    #[serde(flatten)]
+    pub filter: TransactionFilter,
+
+    /// The maximum number of transactions to return. If omitted, all matching transactions after
+    /// `offset` are returned.
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+
+    /// The number of matching transactions to skip before the returned page begins.
+    #[arg(long, default_value_t = 0)]
+    #[serde(default)]
+    pub offset: u32,
+
+    /// Only include these columns in each listed row, e.g. `date,description,amount,category`.
+    /// `transaction_id` is always included. If omitted, every field of each transaction is listed.
+    #[arg(long, value_delimiter = ',')]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<TransactionColumn>,
+}
+
+/// Args for the `tiller list-autocats` command.
+#[derive(Debug, Parser, Clone, Default)]
+pub struct ListAutocatsArgs {
+    // No additional arguments for now.
+}
+
+/// Criteria for selecting transactions. Unset criteria match every transaction; set criteria must
+/// all match.
+#[derive(Debug, Clone, Parser, Serialize, Deserialize, JsonSchema, Default)]
+#[schemars(title = "TransactionFilter")]
+pub struct TransactionFilter {
+    /// Only include transactions with exactly this category.
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+
+    /// Only include transactions that have no category.
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub only_uncategorized: bool,
+
+    /// Only include transactions with exactly this account name.
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+
+    /// Only include transactions with exactly this account ID. Unlike the account name, which can
+    /// be changed in the sheet, the account ID stays the same.
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+
+    /// Only include transactions with exactly this institution.
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub institution: Option<String>,
+
+    /// Only include transactions whose description contains this text (case-insensitive).
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description_contains: Option<String>,
+
+    /// Only include transactions that have a value in this custom column, i.e. a column that is
+    /// not part of the standard Tiller schema such as `Location`. Combine with
+    /// `other_field_contains` to match the value.
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub other_field: Option<String>,
+
+    /// Only include transactions with a custom column value containing this text
+    /// (case-insensitive). When `other_field` is omitted, any custom column may match.
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub other_field_contains: Option<String>,
+}
+
+// =============================================================================
+// Report command structs
+// =============================================================================
+
+/// Args for the `tiller institution-totals` command.
+///
+/// Sums transaction amounts grouped by institution, optionally limited to a date range.
+#[derive(Debug, Clone, Parser, Serialize, Deserialize, JsonSchema, Default)]
+#[schemars(title = "InstitutionTotalsArgs")]
+pub struct InstitutionTotalsArgs {
+    /// Only include transactions on or after this date. Accepts the sheet's date format, e.g.
+    /// 10/20/2025, or an ISO 8601 date, e.g. 2025-10-20.
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_from: Option<String>,
+
+    /// Only include transactions on or before this date. Accepts the sheet's date format, e.g.
+    /// 10/20/2025, or an ISO 8601 date, e.g. 2025-10-20.
+    #[arg(long)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_to: Option<String>,
+}
+
+// =============================================================================
+// Recategorize command structs
+// =============================================================================
+
+/// Args for the `tiller recategorize` command.
+///
+/// Moves every transaction whose category is `from` into the category `to`, for example when
+/// merging "Coffee Shops" into "Restaurants". The `to` category must already exist. All
+/// transactions are updated in a single database transaction.
+///
+/// Changes are made locally only. Use `sync up` to upload local changes to the Google Sheet.
+#[derive(Debug, Clone, Parser, Serialize, Deserialize, JsonSchema)]
+#[schemars(title = "RecategorizeArgs")]
+pub struct RecategorizeArgs {
+    /// The category that transactions are currently assigned to.
+    #[arg(long)]
+    pub from: String,
+
+    /// The category to assign the transactions to. This category must exist.
+    #[arg(long)]
+    pub to: String,
+
+    /// Report how many transactions would be changed without changing them.
+    #[arg(long)]
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Args for the `tiller merge-categories` command.
+///
+/// Moves the transactions and AutoCat rules of each `from` category to the category `into`, then
+/// deletes the `from` categories, for example when folding "Coffee Shops" and "Dining" into
+/// "Restaurants". Every category must already exist. All changes are made in a single database
+/// transaction.
+///
+/// Changes are made locally only. Use `sync up` to upload local changes to the Google Sheet.
+#[derive(Debug, Clone, Parser, Serialize, Deserialize, JsonSchema)]
+#[schemars(title = "MergeCategoriesArgs")]
+pub struct MergeCategoriesArgs {
+    /// One or more categories to merge. They are deleted once their transactions and AutoCat
+    /// rules have been moved.
+    #[arg(long, num_args = 1..)]
+    pub from: Vec<String>,
+
+    /// The category to merge them into. This category must exist.
+    #[arg(long)]
+    pub into: String,
+}
+
+/// Args for the `tiller categorize-uncategorized` command.
+///
+/// Applies the AutoCat rules, in the order Tiller processes them, to the transactions that have
+/// no category. All transactions are updated in a single database transaction.
+///
+/// Changes are made locally only. Use `sync up` to upload local changes to the Google Sheet.
+#[derive(Debug, Clone, Parser, Serialize, Deserialize, JsonSchema, Default)]
+#[schemars(title = "CategorizeUncategorizedArgs")]
+pub struct CategorizeUncategorizedArgs {
+    /// Report the transactions that would be categorized without changing them.
+    #[arg(long)]
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Args for the `tiller move-autocat` command.
+///
+/// Moves an AutoCat rule to `position` in the processing order, shifting the rules in between by
+/// one. Since the first matching rule wins, moving a rule up gives it priority over the rules it
+/// passes.
+///
+/// Changes are made locally only. Use `sync up` to upload local changes to the Google Sheet.
+#[derive(Debug, Clone, Parser, Serialize, Deserialize, JsonSchema)]
+#[schemars(title = "MoveAutocatArgs")]
+pub struct MoveAutocatArgs {
+    /// The ID of the AutoCat rule to move.
+    #[arg(long)]
+    pub id: String,
+
+    /// The 0-based position to move the rule to, as listed by `list_autocats`. Position 0 is
+    /// processed first.
+    #[arg(long)]
+    pub position: u32,
+}
+
+// =============================================================================
+// Backup command structs
+// =============================================================================
+
+/// Args for the `tiller create-backup` command.
+#[derive(Debug, Parser, Clone, Default)]
+pub struct CreateBackupArgs {
+    // No additional arguments for now.
+}
+
+/// Args for the `tiller diff` command.
+#[derive(Debug, Parser, Clone)]
+pub struct DiffArgs {
+    /// The file name of the earlier snapshot, e.g. `sync-down.2025-12-14-001.json`.
+    pub a: String,
+    /// The file name of the later snapshot, e.g. `sync-down.2025-12-15-001.json`.
+    pub b: String,
+}
+
+// =============================================================================
+// Conflict command structs
+// =============================================================================
+
+/// Args for the `tiller list-conflicts` command.
+#[derive(Debug, Parser, Clone, Default)]
+pub struct ListConflictsArgs {
+    // No additional arguments for now.
+}
+
+/// Args for the `tiller resolve-conflict` command.
+#[derive(Debug, Clone, Parser, Serialize, Deserialize, JsonSchema)]
+#[schemars(title = "ResolveConflictArgs")]
+pub struct ResolveConflictArgs {
+    /// The ID of the transaction that has the conflict.
+    #[arg(long)]
+    pub transaction_id: String,
+
+    /// The sheet header of the conflicting field, exactly as reported by `list_conflicts`, e.g.
+    /// "Category".
+    #[arg(long)]
+    pub field: String,
+
+    /// Which value to keep: "local" keeps the value in the local database, "remote" takes the
+    /// value from the Google sheet.
+    #[arg(long, value_enum)]
+    pub choose: ConflictChoice,
+}
+
+/// Which side of a conflict to keep.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ConflictChoice {
+    /// Keep the value in the local database.
+    Local,
+    /// Take the value from the Google sheet.
+    Remote,
+}
+
+serde_plain::derive_display_from_serialize!(ConflictChoice);
+
+// =============================================================================
+// Maintenance command structs
+// =============================================================================
+
+/// Args for the `tiller maintenance` command.
+#[derive(Debug, Parser, Clone, Default)]
+pub struct MaintenanceArgs {
+    // No additional arguments for now.
+}
+
+// =============================================================================
+// Sync history command structs
+// =============================================================================
+
+/// The number of sync history entries returned when no limit is given.
+const SYNC_HISTORY_LIMIT: u32 = 20;
+
+fn default_sync_history_limit() -> u32 {
+    SYNC_HISTORY_LIMIT
+}
+
+/// Args for the `tiller sync-history` command.
+///
+/// Lists the most recent entries of the sync history, newest first.
+#[derive(Debug, Clone, Parser, Serialize, Deserialize, JsonSchema)]
+#[schemars(title = "SyncHistoryArgs")]
+pub struct SyncHistoryArgs {
+    /// The maximum number of entries to return. Defaults to 20.
+    #[arg(long, default_value_t = SYNC_HISTORY_LIMIT)]
+    #[serde(default = "default_sync_history_limit")]
+    pub limit: u32,
+}
+
+impl Default for SyncHistoryArgs {
+    fn default() -> Self {
+        Self {
+            limit: SYNC_HISTORY_LIMIT,
+        }
+    }
+}
+
+// =============================================================================
+// Diagnostic command structs
+// =============================================================================
+
+/// Args for the `tiller diagnose-formulas` command.
+#[derive(Debug, Parser, Clone, Default)]
+pub struct DiagnoseFormulasArgs {
+    // No additional arguments for now.
+}
+
 /// Args for the `tiller update` command.
 #[derive(Debug, Parser, Clone)]
 pub struct UpdateArgs {
@@ -375,6 +992,12 @@ pub struct UpdateTransactionsArgs {
     /// unchanged.
     #[clap(flatten)]
     updates: TransactionUpdates,
+
+    /// Update each transaction on its own and report which ones failed and why, instead of rolling
+    /// back every update when one fails.
+    #[arg(long)]
+    #[serde(default)]
+    keep_going: bool,
 }
 
 impl UpdateTransactionsArgs {
@@ -387,7 +1010,11 @@ impl UpdateTransactionsArgs {
         if ids.is_empty() {
             return Err(anyhow!("At least one ID is required")).pub_result(ErrorType::Request);
         }
-        Ok(Self { ids, updates })
+        Ok(Self {
+            ids,
+            updates,
+            keep_going: false,
+        })
     }
 
     pub fn ids(&self) -> &[String] {
@@ -397,6 +1024,31 @@ impl UpdateTransactionsArgs {
     pub fn updates(&self) -> &TransactionUpdates {
         &self.updates
     }
+
+    pub(crate) fn updates_mut(&mut self) -> &mut TransactionUpdates {
+        &mut self.updates
+    }
+
+    /// Whether each item is processed on its own, continuing past failures, rather than
+    /// atomically.
+    pub fn keep_going(&self) -> bool {
+        self.keep_going
+    }
+
+    /// Sets whether each item is processed on its own, continuing past failures.
+    pub fn with_keep_going(mut self, keep_going: bool) -> Self {
+        self.keep_going = keep_going;
+        self
+    }
+
+    /// A copy of these args that only updates `id`, atomically.
+    pub(crate) fn single(&self, id: &str) -> Self {
+        Self {
+            ids: vec![id.to_string()],
+            keep_going: false,
+            ..self.clone()
+        }
+    }
 }
 
 /// Args for the `tiller update categories` command.
@@ -409,7 +1061,8 @@ impl UpdateTransactionsArgs {
 /// the `--category` update field to the new name.
 ///
 /// Due to `ON UPDATE CASCADE` foreign key constraints, renaming a category automatically
-/// updates all references in transactions and autocat rules.
+/// updates all references in transactions and autocat rules. Renaming a category to the name of
+/// another existing category is an error unless `--merge` is given.
 ///
 /// Changes are made locally only. Use `sync up` to upload local changes to the Google Sheet.
 #[derive(Debug, Parser, Clone, Serialize, Deserialize, JsonSchema)]
@@ -423,6 +1076,19 @@ pub struct UpdateCategoriesArgs {
     /// unchanged.
     #[clap(flatten)]
     updates: CategoryUpdates,
+
+    /// When the new name given by `category` belongs to another existing category, move the
+    /// transactions and AutoCat rules of the renamed category to that category, apply the other
+    /// updates to it, and delete the renamed category. Without this, such a rename is an error.
+    #[arg(long)]
+    #[serde(default)]
+    merge: bool,
+
+    /// Update each category on its own and report which ones failed and why, instead of rolling
+    /// back every update when one fails.
+    #[arg(long)]
+    #[serde(default)]
+    keep_going: bool,
 }
 
 impl UpdateCategoriesArgs {
@@ -436,7 +1102,12 @@ impl UpdateCategoriesArgs {
             return Err(anyhow!("At least one category name is required"))
                 .pub_result(ErrorType::Request);
         }
-        Ok(Self { names, updates })
+        Ok(Self {
+            names,
+            updates,
+            merge: false,
+            keep_going: false,
+        })
     }
 
     pub fn names(&self) -> &[String] {
@@ -446,6 +1117,38 @@ impl UpdateCategoriesArgs {
     pub fn updates(&self) -> &CategoryUpdates {
         &self.updates
     }
+
+    /// Whether a rename onto an existing category merges into it rather than failing.
+    pub fn merge(&self) -> bool {
+        self.merge
+    }
+
+    /// Sets whether a rename onto an existing category merges into it rather than failing.
+    pub fn with_merge(mut self, merge: bool) -> Self {
+        self.merge = merge;
+        self
+    }
+
+    /// Whether each item is processed on its own, continuing past failures, rather than
+    /// atomically.
+    pub fn keep_going(&self) -> bool {
+        self.keep_going
+    }
+
+    /// Sets whether each item is processed on its own, continuing past failures.
+    pub fn with_keep_going(mut self, keep_going: bool) -> Self {
+        self.keep_going = keep_going;
+        self
+    }
+
+    /// A copy of these args that only updates `name`, atomically.
+    pub(crate) fn single(&self, name: &str) -> Self {
+        Self {
+            names: vec![name.to_string()],
+            keep_going: false,
+            ..self.clone()
+        }
+    }
 }
 
 /// Args for the `tiller update autocats` command.
@@ -469,6 +1172,12 @@ pub struct UpdateAutoCatsArgs {
     /// unchanged.
     #[clap(flatten)]
     updates: AutoCatUpdates,
+
+    /// Update each rule on its own and report which ones failed and why, instead of rolling
+    /// back every update when one fails.
+    #[arg(long)]
+    #[serde(default)]
+    keep_going: bool,
 }
 
 impl UpdateAutoCatsArgs {
@@ -482,7 +1191,11 @@ impl UpdateAutoCatsArgs {
             return Err(anyhow!("At least one AutoCat ID is required"))
                 .pub_result(ErrorType::Request);
         }
-        Ok(Self { ids, updates })
+        Ok(Self {
+            ids,
+            updates,
+            keep_going: false,
+        })
     }
 
     pub fn ids(&self) -> &[String] {
@@ -492,6 +1205,27 @@ impl UpdateAutoCatsArgs {
     pub fn updates(&self) -> &AutoCatUpdates {
         &self.updates
     }
+
+    /// Whether each item is processed on its own, continuing past failures, rather than
+    /// atomically.
+    pub fn keep_going(&self) -> bool {
+        self.keep_going
+    }
+
+    /// Sets whether each item is processed on its own, continuing past failures.
+    pub fn with_keep_going(mut self, keep_going: bool) -> Self {
+        self.keep_going = keep_going;
+        self
+    }
+
+    /// A copy of these args that only updates `id`, atomically.
+    pub(crate) fn single(&self, id: &str) -> Self {
+        Self {
+            ids: vec![id.to_string()],
+            keep_going: false,
+            ..self.clone()
+        }
+    }
 }
 
 // =============================================================================
@@ -561,6 +1295,12 @@ pub struct DeleteTransactionsArgs {
     /// One or more transaction IDs to delete.
     #[arg(long = "id", required = true)]
     ids: Vec<String>,
+
+    /// Delete each transaction on its own and report which ones failed and why, instead of rolling
+    /// back every deletion when one fails.
+    #[arg(long)]
+    #[serde(default)]
+    keep_going: bool,
 }
 
 impl DeleteTransactionsArgs {
@@ -573,12 +1313,35 @@ impl DeleteTransactionsArgs {
         if ids.is_empty() {
             return Err(anyhow!("At least one ID is required")).pub_result(ErrorType::Request);
         }
-        Ok(Self { ids })
+        Ok(Self {
+            ids,
+            keep_going: false,
+        })
     }
 
     pub fn ids(&self) -> &[String] {
         &self.ids
     }
+
+    /// Whether each item is processed on its own, continuing past failures, rather than
+    /// atomically.
+    pub fn keep_going(&self) -> bool {
+        self.keep_going
+    }
+
+    /// Sets whether each item is processed on its own, continuing past failures.
+    pub fn with_keep_going(mut self, keep_going: bool) -> Self {
+        self.keep_going = keep_going;
+        self
+    }
+
+    /// A copy of these args that only deletes `id`, atomically.
+    pub(crate) fn single(&self, id: &str) -> Self {
+        Self {
+            ids: vec![id.to_string()],
+            keep_going: false,
+        }
+    }
 }
 
 /// Args for the `tiller delete categories` command.
@@ -599,6 +1362,12 @@ pub struct DeleteCategoriesArgs {
     /// One or more category names to delete.
     #[arg(long = "name", required = true)]
     names: Vec<String>,
+
+    /// Delete each category on its own and report which ones failed and why, instead of rolling
+    /// back every deletion when one fails.
+    #[arg(long)]
+    #[serde(default)]
+    keep_going: bool,
 }
 
 impl DeleteCategoriesArgs {
@@ -612,12 +1381,35 @@ impl DeleteCategoriesArgs {
             return Err(anyhow!("At least one category name is required"))
                 .pub_result(ErrorType::Request);
         }
-        Ok(Self { names })
+        Ok(Self {
+            names,
+            keep_going: false,
+        })
     }
 
     pub fn names(&self) -> &[String] {
         &self.names
     }
+
+    /// Whether each item is processed on its own, continuing past failures, rather than
+    /// atomically.
+    pub fn keep_going(&self) -> bool {
+        self.keep_going
+    }
+
+    /// Sets whether each item is processed on its own, continuing past failures.
+    pub fn with_keep_going(mut self, keep_going: bool) -> Self {
+        self.keep_going = keep_going;
+        self
+    }
+
+    /// A copy of these args that only deletes `name`, atomically.
+    pub(crate) fn single(&self, name: &str) -> Self {
+        Self {
+            names: vec![name.to_string()],
+            keep_going: false,
+        }
+    }
 }
 
 /// Args for the `tiller delete autocats` command.
@@ -637,6 +1429,12 @@ pub struct DeleteAutoCatsArgs {
     /// One or more AutoCat rule IDs to delete.
     #[arg(long = "id", required = true)]
     ids: Vec<String>,
+
+    /// Delete each rule on its own and report which ones failed and why, instead of rolling
+    /// back every deletion when one fails.
+    #[arg(long)]
+    #[serde(default)]
+    keep_going: bool,
 }
 
 impl DeleteAutoCatsArgs {
@@ -649,12 +1447,35 @@ impl DeleteAutoCatsArgs {
         if ids.is_empty() {
             return Err(anyhow!("At least one ID is required")).pub_result(ErrorType::Request);
         }
-        Ok(Self { ids })
+        Ok(Self {
+            ids,
+            keep_going: false,
+        })
     }
 
     pub fn ids(&self) -> &[String] {
         &self.ids
     }
+
+    /// Whether each item is processed on its own, continuing past failures, rather than
+    /// atomically.
+    pub fn keep_going(&self) -> bool {
+        self.keep_going
+    }
+
+    /// Sets whether each item is processed on its own, continuing past failures.
+    pub fn with_keep_going(mut self, keep_going: bool) -> Self {
+        self.keep_going = keep_going;
+        self
+    }
+
+    /// A copy of these args that only deletes `id`, atomically.
+    pub(crate) fn single(&self, id: &str) -> Self {
+        Self {
+            ids: vec![id.to_string()],
+            keep_going: false,
+        }
+    }
 }
 
 /// Arguments for `tiller insert` commands.
@@ -748,12 +1569,14 @@ pub struct InsertTransactionArgs {
     #[arg(long)]
     pub institution: Option<String>,
 
-    /// First day of the transaction's month, useful for pivot tables and reporting.
+    /// First day of the transaction's month, useful for pivot tables and reporting. Derived from
+    /// the date, e.g. `10/1/25`, when not given.
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(long)]
     pub month: Option<String>,
 
-    /// Sunday date of the transaction's week for weekly breakdowns.
+    /// Sunday date of the transaction's week for weekly breakdowns. Derived from the date, e.g.
+    /// `10/19/25`, when not given.
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(long)]
     pub week: Option<String>,
@@ -957,6 +1780,12 @@ pub struct InsertAutoCatArgs {
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     #[arg(long = "other-field", value_parser = utils::parse_key_val)]
     pub other_fields: BTreeMap<String, String>,
+
+    /// If a rule with the same category, overrides and filter criteria already exists, return its
+    /// ID instead of inserting a duplicate. Custom columns are not compared.
+    #[arg(long)]
+    #[serde(default)]
+    pub idempotent: bool,
 }
 
 fn default_tiller_home() -> DisplayPath {
@@ -1020,3 +1849,88 @@ impl DisplayPath {
         &self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_use_color() {
+        assert!(use_color(false, None, true));
+        assert!(!use_color(true, None, true));
+        assert!(!use_color(false, Some(OsString::from("1")), true));
+        assert!(!use_color(false, None, false));
+        // An empty NO_COLOR does not disable color
+        assert!(use_color(false, Some(OsString::new()), true));
+    }
+
+    /// Parses `args` and resolves the tiller home with `TILLER_HOME` set to `env`, or unset if
+    /// `env` is `None`.
+    fn tiller_home_with_env(env: Option<&str>, args: &[&str]) -> PathBuf {
+        let args = Args::try_parse_from(args).unwrap();
+        let flag = args.common().tiller_home.as_ref();
+        resolve_tiller_home(flag, env.map(OsString::from))
+            .path()
+            .to_path_buf()
+    }
+
+    #[test]
+    fn test_tiller_home_flag_overrides_env() {
+        let home = tiller_home_with_env(
+            Some("/from/env"),
+            &["tiller", "--tiller-home", "/from/flag", "create-backup"],
+        );
+        assert_eq!(home, PathBuf::from("/from/flag"));
+    }
+
+    #[test]
+    fn test_tiller_home_env_overrides_default() {
+        let home = tiller_home_with_env(Some("/from/env"), &["tiller", "create-backup"]);
+        assert_eq!(home, PathBuf::from("/from/env"));
+    }
+
+    #[test]
+    fn test_tiller_home_default() {
+        let home = tiller_home_with_env(None, &["tiller", "create-backup"]);
+        assert_eq!(home, default_tiller_home().path());
+        assert!(home.ends_with("tiller"));
+
+        // An empty TILLER_HOME is ignored
+        let home = tiller_home_with_env(Some(""), &["tiller", "create-backup"]);
+        assert_eq!(home, default_tiller_home().path());
+    }
+
+    #[test]
+    fn test_quiet_overrides_log_level() {
+        let args = Args::parse_from(["tiller", "--log-level", "trace", "--quiet", "create-backup"]);
+        assert!(args.common().quiet());
+        assert_eq!(args.common().log_level(), LevelFilter::ERROR);
+
+        // --quiet never makes logging more verbose
+        let args = Args::parse_from(["tiller", "--log-level", "off", "--quiet", "create-backup"]);
+        assert_eq!(args.common().log_level(), LevelFilter::OFF);
+
+        let args = Args::parse_from(["tiller", "--log-level", "debug", "create-backup"]);
+        assert!(!args.common().quiet());
+        assert_eq!(args.common().log_level(), LevelFilter::DEBUG);
+    }
+
+    #[test]
+    fn test_output_format() {
+        let args = Args::parse_from(["tiller", "list-autocats"]);
+        assert_eq!(args.common().output(), Format::Table);
+
+        let args = Args::parse_from(["tiller", "--output", "csv", "list-autocats"]);
+        assert_eq!(args.common().output(), Format::Csv);
+
+        // The query command's own --format is separate from --output
+        let args = Args::parse_from([
+            "tiller", "--output", "json", "query", "--format", "csv", "SELECT 1",
+        ]);
+        assert_eq!(args.common().output(), Format::Json);
+        let Command::Query(query_args) = args.command() else {
+            panic!("Expected the query command");
+        };
+        assert_eq!(query_args.format, OutputFormat::Csv);
+    }
+}
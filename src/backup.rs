@@ -3,9 +3,13 @@
 use crate::error::Res;
 use crate::model::TillerData;
 use crate::{utils, Config};
-use anyhow::Context;
+use anyhow::{bail, Context};
 use chrono::Local;
-use std::path::PathBuf;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 use tracing::debug;
 
 /// Prefix for sync-down backup files.
@@ -14,9 +18,21 @@ pub const SYNC_DOWN: &str = "sync-down";
 /// Prefix for sync-up-pre backup files (snapshot before upload).
 pub const SYNC_UP_PRE: &str = "sync-up-pre";
 
+/// Prefix for on-demand snapshots of the local database contents.
+pub const SNAPSHOT: &str = "snapshot";
+
 /// Prefix for SQLite backup files.
 pub const SQLITE: &str = "tiller.sqlite";
 
+/// Extension of uncompressed JSON backup files.
+const JSON: &str = "json";
+
+/// Extension of gzip compressed JSON backup files.
+const JSON_GZ: &str = "json.gz";
+
+/// The extensions of JSON backup files. Sequence numbers and rotation are shared between them.
+const JSON_EXTENSIONS: &[&str] = &[JSON, JSON_GZ];
+
 /// Manages backup file creation and rotation.
 ///
 /// The `Backup` struct is immutable and owns copies of the paths and settings it needs.
@@ -26,6 +42,7 @@ pub(crate) struct Backup {
     backups_dir: PathBuf,
     backup_copies: u32,
     sqlite_path: PathBuf,
+    compress: bool,
 }
 
 impl Backup {
@@ -35,26 +52,36 @@ impl Backup {
             backups_dir: config.backups().to_path_buf(),
             backup_copies: config.backup_copies(),
             sqlite_path: config.sqlite_path().to_path_buf(),
+            compress: config.compress_backups(),
         }
     }
 
     /// Saves `TillerData` as a pretty-printed JSON backup file.
     ///
-    /// The filename format is `{prefix}.YYYY-MM-DD-NNN.json` where NNN is a sequence number.
+    /// The filename format is `{prefix}.YYYY-MM-DD-NNN.json` where NNN is a sequence number, or
+    /// `{prefix}.YYYY-MM-DD-NNN.json.gz` when `compress_backups` is enabled.
     /// Automatically rotates old backups, keeping only `backup_copies` files.
     ///
     /// Returns the path to the created backup file.
     pub(crate) async fn save_json(&self, prefix: &str, data: &TillerData) -> Res<PathBuf> {
         let date = today();
-        let seq = self.next_sequence_number(prefix, &date, "json").await?;
-        let filename = format!("{prefix}.{date}-{seq:03}.json");
+        let seq = self
+            .next_sequence_number(prefix, &date, JSON_EXTENSIONS)
+            .await?;
+        let extension = if self.compress { JSON_GZ } else { JSON };
+        let filename = format!("{prefix}.{date}-{seq:03}.{extension}");
         let path = self.backups_dir.join(&filename);
 
         let json =
             serde_json::to_string_pretty(data).context("Failed to serialize TillerData to JSON")?;
-        utils::write(&path, json).await?;
+        let contents = if self.compress {
+            gzip(json.as_bytes())?
+        } else {
+            json.into_bytes()
+        };
+        utils::write(&path, contents).await?;
 
-        self.rotate(prefix, "json").await?;
+        self.rotate(prefix, JSON_EXTENSIONS).await?;
 
         Ok(path)
     }
@@ -67,40 +94,58 @@ impl Backup {
     /// Returns the path to the created backup file.
     pub(crate) async fn copy_sqlite(&self) -> Res<PathBuf> {
         let date = today();
-        let seq = self.next_sequence_number(SQLITE, &date, "").await?;
+        let seq = self.next_sequence_number(SQLITE, &date, &[""]).await?;
         let filename = format!("{SQLITE}.{date}-{seq:03}");
         let path = self.backups_dir.join(&filename);
 
         utils::copy(&self.sqlite_path, &path).await?;
 
-        self.rotate(SQLITE, "").await?;
+        self.rotate(SQLITE, &[""]).await?;
 
         Ok(path)
     }
 
-    /// Loads the most recent JSON backup file with the given prefix.
+    /// Loads the most recent JSON backup file with the given prefix, decompressing it if it is a
+    /// `.json.gz` file.
     ///
     /// Returns `None` if no backup files exist.
     pub(crate) async fn load_latest_json(&self, prefix: &str) -> Res<Option<TillerData>> {
-        let latest = self.find_latest_backup(prefix, "json").await?;
+        let latest = self.find_latest_backup(prefix, JSON_EXTENSIONS).await?;
 
         match latest {
             None => {
                 debug!("No {prefix} backup found");
                 Ok(None)
             }
-            Some(path) => {
-                debug!("Loading backup from {}", path.display());
-                let content = utils::read(&path).await?;
-                let data: TillerData = serde_json::from_str(&content)
-                    .with_context(|| format!("Failed to parse backup file: {}", path.display()))?;
-                Ok(Some(data))
-            }
+            Some(path) => Ok(Some(read_snapshot(&path).await?)),
         }
     }
 
-    /// Finds the most recent backup file with the given prefix and extension.
-    async fn find_latest_backup(&self, prefix: &str, extension: &str) -> Res<Option<PathBuf>> {
+    /// Loads the JSON backup file named `name`, e.g. `sync-down.2025-12-14-001.json`, from the
+    /// backups directory, decompressing it if it is a `.json.gz` file.
+    pub(crate) async fn load_json(&self, name: &str) -> Res<TillerData> {
+        let is_file_name = Path::new(name).file_name().is_some_and(|n| n == name);
+        let is_json = JSON_EXTENSIONS
+            .iter()
+            .any(|ext| name.ends_with(&format!(".{ext}")));
+        if !is_file_name || !is_json {
+            bail!(
+                "'{name}' is not the name of a JSON backup file, expected a name such as \
+                {SYNC_DOWN}.2025-12-14-001.json from the backups directory"
+            );
+        }
+        let path = self.backups_dir.join(name);
+        if !path.is_file() {
+            bail!(
+                "Backup file '{name}' not found in {}",
+                self.backups_dir.display()
+            );
+        }
+        read_snapshot(&path).await
+    }
+
+    /// Finds the most recent backup file with the given prefix and any of the given extensions.
+    async fn find_latest_backup(&self, prefix: &str, extensions: &[&str]) -> Res<Option<PathBuf>> {
         let mut files: Vec<(PathBuf, String)> = Vec::new();
 
         let mut dir = utils::read_dir(&self.backups_dir).await?;
@@ -112,7 +157,10 @@ impl Backup {
             let file_name = entry.file_name();
             let name = file_name.to_string_lossy().to_string();
 
-            if is_backup_file(&name, prefix, extension) {
+            if extensions
+                .iter()
+                .any(|extension| is_backup_file(&name, prefix, extension))
+            {
                 files.push((entry.path(), name));
             }
         }
@@ -123,9 +171,14 @@ impl Backup {
         Ok(files.into_iter().next().map(|(path, _)| path))
     }
 
-    /// Scans the backups directory for existing files with the given prefix and date,
-    /// and returns the next sequence number.
-    async fn next_sequence_number(&self, prefix: &str, date: &str, extension: &str) -> Res<u32> {
+    /// Scans the backups directory for existing files with the given prefix, date and any of the
+    /// given extensions, and returns the next sequence number.
+    async fn next_sequence_number(
+        &self,
+        prefix: &str,
+        date: &str,
+        extensions: &[&str],
+    ) -> Res<u32> {
         let pattern_start = format!("{prefix}.{date}-");
         let mut max_seq: u32 = 0;
 
@@ -139,8 +192,10 @@ impl Backup {
             let name = file_name.to_string_lossy();
 
             if name.starts_with(&pattern_start) {
-                if let Some(seq) = parse_sequence_number(&name, prefix, date, extension) {
-                    max_seq = max_seq.max(seq);
+                for extension in extensions {
+                    if let Some(seq) = parse_sequence_number(&name, prefix, date, extension) {
+                        max_seq = max_seq.max(seq);
+                    }
                 }
             }
         }
@@ -148,8 +203,9 @@ impl Backup {
         Ok(max_seq + 1)
     }
 
-    /// Rotates old backup files, keeping only `backup_copies` files with the given prefix.
-    async fn rotate(&self, prefix: &str, extension: &str) -> Res<()> {
+    /// Rotates old backup files, keeping only `backup_copies` files with the given prefix and any
+    /// of the given extensions.
+    async fn rotate(&self, prefix: &str, extensions: &[&str]) -> Res<()> {
         // Collect all matching backup files
         let mut files: Vec<(PathBuf, String)> = Vec::new();
 
@@ -162,7 +218,10 @@ impl Backup {
             let file_name = entry.file_name();
             let name = file_name.to_string_lossy().to_string();
 
-            if is_backup_file(&name, prefix, extension) {
+            if extensions
+                .iter()
+                .any(|extension| is_backup_file(&name, prefix, extension))
+            {
                 files.push((entry.path(), name));
             }
         }
@@ -185,6 +244,39 @@ fn today() -> String {
     Local::now().format("%Y-%m-%d").to_string()
 }
 
+/// Compresses `data` with gzip.
+fn gzip(data: &[u8]) -> Res<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data)
+        .context("Failed to compress backup")?;
+    encoder.finish().context("Failed to compress backup")
+}
+
+/// Reads and parses a JSON backup file.
+async fn read_snapshot(path: &Path) -> Res<TillerData> {
+    debug!("Loading backup from {}", path.display());
+    let content = read_json_backup(path).await?;
+    serde_json::from_slice(&content)
+        .with_context(|| format!("Failed to parse backup file: {}", path.display()))
+}
+
+/// Reads a JSON backup file, decompressing it if its name ends with `.json.gz`.
+async fn read_json_backup(path: &Path) -> Res<Vec<u8>> {
+    let bytes = utils::read_bytes(path).await?;
+    let is_gzip = path
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().ends_with(&format!(".{JSON_GZ}")));
+    if !is_gzip {
+        return Ok(bytes);
+    }
+    let mut decompressed = Vec::new();
+    GzDecoder::new(bytes.as_slice())
+        .read_to_end(&mut decompressed)
+        .with_context(|| format!("Failed to decompress backup file: {}", path.display()))?;
+    Ok(decompressed)
+}
+
 /// Parses the sequence number from a backup filename.
 /// Returns None if the filename doesn't match the expected pattern.
 fn parse_sequence_number(filename: &str, prefix: &str, date: &str, extension: &str) -> Option<u32> {
@@ -214,7 +306,9 @@ fn is_backup_file(filename: &str, prefix: &str, extension: &str) -> bool {
 
     let ends_ok = if extension.is_empty() {
         // For SQLite backups, ensure it doesn't end with a known extension
-        !filename.ends_with(".json")
+        !JSON_EXTENSIONS
+            .iter()
+            .any(|ext| filename.ends_with(&format!(".{ext}")))
     } else {
         filename.ends_with(&format!(".{extension}"))
     };
@@ -225,6 +319,75 @@ fn is_backup_file(filename: &str, prefix: &str, extension: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::api::Mode;
+    use crate::commands::sync_down;
+    use crate::test::TestEnv;
+    use tempfile::TempDir;
+
+    /// Returns a `Backup` that writes to `dir`.
+    fn backup_in(dir: &Path, compress: bool) -> Backup {
+        Backup {
+            backups_dir: dir.to_path_buf(),
+            backup_copies: 5,
+            sqlite_path: dir.join("tiller.sqlite"),
+            compress,
+        }
+    }
+
+    /// Returns the data of the default `TestSheet` as saved in the database by `sync down`.
+    async fn test_data() -> TillerData {
+        let env = TestEnv::new().await;
+        sync_down(env.config(), Mode::Testing).await.unwrap();
+        env.config().db().get_tiller_data().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_compressed_json_round_trip() {
+        let data = test_data().await;
+        let plain_dir = TempDir::new().unwrap();
+        let gz_dir = TempDir::new().unwrap();
+        let plain = backup_in(plain_dir.path(), false);
+        let gz = backup_in(gz_dir.path(), true);
+
+        let plain_path = plain.save_json(SYNC_DOWN, &data).await.unwrap();
+        let gz_path = gz.save_json(SYNC_DOWN, &data).await.unwrap();
+        assert!(plain_path.to_string_lossy().ends_with("-001.json"));
+        assert!(gz_path.to_string_lossy().ends_with("-001.json.gz"));
+        assert!(
+            std::fs::metadata(&gz_path).unwrap().len()
+                < std::fs::metadata(&plain_path).unwrap().len()
+        );
+
+        let from_plain = plain.load_latest_json(SYNC_DOWN).await.unwrap().unwrap();
+        let from_gz = gz.load_latest_json(SYNC_DOWN).await.unwrap().unwrap();
+        assert_eq!(from_plain, data);
+        assert_eq!(from_gz, from_plain);
+    }
+
+    #[tokio::test]
+    async fn test_compressed_and_plain_json_share_sequence() {
+        let data = test_data().await;
+        let dir = TempDir::new().unwrap();
+
+        let first = backup_in(dir.path(), false)
+            .save_json(SYNC_DOWN, &data)
+            .await
+            .unwrap();
+        let second = backup_in(dir.path(), true)
+            .save_json(SYNC_DOWN, &TillerData::default())
+            .await
+            .unwrap();
+        assert!(first.to_string_lossy().ends_with("-001.json"));
+        assert!(second.to_string_lossy().ends_with("-002.json.gz"));
+
+        // The newest snapshot is read, whichever kind it is, regardless of the current setting
+        let latest = backup_in(dir.path(), false)
+            .load_latest_json(SYNC_DOWN)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(latest, TillerData::default());
+    }
 
     #[test]
     fn test_parse_sequence_number() {
@@ -304,5 +467,20 @@ mod tests {
             "tiller.sqlite",
             ""
         ));
+        assert!(!is_backup_file(
+            "tiller.sqlite.2025-12-14-001.json.gz",
+            "tiller.sqlite",
+            ""
+        ));
+        assert!(is_backup_file(
+            "sync-down.2025-12-14-001.json.gz",
+            "sync-down",
+            "json.gz"
+        ));
+        assert!(!is_backup_file(
+            "sync-down.2025-12-14-001.json.gz",
+            "sync-down",
+            "json"
+        ));
     }
 }
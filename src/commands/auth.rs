@@ -3,13 +3,36 @@
 //! This module implements the CLI commands for:
 //! - `tiller auth` - Initial OAuth consent flow
 //! - `tiller auth --verify` - Verify and refresh authentication
+//! - `tiller whoami` - Report the authorized Google account and the targeted spreadsheet
 
-use crate::api::TokenProvider;
+use crate::api::{build_http_client, sheet, Mode, Sheet, TokenProvider};
 use crate::commands::Out;
 use crate::error::{ErrorType, IntoResult};
 use crate::Config;
 use crate::Result;
 use anyhow::Context;
+use serde::Serialize;
+use tracing::info;
+
+/// The result of `tiller auth --verify`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthStatus {
+    /// The OAuth scopes that were granted to the saved token.
+    pub scopes: Vec<String>,
+}
+
+/// The result of `tiller whoami`.
+#[derive(Debug, Clone, Serialize)]
+pub struct WhoAmI {
+    /// The email address of the Google account that authorized the app.
+    pub email: String,
+    /// The display name of the Google account that authorized the app.
+    pub name: String,
+    /// The URL of the Tiller sheet that is synced.
+    pub sheet_url: String,
+    /// The ID of the Tiller sheet that is synced.
+    pub spreadsheet_id: String,
+}
 
 /// Handles the `tiller auth` command - runs the OAuth consent flow
 ///
@@ -18,7 +41,10 @@ use anyhow::Context;
 /// This guides the user through setting up Google Sheets authentication:
 /// 1. Checks for client_secret.json (provides instructions if missing)
 /// 2. Opens browser for OAuth consent
-/// 3. Saves tokens to token.json with required scopes
+/// 3. Saves tokens to token.json with the scopes that were granted
+///
+/// If an existing token is missing any of the required scopes, e.g. because the scopes the app
+/// needs have changed, the missing scopes are reported before asking for consent again.
 ///
 /// # Arguments
 /// * `config` - Reference to the Config struct
@@ -26,9 +52,22 @@ use anyhow::Context;
 /// # Errors
 /// Returns an error if OAuth flow fails or if client_secret.json is missing
 pub async fn auth(config: &Config) -> Result<Out<()>> {
-    let _ = TokenProvider::initialize(config.client_secret_path(), config.token_path())
-        .await
-        .pub_result(ErrorType::Auth)?;
+    if let Some(missing) = TokenProvider::missing_scopes(config.token_path()).await {
+        if !missing.is_empty() {
+            info!(
+                "The saved token is missing the '{}' scope, asking for consent again",
+                missing.join("', '")
+            );
+        }
+    }
+    let _ = TokenProvider::initialize(
+        config.client_secret_path(),
+        config.token_path(),
+        build_http_client(config).pub_result(ErrorType::Config)?,
+        config.token_refresh_buffer(),
+    )
+    .await
+    .pub_result(ErrorType::Auth)?;
     Ok("Successfully authorized the application with a fresh token".into())
 }
 
@@ -41,7 +80,7 @@ pub async fn auth(config: &Config) -> Result<Out<()>> {
 /// 1. Checks that credentials and tokens exist
 /// 2. Verifies tokens have the correct scopes
 /// 3. Makes a test API call to verify access
-/// 4. Reports the results to the user
+/// 4. Reports the results, including the granted scopes, to the user
 ///
 /// If the token is missing, invalid, or has the wrong scopes, this command will
 /// fail with an error message telling the user to run `tiller auth`.
@@ -52,18 +91,85 @@ pub async fn auth(config: &Config) -> Result<Out<()>> {
 /// # Errors
 /// Returns an error if verification fails, credentials are missing, or tokens are invalid.
 /// NEVER opens a browser - always returns an error instead.
-pub async fn auth_verify(config: &Config) -> Result<Out<()>> {
-    let mut token_provider = TokenProvider::load(config.client_secret_path(), config.token_path())
-        .await
-        .context(
-            "Unable to use the existing tokens found in the token JSON file. \n\n\
+pub async fn auth_verify(config: &Config) -> Result<Out<AuthStatus>> {
+    let mut token_provider = TokenProvider::load(
+        config.client_secret_path(),
+        config.token_path(),
+        build_http_client(config).pub_result(ErrorType::Config)?,
+        config.token_refresh_buffer(),
+    )
+    .await
+    .context(
+        "Unable to use the existing tokens found in the token JSON file. \n\n\
             You should run 'tiller auth' (without the --verify flag).",
-        )
-        .pub_result(ErrorType::Auth)?;
+    )
+    .pub_result(ErrorType::Auth)?;
     token_provider
         .refresh()
         .await
         .context("Unable to refresh the token")
         .pub_result(ErrorType::Auth)?;
-    Ok("Your OAuth token is valid!".into())
+    let scopes = token_provider.scopes().to_vec();
+    Ok(Out::new(
+        format!(
+            "Your OAuth token is valid! Granted scopes: {}",
+            scopes.join(", ")
+        ),
+        AuthStatus { scopes },
+    ))
+}
+
+/// Handles the `tiller whoami` command - reports which Google account the saved token belongs to
+/// and which spreadsheet is synced. Use this to check that the right account was authorized.
+///
+/// # Arguments
+/// * `config` - The application configuration.
+/// * `mode` - Whether to ask Google or to report the pretend account of the in-memory test data.
+///
+/// # Errors
+/// Returns an error if the token cannot be loaded or Google cannot be reached.
+pub async fn whoami(config: Config, mode: Mode) -> Result<Out<WhoAmI>> {
+    let mut sheet_client = sheet(config.clone(), mode).await?;
+    let account = sheet_client.account().await.pub_result(ErrorType::Auth)?;
+    let whoami = WhoAmI {
+        email: account.email,
+        name: account.name,
+        sheet_url: config.sheet_url().to_string(),
+        spreadsheet_id: config.spreadsheet_id().to_string(),
+    };
+    let message = format!(
+        "Authorized as {} ({}), syncing {}",
+        whoami.email, whoami.name, whoami.sheet_url
+    );
+    Ok(Out::new(message, whoami))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::TEST_ACCOUNT_EMAIL;
+    use crate::test::TestEnv;
+
+    #[tokio::test]
+    async fn test_whoami_reports_test_account() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        let out = whoami(config.clone(), Mode::Testing).await.unwrap();
+        let whoami = out.structure().unwrap();
+        assert_eq!(whoami.email, TEST_ACCOUNT_EMAIL);
+        assert!(!whoami.name.is_empty());
+        assert_eq!(whoami.sheet_url, config.sheet_url());
+        assert_eq!(whoami.spreadsheet_id, config.spreadsheet_id());
+        assert!(out.message().contains(TEST_ACCOUNT_EMAIL));
+
+        let json = serde_json::to_value(whoami).unwrap();
+        let mut keys: Vec<&str> = json
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(|k| k.as_str())
+            .collect();
+        keys.sort();
+        assert_eq!(keys, vec!["email", "name", "sheet_url", "spreadsheet_id"]);
+    }
 }
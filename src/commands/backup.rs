@@ -0,0 +1,109 @@
+//! Implements an on-demand backup of the local datastore.
+
+use crate::backup::SNAPSHOT;
+use crate::commands::Out;
+use crate::error::{ErrorType, IntoResult};
+use crate::{Config, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// The files written by `create_backup`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupFiles {
+    /// The directory the backup files were written to.
+    pub backups_dir: PathBuf,
+    /// The file name of the copy of the SQLite database, e.g. `tiller.sqlite.2025-10-20-001`.
+    pub sqlite: String,
+    /// The file name of the JSON snapshot of the database contents, e.g.
+    /// `snapshot.2025-10-20-001.json`.
+    pub json: String,
+}
+
+/// Backs up the local datastore without syncing. This copies the SQLite database file and writes
+/// a JSON snapshot of the data currently in the database (not the data in the Google sheet) to the
+/// backups directory. Older backups are rotated as they are during `sync`.
+///
+/// # Arguments
+///
+/// - `config` - The application configuration.
+///
+/// # Returns
+///
+/// On success, returns an `Out` containing a message and the names of the backup files.
+///
+/// # Errors
+///
+/// - Returns an error if the database cannot be read.
+/// - Returns an error if the backup files cannot be written.
+pub async fn create_backup(config: Config) -> Result<Out<BackupFiles>> {
+    let sqlite_backup = config
+        .backup()
+        .copy_sqlite()
+        .await
+        .pub_result(ErrorType::Internal)?;
+    debug!("Saved SQLite backup to {}", sqlite_backup.display());
+
+    let data = config
+        .db()
+        .get_tiller_data()
+        .await
+        .pub_result(ErrorType::Database)?;
+    let json_backup = config
+        .backup()
+        .save_json(SNAPSHOT, &data)
+        .await
+        .pub_result(ErrorType::Internal)?;
+    debug!("Saved JSON backup to {}", json_backup.display());
+
+    let files = BackupFiles {
+        backups_dir: config.backups().to_path_buf(),
+        sqlite: file_name(&sqlite_backup),
+        json: file_name(&json_backup),
+    };
+
+    Ok(Out::new(
+        format!(
+            "Created backups {} and {} in {}",
+            files.sqlite,
+            files.json,
+            files.backups_dir.display()
+        ),
+        files,
+    ))
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::TestEnv;
+
+    #[tokio::test]
+    async fn test_create_backup() {
+        let env = TestEnv::new().await;
+        env.insert_test_transaction("txn-001").await;
+        let config = env.config();
+
+        let out = create_backup(config.clone()).await.unwrap();
+        let files = out.structure().unwrap();
+        assert!(files.sqlite.starts_with("tiller.sqlite."));
+        assert!(files.json.starts_with("snapshot.") && files.json.ends_with(".json"));
+        assert!(config.backups().join(&files.sqlite).is_file());
+
+        // The JSON snapshot holds the database contents
+        let snapshot = config
+            .backup()
+            .load_latest_json(SNAPSHOT)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(snapshot.transactions.data().len(), 1);
+        assert_eq!(snapshot.transactions.data()[0].transaction_id, "txn-001");
+    }
+}
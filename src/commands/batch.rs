@@ -0,0 +1,78 @@
+//! The report returned by commands that update or delete several rows at once.
+
+use crate::error::Res;
+use serde::Serialize;
+use std::fmt::Debug;
+use std::future::Future;
+
+/// The outcome of updating or deleting several rows. By default these commands are atomic, so
+/// either every row succeeds or the command fails and `failed` is empty. With `keep_going`, each
+/// row is processed on its own and the rows that could not be processed are listed in `failed`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchResult<T>
+where
+    T: Serialize + Clone + Debug,
+{
+    /// The updated rows, or the IDs or names of the deleted rows.
+    pub succeeded: Vec<T>,
+    /// The rows that could not be updated or deleted, with the reason for each.
+    pub failed: Vec<BatchFailure>,
+}
+
+/// A row that could not be updated or deleted when using `keep_going`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BatchFailure {
+    /// The ID or name of the row, as it was given.
+    pub id: String,
+    /// Why the row could not be updated or deleted.
+    pub reason: String,
+}
+
+impl<T> BatchResult<T>
+where
+    T: Serialize + Clone + Debug,
+{
+    /// The result of an atomic operation, in which every row succeeded.
+    pub(super) fn atomic(succeeded: Vec<T>) -> Self {
+        Self {
+            succeeded,
+            failed: Vec::new(),
+        }
+    }
+
+    /// Describes the result, e.g. `Updated 2 transactions, 1 failed`. `past` is the verb, e.g.
+    /// `Updated`, and `one` and `many` are the noun for one row and for any other count of rows.
+    pub(super) fn message(&self, past: &str, one: &str, many: &str) -> String {
+        let count = self.succeeded.len();
+        let noun = if count == 1 { one } else { many };
+        let mut message = format!("{past} {count} {noun}");
+        if !self.failed.is_empty() {
+            message.push_str(&format!(", {} failed", self.failed.len()));
+            for failure in &self.failed {
+                message.push_str(&format!("\n  {}: {}", failure.id, failure.reason));
+            }
+        }
+        message
+    }
+}
+
+/// Runs `op` for each of `ids` in turn, continuing past failures. `op` processes a single row,
+/// atomically, and returns the rows it processed.
+pub(super) async fn keep_going<T, F, Fut>(ids: &[String], mut op: F) -> BatchResult<T>
+where
+    T: Serialize + Clone + Debug,
+    F: FnMut(&str) -> Fut,
+    Fut: Future<Output = Res<Vec<T>>>,
+{
+    let mut result = BatchResult::atomic(Vec::new());
+    for id in ids {
+        match op(id).await {
+            Ok(rows) => result.succeeded.extend(rows),
+            Err(e) => result.failed.push(BatchFailure {
+                id: id.clone(),
+                reason: format!("{e:#}"),
+            }),
+        }
+    }
+    result
+}
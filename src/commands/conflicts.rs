@@ -0,0 +1,381 @@
+//! Commands that find and resolve differences between the Google sheet and the last sync down.
+
+use crate::api::{sheet, tiller, Mode, Tiller};
+use crate::args::{ConflictChoice, ResolveConflictArgs};
+use crate::backup::SYNC_DOWN;
+use crate::commands::diff::{diff_items, is_volatile_transaction_field};
+use crate::commands::Out;
+use crate::error::{ErrorType, IntoResult};
+use crate::model::{Item, TillerData, Transactions};
+use crate::{Config, Result};
+use anyhow::anyhow;
+use serde::Serialize;
+
+/// A transaction field whose value in the Google sheet differs from its value at the last sync
+/// down. Unless resolved, `sync up` refuses to run (or, with `--force`, overwrites the sheet's
+/// value with the local one).
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldConflict {
+    /// The ID of the transaction.
+    pub transaction_id: String,
+    /// The sheet header of the field, e.g. `Category`.
+    pub field: String,
+    /// The value at the last sync down.
+    pub base: String,
+    /// The value now in the Google sheet.
+    pub remote: String,
+    /// The value in the local database, or `None` if the transaction was deleted locally.
+    pub local: Option<String>,
+}
+
+/// Compares the transactions in the Google sheet with those from the last sync down and reports
+/// each field that was changed in the sheet.
+///
+/// Only transactions that exist in both are compared; rows added to or removed from the sheet are
+/// not reported.
+///
+/// # Arguments
+///
+/// - `config` - The application configuration.
+/// - `mode` - Whether to use the live Google sheet or in-memory test data.
+///
+/// # Returns
+///
+/// On success, returns an `Out` containing:
+/// - A message listing each conflict.
+/// - A `FieldConflict` for each changed field, ordered as the transactions appear in the sheet.
+///
+/// # Errors
+///
+/// - Returns an error if there is no sync-down backup to compare against.
+/// - Returns an error if the sheet cannot be accessed.
+/// - Returns an error if a database operation fails.
+pub async fn list_conflicts(config: Config, mode: Mode) -> Result<Out<Vec<FieldConflict>>> {
+    let (remote, base) = remote_and_base(&config, mode).await?;
+
+    let mut conflicts = Vec::new();
+    for (transaction_id, field, base_value, remote_value) in
+        changed_fields(&remote.transactions, &base.transactions)
+    {
+        let local = config
+            .db()
+            ._get_transaction(&transaction_id)
+            .await
+            .pub_result(ErrorType::Database)?
+            .map(|t| t.get_by_header(&field));
+        conflicts.push(FieldConflict {
+            transaction_id,
+            field,
+            base: base_value,
+            remote: remote_value,
+            local,
+        });
+    }
+
+    let count = conflicts.len();
+    let mut message = format!(
+        "Found {} conflict{} between the sheet and the last sync down",
+        count,
+        if count == 1 { "" } else { "s" }
+    );
+    for c in &conflicts {
+        message.push_str(&format!(
+            "\n  {} {}: base '{}', remote '{}', local '{}'",
+            c.transaction_id,
+            c.field,
+            c.base,
+            c.remote,
+            c.local.as_deref().unwrap_or("(deleted)")
+        ));
+    }
+
+    Ok(Out::new(message, conflicts))
+}
+
+/// Resolves one conflict reported by [`list_conflicts`].
+///
+/// - `local`: the local database is left as is, so the next `sync up` writes the local value to
+///   the sheet.
+/// - `remote`: the sheet's value is written to the local database.
+///
+/// Either way, the sheet's value is recorded as the new baseline for the field, so the conflict is
+/// no longer reported. Once every conflict is resolved, `sync up` can run without `--force`.
+///
+/// # Arguments
+///
+/// - `config` - The application configuration.
+/// - `mode` - Whether to use the live Google sheet or in-memory test data.
+/// - `args` - The transaction ID and field of the conflict, and which value to keep.
+///
+/// # Returns
+///
+/// On success, returns an `Out` containing:
+/// - A message describing which value was kept.
+/// - The `FieldConflict` that was resolved.
+///
+/// # Errors
+///
+/// - Returns an error if there is no conflict for the given transaction and field.
+/// - Returns an error if `remote` is chosen for a transaction that was deleted locally.
+/// - Returns an error if the sheet cannot be accessed or a database operation fails.
+pub async fn resolve_conflict(
+    config: Config,
+    mode: Mode,
+    args: ResolveConflictArgs,
+) -> Result<Out<FieldConflict>> {
+    let (remote, mut base) = remote_and_base(&config, mode).await?;
+
+    let (_, field, base_value, remote_value) =
+        changed_fields(&remote.transactions, &base.transactions)
+            .into_iter()
+            .find(|(id, field, _, _)| id == &args.transaction_id && field == &args.field)
+            .ok_or_else(|| {
+                anyhow!(
+                    "No conflict found for field '{}' of transaction {}",
+                    args.field,
+                    args.transaction_id
+                )
+            })
+            .pub_result(ErrorType::Request)?;
+
+    let db = config.db();
+    let mut local = db
+        ._get_transaction(&args.transaction_id)
+        .await
+        .pub_result(ErrorType::Database)?;
+
+    if args.choose == ConflictChoice::Remote {
+        let transaction = local
+            .as_mut()
+            .ok_or_else(|| {
+                anyhow!(
+                    "Transaction {} no longer exists in the local database",
+                    args.transaction_id
+                )
+            })
+            .pub_result(ErrorType::Request)?;
+        transaction
+            .set_with_header(&field, remote_value.clone())
+            .pub_result(ErrorType::Request)?;
+        db.update_transaction(transaction)
+            .await
+            .pub_result(ErrorType::Database)?;
+    }
+
+    // Record the sheet's value as the new baseline so that the conflict is not reported again
+    if let Some(transaction) = base
+        .transactions
+        .data_mut()
+        .iter_mut()
+        .find(|t| t.transaction_id == args.transaction_id)
+    {
+        transaction
+            .set_with_header(&field, remote_value.clone())
+            .pub_result(ErrorType::Internal)?;
+    }
+    if !config.dry_run() {
+        config
+            .backup()
+            .save_json(SYNC_DOWN, &base)
+            .await
+            .pub_result(ErrorType::Internal)?;
+    }
+
+    let conflict = FieldConflict {
+        transaction_id: args.transaction_id,
+        field,
+        base: base_value,
+        remote: remote_value,
+        local: local.map(|t| t.get_by_header(&args.field)),
+    };
+    let message = format!(
+        "Resolved conflict in field '{}' of transaction {} by keeping the {} value",
+        conflict.field, conflict.transaction_id, args.choose
+    );
+
+    Ok(Out::new(message, conflict).with_dry_run(config.dry_run()))
+}
+
+/// Downloads the current sheet data and loads the data from the last sync down.
+async fn remote_and_base(config: &Config, mode: Mode) -> Result<(TillerData, TillerData)> {
+    let base = config
+        .backup()
+        .load_latest_json(SYNC_DOWN)
+        .await
+        .pub_result(ErrorType::Internal)?
+        .ok_or_else(|| anyhow!("No sync-down backup found. Run 'tiller sync down' first"))
+        .pub_result(ErrorType::Sync)?;
+
+    let sheet_client = sheet(config.clone(), mode).await?;
+    let mut tiller_client = tiller(
+        sheet_client,
+        config.sync_balances(),
+        config.atomic_write(),
+        config.verify_headers(),
+    )
+    .await
+    .pub_result(ErrorType::Internal)?;
+    let remote = tiller_client.get_data().await.pub_result(ErrorType::Sync)?;
+
+    Ok((remote, base))
+}
+
+/// Returns `(transaction_id, field, base_value, remote_value)` for every field of every
+/// transaction in both `remote` and `base` whose values differ, as reported by `diff_items`. See
+/// `is_volatile_transaction_field` for the fields that are not compared.
+fn changed_fields(
+    remote: &Transactions,
+    base: &Transactions,
+) -> Vec<(String, String, String, String)> {
+    diff_items(
+        base.data(),
+        remote.data(),
+        remote.mapping(),
+        |t| t.transaction_id.clone(),
+        is_volatile_transaction_field,
+    )
+    .modified
+    .into_iter()
+    .map(|c| (c.key, c.field, c.before, c.after))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::sync_down;
+    use crate::test::TestEnv;
+
+    /// The ID of the first transaction in the seed data, in the Groceries category.
+    const FIRST_ID: &str = "tx001a2b3c4d5e6f7g8h9i01";
+
+    /// Changes the category of the first transaction in the test sheet to `category`.
+    fn edit_remote_category(env: &TestEnv, category: &str) {
+        let mut state = env.get_state();
+        // Row 0 is the header row and column 3 is Category
+        state.data.get_mut("Transactions").unwrap()[1][3] = category.to_string();
+        env.set_state(state);
+    }
+
+    #[tokio::test]
+    async fn test_list_conflicts_none_after_sync_down() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        let out = list_conflicts(config, Mode::Testing).await.unwrap();
+        assert!(out.structure().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_conflict_local() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        // The category changes both in the sheet and locally
+        edit_remote_category(&env, "Coffee Shops");
+        let mut local = config
+            .db()
+            ._get_transaction(FIRST_ID)
+            .await
+            .unwrap()
+            .unwrap();
+        local.category = "Restaurants".to_string();
+        config.db().update_transaction(&local).await.unwrap();
+
+        let out = list_conflicts(config.clone(), Mode::Testing).await.unwrap();
+        let conflicts = out.structure().unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].transaction_id, FIRST_ID);
+        assert_eq!(conflicts[0].field, "Category");
+        assert_eq!(conflicts[0].base, "Groceries");
+        assert_eq!(conflicts[0].remote, "Coffee Shops");
+        assert_eq!(conflicts[0].local.as_deref(), Some("Restaurants"));
+
+        let args = ResolveConflictArgs {
+            transaction_id: FIRST_ID.to_string(),
+            field: "Category".to_string(),
+            choose: ConflictChoice::Local,
+        };
+        resolve_conflict(config.clone(), Mode::Testing, args)
+            .await
+            .unwrap();
+
+        // The local value is preserved and the conflict is gone
+        let local = config
+            .db()
+            ._get_transaction(FIRST_ID)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(local.category, "Restaurants");
+        let out = list_conflicts(config, Mode::Testing).await.unwrap();
+        assert!(out.structure().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_conflict_remote() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        edit_remote_category(&env, "Coffee Shops");
+
+        let args = ResolveConflictArgs {
+            transaction_id: FIRST_ID.to_string(),
+            field: "Category".to_string(),
+            choose: ConflictChoice::Remote,
+        };
+        let out = resolve_conflict(config.clone(), Mode::Testing, args)
+            .await
+            .unwrap();
+        assert_eq!(
+            out.structure().unwrap().local.as_deref(),
+            Some("Coffee Shops")
+        );
+
+        let local = config
+            .db()
+            ._get_transaction(FIRST_ID)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(local.category, "Coffee Shops");
+        let out = list_conflicts(config, Mode::Testing).await.unwrap();
+        assert!(out.structure().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_conflicts_ignores_volatile_fields() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        // Column 14 is Date Added and column 15 is Categorized Date
+        let mut state = env.get_state();
+        let row = &mut state.data.get_mut("Transactions").unwrap()[1];
+        row[14] = "10/30/25".to_string();
+        row[15] = "10/30/2025 9:15:30 AM".to_string();
+        env.set_state(state);
+
+        let out = list_conflicts(config, Mode::Testing).await.unwrap();
+        assert!(out.structure().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_conflict_not_found() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        let args = ResolveConflictArgs {
+            transaction_id: FIRST_ID.to_string(),
+            field: "Category".to_string(),
+            choose: ConflictChoice::Local,
+        };
+        let err = resolve_conflict(config, Mode::Testing, args)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("No conflict found"));
+    }
+}
@@ -1,6 +1,7 @@
 //! Delete command handlers.
 
 use crate::args::{DeleteAutoCatsArgs, DeleteCategoriesArgs, DeleteTransactionsArgs};
+use crate::commands::batch::{self, BatchResult};
 use crate::commands::Out;
 use crate::error::{ErrorType, IntoResult};
 use crate::{Config, Result};
@@ -8,70 +9,85 @@ use crate::{Config, Result};
 /// Deletes one or more transactions by ID atomically.
 ///
 /// This operation is all-or-nothing: either all specified transactions are deleted, or none are.
-/// If any transaction ID is not found, the entire operation is rolled back.
+/// If any transaction ID is not found, the entire operation is rolled back. With `keep_going`,
+/// each transaction is deleted on its own and those that fail are reported instead.
 pub async fn delete_transactions(
     config: Config,
     args: DeleteTransactionsArgs,
-) -> Result<Out<Vec<String>>> {
-    let deleted = config
-        .db()
-        .delete_transactions(args)
+) -> Result<Out<BatchResult<String>>> {
+    let db = config.db();
+    let result = if args.keep_going() {
+        batch::keep_going(args.ids(), |id| {
+            let (db, args) = (db.clone(), args.single(id));
+            async move { db.delete_transactions(args).await }
+        })
         .await
-        .pub_result(ErrorType::Database)?;
-
-    let count = deleted.len();
-    let message = format!(
-        "Deleted {} transaction{}",
-        count,
-        if count == 1 { "" } else { "s" }
-    );
-    Ok(Out::new(message, deleted))
+    } else {
+        let deleted = db
+            .delete_transactions(args)
+            .await
+            .pub_result(ErrorType::Database)?;
+        BatchResult::atomic(deleted)
+    };
+    let message = result.message("Deleted", "transaction", "transactions");
+    Ok(Out::new(message, result).with_dry_run(config.dry_run()))
 }
 
 /// Deletes one or more categories by name atomically.
 ///
 /// This operation is all-or-nothing: either all specified categories are deleted, or none are.
-/// If any category is not found, the entire operation is rolled back.
+/// If any category is not found, the entire operation is rolled back. With `keep_going`, each
+/// category is deleted on its own and those that fail are reported instead.
 ///
 /// Due to `ON DELETE RESTRICT` foreign key constraints, a category cannot be deleted if any
 /// transactions or AutoCat rules reference it. Those references must be updated or removed first.
 pub async fn delete_categories(
     config: Config,
     args: DeleteCategoriesArgs,
-) -> Result<Out<Vec<String>>> {
-    let deleted = config
-        .db()
-        .delete_categories(args)
+) -> Result<Out<BatchResult<String>>> {
+    let db = config.db();
+    let result = if args.keep_going() {
+        batch::keep_going(args.names(), |name| {
+            let (db, args) = (db.clone(), args.single(name));
+            async move { db.delete_categories(args).await }
+        })
         .await
-        .pub_result(ErrorType::Database)?;
-
-    let count = deleted.len();
-    let message = format!(
-        "Deleted {} categor{}",
-        count,
-        if count == 1 { "y" } else { "ies" }
-    );
-    Ok(Out::new(message, deleted))
+    } else {
+        let deleted = db
+            .delete_categories(args)
+            .await
+            .pub_result(ErrorType::Database)?;
+        BatchResult::atomic(deleted)
+    };
+    let message = result.message("Deleted", "category", "categories");
+    Ok(Out::new(message, result).with_dry_run(config.dry_run()))
 }
 
 /// Deletes one or more AutoCat rules by ID atomically.
 ///
 /// This operation is all-or-nothing: either all specified rules are deleted, or none are.
-/// If any rule ID is not found, the entire operation is rolled back.
-pub async fn delete_autocats(config: Config, args: DeleteAutoCatsArgs) -> Result<Out<Vec<String>>> {
-    let deleted = config
-        .db()
-        .delete_autocats(args)
+/// If any rule ID is not found, the entire operation is rolled back. With `keep_going`, each rule
+/// is deleted on its own and those that fail are reported instead.
+pub async fn delete_autocats(
+    config: Config,
+    args: DeleteAutoCatsArgs,
+) -> Result<Out<BatchResult<String>>> {
+    let db = config.db();
+    let result = if args.keep_going() {
+        batch::keep_going(args.ids(), |id| {
+            let (db, args) = (db.clone(), args.single(id));
+            async move { db.delete_autocats(args).await }
+        })
         .await
-        .pub_result(ErrorType::Database)?;
-
-    let count = deleted.len();
-    let message = format!(
-        "Deleted {} AutoCat rule{}",
-        count,
-        if count == 1 { "" } else { "s" }
-    );
-    Ok(Out::new(message, deleted))
+    } else {
+        let deleted = db
+            .delete_autocats(args)
+            .await
+            .pub_result(ErrorType::Database)?;
+        BatchResult::atomic(deleted)
+    };
+    let message = result.message("Deleted", "AutoCat rule", "AutoCat rules");
+    Ok(Out::new(message, result).with_dry_run(config.dry_run()))
 }
 
 #[cfg(test)]
@@ -96,7 +112,7 @@ mod tests {
         assert!(result.is_ok());
         let out = result.unwrap();
         assert!(out.message().contains("Deleted 1 transaction"));
-        assert_eq!(out.structure().unwrap(), &vec![txn_id.to_string()]);
+        assert_eq!(out.structure().unwrap().succeeded, vec![txn_id.to_string()]);
 
         // Verify transaction no longer exists
         let deleted = env.config().db()._get_transaction(txn_id).await.unwrap();
@@ -177,7 +193,10 @@ mod tests {
         assert!(result.is_ok());
         let out = result.unwrap();
         assert!(out.message().contains("Deleted 1 category"));
-        assert_eq!(out.structure().unwrap(), &vec!["TestCategory".to_string()]);
+        assert_eq!(
+            out.structure().unwrap().succeeded,
+            vec!["TestCategory".to_string()]
+        );
 
         // Verify category no longer exists
         let deleted = env
@@ -212,8 +231,8 @@ mod tests {
         let out = result.unwrap();
         assert!(out.message().contains("Deleted 2 categories"));
         assert_eq!(
-            out.structure().unwrap(),
-            &vec!["Cat1".to_string(), "Cat2".to_string()]
+            out.structure().unwrap().succeeded,
+            vec!["Cat1".to_string(), "Cat2".to_string()]
         );
 
         // Verify Cat1 and Cat2 no longer exist
@@ -340,7 +359,7 @@ mod tests {
         assert!(result.is_ok());
         let out = result.unwrap();
         assert!(out.message().contains("Deleted 1 AutoCat rule"));
-        assert_eq!(out.structure().unwrap(), &vec!["1".to_string()]);
+        assert_eq!(out.structure().unwrap().succeeded, vec!["1".to_string()]);
 
         // Verify autocat no longer exists
         let deleted = env.config().db()._get_autocat("1").await.unwrap();
@@ -364,8 +383,8 @@ mod tests {
         let out = result.unwrap();
         assert!(out.message().contains("Deleted 2 AutoCat rules"));
         assert_eq!(
-            out.structure().unwrap(),
-            &vec!["1".to_string(), "2".to_string()]
+            out.structure().unwrap().succeeded,
+            vec!["1".to_string(), "2".to_string()]
         );
 
         // Verify both autocats no longer exist
@@ -410,4 +429,29 @@ mod tests {
         let autocat2_exists = env.config().db()._get_autocat("2").await.unwrap();
         assert!(autocat2_exists.is_some());
     }
+
+    #[tokio::test]
+    async fn test_delete_autocats_keep_going() {
+        let env = TestEnv::new().await;
+        env.insert_test_autocat_data().await;
+
+        let args = DeleteAutoCatsArgs::new(vec!["1", "999", "2", "abc"])
+            .unwrap()
+            .with_keep_going(true);
+        let out = delete_autocats(env.config(), args).await.unwrap();
+
+        let report = out.structure().unwrap();
+        assert_eq!(report.succeeded, vec!["1".to_string(), "2".to_string()]);
+        let failed: Vec<&str> = report.failed.iter().map(|f| f.id.as_str()).collect();
+        assert_eq!(failed, vec!["999", "abc"]);
+        assert!(report.failed[0].reason.contains("AutoCat rule not found"));
+        assert!(report.failed[1].reason.contains("Invalid autocat ID"));
+        assert!(out
+            .message()
+            .starts_with("Deleted 2 AutoCat rules, 2 failed"));
+
+        // The valid rules were deleted despite the failures
+        assert!(env.config().db()._get_autocat("1").await.unwrap().is_none());
+        assert!(env.config().db()._get_autocat("2").await.unwrap().is_none());
+    }
 }
@@ -0,0 +1,121 @@
+//! Commands that help debug how data is read from the Tiller sheet.
+
+use crate::api::{sheet, Mode, Sheet, TRANSACTIONS};
+use crate::commands::Out;
+use crate::error::{ErrorType, IntoResult};
+use crate::model::Transactions;
+use crate::{Config, Result};
+use serde::Serialize;
+
+/// A cell in the Transactions tab that holds a formula.
+#[derive(Debug, Clone, Serialize)]
+pub struct FormulaCell {
+    /// The 0-indexed data row (the header row is not counted). This is the row of the `RowCol`
+    /// under which the formula is stored.
+    pub row: usize,
+    /// The 0-indexed column. This is the column of the `RowCol` under which the formula is stored.
+    pub col: usize,
+    /// The header of the column the cell is in.
+    pub header: String,
+    /// The formula, exactly as returned by the Google Sheets API, e.g. `=ABS(E2)`.
+    pub formula: String,
+    /// The computed value of the cell.
+    pub value: String,
+}
+
+/// Fetches both the values and the formulas of the Transactions tab and reports every cell in
+/// which a formula was detected. These are the same formulas that are tracked for
+/// `--formulas preserve` during `sync up`.
+///
+/// # Arguments
+///
+/// - `config` - The application configuration.
+/// - `mode` - Whether to use the live Google sheet or in-memory test data.
+///
+/// # Returns
+///
+/// On success, returns an `Out` containing:
+/// - A message listing each formula and its coordinates.
+/// - A `FormulaCell` for each formula, ordered by row then column.
+///
+/// # Errors
+///
+/// - Returns an error if the sheet cannot be accessed.
+/// - Returns an error if the Transactions tab cannot be parsed.
+pub async fn diagnose_formulas(config: Config, mode: Mode) -> Result<Out<Vec<FormulaCell>>> {
+    let mut sheet_client = sheet(config, mode).await?;
+    let values = sheet_client
+        .get_formatted_values(TRANSACTIONS)
+        .await
+        .pub_result(ErrorType::Sync)?;
+    let formulas = sheet_client
+        .get_formulas(TRANSACTIONS)
+        .await
+        .pub_result(ErrorType::Sync)?;
+
+    let transactions = Transactions::parse(values.clone(), formulas).pub_result(ErrorType::Sync)?;
+    let headers = transactions.mapping().headers();
+
+    let cells: Vec<FormulaCell> = transactions
+        .formulas()
+        .iter()
+        .map(|(row_col, formula)| FormulaCell {
+            row: row_col.0,
+            col: row_col.1,
+            header: headers
+                .get(row_col.1)
+                .map(|h| h.as_ref().to_string())
+                .unwrap_or_default(),
+            formula: formula.clone(),
+            // The first row of `values` is the header row
+            value: values
+                .get(row_col.0 + 1)
+                .and_then(|row| row.get(row_col.1))
+                .cloned()
+                .unwrap_or_default(),
+        })
+        .collect();
+
+    let count = cells.len();
+    let mut message = format!(
+        "Found {} formula{} in {TRANSACTIONS}",
+        count,
+        if count == 1 { "" } else { "s" }
+    );
+    for cell in &cells {
+        message.push_str(&format!(
+            "\n  ({}, {}) {}: {} = {}",
+            cell.row, cell.col, cell.header, cell.formula, cell.value
+        ));
+    }
+
+    Ok(Out::new(message, cells))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::TestEnv;
+
+    #[tokio::test]
+    async fn test_diagnose_formulas_reports_seed_formulas() {
+        let env = TestEnv::new().await;
+        let out = diagnose_formulas(env.config(), Mode::Testing)
+            .await
+            .unwrap();
+        let cells = out.structure().unwrap();
+
+        // The seed data has an =ABS(E{n}) formula in "Custom Column" (index 16) of every row
+        assert_eq!(cells.len(), 20);
+        for (ix, cell) in cells.iter().enumerate() {
+            assert_eq!(cell.row, ix);
+            assert_eq!(cell.col, 16);
+            assert_eq!(cell.header, "Custom Column");
+            assert_eq!(cell.formula, format!("=ABS(E{})", ix + 2));
+        }
+        assert_eq!(cells[0].value, "87.43");
+        assert!(out
+            .message()
+            .contains("(0, 16) Custom Column: =ABS(E2) = 87.43"));
+    }
+}
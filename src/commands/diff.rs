@@ -0,0 +1,366 @@
+//! Implements comparison of two backup snapshots, and the row and field diffing shared with
+//! conflict detection.
+
+use crate::args::DiffArgs;
+use crate::commands::Out;
+use crate::error::{ErrorType, IntoResult};
+use crate::model::{Item, Mapping, TillerData, TransactionColumn};
+use crate::{Config, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// The differences between two backup snapshots, going from the first to the second.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SnapshotDiff {
+    /// Differences in the Transactions tab. Rows are identified by transaction ID.
+    pub transactions: ItemDiff,
+    /// Differences in the Categories tab. Rows are identified by category name.
+    pub categories: ItemDiff,
+    /// Differences in the AutoCat tab. Rules have no ID, so they are identified by their row
+    /// position (`original_order`) in the sheet.
+    pub autocats: ItemDiff,
+}
+
+/// The rows added, deleted and modified between two versions of a tab.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ItemDiff {
+    /// The keys of rows that exist only in the second version.
+    pub added: Vec<String>,
+    /// The keys of rows that exist only in the first version.
+    pub deleted: Vec<String>,
+    /// Each field whose value differs in a row that exists in both versions.
+    pub modified: Vec<FieldChange>,
+}
+
+impl ItemDiff {
+    /// Returns true if there are no differences.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.deleted.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// A field whose value differs between two versions of the same row.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    /// The key of the row, e.g. the transaction ID.
+    pub key: String,
+    /// The sheet header of the field, e.g. `Category`.
+    pub field: String,
+    /// The value in the first version.
+    pub before: String,
+    /// The value in the second version.
+    pub after: String,
+}
+
+/// Compares two JSON backup snapshots from the backups directory and reports the transactions,
+/// categories and AutoCat rules that were added, deleted or modified going from `args.a` to
+/// `args.b`.
+///
+/// # Arguments
+///
+/// - `config` - The application configuration.
+/// - `args` - The file names of the two snapshots, e.g. `sync-down.2025-12-14-001.json`.
+///
+/// # Returns
+///
+/// On success, returns an `Out` containing a summary message and the `SnapshotDiff`.
+///
+/// # Errors
+///
+/// - Returns an error if either snapshot does not exist in the backups directory.
+/// - Returns an error if either snapshot cannot be read or parsed.
+pub async fn diff_backups(config: Config, args: DiffArgs) -> Result<Out<SnapshotDiff>> {
+    let backup = config.backup();
+    let a = backup
+        .load_json(&args.a)
+        .await
+        .pub_result(ErrorType::Request)?;
+    let b = backup
+        .load_json(&args.b)
+        .await
+        .pub_result(ErrorType::Request)?;
+
+    let diff = diff_snapshots(&a, &b);
+
+    let mut message = format!("Differences from {} to {}:", args.a, args.b);
+    for (name, d) in [
+        ("transactions", &diff.transactions),
+        ("categories", &diff.categories),
+        ("autocat rules", &diff.autocats),
+    ] {
+        message.push_str(&format!(
+            "\n  {name}: {} added, {} deleted, {} fields modified",
+            d.added.len(),
+            d.deleted.len(),
+            d.modified.len()
+        ));
+    }
+
+    Ok(Out::new(message, diff))
+}
+
+/// Compares the transactions, categories and AutoCat rules of `before` and `after`.
+pub(super) fn diff_snapshots(before: &TillerData, after: &TillerData) -> SnapshotDiff {
+    SnapshotDiff {
+        transactions: diff_items(
+            before.transactions.data(),
+            after.transactions.data(),
+            after.transactions.mapping(),
+            |t| t.transaction_id.clone(),
+            is_volatile_transaction_field,
+        ),
+        categories: diff_items(
+            before.categories.data(),
+            after.categories.data(),
+            after.categories.mapping(),
+            |c| c.category.clone(),
+            |_| false,
+        ),
+        autocats: diff_items(
+            before.auto_cats.data(),
+            after.auto_cats.data(),
+            after.auto_cats.mapping(),
+            |a| a.get_original_order().unwrap_or_default().to_string(),
+            |_| false,
+        ),
+    }
+}
+
+/// Compares two versions of a tab, matching rows by `key`. Added and modified rows are reported in
+/// the order they appear in `after`, deleted rows in the order they appear in `before`.
+///
+/// Only the fields named by the headers of `mapping` are compared, leaving out those for which
+/// `skip` returns true, e.g. `is_volatile_transaction_field` for the Transactions tab.
+pub(super) fn diff_items<T, F, S>(
+    before: &[T],
+    after: &[T],
+    mapping: &Mapping,
+    key: F,
+    skip: S,
+) -> ItemDiff
+where
+    T: Item,
+    F: Fn(&T) -> String,
+    S: Fn(&str) -> bool,
+{
+    let before_by_key: BTreeMap<String, &T> = before.iter().map(|i| (key(i), i)).collect();
+    let after_by_key: BTreeMap<String, &T> = after.iter().map(|i| (key(i), i)).collect();
+
+    let mut diff = ItemDiff::default();
+    for after_item in after {
+        let item_key = key(after_item);
+        let Some(before_item) = before_by_key.get(&item_key) else {
+            diff.added.push(item_key);
+            continue;
+        };
+        for header in mapping.headers() {
+            let field = header.as_ref();
+            if skip(field) {
+                continue;
+            }
+            let before_value = before_item.get_by_header(field);
+            let after_value = after_item.get_by_header(field);
+            if before_value != after_value {
+                diff.modified.push(FieldChange {
+                    key: item_key.clone(),
+                    field: field.to_string(),
+                    before: before_value,
+                    after: after_value,
+                });
+            }
+        }
+    }
+    diff.deleted = before
+        .iter()
+        .map(&key)
+        .filter(|k| !after_by_key.contains_key(k))
+        .collect();
+    diff
+}
+
+/// Whether the Transactions column named `header` is volatile, see `TransactionColumn::is_volatile`.
+/// Changes to volatile fields such as `Date Added` are bookkeeping, not edits, and are not
+/// reported.
+pub(super) fn is_volatile_transaction_field(header: &str) -> bool {
+    TransactionColumn::from_header(header).is_ok_and(|c| c.is_volatile())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::SNAPSHOT;
+    use crate::model::{AutoCats, Categories, Transactions};
+    use crate::test::TestEnv;
+
+    /// Builds a snapshot from sheet rows, the first row of each tab being its headers.
+    fn snapshot(
+        transactions: Vec<Vec<&str>>,
+        categories: Vec<Vec<&str>>,
+        autocats: Vec<Vec<&str>>,
+    ) -> TillerData {
+        let none = Vec::<Vec<&str>>::new;
+        TillerData {
+            transactions: Transactions::parse(transactions, none()).unwrap(),
+            categories: Categories::parse(categories, none()).unwrap(),
+            auto_cats: AutoCats::parse(autocats, none()).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    const TXN_HEADERS: [&str; 4] = ["Transaction ID", "Description", "Category", "Date Added"];
+    const CAT_HEADERS: [&str; 2] = ["Category", "Group"];
+    const AC_HEADERS: [&str; 2] = ["Category", "Description Contains"];
+
+    fn before() -> TillerData {
+        snapshot(
+            vec![
+                TXN_HEADERS.to_vec(),
+                vec!["t1", "Coffee", "Food", "1/1/2025"],
+                vec!["t2", "Rent", "Home", "1/1/2025"],
+                vec!["t3", "Gas", "Auto", "1/1/2025"],
+            ],
+            vec![
+                CAT_HEADERS.to_vec(),
+                vec!["Food", "Living"],
+                vec!["Home", "Living"],
+                vec!["Auto", "Living"],
+            ],
+            vec![AC_HEADERS.to_vec(), vec!["Food", "coffee"]],
+        )
+    }
+
+    fn after() -> TillerData {
+        snapshot(
+            vec![
+                TXN_HEADERS.to_vec(),
+                // Category edited, and Date Added changed, which is not reported
+                vec!["t1", "Coffee", "Dining", "2/1/2025"],
+                vec!["t3", "Gas", "Auto", "1/1/2025"],
+                vec!["t4", "Books", "Fun", "2/1/2025"],
+            ],
+            vec![
+                CAT_HEADERS.to_vec(),
+                vec!["Food", "Eating"],
+                vec!["Auto", "Living"],
+                vec!["Dining", "Eating"],
+                vec!["Fun", "Leisure"],
+            ],
+            vec![
+                AC_HEADERS.to_vec(),
+                vec!["Dining", "coffee"],
+                vec!["Fun", "books"],
+            ],
+        )
+    }
+
+    #[test]
+    fn test_diff_snapshots() {
+        let diff = diff_snapshots(&before(), &after());
+
+        assert_eq!(diff.transactions.added, vec!["t4"]);
+        assert_eq!(diff.transactions.deleted, vec!["t2"]);
+        assert_eq!(
+            diff.transactions.modified,
+            vec![FieldChange {
+                key: "t1".to_string(),
+                field: "Category".to_string(),
+                before: "Food".to_string(),
+                after: "Dining".to_string(),
+            }]
+        );
+
+        assert_eq!(diff.categories.added, vec!["Dining", "Fun"]);
+        assert_eq!(diff.categories.deleted, vec!["Home"]);
+        assert_eq!(
+            diff.categories.modified,
+            vec![FieldChange {
+                key: "Food".to_string(),
+                field: "Group".to_string(),
+                before: "Living".to_string(),
+                after: "Eating".to_string(),
+            }]
+        );
+
+        // AutoCat rules are keyed by their row position
+        assert_eq!(diff.autocats.added, vec!["1"]);
+        assert!(diff.autocats.deleted.is_empty());
+        assert_eq!(
+            diff.autocats.modified,
+            vec![FieldChange {
+                key: "0".to_string(),
+                field: "Category".to_string(),
+                before: "Food".to_string(),
+                after: "Dining".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_snapshots_reports_date_added_outside_transactions() {
+        let categories = |date_added: &str| {
+            snapshot(
+                vec![TXN_HEADERS.to_vec()],
+                vec![
+                    vec!["Category", "Group", "Date Added"],
+                    vec!["Food", "Living", date_added],
+                ],
+                vec![AC_HEADERS.to_vec()],
+            )
+        };
+        let diff = diff_snapshots(&categories("1/1/2025"), &categories("2/1/2025"));
+        assert_eq!(
+            diff.categories.modified,
+            vec![FieldChange {
+                key: "Food".to_string(),
+                field: "Date Added".to_string(),
+                before: "1/1/2025".to_string(),
+                after: "2/1/2025".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_snapshots_identical() {
+        let diff = diff_snapshots(&before(), &before());
+        assert!(diff.transactions.is_empty());
+        assert!(diff.categories.is_empty());
+        assert!(diff.autocats.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_diff_backups_by_name() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        let a = config
+            .backup()
+            .save_json(SNAPSHOT, &before())
+            .await
+            .unwrap();
+        let b = config.backup().save_json(SNAPSHOT, &after()).await.unwrap();
+        let name = |p: &std::path::Path| p.file_name().unwrap().to_string_lossy().to_string();
+
+        let args = DiffArgs {
+            a: name(&a),
+            b: name(&b),
+        };
+        let out = diff_backups(config.clone(), args).await.unwrap();
+        let diff = out.structure().unwrap();
+        assert_eq!(diff.transactions.added, vec!["t4"]);
+        assert_eq!(diff.transactions.deleted, vec!["t2"]);
+        assert!(out
+            .message()
+            .contains("transactions: 1 added, 1 deleted, 1 fields modified"));
+
+        let args = DiffArgs {
+            a: name(&a),
+            b: "snapshot.1999-01-01-001.json".to_string(),
+        };
+        assert!(diff_backups(config.clone(), args).await.is_err());
+
+        let args = DiffArgs {
+            a: name(&a),
+            b: "../config.json".to_string(),
+        };
+        assert!(diff_backups(config.clone(), args).await.is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::api::SecretFile;
 use crate::commands::Out;
 use crate::error::{ErrorType, IntoResult};
 use crate::{Config, Result};
@@ -8,6 +9,10 @@ use std::path::Path;
 /// - Creates an initial `config.json` file using `sheet_url` along with default settings
 /// - Copies `secret_file` into its default location in the data dir.
 ///
+/// When `dry_run` is true, the credentials and `sheet_url` are still checked, but nothing is
+/// created. Instead, the returned message lists the paths that would be created and the
+/// spreadsheet ID extracted from `sheet_url`.
+///
 /// # Arguments
 /// - `tiller_home` - The directory that will be the root of data directory, e.g. `$HOME/tiller`
 /// - `secret_file` - The downloaded OAuth 2.0 client credentials JSON needed to start the Google
@@ -15,13 +20,164 @@ use std::path::Path;
 ///   name in the data directory.
 /// - `sheet_url` - The URL of the Google Sheet where the Tiller financial data is stored.
 ///   e.g.https://docs.google.com/spreadsheets/d/1a7Km9FxQwRbPt82JvN4LzYpH5OcGnWsT6iDuE3VhMjX
+/// - `dry_run` - Report what would be created without touching the filesystem.
 ///
 /// # Errors
+/// - Returns an error if `secret_file` is not the credentials file of a Google OAuth client for a
+///   desktop app, e.g. if it is for a web application client or is missing values.
+/// - Returns an error if the spreadsheet ID cannot be extracted from `sheet_url`.
 /// - Returns an error if any file operations fail.
-pub async fn init(tiller_home: &Path, secret_file: &Path, url: &str) -> Result<Out<()>> {
-    let _config = Config::create(tiller_home, secret_file, url)
+pub async fn init(
+    tiller_home: &Path,
+    secret_file: &Path,
+    url: &str,
+    dry_run: bool,
+) -> Result<Out<()>> {
+    // Check the credentials now, otherwise a wrong file only fails later during `tiller auth`
+    SecretFile::load(secret_file).await?;
+
+    if dry_run {
+        let (paths, spreadsheet_id) = Config::plan_create(tiller_home, url)?;
+        let mut message = format!("Dry run: spreadsheet ID is {spreadsheet_id}, would create:");
+        for path in paths {
+            message.push_str(&format!("\n  {}", path.display()));
+        }
+        return Ok(message.into());
+    }
+
+    let config = Config::create(tiller_home, secret_file, url)
         .await
         .context("Unable to create the data directory and configs")
         .pub_result(ErrorType::Config)?;
+    config.close().await;
     Ok("Successfully created the tiller directory and config".into())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const URL: &str =
+        "https://docs.google.com/spreadsheets/d/7KpXm2RfZwNJgs84QhVYno5DU6iM9Wlr3bCzAv1txRpL";
+
+    fn credentials(kind: &str) -> String {
+        format!(
+            r#"{{
+                "{kind}": {{
+                    "client_id": "test-client-id",
+                    "client_secret": "test-secret",
+                    "redirect_uris": ["http://localhost"],
+                    "auth_uri": "https://accounts.google.com/o/oauth2/auth",
+                    "token_uri": "https://oauth2.googleapis.com/token"
+                }}
+            }}"#
+        )
+    }
+
+    #[tokio::test]
+    async fn test_init_with_installed_credentials() {
+        let dir = TempDir::new().unwrap();
+        let secret = dir.path().join("client_secret.json");
+        std::fs::write(&secret, credentials("installed")).unwrap();
+
+        let home = dir.path().join("tiller");
+        init(&home, &secret, URL, false).await.unwrap();
+        assert!(home.join("config.json").is_file());
+    }
+
+    #[tokio::test]
+    async fn test_init_rejects_web_credentials() {
+        let dir = TempDir::new().unwrap();
+        let secret = dir.path().join("client_secret.json");
+        std::fs::write(&secret, credentials("web")).unwrap();
+
+        let home = dir.path().join("tiller");
+        let err = init(&home, &secret, URL, false).await.unwrap_err();
+        assert!(format!("{err:?}").contains("Desktop app"), "{err:?}");
+        assert!(!home.exists());
+    }
+
+    #[tokio::test]
+    async fn test_init_failure_removes_what_it_created() {
+        let dir = TempDir::new().unwrap();
+        let secret = dir.path().join("client_secret.json");
+        std::fs::write(&secret, credentials("installed")).unwrap();
+
+        // The URL is only checked after the secret is copied and config.json is written
+        let home = dir.path().join("tiller");
+        let err = init(&home, &secret, "https://example.com/not-a-sheet", false)
+            .await
+            .unwrap_err();
+        assert!(format!("{err:?}").contains("spreadsheet ID"), "{err:?}");
+        assert!(!home.exists());
+        assert_eq!(
+            std::fs::read_to_string(&secret).unwrap(),
+            credentials("installed")
+        );
+
+        // Nothing is left behind to make a second attempt fail
+        init(&home, &secret, URL, false).await.unwrap();
+        assert!(home.join("config.json").is_file());
+    }
+
+    #[tokio::test]
+    async fn test_init_failure_keeps_an_existing_home_dir() {
+        let dir = TempDir::new().unwrap();
+        let secret = dir.path().join("client_secret.json");
+        std::fs::write(&secret, credentials("installed")).unwrap();
+        let home = dir.path().join("tiller");
+        std::fs::create_dir(&home).unwrap();
+        std::fs::write(home.join("notes.txt"), "mine").unwrap();
+
+        init(&home, &secret, "https://example.com/not-a-sheet", false)
+            .await
+            .unwrap_err();
+        let mut left: Vec<String> = std::fs::read_dir(&home)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["notes.txt"]);
+        assert!(secret.is_file());
+    }
+
+    #[tokio::test]
+    async fn test_init_failure_restores_overwritten_files() {
+        let dir = TempDir::new().unwrap();
+        let secret = dir.path().join("client_secret.json");
+        std::fs::write(&secret, credentials("installed")).unwrap();
+        let home = dir.path().join("tiller");
+        std::fs::create_dir_all(home.join(".secrets")).unwrap();
+        std::fs::write(home.join("config.json"), "old config").unwrap();
+        std::fs::write(home.join(".secrets/client_secret.json"), "old secret").unwrap();
+
+        init(&home, &secret, "https://example.com/not-a-sheet", false)
+            .await
+            .unwrap_err();
+        let read = |path: &str| std::fs::read_to_string(home.join(path)).unwrap();
+        assert_eq!(read("config.json"), "old config");
+        assert_eq!(read(".secrets/client_secret.json"), "old secret");
+        assert!(!home.join(".backups").exists());
+    }
+
+    #[tokio::test]
+    async fn test_init_dry_run_creates_nothing() {
+        let dir = TempDir::new().unwrap();
+        let secret = dir.path().join("client_secret.json");
+        std::fs::write(&secret, credentials("installed")).unwrap();
+
+        let home = dir.path().join("tiller");
+        std::fs::create_dir(&home).unwrap();
+        let out = init(&home, &secret, URL, true).await.unwrap();
+
+        assert!(out
+            .message()
+            .contains("spreadsheet ID is 7KpXm2RfZwNJgs84QhVYno5DU6iM9Wlr3bCzAv1txRpL"));
+        assert!(out
+            .message()
+            .contains(&home.join("config.json").display().to_string()));
+        assert_eq!(std::fs::read_dir(&home).unwrap().count(), 0);
+        assert!(secret.is_file());
+    }
+}
@@ -4,14 +4,22 @@ use crate::args::{InsertAutoCatArgs, InsertCategoryArgs, InsertTransactionArgs};
 use crate::commands::Out;
 use crate::error::{ErrorType, IntoResult};
 use crate::model::{AutoCat, Category, Transaction};
-use crate::utils::generate_transaction_id;
+use crate::utils::{self, generate_transaction_id};
 use crate::{Config, Result};
+use chrono::{Local, NaiveDate, NaiveDateTime};
 
 /// Inserts a new transaction into the local SQLite database.
 ///
 /// A unique transaction ID is automatically generated with a `user-` prefix to distinguish it
 /// from Tiller-created transactions. The generated ID is returned on success.
 ///
+/// When `month` or `week` is left blank, it is derived from `date` the way Tiller does, e.g.
+/// `10/1/25` and `10/19/25` for `10/20/2025`, so that the sheet's monthly and weekly reports
+/// include the transaction. They are left blank if `date` cannot be parsed.
+///
+/// When `date_added` is left blank, it is set to today, e.g. `10/21/25`. When a `category` is
+/// given and `categorized_date` is left blank, it is set to now, e.g. `10/21/2025 9:15:30 AM`.
+///
 /// # Arguments
 ///
 /// - `config` - The application configuration containing the database connection.
@@ -31,10 +39,39 @@ use crate::{Config, Result};
 pub async fn insert_transaction(
     config: Config,
     args: InsertTransactionArgs,
+) -> Result<Out<String>> {
+    insert_transaction_at(config, args, Local::now().naive_local()).await
+}
+
+/// Inserts a new transaction as `insert_transaction` does, with `now` as the current time.
+async fn insert_transaction_at(
+    config: Config,
+    args: InsertTransactionArgs,
+    now: NaiveDateTime,
 ) -> Result<Out<String>> {
     // Generate a unique transaction ID
     let transaction_id = generate_transaction_id();
 
+    // Derive Month and Week from the date, as Tiller does, unless they were given
+    let date = utils::parse_date(&args.date).ok();
+    let derive = |given: Option<String>, derived: fn(NaiveDate) -> String| {
+        non_blank(given)
+            .or_else(|| date.map(derived))
+            .unwrap_or_default()
+    };
+    let month = derive(args.month, utils::tiller_month);
+    let week = derive(args.week, utils::tiller_week);
+
+    // Stamp when the transaction was added and, if it has a category, categorized
+    let date_added = non_blank(args.date_added).unwrap_or_else(|| utils::tiller_date(now.date()));
+    let categorized_date = non_blank(args.categorized_date).unwrap_or_else(|| {
+        if non_blank(args.category.clone()).is_some() {
+            utils::tiller_timestamp(now)
+        } else {
+            String::new()
+        }
+    });
+
     // Build the Transaction object from args
     let transaction = Transaction {
         transaction_id: transaction_id.clone(),
@@ -44,18 +81,18 @@ pub async fn insert_transaction(
         account: args.account.unwrap_or_default(),
         account_number: args.account_number.unwrap_or_default(),
         institution: args.institution.unwrap_or_default(),
-        month: args.month.unwrap_or_default(),
-        week: args.week.unwrap_or_default(),
+        month,
+        week,
         full_description: args.full_description.unwrap_or_default(),
         account_id: args.account_id.unwrap_or_default(),
         check_number: args.check_number.unwrap_or_default(),
-        date_added: args.date_added.unwrap_or_default(),
+        date_added,
         merchant_name: args.merchant_name.unwrap_or_default(),
         category_hint: args.category_hint.unwrap_or_default(),
         category: args.category.clone().unwrap_or_default(),
         note: args.note.unwrap_or_default(),
         tags: args.tags.unwrap_or_default(),
-        categorized_date: args.categorized_date.unwrap_or_default(),
+        categorized_date,
         statement: args.statement.unwrap_or_default(),
         metadata: args.metadata.unwrap_or_default(),
         no_name: String::new(),
@@ -84,7 +121,12 @@ pub async fn insert_transaction(
         .pub_result(ErrorType::Database)?;
 
     let message = format!("Inserted transaction with ID: {}", transaction_id);
-    Ok(Out::new(message, transaction_id))
+    Ok(Out::new(message, transaction_id).with_dry_run(config.dry_run()))
+}
+
+/// Returns `value` unless it is missing or blank.
+fn non_blank(value: Option<String>) -> Option<String> {
+    value.filter(|value| !value.trim().is_empty())
 }
 
 /// Inserts a new category into the local SQLite database.
@@ -104,6 +146,7 @@ pub async fn insert_transaction(
 ///
 /// # Errors
 ///
+/// - Returns an error if `type` is not one of "Expense", "Income" or "Transfer" (or empty).
 /// - Returns an error if a category with the same name already exists.
 /// - Returns an error if a database operation fails.
 pub async fn insert_category(config: Config, args: InsertCategoryArgs) -> Result<Out<String>> {
@@ -116,6 +159,7 @@ pub async fn insert_category(config: Config, args: InsertCategoryArgs) -> Result
         other_fields: args.other_fields,
         original_order: None, // Locally-added rows have no original order
     };
+    category.validate().pub_result(ErrorType::Request)?;
 
     // Insert into database
     config
@@ -134,13 +178,16 @@ pub async fn insert_category(config: Config, args: InsertCategoryArgs) -> Result
         .pub_result(ErrorType::Database)?;
 
     let message = format!("Inserted category: {}", args.name);
-    Ok(Out::new(message, args.name))
+    Ok(Out::new(message, args.name).with_dry_run(config.dry_run()))
 }
 
 /// Inserts a new AutoCat rule into the local SQLite database.
 ///
 /// The primary key is auto-generated (synthetic auto-increment) and returned on success.
 ///
+/// With `idempotent`, if a rule with the same category, overrides and filter criteria already
+/// exists, nothing is inserted and the existing rule's ID is returned instead.
+///
 /// # Arguments
 ///
 /// - `config` - The application configuration containing the database connection.
@@ -150,8 +197,8 @@ pub async fn insert_category(config: Config, args: InsertCategoryArgs) -> Result
 /// # Returns
 ///
 /// On success, returns an `Out` containing:
-/// - A message indicating the AutoCat rule was inserted.
-/// - The generated AutoCat ID as a string.
+/// - A message indicating the AutoCat rule was inserted, or that it already existed.
+/// - The generated, or existing, AutoCat ID as a string.
 ///
 /// # Errors
 ///
@@ -176,6 +223,19 @@ pub async fn insert_autocat(config: Config, args: InsertAutoCatArgs) -> Result<O
         original_order: None, // Locally-added rows have no original order
     };
 
+    if args.idempotent {
+        let existing = config
+            .db()
+            .find_autocat_by_criteria(&autocat)
+            .await
+            .pub_result(ErrorType::Database)?;
+        if let Some(existing) = existing {
+            let id_str = existing.id.to_string();
+            let message = format!("AutoCat rule already exists with ID: {}", id_str);
+            return Ok(Out::new(message, id_str));
+        }
+    }
+
     // Insert into database and get the generated ID
     let id = config
         .db()
@@ -198,7 +258,7 @@ pub async fn insert_autocat(config: Config, args: InsertAutoCatArgs) -> Result<O
 
     let id_str = id.to_string();
     let message = format!("Inserted AutoCat rule with ID: {}", id_str);
-    Ok(Out::new(message, id_str))
+    Ok(Out::new(message, id_str).with_dry_run(config.dry_run()))
 }
 
 #[cfg(test)]
@@ -431,6 +491,157 @@ mod tests {
         assert_eq!(txn.account, "");
     }
 
+    /// Returns args for a transaction with only a date and an amount.
+    fn dated_transaction_args(date: &str) -> InsertTransactionArgs {
+        InsertTransactionArgs {
+            date: date.to_string(),
+            amount: Amount::new(rust_decimal::Decimal::new(-500, 2)), // -5.00
+            description: None,
+            account: None,
+            account_number: None,
+            institution: None,
+            month: None,
+            week: None,
+            full_description: None,
+            account_id: None,
+            check_number: None,
+            date_added: None,
+            merchant_name: None,
+            category_hint: None,
+            category: None,
+            note: None,
+            tags: None,
+            categorized_date: None,
+            statement: None,
+            metadata: None,
+            other_fields: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_insert_transaction_derives_month_and_week() {
+        let env = TestEnv::new().await;
+
+        let args = dated_transaction_args("10/20/2025");
+        let out = insert_transaction(env.config(), args).await.unwrap();
+        let id = out.structure().unwrap();
+        let txn = env
+            .config()
+            .db()
+            ._get_transaction(id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(txn.month, "10/1/25");
+        assert_eq!(txn.week, "10/19/25");
+
+        // ISO dates are accepted too
+        let args = dated_transaction_args("2026-01-02");
+        let out = insert_transaction(env.config(), args).await.unwrap();
+        let id = out.structure().unwrap();
+        let txn = env
+            .config()
+            .db()
+            ._get_transaction(id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(txn.month, "1/1/26");
+        assert_eq!(txn.week, "12/28/25");
+    }
+
+    #[tokio::test]
+    async fn test_insert_transaction_keeps_given_month_and_week() {
+        let env = TestEnv::new().await;
+
+        let args = InsertTransactionArgs {
+            month: Some("9/1/25".to_string()),
+            week: Some("10/20/25".to_string()),
+            ..dated_transaction_args("10/20/2025")
+        };
+        let out = insert_transaction(env.config(), args).await.unwrap();
+        let id = out.structure().unwrap();
+        let txn = env
+            .config()
+            .db()
+            ._get_transaction(id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(txn.month, "9/1/25");
+        assert_eq!(txn.week, "10/20/25");
+    }
+
+    /// The time that the seed data's first transaction was categorized.
+    fn seed_categorized_time() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 10, 21)
+            .unwrap()
+            .and_hms_opt(9, 15, 30)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_insert_transaction_stamps_dates() {
+        let env = TestEnv::new().await;
+        env.insert_test_transaction("txn-seed").await;
+        let now = seed_categorized_time();
+
+        let args = InsertTransactionArgs {
+            category: Some("Food".to_string()),
+            ..dated_transaction_args("10/20/2025")
+        };
+        let out = insert_transaction_at(env.config(), args, now)
+            .await
+            .unwrap();
+        let id = out.structure().unwrap();
+        let txn = env
+            .config()
+            .db()
+            ._get_transaction(id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(txn.date_added, "10/21/25");
+        assert_eq!(txn.categorized_date, "10/21/2025 9:15:30 AM");
+
+        // Without a category the transaction has not been categorized
+        let args = dated_transaction_args("10/20/2025");
+        let out = insert_transaction_at(env.config(), args, now)
+            .await
+            .unwrap();
+        let id = out.structure().unwrap();
+        let txn = env
+            .config()
+            .db()
+            ._get_transaction(id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(txn.date_added, "10/21/25");
+        assert_eq!(txn.categorized_date, "");
+
+        // Given values are kept
+        let args = InsertTransactionArgs {
+            category: Some("Food".to_string()),
+            date_added: Some("10/20/25".to_string()),
+            categorized_date: Some("10/20/2025 8:00:00 PM".to_string()),
+            ..dated_transaction_args("10/20/2025")
+        };
+        let out = insert_transaction_at(env.config(), args, now)
+            .await
+            .unwrap();
+        let id = out.structure().unwrap();
+        let txn = env
+            .config()
+            .db()
+            ._get_transaction(id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(txn.date_added, "10/20/25");
+        assert_eq!(txn.categorized_date, "10/20/2025 8:00:00 PM");
+    }
+
     // ==================== insert_category tests ====================
 
     #[tokio::test]
@@ -493,6 +704,30 @@ mod tests {
         assert_eq!(cat.r#type, "");
     }
 
+    #[tokio::test]
+    async fn test_insert_category_invalid_type_error() {
+        let env = TestEnv::new().await;
+
+        let args = InsertCategoryArgs {
+            name: "Groceries".to_string(),
+            group: Some("Food".to_string()),
+            r#type: Some("Expnse".to_string()),
+            hide_from_reports: None,
+            other_fields: Default::default(),
+        };
+
+        let result = insert_category(env.config(), args).await;
+        let err_msg = result.unwrap_err().to_string();
+        assert!(
+            err_msg.contains("Invalid category type 'Expnse'"),
+            "Unexpected error message: {err_msg}"
+        );
+
+        // Nothing was inserted
+        let cat = env.config().db()._get_category("Groceries").await.unwrap();
+        assert!(cat.is_none());
+    }
+
     #[tokio::test]
     async fn test_insert_category_duplicate_error() {
         let env = TestEnv::new().await;
@@ -571,6 +806,7 @@ mod tests {
             full_description_contains: None,
             amount_contains: None,
             other_fields: std::collections::BTreeMap::new(),
+            idempotent: false,
         };
 
         let result = insert_autocat(env.config(), args).await;
@@ -612,6 +848,7 @@ mod tests {
             full_description_contains: None,
             amount_contains: None,
             other_fields: std::collections::BTreeMap::new(),
+            idempotent: false,
         };
 
         let result = insert_autocat(env.config(), args).await;
@@ -650,6 +887,7 @@ mod tests {
             full_description_contains: None,
             amount_contains: None,
             other_fields: std::collections::BTreeMap::new(),
+            idempotent: false,
         };
 
         let result = insert_autocat(env.config(), args).await;
@@ -681,6 +919,7 @@ mod tests {
             full_description_contains: None,
             amount_contains: None,
             other_fields: std::collections::BTreeMap::new(),
+            idempotent: false,
         };
 
         let result1 = insert_autocat(env.config(), make_args()).await.unwrap();
@@ -692,6 +931,55 @@ mod tests {
         assert_ne!(id1, id2, "Generated IDs should be unique");
     }
 
+    #[tokio::test]
+    async fn test_insert_autocat_idempotent() {
+        let env = TestEnv::new().await;
+
+        let make_args = |amount_min: &str| InsertAutoCatArgs {
+            category: None,
+            description: Some("Coffee".to_string()),
+            description_contains: Some("starbucks".to_string()),
+            account_contains: None,
+            institution_contains: None,
+            amount_min: Some(amount_min.parse().unwrap()),
+            amount_max: None,
+            amount_equals: None,
+            description_equals: None,
+            description_full: None,
+            full_description_contains: None,
+            amount_contains: None,
+            other_fields: std::collections::BTreeMap::new(),
+            idempotent: true,
+        };
+
+        let first = insert_autocat(env.config(), make_args("5.00"))
+            .await
+            .unwrap();
+        let second = insert_autocat(env.config(), make_args("5")).await.unwrap();
+        assert_eq!(first.structure(), second.structure());
+        assert!(second
+            .message()
+            .starts_with("AutoCat rule already exists with ID:"));
+
+        // A rule with different criteria is inserted
+        let other = insert_autocat(env.config(), make_args("6.00"))
+            .await
+            .unwrap();
+        assert_ne!(first.structure(), other.structure());
+
+        // Without the flag a duplicate is inserted
+        let args = InsertAutoCatArgs {
+            idempotent: false,
+            ..make_args("5.00")
+        };
+        let duplicate = insert_autocat(env.config(), args).await.unwrap();
+        assert_ne!(first.structure(), duplicate.structure());
+        assert_eq!(
+            env.config().db().get_autocats().await.unwrap().data().len(),
+            3
+        );
+    }
+
     #[tokio::test]
     async fn test_insert_autocat_minimal_fields() {
         let env = TestEnv::new().await;
@@ -711,6 +999,7 @@ mod tests {
             full_description_contains: None,
             amount_contains: None,
             other_fields: std::collections::BTreeMap::new(),
+            idempotent: false,
         };
 
         let result = insert_autocat(env.config(), args).await;
@@ -750,6 +1039,7 @@ mod tests {
             full_description_contains: None,
             amount_contains: None,
             other_fields: std::collections::BTreeMap::new(),
+            idempotent: false,
         };
 
         let result = insert_autocat(env.config(), args).await;
@@ -0,0 +1,281 @@
+//! Commands that list rows from the local datastore.
+
+use crate::args::ListTransactionsArgs;
+use crate::commands::Out;
+use crate::db::_Row;
+use crate::error::{ErrorType, IntoResult};
+use crate::model::{AutoCat, Item, Transaction, TransactionColumn};
+use crate::{Config, Result};
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+use std::fmt::Debug;
+use std::ops::Deref;
+
+/// A page of rows along with what is needed to request the next page.
+#[derive(Debug, Clone, Serialize)]
+pub struct ListResult<T>
+where
+    T: Serialize + Clone + Debug,
+{
+    /// The rows in this page.
+    pub rows: Vec<T>,
+    /// The total number of rows matching the filter, including those outside of this page.
+    pub total: u64,
+    /// The number of matching rows that were skipped before this page.
+    pub offset: u32,
+    /// The maximum page size that was requested, if any.
+    pub limit: Option<u32>,
+}
+
+impl<T> ListResult<T>
+where
+    T: Serialize + Clone + Debug,
+{
+    /// Returns true if there are matching rows after this page.
+    pub fn has_more(&self) -> bool {
+        u64::from(self.offset) + (self.rows.len() as u64) < self.total
+    }
+}
+
+/// A listed transaction. When columns were selected, only those columns are serialized.
+#[derive(Debug, Clone)]
+pub struct TransactionRow {
+    transaction: Transaction,
+    columns: Vec<TransactionColumn>,
+}
+
+impl TransactionRow {
+    /// The header and value of each selected column, in the order they were selected, with
+    /// `Transaction ID` first. Empty when no columns were selected.
+    pub fn cells(&self) -> Vec<(&'static str, String)> {
+        if self.columns.is_empty() {
+            return Vec::new();
+        }
+        let id = std::iter::once(TransactionColumn::TransactionId);
+        let rest = self
+            .columns
+            .iter()
+            .filter(|c| **c != TransactionColumn::TransactionId)
+            .cloned();
+        id.chain(rest)
+            .map(|c| (c.to_header(), self.transaction.get_by_header(c.to_header())))
+            .collect()
+    }
+}
+
+impl Deref for TransactionRow {
+    type Target = Transaction;
+
+    fn deref(&self) -> &Self::Target {
+        &self.transaction
+    }
+}
+
+impl Serialize for TransactionRow {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        if self.columns.is_empty() {
+            return self.transaction.serialize(serializer);
+        }
+        let cells = self.cells();
+        let mut map = serializer.serialize_map(Some(cells.len()))?;
+        for (header, value) in &cells {
+            map.serialize_entry(header, value)?;
+        }
+        map.end()
+    }
+}
+
+/// Lists transactions matching `args.filter`, in sheet order, one page at a time.
+///
+/// # Arguments
+///
+/// - `config` - The application configuration containing the database connection.
+/// - `args` - The filter, the `limit` and `offset` of the page to return, and the `columns` to
+///   include in each row.
+///
+/// # Returns
+///
+/// On success, returns an `Out` containing:
+/// - A message describing which rows of the total were returned.
+/// - A `ListResult` with the transactions and the total number of matching transactions. When
+///   `args.columns` is set, each transaction serializes as an object of only those columns, keyed
+///   by header, plus `Transaction ID`.
+///
+/// # Errors
+///
+/// - Returns an error if a database operation fails.
+pub async fn list_transactions(
+    config: Config,
+    args: ListTransactionsArgs,
+) -> Result<Out<ListResult<TransactionRow>>> {
+    let (rows, total) = config
+        .db()
+        .list_transactions(&args.filter, args.limit, args.offset)
+        .await
+        .pub_result(ErrorType::Database)?;
+
+    let rows = rows
+        .into_iter()
+        .map(|transaction| TransactionRow {
+            transaction,
+            columns: args.columns.clone(),
+        })
+        .collect();
+
+    let result = ListResult {
+        rows,
+        total,
+        offset: args.offset,
+        limit: args.limit,
+    };
+
+    let message = if result.rows.is_empty() {
+        format!("Listed 0 of {total} matching transactions")
+    } else {
+        let first = u64::from(result.offset) + 1;
+        let last = u64::from(result.offset) + result.rows.len() as u64;
+        format!("Listed transactions {first}-{last} of {total} matching transactions")
+    };
+
+    Ok(Out::new(message, result))
+}
+
+/// Lists the AutoCat rules in the order Tiller processes them: their order in the AutoCat tab as of
+/// the last sync down, followed by the rules added locally in the order they were added. When more
+/// than one rule matches a transaction, the first one in this order wins.
+///
+/// # Arguments
+///
+/// - `config` - The application configuration containing the database connection.
+///
+/// # Returns
+///
+/// On success, returns an `Out` containing:
+/// - A message with the number of rules.
+/// - The `AutoCat` rules wrapped in `_Row` (includes the ID), in processing order.
+///
+/// # Errors
+///
+/// - Returns an error if a database operation fails.
+pub async fn list_autocats(config: Config) -> Result<Out<Vec<_Row<AutoCat>>>> {
+    let rules = config
+        .db()
+        .list_autocats()
+        .await
+        .pub_result(ErrorType::Database)?;
+
+    let message = format!("Listed {} AutoCat rules in processing order", rules.len());
+    Ok(Out::new(message, rules))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Mode;
+    use crate::args::TransactionFilter;
+    use crate::commands::sync_down;
+    use crate::test::TestEnv;
+
+    #[tokio::test]
+    async fn test_list_transactions_total_with_limit() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        // The seed data has 4 Groceries transactions
+        let args = ListTransactionsArgs {
+            filter: TransactionFilter {
+                category: Some("Groceries".to_string()),
+                ..Default::default()
+            },
+            limit: Some(3),
+            offset: 0,
+            columns: Vec::new(),
+        };
+        let out = list_transactions(config.clone(), args.clone())
+            .await
+            .unwrap();
+        let result = out.structure().unwrap();
+        assert_eq!(result.rows.len(), 3);
+        assert_eq!(result.total, 4);
+        assert!(result.has_more());
+        assert!(result.rows.iter().all(|t| t.category == "Groceries"));
+        assert_eq!(
+            out.message(),
+            "Listed transactions 1-3 of 4 matching transactions"
+        );
+
+        let args = ListTransactionsArgs { offset: 3, ..args };
+        let out = list_transactions(config, args).await.unwrap();
+        let result = out.structure().unwrap();
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.total, 4);
+        assert!(!result.has_more());
+    }
+
+    #[tokio::test]
+    async fn test_list_transactions_selected_columns() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        let args = ListTransactionsArgs {
+            limit: Some(2),
+            columns: vec![
+                TransactionColumn::Date,
+                TransactionColumn::Description,
+                TransactionColumn::Amount,
+            ],
+            ..Default::default()
+        };
+        let out = list_transactions(config, args).await.unwrap();
+        let result = out.structure().unwrap();
+        assert_eq!(result.rows.len(), 2);
+
+        let first = &result.rows[0];
+        let headers: Vec<&str> = first.cells().iter().map(|(h, _)| *h).collect();
+        assert_eq!(
+            headers,
+            vec!["Transaction ID", "Date", "Description", "Amount"]
+        );
+
+        let json = serde_json::to_value(first).unwrap();
+        let object = json.as_object().unwrap();
+        assert_eq!(object.len(), 4);
+        assert_eq!(object["Transaction ID"], first.transaction_id.as_str());
+        assert_eq!(object["Description"], first.description.as_str());
+        assert_eq!(object["Amount"], first.amount.to_string().as_str());
+        assert!(!object.contains_key("Category"));
+    }
+
+    #[tokio::test]
+    async fn test_list_autocats_in_processing_order() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        // A rule added locally comes after the rules from the sheet
+        let rule = AutoCat {
+            category: "Groceries".to_string(),
+            description_contains: "Trader Joe".to_string(),
+            ..Default::default()
+        };
+        let id = config.db().insert_autocat(&rule).await.unwrap();
+
+        let out = list_autocats(config).await.unwrap();
+        let rules = out.structure().unwrap();
+        let order: Vec<&str> = rules
+            .iter()
+            .map(|r| r.row.description_contains.as_str())
+            .collect();
+        assert_eq!(
+            order,
+            vec!["Whole Foods", "Starbucks", "Shell", "Trader Joe"]
+        );
+        assert_eq!(rules[3].id, id);
+        assert_eq!(out.message(), "Listed 4 AutoCat rules in processing order");
+    }
+}
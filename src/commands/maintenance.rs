@@ -0,0 +1,96 @@
+//! Implements maintenance of the local SQLite database.
+
+use crate::commands::Out;
+use crate::error::{ErrorType, IntoResult};
+use crate::utils;
+use crate::{Config, Result};
+use serde::Serialize;
+
+/// The outcome of `maintenance`.
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceReport {
+    /// The size of the SQLite file, in bytes, before maintenance.
+    pub size_before: u64,
+    /// The size of the SQLite file, in bytes, after maintenance.
+    pub size_after: u64,
+}
+
+/// Compacts the local SQLite database with `VACUUM` and refreshes its query planner statistics
+/// with `ANALYZE` and `PRAGMA optimize`.
+///
+/// Every `sync down` deletes and re-inserts all rows, which leaves free pages in the file. `VACUUM`
+/// rebuilds the file without them.
+///
+/// # Arguments
+///
+/// - `config` - The application configuration.
+///
+/// # Returns
+///
+/// On success, returns an `Out` containing a message and the file size before and after.
+///
+/// # Errors
+///
+/// - Returns an error if the size of the SQLite file cannot be read.
+/// - Returns an error if a database operation fails.
+pub async fn maintenance(config: Config) -> Result<Out<MaintenanceReport>> {
+    let size_before = utils::file_size(config.sqlite_path())
+        .await
+        .pub_result(ErrorType::Internal)?;
+
+    // VACUUM cannot run in a database transaction, so a dry run skips it rather than rolling back
+    let db = config.db();
+    if !config.dry_run() {
+        db.vacuum().await.pub_result(ErrorType::Database)?;
+        db.analyze().await.pub_result(ErrorType::Database)?;
+    }
+
+    let size_after = utils::file_size(config.sqlite_path())
+        .await
+        .pub_result(ErrorType::Internal)?;
+
+    let message = format!(
+        "Database maintenance complete, file size went from {size_before} to {size_after} bytes"
+    );
+    Ok(Out::new(
+        message,
+        MaintenanceReport {
+            size_before,
+            size_after,
+        },
+    )
+    .with_dry_run(config.dry_run()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::DeleteTransactionsArgs;
+    use crate::model::Transaction;
+    use crate::test::TestEnv;
+
+    #[tokio::test]
+    async fn test_maintenance_after_churn() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+
+        let ids: Vec<String> = (0..500).map(|ix| format!("txn-{ix:04}")).collect();
+        for id in &ids {
+            let mut transaction = Transaction::default();
+            transaction.transaction_id = id.clone();
+            transaction.description = "Padding to make the rows take up some space".repeat(10);
+            config.db().insert_transaction(&transaction).await.unwrap();
+        }
+        config
+            .db()
+            .delete_transactions(DeleteTransactionsArgs::new(ids).unwrap())
+            .await
+            .unwrap();
+
+        let out = maintenance(config).await.unwrap();
+        let report = out.structure().unwrap();
+        assert!(report.size_before > 0);
+        assert!(report.size_after > 0);
+        assert!(out.message().contains("bytes"));
+    }
+}
@@ -3,26 +3,58 @@
 //! This module contains implementations for all CLI subcommands.
 
 mod auth;
+mod backup;
+mod batch;
+mod conflicts;
 mod delete;
+mod diagnose;
+mod diff;
 mod init;
 mod insert;
+mod list;
+mod maintenance;
 mod mcp;
 pub mod query;
+mod reconcile;
+mod render;
 mod sync;
+mod sync_history;
+mod sync_progress;
+mod totals;
 mod update;
+mod validate;
 
+use crate::args::Format;
+use crate::error::{ErrorType, IntoResult};
+use crate::Result;
+use anyhow::Context;
 use serde::Serialize;
 use std::fmt::Debug;
-use tracing::{debug, info};
+use std::io::Write;
+use tracing::{debug, info, warn};
 
-pub use auth::{auth, auth_verify};
+pub use auth::{auth, auth_verify, whoami, AuthStatus, WhoAmI};
+pub use backup::{create_backup, BackupFiles};
+pub use batch::{BatchFailure, BatchResult};
+pub use conflicts::{list_conflicts, resolve_conflict, FieldConflict};
 pub use delete::{delete_autocats, delete_categories, delete_transactions};
+pub use diagnose::{diagnose_formulas, FormulaCell};
+pub use diff::{diff_backups, FieldChange, ItemDiff, SnapshotDiff};
 pub use init::init;
 pub use insert::{insert_autocat, insert_category, insert_transaction};
+pub use list::{list_autocats, list_transactions, ListResult, TransactionRow};
+pub use maintenance::{maintenance, MaintenanceReport};
 pub use mcp::mcp;
 pub use query::{query, schema, ColumnInfo, ForeignKeyInfo, IndexInfo, Rows, Schema, TableInfo};
-pub use sync::{sync_down, sync_up};
-pub use update::{update_autocats, update_categories, update_transactions};
+pub use reconcile::{reconcile_ids, ReconciledId};
+pub use sync::{sync, sync_down, sync_up, SyncUpPlan, WriteRange};
+pub use sync_history::sync_history;
+pub use totals::institution_totals;
+pub use update::{
+    categorize_uncategorized, merge_categories, move_autocat, recategorize, update_autocats,
+    update_categories, update_transactions, CategorizeReport, CategoryAssignment, MergeReport,
+};
+pub use validate::{validate, IssueKind, ValidationIssue};
 
 /// The output type for a command. This allows the command to return a consistent message and,
 /// optionally, structured data to both the command line and MCP server interfaces.
@@ -36,6 +68,10 @@ where
 
     /// Any structured data that needs to be output from the call.
     structure: Option<T>,
+
+    /// Non-fatal problems that the user should know about, e.g. that formulas were not written.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
 }
 
 impl<T, S> From<S> for Out<T>
@@ -75,6 +111,32 @@ pub enum FormulasMode {
 serde_plain::derive_display_from_serialize!(FormulasMode);
 serde_plain::derive_fromstr_from_deserialize!(FormulasMode);
 
+/// Controls how `sync up` writes to the Google sheet.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    serde::Serialize,
+    serde::Deserialize,
+    schemars::JsonSchema,
+    clap::ValueEnum,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncUpStrategy {
+    /// Default: clear each tab and rewrite it from the local database.
+    #[default]
+    Replace,
+    /// Only write transactions added locally (those with `user-` IDs) that are not yet in the
+    /// sheet, below its last row. Existing rows, formulas and the other tabs are left untouched.
+    AppendOnly,
+}
+
+serde_plain::derive_display_from_serialize!(SyncUpStrategy);
+serde_plain::derive_fromstr_from_deserialize!(SyncUpStrategy);
+
 impl<T> Out<T>
 where
     T: Serialize + Clone + Debug,
@@ -87,6 +149,7 @@ where
         Self {
             message: message.into(),
             structure: Some(structure),
+            warnings: Vec::new(),
         }
     }
 
@@ -98,6 +161,7 @@ where
         Self {
             message: message.into(),
             structure: None,
+            warnings: Vec::new(),
         }
     }
 
@@ -111,13 +175,234 @@ where
         self.structure.as_ref()
     }
 
-    /// Print the message to `info!` and the structured data (if it exists) as JSON to `debug!`.
+    /// Adds `warnings` to the warnings of this `Out`.
+    pub fn with_warnings<I, S>(mut self, warnings: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.warnings.extend(warnings.into_iter().map(Into::into));
+        self
+    }
+
+    /// Starts the message with `Dry run, nothing was saved:` when `dry_run` is true, since the
+    /// changes it describes were rolled back.
+    pub(crate) fn with_dry_run(mut self, dry_run: bool) -> Self {
+        if dry_run {
+            self.message = format!("Dry run, nothing was saved: {}", self.message);
+        }
+        self
+    }
+
+    /// Get the `warnings`.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
+    }
+
+    /// Print the message to `info!`, each warning to `warn!`, and the structured data (if it
+    /// exists) as JSON to `debug!`.
     pub fn print(&self) {
         info!("{}", self.message);
+        for warning in &self.warnings {
+            warn!("{warning}");
+        }
         if let Some(structure) = self.structure() {
             if let Ok(json) = serde_json::to_string_pretty(structure) {
                 debug!("Command output:\n\n{json}\n\n");
             }
         }
     }
+
+    /// Print the message to `info!` and each warning to `warn!`, like `print`, then write the
+    /// structured data (if it exists) to `stdout` in `format`. This is for commands whose
+    /// structured data is the result the user asked for, such as a list of transactions. Pass
+    /// `std::io::sink()` as `stdout` to leave the structured data out, e.g. for `--quiet`.
+    pub fn print_as(&self, format: Format, stdout: &mut dyn Write) -> Result<()> {
+        info!("{}", self.message);
+        for warning in &self.warnings {
+            warn!("{warning}");
+        }
+        if let Some(structure) = self.structure() {
+            writeln!(stdout, "{}", render::render(structure, format)?)
+                .context("Unable to write the command output to stdout")
+                .pub_result(ErrorType::Internal)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{Mode, SheetCall, TestSheet};
+    use crate::args::{
+        DeleteAutoCatsArgs, DeleteCategoriesArgs, DeleteTransactionsArgs, InsertAutoCatArgs,
+        InsertCategoryArgs, InsertTransactionArgs, UpdateAutoCatsArgs, UpdateCategoriesArgs,
+        UpdateTransactionsArgs,
+    };
+    use crate::model::{AutoCatUpdates, CategoryUpdates, TransactionUpdates};
+    use crate::test::TestEnv;
+    use clap::Parser;
+
+    #[test]
+    fn test_print_as_writes_to_the_given_stdout() {
+        let out = Out::new("Listed 1 item", vec!["a"]);
+        let mut stdout = Vec::new();
+        out.print_as(Format::Json, &mut stdout).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&stdout).unwrap();
+        assert_eq!(json, serde_json::json!(["a"]));
+
+        out.print_as(Format::Json, &mut std::io::sink()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_changes_nothing() {
+        let env = TestEnv::new().await;
+        sync_down(env.config(), Mode::Testing).await.unwrap();
+        // Every seeded category is in use, so add one that can be deleted
+        let args = InsertCategoryArgs::parse_from(["insert", "--name", "Unused"]);
+        insert_category(env.config(), args).await.unwrap();
+
+        let config = env.config().with_dry_run(true);
+        let db = config.db();
+        let before = db.get_tiller_data().await.unwrap();
+        let syncs_before = db.sync_history(100).await.unwrap().len();
+        let backups_before = std::fs::read_dir(config.backups()).unwrap().count();
+        let test_sheet = TestSheet::new(config.spreadsheet_id());
+        test_sheet.clear_history();
+
+        let txn_id = before.transactions.data()[0].transaction_id.clone();
+        let rules = db.list_autocats().await.unwrap();
+        let (first_rule, last_rule) = (rules[0].id.to_string(), rules[2].id.to_string());
+        let dry = |message: &str| {
+            assert!(
+                message.starts_with("Dry run, nothing was saved: "),
+                "Unexpected message: {message}"
+            );
+        };
+
+        let args = InsertTransactionArgs::parse_from([
+            "insert",
+            "--date",
+            "2025-10-20",
+            "--amount",
+            "-1.00",
+        ]);
+        dry(insert_transaction(config.clone(), args)
+            .await
+            .unwrap()
+            .message());
+        let args = InsertCategoryArgs::parse_from(["insert", "--name", "Travel"]);
+        dry(insert_category(config.clone(), args)
+            .await
+            .unwrap()
+            .message());
+        let args = InsertAutoCatArgs::parse_from([
+            "insert",
+            "--category",
+            "Groceries",
+            "--description-contains",
+            "Trader Joe",
+        ]);
+        dry(insert_autocat(config.clone(), args)
+            .await
+            .unwrap()
+            .message());
+
+        let updates = TransactionUpdates {
+            note: Some("changed".to_string()),
+            ..Default::default()
+        };
+        let args = UpdateTransactionsArgs::new([txn_id.as_str()], updates).unwrap();
+        dry(update_transactions(config.clone(), args)
+            .await
+            .unwrap()
+            .message());
+        let updates = CategoryUpdates {
+            category: Some("Food".to_string()),
+            ..Default::default()
+        };
+        let args = UpdateCategoriesArgs::new(["Groceries"], updates).unwrap();
+        dry(update_categories(config.clone(), args)
+            .await
+            .unwrap()
+            .message());
+        let updates = AutoCatUpdates {
+            description_contains: Some("changed".to_string()),
+            ..Default::default()
+        };
+        let args = UpdateAutoCatsArgs::new([first_rule.as_str()], updates).unwrap();
+        dry(update_autocats(config.clone(), args)
+            .await
+            .unwrap()
+            .message());
+        dry(move_autocat(config.clone(), last_rule, 0)
+            .await
+            .unwrap()
+            .message());
+        let out = recategorize(
+            config.clone(),
+            "Groceries".into(),
+            "Utilities".into(),
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(out.message().starts_with("Dry run"), "{}", out.message());
+        dry(merge_categories(
+            config.clone(),
+            vec!["Coffee Shops".into()],
+            "Restaurants".into(),
+        )
+        .await
+        .unwrap()
+        .message());
+
+        let args = DeleteTransactionsArgs::new([txn_id.as_str()]).unwrap();
+        dry(delete_transactions(config.clone(), args)
+            .await
+            .unwrap()
+            .message());
+        let args = DeleteAutoCatsArgs::new([first_rule.as_str()]).unwrap();
+        dry(delete_autocats(config.clone(), args)
+            .await
+            .unwrap()
+            .message());
+        let args = DeleteCategoriesArgs::new(["Unused"]).unwrap();
+        dry(delete_categories(config.clone(), args)
+            .await
+            .unwrap()
+            .message());
+
+        dry(maintenance(config.clone()).await.unwrap().message());
+        dry(sync_down(config.clone(), Mode::Testing)
+            .await
+            .unwrap()
+            .message());
+        let out = sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(out.message().contains("Dry run"), "{}", out.message());
+
+        assert_eq!(db.get_tiller_data().await.unwrap(), before);
+        assert_eq!(db.sync_history(100).await.unwrap().len(), syncs_before);
+        assert_eq!(
+            std::fs::read_dir(config.backups()).unwrap().count(),
+            backups_before
+        );
+        assert!(!test_sheet.call_history().iter().any(|c| matches!(
+            c,
+            SheetCall::ClearRanges { .. }
+                | SheetCall::WriteRanges { .. }
+                | SheetCall::CopySpreadsheet { .. }
+        )));
+    }
 }
@@ -0,0 +1,266 @@
+//! Implements merging locally inserted transactions into the rows that Tiller later added for them.
+
+use crate::commands::Out;
+use crate::error::{ErrorType, IntoResult};
+use crate::model::Transaction;
+use crate::utils::{self, USER_TRANSACTION_PREFIX};
+use crate::{Config, Result};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A locally inserted transaction that `reconcile_ids` merged into a transaction added by Tiller.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReconciledId {
+    /// The `user-` ID of the locally inserted transaction, which no longer exists.
+    pub user_id: String,
+    /// The ID that Tiller assigned, which the merged transaction keeps.
+    pub transaction_id: String,
+}
+
+/// What a locally inserted transaction and a Tiller transaction must have in common to be merged:
+/// the date, amount, description and account.
+type MatchKey = (String, Decimal, String, String);
+
+fn match_key(transaction: &Transaction) -> MatchKey {
+    // The date and amount are compared by value, since the sheet may format them differently from
+    // how they were entered locally.
+    let date = utils::parse_date(&transaction.date)
+        .map(|date| date.to_string())
+        .unwrap_or_else(|_| transaction.date.trim().to_string());
+    (
+        date,
+        transaction.amount.value().normalize(),
+        transaction.description.trim().to_lowercase(),
+        transaction.account.trim().to_string(),
+    )
+}
+
+/// Merges transactions that were inserted locally, and so have `user-` IDs, into the transactions
+/// that Tiller later added to the sheet for the same bank activity. Run it after a `sync down`
+/// that brought in Tiller's rows.
+///
+/// A locally inserted transaction is merged when exactly one Tiller transaction has the same date,
+/// amount, description and account, and no other locally inserted transaction matches it. The
+/// merged transaction keeps Tiller's ID and fields. The category, note and tags entered locally,
+/// and any custom columns, are copied to it where Tiller left them blank. The locally inserted
+/// transaction is then deleted. Matches that are ambiguous are left alone and reported as
+/// warnings.
+///
+/// Changes are made locally only. Use `sync up` to upload them to the Google Sheet.
+///
+/// # Arguments
+///
+/// - `config` - The application configuration containing the database connection.
+///
+/// # Returns
+///
+/// On success, returns an `Out` containing a message and the pairs of IDs that were merged.
+///
+/// # Errors
+///
+/// - Returns an error if a database operation fails.
+pub async fn reconcile_ids(config: Config) -> Result<Out<Vec<ReconciledId>>> {
+    let transactions = config
+        .db()
+        .get_transactions()
+        .await
+        .pub_result(ErrorType::Database)?;
+
+    let mut candidates: BTreeMap<MatchKey, (Vec<&Transaction>, Vec<&Transaction>)> =
+        BTreeMap::new();
+    for transaction in transactions.data() {
+        let (user, tiller) = candidates.entry(match_key(transaction)).or_default();
+        if transaction
+            .transaction_id
+            .starts_with(USER_TRANSACTION_PREFIX)
+        {
+            user.push(transaction);
+        } else {
+            tiller.push(transaction);
+        }
+    }
+
+    let mut merges = Vec::new();
+    let mut warnings = Vec::new();
+    for (user, tiller) in candidates.values() {
+        match (user.as_slice(), tiller.as_slice()) {
+            ([], _) | (_, []) => {}
+            ([user], [tiller]) => merges.push((merged(tiller, user), user.transaction_id.clone())),
+            _ => warnings.push(format!(
+                "Did not merge the locally inserted transactions {} into the transactions from \
+                Tiller {}, because more than one has the same date, amount, description and \
+                account. Merge them by hand",
+                ids(user),
+                ids(tiller)
+            )),
+        }
+    }
+
+    config
+        .db()
+        .merge_transactions(&merges)
+        .await
+        .pub_result(ErrorType::Database)?;
+
+    let reconciled: Vec<ReconciledId> = merges
+        .into_iter()
+        .map(|(merged, user_id)| ReconciledId {
+            user_id,
+            transaction_id: merged.transaction_id,
+        })
+        .collect();
+    let message = match reconciled.len() {
+        0 => "No locally inserted transactions match a transaction from Tiller".to_string(),
+        count => format!(
+            "Merged {count} locally inserted transaction{} into the transaction{} from Tiller. \
+            Use sync up to upload the change",
+            if count == 1 { "" } else { "s" },
+            if count == 1 { "" } else { "s" }
+        ),
+    };
+    Ok(Out::new(message, reconciled)
+        .with_warnings(warnings)
+        .with_dry_run(config.dry_run()))
+}
+
+/// Tiller's transaction, with the fields that were entered locally filled in where Tiller left
+/// them blank.
+fn merged(tiller: &Transaction, user: &Transaction) -> Transaction {
+    let mut merged = tiller.clone();
+    for (field, local) in [
+        (&mut merged.category, &user.category),
+        (&mut merged.note, &user.note),
+        (&mut merged.tags, &user.tags),
+    ] {
+        if field.is_empty() {
+            field.clone_from(local);
+        }
+    }
+    for (header, value) in user.other_fields.iter().filter(|(_, v)| !v.is_empty()) {
+        let field = merged.other_fields.entry(header.clone()).or_default();
+        if field.is_empty() {
+            field.clone_from(value);
+        }
+    }
+    merged
+}
+
+fn ids(transactions: &[&Transaction]) -> String {
+    transactions
+        .iter()
+        .map(|t| t.transaction_id.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Mode;
+    use crate::commands::{sync_down, sync_up, FormulasMode, SyncUpStrategy};
+    use crate::test::TestEnv;
+    use std::str::FromStr;
+
+    const USER_ID: &str = "user-00000000000000000001";
+    const TILLER_ID: &str = "tx001a2b3c4d5e6f7g8h9i99";
+
+    /// Inserts a categorized transaction locally and appends it to the sheet, then adds the row
+    /// that Tiller would add for the same purchase, uncategorized, to the sheet.
+    async fn insert_then_add_tiller_row(env: &TestEnv, amount: &str) {
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        let mut t = Transaction::default();
+        t.transaction_id = USER_ID.to_string();
+        t.date = "2025-10-22".to_string();
+        t.description = "Farmers Market".to_string();
+        t.amount = crate::model::Amount::from_str("-23.10").unwrap();
+        t.account = "Checking 1".to_string();
+        t.category = "Groceries".to_string();
+        config.db().insert_transaction(&t).await.unwrap();
+        sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::AppendOnly,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap();
+
+        let mut state = env.get_state();
+        let sheet = state.data.get_mut("Transactions").unwrap();
+        let col = |header: &str| sheet[0].iter().position(|h| h == header).unwrap();
+        let (id, date, category, amount_col) = (
+            col("Transaction ID"),
+            col("Date"),
+            col("Category"),
+            col("Amount"),
+        );
+        let mut row = sheet.last().unwrap().clone();
+        row[id] = TILLER_ID.to_string();
+        row[date] = "10/22/2025".to_string();
+        row[category] = String::new();
+        row[amount_col] = amount.to_string();
+        sheet.push(row);
+        env.set_state(state);
+        sync_down(config, Mode::Testing).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_ids_merges_user_row_into_tiller_row() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        insert_then_add_tiller_row(&env, "-$23.10").await;
+        let count = config.db().count_transactions().await.unwrap();
+
+        let out = reconcile_ids(config.clone()).await.unwrap();
+        assert_eq!(
+            out.structure().unwrap(),
+            &vec![ReconciledId {
+                user_id: USER_ID.to_string(),
+                transaction_id: TILLER_ID.to_string(),
+            }]
+        );
+        assert!(out.warnings().is_empty(), "{:?}", out.warnings());
+
+        // The local row is gone and Tiller's row has the category that was entered locally
+        let db = config.db();
+        assert_eq!(db.count_transactions().await.unwrap(), count - 1);
+        assert!(db._get_transaction(USER_ID).await.unwrap().is_none());
+        let merged = db._get_transaction(TILLER_ID).await.unwrap().unwrap();
+        assert_eq!(merged.category, "Groceries");
+        assert_eq!(merged.date, "10/22/2025");
+
+        // Nothing is left to merge
+        let out = reconcile_ids(config).await.unwrap();
+        assert!(out.structure().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_ids_leaves_rows_that_do_not_match() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        insert_then_add_tiller_row(&env, "-$23.11").await;
+        let before = config.db().get_transactions().await.unwrap();
+
+        let out = reconcile_ids(config.clone()).await.unwrap();
+        assert!(out.structure().unwrap().is_empty());
+        assert_eq!(config.db().get_transactions().await.unwrap(), before);
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_ids_dry_run_changes_nothing() {
+        let env = TestEnv::new().await;
+        insert_then_add_tiller_row(&env, "-$23.10").await;
+        let config = env.config().with_dry_run(true);
+        let before = config.db().get_transactions().await.unwrap();
+
+        let out = reconcile_ids(config.clone()).await.unwrap();
+        assert_eq!(out.structure().unwrap().len(), 1);
+        assert!(out.message().starts_with("Dry run"), "{}", out.message());
+        assert_eq!(config.db().get_transactions().await.unwrap(), before);
+    }
+}
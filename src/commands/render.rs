@@ -0,0 +1,408 @@
+//! Renders the structured output of a command for the command line, in the format chosen with
+//! `--output`.
+
+use crate::args::Format;
+use crate::error::{ErrorType, IntoResult, Res};
+use crate::model::Amount;
+use crate::Result;
+use serde::de::{Deserializer, Error, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fmt::Formatter;
+use std::str::FromStr;
+
+/// Renders `value` in `format`.
+///
+/// JSON is `value` pretty-printed. For a table or CSV, `value` is first flattened into rows: an
+/// array gives one row per element, an object with a `rows` array (e.g. a page of transactions)
+/// gives one row per element of `rows`, and any other object gives a single row. The fields of
+/// nested objects become columns named `outer.inner`, and the columns are in the order they are
+/// first seen. A string, such as a query result that is already formatted, is rendered as is.
+pub(crate) fn render<T: Serialize>(value: &T, format: Format) -> Result<String> {
+    render_value(value, format).pub_result(ErrorType::Internal)
+}
+
+fn render_value<T: Serialize>(value: &T, format: Format) -> Res<String> {
+    if format == Format::Json {
+        return Ok(serde_json::to_string_pretty(value)?);
+    }
+    // Going through a string keeps the fields in the order they were serialized, which converting
+    // to a `serde_json::Value` would not.
+    let node: Node = serde_json::from_str(&serde_json::to_string(value)?)?;
+    if let Node::Leaf(Value::String(s)) = node {
+        return Ok(s);
+    }
+    let (headers, rows) = tabulate(node);
+    match format {
+        Format::Csv => csv(&headers, &rows),
+        _ => Ok(table(&headers, &rows)),
+    }
+}
+
+/// A JSON value whose object fields are kept in the order they were serialized.
+enum Node {
+    Object(Vec<(String, Node)>),
+    Array(Vec<Node>),
+    Leaf(Value),
+}
+
+impl Node {
+    fn into_value(self) -> Value {
+        match self {
+            Node::Object(fields) => Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(key, node)| (key, node.into_value()))
+                    .collect::<Map<_, _>>(),
+            ),
+            Node::Array(items) => Value::Array(items.into_iter().map(Node::into_value).collect()),
+            Node::Leaf(value) => value,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+struct NodeVisitor;
+
+impl<'de> Visitor<'de> for NodeVisitor {
+    type Value = Node;
+
+    fn expecting(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_bool<E: Error>(self, v: bool) -> std::result::Result<Node, E> {
+        Ok(Node::Leaf(Value::Bool(v)))
+    }
+
+    fn visit_i64<E: Error>(self, v: i64) -> std::result::Result<Node, E> {
+        Ok(Node::Leaf(Value::from(v)))
+    }
+
+    fn visit_u64<E: Error>(self, v: u64) -> std::result::Result<Node, E> {
+        Ok(Node::Leaf(Value::from(v)))
+    }
+
+    fn visit_f64<E: Error>(self, v: f64) -> std::result::Result<Node, E> {
+        Ok(Node::Leaf(Value::from(v)))
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> std::result::Result<Node, E> {
+        Ok(Node::Leaf(Value::String(v.to_string())))
+    }
+
+    fn visit_unit<E: Error>(self) -> std::result::Result<Node, E> {
+        Ok(Node::Leaf(Value::Null))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<Node, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Node::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> std::result::Result<Node, A::Error> {
+        let mut fields = Vec::new();
+        while let Some(field) = map.next_entry()? {
+            fields.push(field);
+        }
+        Ok(Node::Object(fields))
+    }
+}
+
+/// Flattens `node` into a header row and data rows with a cell for each header.
+fn tabulate(node: Node) -> (Vec<String>, Vec<Vec<String>>) {
+    let items = match node {
+        Node::Array(items) => items,
+        Node::Object(mut fields) => {
+            let rows = fields
+                .iter()
+                .position(|(key, node)| key == "rows" && matches!(node, Node::Array(_)));
+            match rows.map(|ix| fields.remove(ix).1) {
+                Some(Node::Array(items)) => items,
+                _ => vec![Node::Object(fields)],
+            }
+        }
+        leaf => vec![leaf],
+    };
+
+    let mut headers: Vec<String> = Vec::new();
+    let mut flat_rows = Vec::with_capacity(items.len());
+    for item in items {
+        let mut cells = Vec::new();
+        match item {
+            Node::Object(fields) => flatten("", fields, &mut cells),
+            other => cells.push(("value".to_string(), cell(other))),
+        }
+        for (header, _) in &cells {
+            if !headers.contains(header) {
+                headers.push(header.clone());
+            }
+        }
+        flat_rows.push(cells);
+    }
+
+    let rows = flat_rows
+        .into_iter()
+        .map(|cells| {
+            headers
+                .iter()
+                .map(|header| {
+                    cells
+                        .iter()
+                        .find(|(h, _)| h == header)
+                        .map(|(_, v)| v.clone())
+                        .unwrap_or_default()
+                })
+                .collect()
+        })
+        .collect();
+    (headers, rows)
+}
+
+/// Appends a `(header, cell)` pair for each field of `fields`, recursing into nested objects.
+fn flatten(prefix: &str, fields: Vec<(String, Node)>, out: &mut Vec<(String, String)>) {
+    for (key, node) in fields {
+        let header = if prefix.is_empty() {
+            key
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match node {
+            Node::Object(inner) => flatten(&header, inner, out),
+            other => out.push((header, cell(other))),
+        }
+    }
+}
+
+/// The text of a single cell. Strings are unquoted, null is empty and arrays are JSON.
+fn cell(node: Node) -> String {
+    match node.into_value() {
+        Value::Null => String::new(),
+        Value::String(s) => s,
+        other => other.to_string(),
+    }
+}
+
+/// A plain-text table with columns padded to the width of their widest cell. Columns of amounts
+/// are written with two decimal places and right-aligned, so that their decimal points line up.
+fn table(headers: &[String], rows: &[Vec<String>]) -> String {
+    let amounts: Vec<bool> = headers
+        .iter()
+        .enumerate()
+        .map(|(ix, header)| is_amount_column(header, rows.iter().filter_map(|row| row.get(ix))))
+        .collect();
+    let rows: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .zip(&amounts)
+                .map(|(cell, &amount)| match Amount::from_str(cell) {
+                    Ok(value) if amount && !cell.trim().is_empty() => value.to_display_string(),
+                    _ => cell.clone(),
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let line = |cells: &[String]| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(&widths)
+            .zip(&amounts)
+            .map(|((cell, &width), &amount)| {
+                if amount {
+                    format!("{cell:>width$}")
+                } else {
+                    format!("{cell:<width$}")
+                }
+            })
+            .collect();
+        padded.join("  ").trim_end().to_string()
+    };
+    let separator: Vec<String> = widths.iter().map(|&width| "-".repeat(width)).collect();
+
+    let mut lines = vec![line(headers), line(&separator)];
+    lines.extend(rows.iter().map(|row| line(row)));
+    lines.join("\n")
+}
+
+/// True if the column named `header` holds amounts: its name (after any `outer.` prefix) mentions
+/// an amount or balance, and every cell that is not blank parses as an `Amount`.
+fn is_amount_column<'a>(header: &str, mut cells: impl Iterator<Item = &'a String>) -> bool {
+    let name = header.rsplit('.').next().unwrap_or(header).to_lowercase();
+    (name.contains("amount") || name.contains("balance"))
+        && cells.all(|cell| cell.trim().is_empty() || Amount::from_str(cell).is_ok())
+}
+
+fn csv(headers: &[String], rows: &[Vec<String>]) -> Res<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(headers)?;
+    for row in rows {
+        writer.write_record(row)?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Mode;
+    use crate::args::ListTransactionsArgs;
+    use crate::commands::{list_autocats, list_transactions, sync_down};
+    use crate::test::TestEnv;
+    use clap::Parser;
+
+    #[tokio::test]
+    async fn test_render_list_transactions_in_each_format() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        let args = ListTransactionsArgs::parse_from([
+            "list-transactions",
+            "--limit",
+            "2",
+            "--columns",
+            "date,description",
+        ]);
+        let out = list_transactions(config, args).await.unwrap();
+        let page = out.structure().unwrap();
+
+        let json = render(page, Format::Json).unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["rows"].as_array().unwrap().len(), 2);
+        assert_eq!(value["total"], 20);
+
+        let table = render(page, Format::Table).unwrap();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4, "Unexpected table:\n{table}");
+        assert!(lines[0].starts_with("Transaction ID"), "{}", lines[0]);
+        assert!(lines[0].contains("Date") && lines[0].contains("Description"));
+        assert!(lines[1].chars().all(|c| c == '-' || c == ' '));
+        assert!(
+            lines[2].starts_with("tx001a2b3c4d5e6f7g8h9i01"),
+            "{}",
+            lines[2]
+        );
+
+        let csv = render(page, Format::Csv).unwrap();
+        let mut reader = csv::Reader::from_reader(csv.as_bytes());
+        let headers = reader.headers().unwrap().clone();
+        assert_eq!(
+            headers.iter().collect::<Vec<_>>(),
+            vec!["Transaction ID", "Date", "Description"]
+        );
+        let records: Vec<_> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(&records[0][0], "tx001a2b3c4d5e6f7g8h9i01");
+    }
+
+    #[tokio::test]
+    async fn test_render_list_autocats_flattens_rows() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        let out = list_autocats(config).await.unwrap();
+        let rules = out.structure().unwrap();
+
+        let csv = render(rules, Format::Csv).unwrap();
+        let mut reader = csv::Reader::from_reader(csv.as_bytes());
+        let headers = reader.headers().unwrap().clone();
+        assert_eq!(&headers[0], "id");
+        let contains = headers
+            .iter()
+            .position(|h| h == "row.description_contains")
+            .unwrap();
+        let values: Vec<String> = reader
+            .records()
+            .map(|r| r.unwrap()[contains].to_string())
+            .collect();
+        assert_eq!(values, vec!["Whole Foods", "Starbucks", "Shell"]);
+
+        let table = render(rules, Format::Table).unwrap();
+        assert_eq!(table.lines().count(), 5, "Unexpected table:\n{table}");
+        assert!(table.lines().next().unwrap().starts_with("id  "));
+    }
+
+    #[tokio::test]
+    async fn test_render_table_right_aligns_amounts() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        let args = ListTransactionsArgs::parse_from([
+            "list-transactions",
+            "--limit",
+            "5",
+            "--columns",
+            "amount,description",
+        ]);
+        let out = list_transactions(config, args).await.unwrap();
+        let table = render(out.structure().unwrap(), Format::Table).unwrap();
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 7, "Unexpected table:\n{table}");
+
+        // The amounts end in the same column, each with a minus sign or not and two decimals.
+        let header_end = lines[0].find("Amount").unwrap() + "Amount".len();
+        let mut amount_end = None;
+        for line in &lines[2..] {
+            let amount = line
+                .split_whitespace()
+                .nth(1)
+                .unwrap_or_else(|| panic!("No amount in {line}"));
+            assert!(!amount.contains('$') && !amount.contains(','), "{line}");
+            assert_eq!(amount.split_once('.').unwrap().1.len(), 2, "{line}");
+            let end = line.find(amount).unwrap() + amount.len();
+            assert_eq!(*amount_end.get_or_insert(end), end, "Misaligned:\n{table}");
+        }
+        assert!(amount_end.unwrap() >= header_end, "Misaligned:\n{table}");
+
+        // Other formats keep the amounts as they were synced.
+        let csv = render(out.structure().unwrap(), Format::Csv).unwrap();
+        assert!(csv.contains('$'), "{csv}");
+    }
+
+    #[test]
+    fn test_table_only_aligns_columns_of_amounts() {
+        let headers = vec!["name".to_string(), "Amount".to_string()];
+        let rows = vec![
+            vec!["a".to_string(), "-87.4".to_string()],
+            vec!["b".to_string(), "$1,234.5".to_string()],
+            vec!["c".to_string(), String::new()],
+        ];
+        assert_eq!(
+            table(&headers, &rows),
+            "name   Amount\n----  -------\na      -87.40\nb     1234.50\nc"
+        );
+
+        let rows = vec![vec!["a".to_string(), "n/a".to_string()]];
+        assert_eq!(
+            table(&headers, &rows),
+            "name  Amount\n----  ------\na     n/a"
+        );
+    }
+
+    #[test]
+    fn test_render_string_as_is() {
+        let text = "| a |\n| --- |\n| 1 |".to_string();
+        assert_eq!(render(&text, Format::Table).unwrap(), text);
+        assert_eq!(render(&text, Format::Csv).unwrap(), text);
+        assert_eq!(
+            render(&text, Format::Json).unwrap(),
+            serde_json::to_string_pretty(&text).unwrap()
+        );
+    }
+}
@@ -1,58 +1,347 @@
-use super::{FormulasMode, Out};
-use crate::api::{sheet, tiller, Mode, Tiller};
+use super::diff::diff_snapshots;
+use super::sync_progress::SyncUpProgress;
+use super::{FormulasMode, Out, SyncUpStrategy};
+use crate::api::{sheet, tiller, Mode, Tiller, WritePlan, TRANSACTIONS};
+use crate::args::UpDown;
 use crate::backup::{SYNC_DOWN, SYNC_UP_PRE};
+use crate::config::ConflictScope;
 use crate::error::{ErrorType, IntoResult};
+use crate::model::{
+    Category, Item, RowCol, SyncCounts, TillerData, Transaction, TransactionColumn, Transactions,
+};
+use crate::utils::USER_TRANSACTION_PREFIX;
 use crate::{Config, Result};
 use anyhow::anyhow;
-use tracing::{debug, info, warn};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use tracing::{debug, info, info_span, warn, Instrument};
+
+/// The ranges of the Google sheet that `sync up` clears and writes.
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncUpPlan {
+    /// The A1 notation ranges that are cleared, e.g. `Transactions!A2:Q`.
+    pub clear_ranges: Vec<String>,
+    /// The ranges that are written, with the dimensions of the data.
+    pub write_ranges: Vec<WriteRange>,
+    /// Whether the ranges are written first and then trimmed by clearing, as configured by
+    /// `atomic_write`, rather than cleared and then written.
+    pub clear_after_write: bool,
+}
 
-/// Gets data from the tiller Google sheet and persists it to the local datastore. Returns an info
-/// message that can be printed for the user.
-pub async fn sync_down(config: Config, mode: Mode) -> Result<Out<()>> {
-    // Backup SQLite database before modifying
-    let sqlite_backup = config
+/// A range written by `sync up` and the dimensions of the data written to it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WriteRange {
+    /// The A1 notation range, e.g. `Transactions!A1:Q`.
+    pub range: String,
+    /// The number of rows written, including the header row.
+    pub rows: usize,
+    /// The number of columns in the widest row.
+    pub columns: usize,
+}
+
+impl From<WritePlan> for SyncUpPlan {
+    fn from(plan: WritePlan) -> Self {
+        Self {
+            clear_ranges: plan.clear_ranges,
+            clear_after_write: plan.clear_after_write,
+            write_ranges: plan
+                .write_ranges
+                .into_iter()
+                .map(|r| WriteRange {
+                    rows: r.values.len(),
+                    columns: r.values.iter().map(Vec::len).max().unwrap_or(0),
+                    range: r.range,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Reconciles the local datastore and the Google sheet in one call. If the local datastore has
+/// changed since the last sync down, the changes are uploaded with `sync up` first. The sheet is
+/// then downloaded with `sync down`, so that the local datastore matches the sheet and becomes the
+/// new baseline for conflict detection.
+///
+/// Local changes are uploaded before downloading because `sync down` replaces the local datastore
+/// with the sheet's data, which would discard them.
+///
+/// # Arguments
+///
+/// - `config` - The application configuration.
+/// - `mode` - Whether to use the live Google sheet or in-memory test data.
+/// - `force` - Passed to `sync up`, overwrite the sheet even if it changed since the last sync down.
+/// - `formulas_mode` - Passed to `sync up`, how to handle formulas.
+///
+/// # Errors
+///
+/// - Returns the `sync up` error, without downloading anything, if the sheet was changed since
+///   the last sync down and `force` is false. Review the changes with `list-conflicts`.
+/// - Returns an error if either phase fails.
+pub async fn sync(
+    config: Config,
+    mode: Mode,
+    force: bool,
+    formulas_mode: FormulasMode,
+) -> Result<Out<()>> {
+    let mut message = String::new();
+    let mut warnings = Vec::new();
+    if has_local_changes(&config).await? {
+        let up = sync_up(
+            config.clone(),
+            mode,
+            force,
+            formulas_mode,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await?;
+        message.push_str(up.message());
+        warnings.extend_from_slice(up.warnings());
+    } else {
+        message.push_str("No local changes to upload");
+    }
+
+    let down = sync_down(config, mode).await?;
+    message.push('\n');
+    message.push_str(down.message());
+    warnings.extend_from_slice(down.warnings());
+    Ok(Out::new_message(message).with_warnings(warnings))
+}
+
+/// Returns true if the transactions, categories or AutoCat rules in the local datastore differ
+/// from the last sync down. Without a sync-down backup, any local data counts as a change.
+async fn has_local_changes(config: &Config) -> Result<bool> {
+    let local = config
+        .db()
+        .get_tiller_data()
+        .await
+        .pub_result(ErrorType::Database)?;
+    let base = config
         .backup()
-        .copy_sqlite()
+        .load_latest_json(SYNC_DOWN)
         .await
         .pub_result(ErrorType::Internal)?;
-    debug!("Saved SQLite backup to {}", sqlite_backup.display());
+    let Some(base) = base else {
+        return Ok(!local.transactions.data().is_empty());
+    };
+
+    let diff = diff_snapshots(&base, &local);
+    Ok(!(diff.transactions.is_empty() && diff.categories.is_empty() && diff.autocats.is_empty()))
+}
+
+/// Gets data from the tiller Google sheet and persists it to the local datastore. Returns an info
+/// message that can be printed for the user. The outcome is recorded in the sync history.
+///
+/// In dry-run mode the sheet is downloaded and checked, but no backups are written, the local
+/// datastore is left unchanged and nothing is recorded in the sync history.
+///
+/// If more than one row of the Transactions tab has the same `Transaction ID`, nothing is saved and
+/// an error names each duplicated ID and its rows. There is no way to tell which of the rows is the
+/// right one, so the duplicates must be fixed in the sheet.
+///
+/// If the Transactions tab has more rows than the configured `max_rows`, nothing is saved either,
+/// in case the configuration points at the wrong sheet.
+///
+/// A sync up that was interrupted left the sheet partly written, and its local changes are not in
+/// the sheet yet. Until it is finished with another sync up, a sync down fails rather than replace
+/// those changes with the partly written sheet.
+pub async fn sync_down(config: Config, mode: Mode) -> Result<Out<()>> {
+    let result = sync_down_inner(config.clone(), mode).await;
+    record_sync(&config, UpDown::Down, &result).await;
+    result.map(|(out, _)| out)
+}
+
+async fn sync_down_inner(config: Config, mode: Mode) -> Result<(Out<()>, SyncCounts)> {
+    if SyncUpProgress::load(&config)
+        .await
+        .pub_result(ErrorType::Internal)?
+        .is_some()
+    {
+        return Err(anyhow!(
+            "A previous sync up was interrupted after writing part of the sheet. Finish it with \
+            sync up first, otherwise the local changes it has not uploaded would be lost"
+        ))
+        .pub_result(ErrorType::Sync);
+    }
+
+    // Backup SQLite database before modifying
+    if !config.dry_run() {
+        let sqlite_backup = config
+            .backup()
+            .copy_sqlite()
+            .await
+            .pub_result(ErrorType::Internal)?;
+        debug!("Saved SQLite backup to {}", sqlite_backup.display());
+    }
 
     // Download data from Google Sheets (or test data in test mode)
     let sheet_client = sheet(config.clone(), mode).await?;
-    let mut tiller_client = tiller(sheet_client).await.pub_result(ErrorType::Internal)?;
-    let tiller_data = tiller_client.get_data().await.pub_result(ErrorType::Sync)?;
+    let mut tiller_client = tiller(
+        sheet_client,
+        config.sync_balances(),
+        config.atomic_write(),
+        config.verify_headers(),
+    )
+    .await
+    .pub_result(ErrorType::Internal)?;
+    tiller_client
+        .check_tabs()
+        .await
+        .pub_result(ErrorType::Sync)?;
+    let tiller_data = tiller_client
+        .get_data()
+        .instrument(info_span!("fetch"))
+        .await
+        .pub_result(ErrorType::Sync)?;
+    check_max_rows(&config, &tiller_data.transactions)?;
+    check_duplicate_ids(&tiller_data.transactions)?;
 
     // Save JSON backup of downloaded data
-    let json_backup = config
-        .backup()
-        .save_json(SYNC_DOWN, &tiller_data)
-        .await
-        .pub_result(ErrorType::Internal)?;
-    debug!("Saved JSON backup to {}", json_backup.display());
+    if !config.dry_run() {
+        let json_backup = config
+            .backup()
+            .save_json(SYNC_DOWN, &tiller_data)
+            .await
+            .pub_result(ErrorType::Internal)?;
+        debug!("Saved JSON backup to {}", json_backup.display());
+    }
 
-    // Save to SQLite database
+    // Save to SQLite database, which is rolled back in a dry run
     config
         .db()
         .save_tiller_data(&tiller_data)
+        .instrument(info_span!("save"))
         .await
         .pub_result(ErrorType::Database)?;
 
-    Ok(Out::new_message(format!(
-        "Synced {} transactions, {} categories, {} autocat rules from sheet to local datastore",
-        tiller_data.transactions.data().len(),
-        tiller_data.categories.data().len(),
-        tiller_data.auto_cats.data().len()
-    )))
+    let counts = SyncCounts {
+        transactions: tiller_data.transactions.data().len() as u64,
+        categories: tiller_data.categories.data().len() as u64,
+        autocats: tiller_data.auto_cats.data().len() as u64,
+        balances: if config.sync_balances() {
+            tiller_data.balances.data().len() as u64
+        } else {
+            0
+        },
+    };
+    let mut message = format!(
+        "Synced {} transactions, {} categories, {} autocat rules",
+        counts.transactions, counts.categories, counts.autocats
+    );
+    if config.sync_balances() {
+        message.push_str(&format!(", {} balances", counts.balances));
+    }
+    message.push_str(" from sheet to local datastore");
+
+    Ok((
+        Out::new_message(message).with_dry_run(config.dry_run()),
+        counts,
+    ))
+}
+
+/// Returns an error if `transactions` has more rows than the `max_rows` of `config`.
+fn check_max_rows(config: &Config, transactions: &Transactions) -> Result<()> {
+    let rows = transactions.data().len() as u64;
+    match config.max_rows() {
+        Some(max_rows) if rows > max_rows => Err(anyhow!(
+            "The {TRANSACTIONS} tab has {rows} rows, more than the limit of {max_rows}, so \
+            nothing was saved. Check that sheet_url points to your Tiller sheet, or raise the \
+            limit with max_rows in config.json or --max-rows"
+        ))
+        .pub_result(ErrorType::Sync),
+        _ => Ok(()),
+    }
+}
+
+/// Returns an error naming each `Transaction ID` that appears on more than one row of
+/// `transactions`, along with the sheet rows it appears on.
+fn check_duplicate_ids(transactions: &Transactions) -> Result<()> {
+    let mut rows: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (ix, transaction) in transactions.data().iter().enumerate() {
+        // Row 1 of the sheet is the header row
+        rows.entry(transaction.transaction_id.as_str())
+            .or_default()
+            .push(ix + 2);
+    }
+    let duplicates: Vec<String> = rows
+        .into_iter()
+        .filter(|(_, rows)| rows.len() > 1)
+        .map(|(id, rows)| {
+            let rows: Vec<String> = rows.iter().map(usize::to_string).collect();
+            format!("'{id}' (rows {})", rows.join(", "))
+        })
+        .collect();
+    if duplicates.is_empty() {
+        return Ok(());
+    }
+    Err(anyhow!(
+        "The {TRANSACTIONS} tab has more than one row with the same Transaction ID: {}. Remove \
+        or fix the duplicate rows in the sheet, then sync down again",
+        duplicates.join(", ")
+    ))
+    .pub_result(ErrorType::Sync)
 }
 
 /// Sends data from the local datastore to the Google sheet, returns a message that can be printed
-/// for the user.
+/// for the user along with the ranges that were cleared and written.
+///
+/// With `SyncUpStrategy::AppendOnly`, only transactions created locally (with `user-` IDs) that
+/// are not yet in the sheet are written, below the last row of the Transactions tab. Nothing is
+/// cleared, so conflict detection and the formula checks are skipped.
+///
+/// With `only` columns, only those columns of the Transactions tab are written. The sheet is read
+/// first and each of its rows gets the values of the local transaction with the same ID, so the
+/// other columns, the rows that are not in the local datastore and the other tabs keep what the
+/// sheet has. Transactions that are not yet in the sheet are not added. `only` cannot be combined
+/// with `SyncUpStrategy::AppendOnly`.
+///
+/// Otherwise the tabs are written one at a time, and each finished tab is recorded in a
+/// `.sync-up-progress` marker in the tiller home directory. If the sync up fails part way through,
+/// the marker is left behind and a retry skips the tabs that were already written. Because the
+/// sheet is then partly overwritten, the retry skips conflict detection and the backups, which the
+/// interrupted sync up already made. If the data to write to the finished tabs has changed since,
+/// the retry fails unless `force` is given, which starts over and writes every tab, still without
+/// conflict detection. The marker is removed once the write has been verified.
+///
+/// When `dry_run` is true, or `config` is in dry-run mode, all checks are performed but nothing is
+/// backed up, cleared or written; the returned plan shows exactly what a real run would clear and
+/// write. Dry runs are not recorded in the sync history, other outcomes are.
 pub async fn sync_up(
     config: Config,
     mode: Mode,
     force: bool,
     formulas_mode: FormulasMode,
-) -> Result<Out<()>> {
+    strategy: SyncUpStrategy,
+    only: Vec<TransactionColumn>,
+    dry_run: bool,
+) -> Result<Out<SyncUpPlan>> {
+    let dry_run = dry_run || config.dry_run();
+    let result = sync_up_inner(
+        config.clone(),
+        mode,
+        force,
+        formulas_mode,
+        strategy,
+        only,
+        dry_run,
+    )
+    .await;
+    if !dry_run {
+        record_sync(&config, UpDown::Up, &result).await;
+    }
+    result.map(|(out, _)| out)
+}
+
+async fn sync_up_inner(
+    config: Config,
+    mode: Mode,
+    force: bool,
+    formulas_mode: FormulasMode,
+    strategy: SyncUpStrategy,
+    only: Vec<TransactionColumn>,
+    dry_run: bool,
+) -> Result<(Out<SyncUpPlan>, SyncCounts)> {
     // Precondition: verify database has transactions
     if config
         .db()
@@ -67,58 +356,106 @@ pub async fn sync_up(
         .pub_result(ErrorType::Sync);
     }
 
-    // Download current sheet state (or test data in test mode)
     let sheet_client = sheet(config.clone(), mode).await?;
-    let mut tiller_client = tiller(sheet_client).await.pub_result(ErrorType::Internal)?;
-    let current_sheet = tiller_client.get_data().await.pub_result(ErrorType::Sync)?;
+    let mut tiller_client = tiller(
+        sheet_client,
+        config.sync_balances(),
+        config.atomic_write(),
+        config.verify_headers(),
+    )
+    .await
+    .pub_result(ErrorType::Internal)?;
+    tiller_client
+        .check_tabs()
+        .await
+        .pub_result(ErrorType::Sync)?;
 
-    // Save sync-up-pre backup (before any modifications)
-    let pre_backup = config
-        .backup()
-        .save_json(SYNC_UP_PRE, &current_sheet)
+    if strategy == SyncUpStrategy::AppendOnly {
+        if !only.is_empty() {
+            return Err(anyhow!(
+                "Only some columns can be synced up with the replace strategy, append-only always \
+                writes whole rows"
+            ))
+            .pub_result(ErrorType::Sync);
+        }
+        let current_sheet = tiller_client.get_data().await.pub_result(ErrorType::Sync)?;
+        save_pre_backup(&config, &current_sheet, dry_run).await?;
+        return append_up(&config, &mut tiller_client, current_sheet, dry_run).await;
+    }
+
+    // Build output data from SQLite
+    let db_data = config
+        .db()
+        .get_tiller_data()
         .await
+        .pub_result(ErrorType::Database)?;
+    if let Err(e) = db_data.validate_references() {
+        return Err(anyhow!(
+            "{e}. Add the missing categories or change these references, then sync up again"
+        ))
+        .pub_result(ErrorType::Sync);
+    }
+    if !only.is_empty() {
+        let only: BTreeSet<TransactionColumn> = only.into_iter().collect();
+        let only: Vec<TransactionColumn> = only.into_iter().collect();
+        return columns_up(
+            &config,
+            &mut tiller_client,
+            &db_data,
+            &only,
+            force,
+            formulas_mode,
+            dry_run,
+        )
+        .await;
+    }
+    let plan = tiller_client
+        .write_plan(&db_data)
         .pub_result(ErrorType::Internal)?;
-    debug!("Saved pre-upload backup to {}", pre_backup.display());
 
-    // Conflict detection: compare current sheet with last sync-down backup
-    let last_sync_down = config
-        .backup()
-        .load_latest_json(SYNC_DOWN)
+    // Non-fatal problems, reported to the user along with the result
+    let mut warnings = Vec::new();
+
+    // A sync up that failed part way through leaves a marker of the tabs it finished. If it was
+    // writing the same data, carry on from there. The sheet is already partly overwritten, so it
+    // can no longer be compared with the last sync down.
+    let progress = SyncUpProgress::load(&config)
         .await
         .pub_result(ErrorType::Internal)?;
-    match last_sync_down {
+    let resumed = match progress {
         None => {
-            if !force {
+            detect_conflicts(&config, &mut tiller_client, force, dry_run, &mut warnings).await?;
+            None
+        }
+        Some(_) if force => {
+            warnings.push(
+                "Overwrote a sheet left partly written by an interrupted sync up, skipped \
+                conflict detection (--force)"
+                    .to_string(),
+            );
+            None
+        }
+        Some(progress) => {
+            if !progress.matches(&plan) {
                 return Err(anyhow!(
-                    "No sync-down backup found. Run 'tiller sync down' first, \
-                     or use --force to proceed without conflict detection"
+                    "A previous sync up was interrupted after writing part of the sheet, and the \
+                     local data has changed since, so it cannot be resumed. Use --force to \
+                     overwrite the partly written sheet"
                 ))
                 .pub_result(ErrorType::Sync);
             }
-            warn!("No sync-down backup found, skipping conflict detection (--force)");
-        }
-        Some(backup_data) => {
-            // Compare current sheet with backup
-            if current_sheet != backup_data {
-                if !force {
-                    return Err(anyhow!(
-                        "Sheet has been modified since last sync down. \
-                         Run 'tiller sync down' first to merge changes, \
-                         or use --force to overwrite"
-                    ))
-                    .pub_result(ErrorType::Sync);
-                }
-                warn!("Sheet differs from last sync-down, proceeding anyway (--force)");
-            }
+            let finished = progress.finished(&plan);
+            let skipped = if finished.is_empty() {
+                String::new()
+            } else {
+                format!(", skipped the tabs it had written: {}", finished.join(", "))
+            };
+            warnings.push(format!(
+                "Resumed an interrupted sync up without conflict detection{skipped}"
+            ));
+            Some(progress)
         }
-    }
-
-    // Build output data from SQLite
-    let db_data = config
-        .db()
-        .get_tiller_data()
-        .await
-        .pub_result(ErrorType::Database)?;
+    };
 
     // Formula safety checks
     match formulas_mode {
@@ -141,41 +478,58 @@ pub async fn sync_up(
                     ))
                     .pub_result(ErrorType::Sync);
                 }
-                warn!("Gaps detected in original_order, proceeding anyway (--force)");
+                warnings.push(
+                    "Gaps detected in original_order, wrote formulas anyway (--force)".to_string(),
+                );
             }
         }
         FormulasMode::Ignore => {
             debug!("Not considering formulas due to '--formulas ignore'");
+            let count = db_data.transactions.formulas().len()
+                + db_data.categories.formulas().len()
+                + db_data.auto_cats.formulas().len()
+                + db_data.balances.formulas().len();
+            if count > 0 {
+                warnings.push(format!(
+                    "{count} formula{} ignored (--formulas ignore), their cells were written as \
+                    values",
+                    if count == 1 { " was" } else { "s were" }
+                ));
+            }
         }
     }
 
-    // Backup SQLite database before uploading
-    let sqlite_backup = config
-        .backup()
-        .copy_sqlite()
-        .await
-        .pub_result(ErrorType::Internal)?;
-    debug!("Saved SQLite backup to {}", sqlite_backup.display());
+    let remaining = match &resumed {
+        Some(progress) => progress.remaining(&plan),
+        None => plan.clone(),
+    };
+    if dry_run {
+        let (out, counts) = dry_run_out(remaining);
+        return Ok((out.with_warnings(warnings), counts));
+    }
 
-    // Backup Google Sheet via Drive API
-    let backup_name = format!(
-        "tiller-backup-{}",
-        chrono::Local::now().format("%Y-%m-%d-%H%M%S")
-    );
-    let backup_id = tiller_client
-        .copy_spreadsheet(&backup_name)
-        .await
-        .pub_result(ErrorType::Sync)?;
-    debug!(
-        "Created Google Sheet backup '{}' (ID: {})",
-        backup_name, backup_id
-    );
+    // The interrupted sync up already made the backups
+    if resumed.is_none() {
+        backup_before_upload(&config, &mut tiller_client).await?;
+    }
 
-    // Execute batch clear and write to Google Sheet
-    tiller_client
-        .clear_and_write_data(&db_data)
+    // Write one tab at a time, recording each finished tab so that a retry can skip it
+    let mut progress = resumed.unwrap_or_default();
+    progress
+        .save(&config)
         .await
-        .pub_result(ErrorType::Sync)?;
+        .pub_result(ErrorType::Internal)?;
+    for tab in remaining.tabs() {
+        tiller_client
+            .apply_plan(&remaining.filter_tabs(|t| t == tab))
+            .await
+            .pub_result(ErrorType::Sync)?;
+        progress.record(&plan, tab);
+        progress
+            .save(&config)
+            .await
+            .pub_result(ErrorType::Internal)?;
+    }
 
     // Verification - re-fetch row counts and compare
     let (txn_count, cat_count, ac_count) = tiller_client
@@ -183,83 +537,867 @@ pub async fn sync_up(
         .await
         .pub_result(ErrorType::Sync)?;
 
+    SyncUpProgress::remove(&config)
+        .await
+        .pub_result(ErrorType::Internal)?;
+
     info!(
         "Synced {} transactions, {} categories, {} autocat rules to sheet",
         txn_count, cat_count, ac_count
     );
 
-    Ok(Out::new_message(format!(
-        "Synced {txn_count} transactions, {cat_count} categories, {ac_count} autocat rules \
-        from local datastore to sheet",
-    )))
+    let counts = SyncCounts {
+        transactions: txn_count as u64,
+        categories: cat_count as u64,
+        autocats: ac_count as u64,
+        balances: if config.sync_balances() {
+            db_data.balances.data().len() as u64
+        } else {
+            0
+        },
+    };
+
+    Ok((
+        Out::new(
+            format!(
+                "Synced {txn_count} transactions, {cat_count} categories, {ac_count} autocat \
+                rules from local datastore to sheet",
+            ),
+            SyncUpPlan::from(remaining),
+        )
+        .with_warnings(warnings),
+        counts,
+    ))
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::api::{SheetCall, TestSheet, MODE_ENV};
-    use crate::args::DeleteTransactionsArgs;
-    use crate::test::TestEnv;
+/// Saves the sync-up-pre backup of `current_sheet`, unless this is a dry run.
+async fn save_pre_backup(config: &Config, current_sheet: &TillerData, dry_run: bool) -> Result<()> {
+    if dry_run {
+        return Ok(());
+    }
+    let pre_backup = config
+        .backup()
+        .save_json(SYNC_UP_PRE, current_sheet)
+        .await
+        .pub_result(ErrorType::Internal)?;
+    debug!("Saved pre-upload backup to {}", pre_backup.display());
+    Ok(())
+}
 
-    #[tokio::test]
-    async fn test_sync_down_saves_to_database() {
-        let env = TestEnv::new().await;
-        let config = env.config();
+/// Downloads the current sheet, saves the sync-up-pre backup of it and compares it with the last
+/// sync down. Returns an error if the sheet has changed, or there is no sync down to compare with,
+/// unless `force` is true, in which case a warning is added to `warnings` instead. Returns the
+/// current sheet.
+async fn detect_conflicts(
+    config: &Config,
+    tiller_client: &mut impl Tiller,
+    force: bool,
+    dry_run: bool,
+    warnings: &mut Vec<String>,
+) -> Result<TillerData> {
+    let current_sheet = tiller_client.get_data().await.pub_result(ErrorType::Sync)?;
 
-        // Run sync_down
-        sync_down(config.clone(), Mode::Testing).await.unwrap();
+    // Save sync-up-pre backup (before any modifications)
+    save_pre_backup(config, &current_sheet, dry_run).await?;
 
-        // Verify SQLite backup was created
-        let backup_files: Vec<_> = std::fs::read_dir(config.backups())
-            .unwrap()
-            .filter_map(|e| e.ok())
-            .map(|e| e.file_name().to_string_lossy().to_string())
-            .collect();
+    // Conflict detection: compare current sheet with last sync-down backup
+    let last_sync_down = config
+        .backup()
+        .load_latest_json(SYNC_DOWN)
+        .await
+        .pub_result(ErrorType::Internal)?;
+    match last_sync_down {
+        None => {
+            if !force {
+                return Err(anyhow!(
+                    "No sync-down backup found. Run 'tiller sync down' first, \
+                     or use --force to proceed without conflict detection"
+                ))
+                .pub_result(ErrorType::Sync);
+            }
+            warnings.push(
+                "No sync-down backup found, skipped conflict detection (--force)".to_string(),
+            );
+        }
+        Some(backup_data) => {
+            // Compare current sheet with backup, as far as the configured scope cares
+            if sheet_changed(config.conflict_scope(), &current_sheet, &backup_data) {
+                if !force {
+                    return Err(anyhow!(
+                        "Sheet has been modified since last sync down. \
+                         Run 'tiller sync down' first to merge changes, \
+                         or use --force to overwrite"
+                    ))
+                    .pub_result(ErrorType::Sync);
+                }
+                warnings.push(
+                    "Sheet differed from last sync-down, overwrote it anyway (--force)".to_string(),
+                );
+            }
+        }
+    }
+    Ok(current_sheet)
+}
 
-        assert!(
-            backup_files.iter().any(|f| f.starts_with("tiller.sqlite.")),
-            "SQLite backup should be created"
-        );
+/// Returns true if `current` differs from `base`, the last sync down, in a way that `scope` treats
+/// as a conflict. Transactions are compared with `Transaction::content_eq`, so a change that Tiller
+/// made on its own to a field such as `Date Added` is not a conflict.
+fn sheet_changed(scope: ConflictScope, current: &TillerData, base: &TillerData) -> bool {
+    let formulas = scope != ConflictScope::ValuesOnly;
+    if !current
+        .transactions
+        .content_eq(&base.transactions, formulas)
+    {
+        return true;
+    }
+    match scope {
+        ConflictScope::All => {
+            current.categories != base.categories
+                || current.auto_cats != base.auto_cats
+                || current.balances != base.balances
+        }
+        ConflictScope::TransactionsOnly => false,
+        ConflictScope::ValuesOnly => {
+            !current.categories.values_eq(&base.categories)
+                || !current.auto_cats.values_eq(&base.auto_cats)
+                || !current.balances.values_eq(&base.balances)
+        }
+    }
+}
 
-        // Verify JSON backup was created
-        assert!(
-            backup_files
+/// The `SyncUpStrategy::AppendOnly` part of `sync up`: writes the locally-created transactions that
+/// are not yet in `current_sheet` below its last row.
+async fn append_up(
+    config: &Config,
+    tiller_client: &mut impl Tiller,
+    current_sheet: TillerData,
+    dry_run: bool,
+) -> Result<(Out<SyncUpPlan>, SyncCounts)> {
+    let db_data = config
+        .db()
+        .get_tiller_data()
+        .await
+        .pub_result(ErrorType::Database)?;
+    let current = &current_sheet.transactions;
+    let new: Vec<Transaction> = db_data
+        .transactions
+        .data()
+        .iter()
+        .filter(|t| t.transaction_id.starts_with(USER_TRANSACTION_PREFIX))
+        .filter(|t| {
+            !current
+                .data()
                 .iter()
-                .any(|f| f.starts_with("sync-down.") && f.ends_with(".json")),
-            "JSON backup should be created"
-        );
-
-        // Verify data was saved to database
-        // TestSheet::default() has 20 transactions, 5 categories, 3 autocat rules
-        let tiller_data = config.db().get_tiller_data().await.unwrap();
-        assert_eq!(
-            tiller_data.transactions.data().len(),
-            20,
-            "Should have 20 transactions from TestSheet"
-        );
-        assert_eq!(
-            tiller_data.categories.data().len(),
-            5,
-            "Should have 5 categories from TestSheet"
-        );
-        assert_eq!(
-            tiller_data.auto_cats.data().len(),
-            3,
-            "Should have 3 autocat rules from TestSheet"
-        );
-
-        // Clean up env var
-        std::env::remove_var(MODE_ENV);
+                .any(|c| c.transaction_id == t.transaction_id)
+        })
+        .cloned()
+        .collect();
+    let categories = missing_categories(&current_sheet, &db_data, &new)?;
+
+    let plan = tiller_client
+        .append_plan(&current_sheet, &categories, &new)
+        .pub_result(ErrorType::Internal)?;
+    if dry_run {
+        return Ok(dry_run_out(plan));
+    }
+    if new.is_empty() {
+        let message = "No new transactions to append, the sheet was not changed";
+        return Ok((
+            Out::new(message, SyncUpPlan::from(plan)),
+            SyncCounts::default(),
+        ));
     }
 
-    #[tokio::test]
-    async fn test_sync_up_errors_when_database_is_empty() {
+    backup_before_upload(config, tiller_client).await?;
+
+    tiller_client
+        .append_transactions(&current_sheet, &categories, &new)
+        .await
+        .pub_result(ErrorType::Sync)?;
+
+    // Verification - the tabs should have grown by exactly the appended rows
+    let after = tiller_client.get_data().await.pub_result(ErrorType::Sync)?;
+    for (what, expected, actual) in [
+        (
+            "transactions",
+            current.data().len() + new.len(),
+            after.transactions.data().len(),
+        ),
+        (
+            "categories",
+            current_sheet.categories.data().len() + categories.len(),
+            after.categories.data().len(),
+        ),
+    ] {
+        if actual != expected {
+            return Err(anyhow!(
+                "Verification failed: expected {expected} {what} after appending, found {actual}"
+            ))
+            .pub_result(ErrorType::Sync);
+        }
+    }
+
+    info!(
+        "Appended {} transactions and {} categories to sheet",
+        new.len(),
+        categories.len()
+    );
+
+    let counts = SyncCounts {
+        transactions: new.len() as u64,
+        categories: categories.len() as u64,
+        ..SyncCounts::default()
+    };
+    let added_categories = match categories.len() {
+        0 => String::new(),
+        1 => ", and the category it uses that the sheet lacked".to_string(),
+        n => format!(", and the {n} categories they use that the sheet lacked"),
+    };
+    Ok((
+        Out::new(
+            format!(
+                "Appended {} transactions from local datastore to sheet{added_categories}",
+                new.len()
+            ),
+            SyncUpPlan::from(plan),
+        ),
+        counts,
+    ))
+}
+
+/// The `only` part of `sync up`: copies the `only` columns of the local transactions into the rows
+/// of the Transactions tab with the same IDs, then writes back just those columns. The sheet is
+/// checked for conflicts first, as for a full sync up.
+async fn columns_up(
+    config: &Config,
+    tiller_client: &mut impl Tiller,
+    db_data: &TillerData,
+    only: &[TransactionColumn],
+    force: bool,
+    formulas_mode: FormulasMode,
+    dry_run: bool,
+) -> Result<(Out<SyncUpPlan>, SyncCounts)> {
+    // An interrupted sync up left the sheet partly written, which a column-by-column write would
+    // not repair
+    if SyncUpProgress::load(config)
+        .await
+        .pub_result(ErrorType::Internal)?
+        .is_some()
+    {
+        return Err(anyhow!(
+            "A previous sync up was interrupted after writing part of the sheet. Finish it with a \
+            sync up of all columns first"
+        ))
+        .pub_result(ErrorType::Sync);
+    }
+
+    let mut warnings = Vec::new();
+    let current_sheet =
+        detect_conflicts(config, tiller_client, force, dry_run, &mut warnings).await?;
+
+    // Writing a column replaces any formulas in it with values
+    let headers = current_sheet.transactions.mapping().headers();
+    let columns: BTreeSet<usize> = only
+        .iter()
+        .filter_map(|column| {
+            headers
+                .iter()
+                .position(|h| h.as_ref() == column.to_header())
+        })
+        .collect();
+    let formulas = current_sheet
+        .transactions
+        .formulas()
+        .keys()
+        .filter(|RowCol(_, col)| columns.contains(col))
+        .count();
+    if formulas > 0 {
+        if formulas_mode != FormulasMode::Ignore {
+            return Err(anyhow!(
+                "The columns to sync up have {formulas} formula{} in the sheet, which would be \
+                replaced with values. Use `--formulas ignore` to overwrite them anyway, or leave \
+                those columns out",
+                if formulas == 1 { "" } else { "s" }
+            ))
+            .pub_result(ErrorType::Sync);
+        }
+        warnings.push(format!(
+            "Overwrote {formulas} formula{} in the synced columns with values (--formulas ignore)",
+            if formulas == 1 { "" } else { "s" }
+        ));
+    }
+
+    // Start from the sheet's rows, in the sheet's order, and take only the chosen columns from
+    // the local transaction with the same ID
+    let local: HashMap<&str, &Transaction> = db_data
+        .transactions
+        .data()
+        .iter()
+        .map(|t| (t.transaction_id.as_str(), t))
+        .collect();
+    let mut merged = current_sheet.transactions.clone();
+    let mut synced = 0;
+    // The sheet may have more than one row with the same ID, so the rows synced are not a count
+    // of the local transactions found in the sheet
+    let mut found = BTreeSet::new();
+    for row in merged.data_mut() {
+        let Some(transaction) = local.get(row.transaction_id.as_str()) else {
+            continue;
+        };
+        for column in only {
+            let header = column.to_header();
+            row.set_with_header(header, transaction.get_by_header(header))
+                .pub_result(ErrorType::Internal)?;
+        }
+        synced += 1;
+        found.insert(row.transaction_id.clone());
+    }
+
+    let unsynced = merged.data().len() - synced;
+    if unsynced > 0 {
+        warnings.push(format!(
+            "{unsynced} row{} of the sheet {} not in the local datastore and kept {} values",
+            if unsynced == 1 { "" } else { "s" },
+            if unsynced == 1 { "is" } else { "are" },
+            if unsynced == 1 { "its" } else { "their" }
+        ));
+    }
+    let missing = local.keys().filter(|id| !found.contains(**id)).count();
+    if missing > 0 {
+        warnings.push(format!(
+            "{missing} local transaction{} not in the sheet and {} not added. Sync up all \
+            columns to add {}",
+            if missing == 1 { " is" } else { "s are" },
+            if missing == 1 { "was" } else { "were" },
+            if missing == 1 { "it" } else { "them" }
+        ));
+    }
+
+    let plan = tiller_client
+        .columns_plan(&merged, only)
+        .pub_result(ErrorType::Sync)?;
+    if dry_run {
+        let (out, counts) = dry_run_out(plan);
+        return Ok((out.with_warnings(warnings), counts));
+    }
+
+    backup_before_upload(config, tiller_client).await?;
+    tiller_client
+        .apply_plan(&plan)
+        .await
+        .pub_result(ErrorType::Sync)?;
+
+    // Verification - only cells were overwritten, so the tab should have the same rows
+    let after = tiller_client.get_data().await.pub_result(ErrorType::Sync)?;
+    let (expected, actual) = (merged.data().len(), after.transactions.data().len());
+    if actual != expected {
+        return Err(anyhow!(
+            "Verification failed: expected {expected} transactions, found {actual}"
+        ))
+        .pub_result(ErrorType::Sync);
+    }
+
+    let names: Vec<&str> = only.iter().map(TransactionColumn::to_header).collect();
+    info!(
+        "Synced the {} columns of {synced} transactions to sheet",
+        names.join(", ")
+    );
+    let counts = SyncCounts {
+        transactions: synced as u64,
+        ..SyncCounts::default()
+    };
+    Ok((
+        Out::new(
+            format!(
+                "Synced the {} column{} of {synced} transactions from local datastore to sheet",
+                names.join(", "),
+                if names.len() == 1 { "" } else { "s" }
+            ),
+            SyncUpPlan::from(plan),
+        )
+        .with_warnings(warnings),
+        counts,
+    ))
+}
+
+/// The local categories that `new` transactions use but the Categories tab of `current_sheet`
+/// lacks, so that they can be appended to the sheet along with the transactions.
+///
+/// # Errors
+/// - Returns an error listing the transactions whose category does not exist locally either.
+fn missing_categories(
+    current_sheet: &TillerData,
+    db_data: &TillerData,
+    new: &[Transaction],
+) -> Result<Vec<Category>> {
+    let on_sheet: BTreeSet<&str> = current_sheet
+        .categories
+        .data()
+        .iter()
+        .map(|c| c.category.as_str())
+        .collect();
+    let mut missing = Vec::new();
+    let mut unknown = Vec::new();
+    for transaction in new {
+        let name = transaction.category.as_str();
+        if name.is_empty()
+            || on_sheet.contains(name)
+            || missing.iter().any(|c: &Category| c.category == name)
+        {
+            continue;
+        }
+        match db_data
+            .categories
+            .data()
+            .iter()
+            .find(|c| c.category == name)
+        {
+            Some(category) => missing.push(category.clone()),
+            None => unknown.push(format!("{} ('{name}')", transaction.transaction_id)),
+        }
+    }
+    if !unknown.is_empty() {
+        return Err(anyhow!(
+            "These transactions refer to categories that exist neither in the sheet nor locally: \
+            {}. Insert the categories or change the transactions, then sync up again",
+            unknown.join(", ")
+        ))
+        .pub_result(ErrorType::Sync);
+    }
+    Ok(missing)
+}
+
+/// Describes what a `sync up` would have cleared and written, for a dry run.
+fn dry_run_out(plan: WritePlan) -> (Out<SyncUpPlan>, SyncCounts) {
+    let plan = SyncUpPlan::from(plan);
+    let clears: String = plan
+        .clear_ranges
+        .iter()
+        .map(|range| format!("\n  {range}"))
+        .collect();
+    let writes: String = plan
+        .write_ranges
+        .iter()
+        .map(|w| format!("\n  {} ({} rows x {} columns)", w.range, w.rows, w.columns))
+        .collect();
+    let steps = if plan.clear_after_write {
+        format!("write:{writes}\nand then clear:{clears}")
+    } else {
+        format!("clear:{clears}\nand write:{writes}")
+    };
+    let message = format!("Dry run, nothing was changed. Sync up would {steps}");
+    (Out::new(message, plan), SyncCounts::default())
+}
+
+/// Backs up the SQLite database and copies the Google sheet via the Drive API before `sync up`
+/// writes to the sheet. The Drive copy is skipped when `drive_backup` is off.
+async fn backup_before_upload(config: &Config, tiller_client: &mut impl Tiller) -> Result<()> {
+    let sqlite_backup = config
+        .backup()
+        .copy_sqlite()
+        .await
+        .pub_result(ErrorType::Internal)?;
+    debug!("Saved SQLite backup to {}", sqlite_backup.display());
+
+    if !config.drive_backup() {
+        debug!("Skipped the Google Sheet backup because drive_backup is off");
+        return Ok(());
+    }
+
+    let backup_name = format!(
+        "tiller-backup-{}",
+        chrono::Local::now().format("%Y-%m-%d-%H%M%S")
+    );
+    let backup_id = tiller_client
+        .copy_spreadsheet(&backup_name)
+        .await
+        .pub_result(ErrorType::Sync)?;
+    debug!(
+        "Created Google Sheet backup '{}' (ID: {})",
+        backup_name, backup_id
+    );
+    Ok(())
+}
+
+/// Writes the outcome of a sync to the `sync_history` table. A failure to record is logged rather
+/// than returned so that it cannot mask the outcome of the sync itself.
+async fn record_sync<T>(config: &Config, direction: UpDown, result: &Result<(Out<T>, SyncCounts)>) {
+    let (counts, success, message) = match result {
+        Ok((out, counts)) => (*counts, true, out.message().to_string()),
+        Err(e) => (SyncCounts::default(), false, e.to_string()),
+    };
+    if let Err(e) = config
+        .db()
+        .record_sync(direction, counts, success, &message)
+        .await
+    {
+        warn!("Unable to record sync {direction} in the sync history: {e:#}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{SheetCall, TestSheet, TestSheetState, MODE_ENV};
+    use crate::args::DeleteTransactionsArgs;
+    use crate::model::{AutoCat, Category, RowCol};
+    use crate::test::{assert_round_trip, written_range, LogBuffer, TestEnv};
+
+    #[tokio::test]
+    async fn test_sync_down_saves_to_database() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+
+        // Run sync_down
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        // Verify SQLite backup was created
+        let backup_files: Vec<_> = std::fs::read_dir(config.backups())
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+
+        assert!(
+            backup_files.iter().any(|f| f.starts_with("tiller.sqlite.")),
+            "SQLite backup should be created"
+        );
+
+        // Verify JSON backup was created
+        assert!(
+            backup_files
+                .iter()
+                .any(|f| f.starts_with("sync-down.") && f.ends_with(".json")),
+            "JSON backup should be created"
+        );
+
+        // Verify data was saved to database
+        // TestSheet::default() has 20 transactions, 5 categories, 3 autocat rules
+        let tiller_data = config.db().get_tiller_data().await.unwrap();
+        assert_eq!(
+            tiller_data.transactions.data().len(),
+            20,
+            "Should have 20 transactions from TestSheet"
+        );
+        assert_eq!(
+            tiller_data.categories.data().len(),
+            5,
+            "Should have 5 categories from TestSheet"
+        );
+        assert_eq!(
+            tiller_data.auto_cats.data().len(),
+            3,
+            "Should have 3 autocat rules from TestSheet"
+        );
+
+        // Clean up env var
+        std::env::remove_var(MODE_ENV);
+    }
+
+    /// A sheet seeded from CSV files, as with `TILLER_SYNC_TEST_SEED_DIR`, is what sync down
+    /// downloads in testing mode.
+    #[tokio::test]
+    async fn test_sync_down_custom_seed_data() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        let seed_dir = tempfile::TempDir::new().unwrap();
+        for (file, csv) in [
+            (
+                "Transactions.csv",
+                "Transaction ID,Date,Description,Amount,Account,Account #,Institution,\
+                Account ID,Category\n\
+                custom-1,1/15/2025,Coffee Shop,-$4.50,Checking,1234,Test Bank,acct-001,Food\n\
+                custom-2,1/16/2025,Paycheck,\"$1,000.00\",Checking,1234,Test Bank,acct-001,\n",
+            ),
+            (
+                "Categories.csv",
+                "Category,Group,Type,Hide From Reports\nFood,Living,Expense,\n",
+            ),
+            ("AutoCat.csv", "Category,Description Contains\n"),
+        ] {
+            std::fs::write(seed_dir.path().join(file), csv).unwrap();
+        }
+        TestSheet::new_with_seed_dir(config.spreadsheet_id(), seed_dir.path()).unwrap();
+
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        let data = config.db().get_tiller_data().await.unwrap();
+        let transactions: Vec<(&str, String)> = data
+            .transactions
+            .data()
+            .iter()
+            .map(|t| (t.transaction_id.as_str(), t.amount.to_display_string()))
+            .collect();
+        assert_eq!(
+            transactions,
+            vec![
+                ("custom-1", "-4.50".to_string()),
+                ("custom-2", "1000.00".to_string())
+            ]
+        );
+        assert_eq!(data.categories.data().len(), 1);
+        assert!(data.auto_cats.data().is_empty());
+
+        // A directory without seed data is an error
+        let empty = tempfile::TempDir::new().unwrap();
+        assert!(
+            TestSheet::new_with_seed_dir("test_sync_down_custom_seed_data", empty.path()).is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sync_down_records_history() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        let history = config.db().sync_history(10).await.unwrap();
+        assert_eq!(history.len(), 1);
+        let record = &history[0];
+        assert!(matches!(record.direction, UpDown::Down));
+        assert!(record.success);
+        assert_eq!(
+            record.counts,
+            SyncCounts {
+                transactions: 20,
+                categories: 5,
+                autocats: 3,
+                balances: 0,
+            }
+        );
+        assert!(record.message.starts_with("Synced 20 transactions"));
+    }
+
+    #[tokio::test]
+    async fn test_sync_down_logs_progress() {
+        let env = TestEnv::new().await;
+        let logs = LogBuffer::default();
+        let guard = logs.capture();
+        sync_down(env.config(), Mode::Testing).await.unwrap();
+        drop(guard);
+
+        let expected = [
+            ("fetch", "Fetched Transactions (20 rows)"),
+            ("fetch", "Fetched Categories (5 rows)"),
+            ("fetch", "Fetched AutoCat (3 rows)"),
+            ("save", "Saving to the database (20/20 transactions)"),
+        ];
+        let lines = logs.lines();
+        let mut positions = Vec::new();
+        for (span, message) in expected {
+            let position = lines
+                .iter()
+                .position(|line| line.contains(&format!("{span}:")) && line.contains(message))
+                .unwrap_or_else(|| panic!("'{message}' was not logged in '{span}': {lines:#?}"));
+            positions.push(position);
+        }
+        assert!(
+            positions.windows(2).all(|w| w[0] < w[1]),
+            "Logged out of order: {lines:#?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sync_down_skips_balances_by_default() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        let tiller_data = config.db().get_tiller_data().await.unwrap();
+        assert!(tiller_data.balances.data().is_empty());
+        assert!(tiller_data.balances.mapping().headers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sync_down_rejects_duplicate_transaction_ids() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+
+        // Copy the first data row (sheet row 2) below the last one (sheet row 21)
+        let mut state = env.get_state();
+        let transactions = state.data.get_mut("Transactions").unwrap();
+        let duplicate = transactions[1].clone();
+        transactions.push(duplicate);
+        state.formatted.remove("Transactions");
+        env.set_state(state);
+
+        let err = sync_down(config.clone(), Mode::Testing).await.unwrap_err();
+        let message = err.to_string();
+        assert!(
+            message.contains("'tx001a2b3c4d5e6f7g8h9i01' (rows 2, 22)"),
+            "{message}"
+        );
+
+        // Nothing was saved and the failure was recorded
+        assert_eq!(config.db().count_transactions().await.unwrap(), 0);
+        let history = config.db().sync_history(1).await.unwrap();
+        assert!(!history[0].success);
+    }
+
+    #[tokio::test]
+    async fn test_sync_down_aborts_above_max_rows() {
+        let mut env = TestEnv::new().await;
+        sync_down(env.config(), Mode::Testing).await.unwrap();
+        let before = env.config().db().get_tiller_data().await.unwrap();
+
+        // The seeded sheet has 20 transactions, and one more is added in the sheet
+        let mut state = env.get_state();
+        let transactions = state.data.get_mut("Transactions").unwrap();
+        let mut row = transactions[1].clone();
+        row[10] = "tx001a2b3c4d5e6f7g8h9i21".to_string();
+        transactions.push(row);
+        state.formatted.remove("Transactions");
+        env.set_state(state);
+        env.set_config_value("max_rows", 10.into()).await;
+        let config = env.config();
+
+        let err = sync_down(config.clone(), Mode::Testing)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("The Transactions tab has 21 rows, more than the limit of 10"),
+            "{err}"
+        );
+        assert_eq!(config.db().get_tiller_data().await.unwrap(), before);
+
+        // The command line limit takes the place of the one in config.json
+        let config = config.with_max_rows(Some(21));
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        assert_eq!(config.db().count_transactions().await.unwrap(), 21);
+    }
+
+    #[tokio::test]
+    async fn test_sync_errors_when_a_tab_is_missing() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        // The AutoCat tab has been renamed
+        let mut state = env.get_state();
+        let auto_cat = state.data.remove("AutoCat").unwrap();
+        state.data.insert("Rules".to_string(), auto_cat);
+        env.set_state(state);
+
+        let e = sync_down(config.clone(), Mode::Testing).await.unwrap_err();
+        assert!(
+            e.to_string().contains(
+                "The sheet has no AutoCat tab. Found the tabs: Balance History, Categories, Rules, \
+                 Transactions"
+            ),
+            "{e}"
+        );
+        let e = sync_up(
+            config,
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap_err();
+        assert!(e.to_string().contains("no AutoCat tab"), "{e}");
+    }
+
+    #[tokio::test]
+    async fn test_sync_balances_round_trip() {
+        let mut env = TestEnv::new().await;
+        env.enable_sync_balances().await;
+        let config = env.config();
+        assert!(config.sync_balances());
+
+        // TestSheet seed data has 4 balance rows
+        let out = sync_down(config.clone(), Mode::Testing).await.unwrap();
+        assert!(out.message().contains("4 balances"));
+
+        let tiller_data = config.db().get_tiller_data().await.unwrap();
+        let balances = tiller_data.balances.data();
+        assert_eq!(balances.len(), 4);
+        assert_eq!(balances[0].account, "Credit Card 1");
+        assert_eq!(balances[0].account_id, "acct001a2b3c4d5e6f7g");
+        assert_eq!(balances[0].date, "10/20/2025");
+        assert_eq!(balances[0].balance.to_string(), "-$1,024.37");
+        assert_eq!(balances[1].other_fields.get("Class").unwrap(), "Asset");
+
+        // Sync back up and make sure the Balance History tab was rewritten
+        let test_sheet = TestSheet::new(config.spreadsheet_id());
+        test_sheet.clear_history();
+        sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap();
+
+        let (_, rows) = written_range(&test_sheet.call_history(), "Balance History");
+        assert_eq!(rows.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_sync_up_warns_when_formulas_are_ignored() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        // The seed data has a formula in "Custom Column" of each of its 20 transactions
+        let out = sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            out.warnings(),
+            ["20 formulas were ignored (--formulas ignore), their cells were written as values"]
+        );
+
+        // Preserving the formulas is nothing to warn about
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        let out = sync_up(
+            config,
+            Mode::Testing,
+            false,
+            FormulasMode::Preserve,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(out.warnings().is_empty(), "{:?}", out.warnings());
+    }
+
+    #[tokio::test]
+    async fn test_sync_up_errors_when_database_is_empty() {
         let env = TestEnv::new().await;
         let config = env.config();
 
         // Database exists but is empty (no sync_down has been run)
         // sync_up should error because there are no transactions
-        let result = sync_up(config, Mode::Testing, false, FormulasMode::Ignore).await;
+        let result = sync_up(
+            config,
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await;
 
         assert!(
             result.is_err(),
@@ -282,9 +1420,17 @@ mod tests {
         sync_down(config.clone(), Mode::Testing).await.unwrap();
 
         // Run sync_up - should create sync-up-pre backup
-        sync_up(config.clone(), Mode::Testing, false, FormulasMode::Ignore)
-            .await
-            .unwrap();
+        sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap();
 
         // Verify sync-up-pre.*.json backup was created
         let backup_files: Vec<_> = std::fs::read_dir(config.backups())
@@ -320,7 +1466,16 @@ mod tests {
         }
 
         // Run sync_up without --force - should error because no sync-down backup exists
-        let result = sync_up(config, Mode::Testing, false, FormulasMode::Ignore).await;
+        let result = sync_up(
+            config,
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await;
 
         assert!(
             result.is_err(),
@@ -352,7 +1507,16 @@ mod tests {
         }
 
         // Run sync_up WITH --force - should NOT error despite missing sync-down backup
-        let result = sync_up(config, Mode::Testing, true, FormulasMode::Ignore).await;
+        let result = sync_up(
+            config,
+            Mode::Testing,
+            true,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await;
 
         assert!(
             result.is_ok(),
@@ -383,7 +1547,16 @@ mod tests {
         env.set_state(state);
 
         // Run sync_up without --force - should error due to detected differences
-        let result = sync_up(config, Mode::Testing, false, FormulasMode::Ignore).await;
+        let result = sync_up(
+            config,
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await;
 
         assert!(
             result.is_err(),
@@ -399,6 +1572,85 @@ mod tests {
         );
     }
 
+    /// Syncs down, applies `change` to the remote sheet, and then syncs up (without `--force`)
+    /// with `conflict_scope` set to `scope`.
+    async fn sync_up_after_remote_change(
+        scope: &str,
+        change: impl FnOnce(&mut TestSheetState),
+    ) -> Result<Out<SyncUpPlan>> {
+        let mut env = TestEnv::new().await;
+        env.set_config_value("conflict_scope", scope.into()).await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        let mut state = env.get_state();
+        change(&mut state);
+        env.set_state(state);
+
+        sync_up(
+            config,
+            Mode::Testing,
+            false,
+            FormulasMode::Preserve,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_sync_up_conflict_scope() {
+        // Change the formula of the first transaction's "Custom Column" without changing its value
+        let change_formula = |state: &mut TestSheetState| {
+            let cell = &mut state.formulas.get_mut("Transactions").unwrap()[1][16];
+            assert_eq!(cell, "=ABS(E2)");
+            *cell = "=ABS(E2)*1".to_string();
+        };
+        let err = sync_up_after_remote_change("all", change_formula)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("modified"), "{err}");
+        sync_up_after_remote_change("values_only", change_formula)
+            .await
+            .unwrap();
+
+        // Change a value in the Categories tab
+        let change_category = |state: &mut TestSheetState| {
+            state.data.get_mut("Categories").unwrap()[1][1].push_str(" Edit");
+        };
+        sync_up_after_remote_change("transactions_only", change_category)
+            .await
+            .unwrap();
+        let err = sync_up_after_remote_change("values_only", change_category)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("modified"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_sync_up_ignores_changes_to_volatile_fields() {
+        // Tiller updated the Date Added and Categorized Date of the first transaction on its own
+        let change_volatile = |state: &mut TestSheetState| {
+            let row = &mut state.data.get_mut("Transactions").unwrap()[1];
+            assert_eq!(row[14], "10/21/25");
+            row[14] = "10/22/25".to_string();
+            row[15] = "10/22/2025 8:00:00 AM".to_string();
+        };
+        sync_up_after_remote_change("all", change_volatile)
+            .await
+            .unwrap();
+
+        // A change to any other field is still a conflict
+        let change_description = |state: &mut TestSheetState| {
+            state.data.get_mut("Transactions").unwrap()[1][2].push_str(" Edit");
+        };
+        let err = sync_up_after_remote_change("all", change_description)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("modified"), "{err}");
+    }
+
     #[tokio::test]
     async fn test_sync_up_proceeds_with_force_when_sheet_modified() {
         let env = TestEnv::new().await;
@@ -421,7 +1673,16 @@ mod tests {
         env.set_state(state);
 
         // Run sync_up WITH --force - should succeed despite differences
-        let result = sync_up(config, Mode::Testing, true, FormulasMode::Ignore).await;
+        let result = sync_up(
+            config,
+            Mode::Testing,
+            true,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await;
 
         assert!(
             result.is_ok(),
@@ -430,6 +1691,229 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_sync_up_errors_when_a_category_does_not_exist() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        config
+            .db()
+            .execute_without_foreign_keys(
+                "UPDATE transactions SET category = 'Nope' \
+                WHERE transaction_id = 'tx001a2b3c4d5e6f7g8h9i01'",
+            )
+            .await
+            .unwrap();
+        let before = env.get_state();
+
+        let err = sync_up(
+            config,
+            Mode::Testing,
+            true,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(
+            err.contains("transaction tx001a2b3c4d5e6f7g8h9i01 ('Nope')"),
+            "{err}"
+        );
+        assert_eq!(env.get_state().data, before.data);
+    }
+
+    #[tokio::test]
+    async fn test_sync_up_only_keeps_the_other_columns_of_the_sheet() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        config
+            .db()
+            .execute_without_foreign_keys(
+                "UPDATE transactions SET category = 'Restaurants', \
+                description = 'Local Description' \
+                WHERE transaction_id = 'tx001a2b3c4d5e6f7g8h9i01'",
+            )
+            .await
+            .unwrap();
+
+        // The description is changed in the sheet as well, and the local change must not win
+        let mut state = env.get_state();
+        state.data.get_mut("Transactions").unwrap()[1][2] = "Remote Description".to_string();
+        env.set_state(state);
+        let before = env.get_state();
+        let test_sheet = TestSheet::new(config.spreadsheet_id());
+        test_sheet.clear_history();
+
+        let out = sync_up(
+            config,
+            Mode::Testing,
+            true,
+            FormulasMode::Unknown,
+            SyncUpStrategy::Replace,
+            vec![TransactionColumn::Category],
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            out.message(),
+            "Synced the Category column of 20 transactions from local datastore to sheet"
+        );
+
+        // Only the Category column was written, nothing was cleared
+        let writes: Vec<String> = test_sheet
+            .call_history()
+            .into_iter()
+            .filter_map(|call| match call {
+                SheetCall::WriteRanges { ranges } => Some(ranges),
+                SheetCall::ClearRanges { .. } => panic!("Nothing should be cleared"),
+                _ => None,
+            })
+            .flatten()
+            .map(|(range, _)| range)
+            .collect();
+        assert_eq!(writes, vec!["Transactions!D2:D21".to_string()]);
+
+        // Every other cell, including the remote description, keeps its value
+        let after = env.get_state();
+        assert_eq!(after.data["Categories"], before.data["Categories"]);
+        let (before, after) = (&before.data["Transactions"], &after.data["Transactions"]);
+        assert_eq!(after[1][3], "Restaurants");
+        assert_eq!(after[1][2], "Remote Description");
+        for (row, (before, after)) in before.iter().zip(after).enumerate() {
+            for (col, (before, after)) in before.iter().zip(after).enumerate() {
+                if (row, col) != (1, 3) {
+                    assert_eq!(before, after, "Cell ({row}, {col}) changed");
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sync_up_only_with_a_duplicated_id_in_the_sheet() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        // The sheet gets a second row with the ID of the first transaction
+        let mut state = env.get_state();
+        for sheet in [&mut state.data, &mut state.formulas] {
+            let rows = sheet.get_mut("Transactions").unwrap();
+            let copy = rows[1].clone();
+            rows.push(copy);
+        }
+        env.set_state(state);
+
+        let out = sync_up(
+            config,
+            Mode::Testing,
+            true,
+            FormulasMode::Preserve,
+            SyncUpStrategy::Replace,
+            vec![TransactionColumn::Category],
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            out.message(),
+            "Synced the Category column of 21 transactions from local datastore to sheet"
+        );
+        assert!(
+            !out.warnings()
+                .iter()
+                .any(|w| w.contains("not in the sheet")),
+            "{:?}",
+            out.warnings()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sync_up_only_errors_on_formulas_unless_ignored() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        let mut state = env.get_state();
+        state.formulas.get_mut("Transactions").unwrap()[1][3] = "=\"Groceries\"".to_string();
+        env.set_state(state);
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        let only = || vec![TransactionColumn::Category, TransactionColumn::Note];
+        let err = sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Preserve,
+            SyncUpStrategy::Replace,
+            only(),
+            false,
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("have 1 formula in the sheet"), "{err}");
+
+        // The seeded sheet has no Note column
+        let err = sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            only(),
+            false,
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("no 'Note' column"), "{err}");
+
+        let out = sync_up(
+            config,
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            vec![TransactionColumn::Category],
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            out.warnings(),
+            ["Overwrote 1 formula in the synced columns with values (--formulas ignore)"]
+        );
+        assert_eq!(env.get_state().formulas["Transactions"][1][3], "Groceries");
+    }
+
+    #[tokio::test]
+    async fn test_sync_up_only_is_not_allowed_with_append_only() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        let err = sync_up(
+            config,
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::AppendOnly,
+            vec![TransactionColumn::Category],
+            false,
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(
+            err.contains("append-only always writes whole rows"),
+            "{err}"
+        );
+    }
+
     #[tokio::test]
     async fn test_sync_up_errors_with_gaps_preserve_no_force() {
         let env = TestEnv::new().await;
@@ -448,7 +1932,16 @@ mod tests {
 
         // Run sync_up with --formulas preserve (no --force)
         // Should error because gaps detected and formulas would be misaligned
-        let result = sync_up(config, Mode::Testing, false, FormulasMode::Preserve).await;
+        let result = sync_up(
+            config,
+            Mode::Testing,
+            false,
+            FormulasMode::Preserve,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await;
 
         assert!(
             result.is_err(),
@@ -479,7 +1972,16 @@ mod tests {
 
         // Run sync_up with --formulas preserve AND --force
         // Should succeed despite gaps
-        let result = sync_up(config, Mode::Testing, true, FormulasMode::Preserve).await;
+        let result = sync_up(
+            config,
+            Mode::Testing,
+            true,
+            FormulasMode::Preserve,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await;
 
         assert!(
             result.is_ok(),
@@ -505,7 +2007,16 @@ mod tests {
 
         // Run sync_up with --formulas ignore (no --force needed)
         // Should succeed because we're ignoring formulas, so gaps don't matter
-        let result = sync_up(config, Mode::Testing, false, FormulasMode::Ignore).await;
+        let result = sync_up(
+            config,
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await;
 
         assert!(
             result.is_ok(),
@@ -534,9 +2045,17 @@ mod tests {
             .count();
 
         // Run sync_up
-        sync_up(config.clone(), Mode::Testing, false, FormulasMode::Ignore)
-            .await
-            .unwrap();
+        sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap();
 
         // Count SQLite backups after sync_up
         let backup_count_after: usize = std::fs::read_dir(config.backups())
@@ -570,9 +2089,17 @@ mod tests {
         test_sheet.clear_history();
 
         // Run sync_up
-        sync_up(config.clone(), Mode::Testing, false, FormulasMode::Ignore)
-            .await
-            .unwrap();
+        sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap();
 
         // Check that copy_spreadsheet was called
         let history = test_sheet.call_history();
@@ -580,68 +2107,373 @@ mod tests {
             .iter()
             .filter(|c| matches!(c, SheetCall::CopySpreadsheet { .. }))
             .collect();
-
-        assert!(
-            !copy_calls.is_empty(),
-            "sync_up should create a Google Sheet backup via copy_spreadsheet. Call history: {:?}",
-            history
-        );
+
+        assert!(
+            !copy_calls.is_empty(),
+            "sync_up should create a Google Sheet backup via copy_spreadsheet. Call history: {:?}",
+            history
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sync_up_skips_google_sheet_backup_when_drive_backup_is_off() {
+        let mut env = TestEnv::new().await;
+        env.set_config_value("drive_backup", false.into()).await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        let sqlite_backups = || {
+            std::fs::read_dir(config.backups())
+                .unwrap()
+                .filter_map(|e| e.ok())
+                .filter(|e| {
+                    e.file_name()
+                        .to_string_lossy()
+                        .starts_with("tiller.sqlite.")
+                })
+                .count()
+        };
+        let backup_count_before = sqlite_backups();
+        let test_sheet = TestSheet::new(config.spreadsheet_id());
+        test_sheet.clear_history();
+
+        sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap();
+
+        let history = test_sheet.call_history();
+        assert!(
+            !history
+                .iter()
+                .any(|c| matches!(c, SheetCall::CopySpreadsheet { .. })),
+            "copy_spreadsheet should not be called. Call history: {history:?}"
+        );
+        // The local backups are still made
+        assert!(sqlite_backups() > backup_count_before);
+    }
+
+    #[tokio::test]
+    async fn test_sync_up_clears_and_writes_sheet_data() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+
+        // Run sync_down to populate the database
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        // Clear call history to isolate sync_up calls
+        let test_sheet = TestSheet::new(config.spreadsheet_id());
+        test_sheet.clear_history();
+
+        // Run sync_up
+        sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap();
+
+        // Check that clear_ranges was called
+        let history = test_sheet.call_history();
+        let clear_calls: Vec<_> = history
+            .iter()
+            .filter(|c| matches!(c, SheetCall::ClearRanges { .. }))
+            .collect();
+
+        assert!(
+            !clear_calls.is_empty(),
+            "sync_up should clear sheet ranges before writing. Call history: {:?}",
+            history
+        );
+
+        // Check that write_ranges was called
+        let write_calls: Vec<_> = history
+            .iter()
+            .filter(|c| matches!(c, SheetCall::WriteRanges { .. }))
+            .collect();
+
+        assert!(
+            !write_calls.is_empty(),
+            "sync_up should write data to sheet ranges. Call history: {:?}",
+            history
+        );
+
+        // Verify clear happens before write
+        let clear_idx = history
+            .iter()
+            .position(|c| matches!(c, SheetCall::ClearRanges { .. }));
+        let write_idx = history
+            .iter()
+            .position(|c| matches!(c, SheetCall::WriteRanges { .. }));
+
+        assert!(
+            clear_idx < write_idx,
+            "clear_ranges should be called before write_ranges"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sync_up_dry_run_previews_ranges() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        let test_sheet = TestSheet::new(config.spreadsheet_id());
+        test_sheet.clear_history();
+
+        // A dry run reads the sheet but never copies, clears or writes it
+        let preview = sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            true,
+        )
+        .await
+        .unwrap();
+        let preview = preview.structure().unwrap().clone();
+        assert!(!test_sheet.call_history().iter().any(|c| matches!(
+            c,
+            SheetCall::ClearRanges { .. }
+                | SheetCall::WriteRanges { .. }
+                | SheetCall::CopySpreadsheet { .. }
+        )));
+
+        // The real run clears and writes exactly what the dry run previewed
+        let out = sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(out.structure().unwrap().clear_ranges, preview.clear_ranges);
+        assert_eq!(out.structure().unwrap().write_ranges, preview.write_ranges);
+
+        // Each tab is cleared and written separately
+        let history = test_sheet.call_history();
+        let cleared: Vec<String> = history
+            .iter()
+            .filter_map(|c| match c {
+                SheetCall::ClearRanges { ranges } => Some(ranges.clone()),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        assert_eq!(cleared, preview.clear_ranges);
+
+        let written: Vec<WriteRange> = history
+            .iter()
+            .filter_map(|c| match c {
+                SheetCall::WriteRanges { ranges } => Some(ranges.clone()),
+                _ => None,
+            })
+            .flatten()
+            .map(|(range, values)| WriteRange {
+                range,
+                rows: values.len(),
+                columns: values.iter().map(Vec::len).max().unwrap_or(0),
+            })
+            .collect();
+        assert_eq!(written, preview.write_ranges);
+        let transactions = preview
+            .write_ranges
+            .iter()
+            .find(|r| r.range == "Transactions!A1:Q")
+            .unwrap();
+        // The header row plus the 20 seeded transactions
+        assert_eq!(transactions.rows, 21);
     }
 
     #[tokio::test]
-    async fn test_sync_up_clears_and_writes_sheet_data() {
+    async fn test_sync_up_resumes_after_partial_failure() {
         let env = TestEnv::new().await;
         let config = env.config();
-
-        // Run sync_down to populate the database
         sync_down(config.clone(), Mode::Testing).await.unwrap();
+        let progress_file = config.root().join(".sync-up-progress");
 
-        // Clear call history to isolate sync_up calls
-        let test_sheet = TestSheet::new(config.spreadsheet_id());
+        // The first tab is written, then writing the second one fails
+        let test_sheet = TestSheet::new(config.spreadsheet_id()).with_write_failure_after(1);
         test_sheet.clear_history();
+        let result = sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await;
+        assert!(result.is_err());
+        assert!(progress_file.exists());
+        let copies = |history: &[SheetCall]| {
+            history
+                .iter()
+                .filter(|c| matches!(c, SheetCall::CopySpreadsheet { .. }))
+                .count()
+        };
+        assert_eq!(copies(&test_sheet.call_history()), 1);
 
-        // Run sync_up
-        sync_up(config.clone(), Mode::Testing, false, FormulasMode::Ignore)
-            .await
-            .unwrap();
-
-        // Check that clear_ranges was called
+        // A retry picks up with the tab that failed and skips the one that was written
+        test_sheet.clear_history();
+        let out = sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(
+            out.warnings()
+                .iter()
+                .any(|w| w.contains("Resumed") && w.contains("Categories")),
+            "{:?}",
+            out.warnings()
+        );
         let history = test_sheet.call_history();
-        let clear_calls: Vec<_> = history
+        assert_eq!(copies(&history), 0);
+        let written: Vec<String> = history
             .iter()
-            .filter(|c| matches!(c, SheetCall::ClearRanges { .. }))
+            .filter_map(|c| match c {
+                SheetCall::WriteRanges { ranges } => Some(ranges.clone()),
+                _ => None,
+            })
+            .flatten()
+            .map(|(range, _)| range)
             .collect();
+        assert_eq!(written, vec!["AutoCat!A1:K", "Transactions!A1:Q"]);
+        assert!(!progress_file.exists());
 
+        // The sheet is complete again, so the next sync up starts from scratch
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        let out = sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap();
         assert!(
-            !clear_calls.is_empty(),
-            "sync_up should clear sheet ranges before writing. Call history: {:?}",
-            history
+            !out.warnings().iter().any(|w| w.contains("Resumed")),
+            "{:?}",
+            out.warnings()
         );
+        assert_eq!(out.structure().unwrap().write_ranges.len(), 3);
+    }
 
-        // Check that write_ranges was called
-        let write_calls: Vec<_> = history
-            .iter()
-            .filter(|c| matches!(c, SheetCall::WriteRanges { .. }))
-            .collect();
+    #[tokio::test]
+    async fn test_sync_down_fails_after_an_interrupted_sync_up() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        config
+            .db()
+            .execute_without_foreign_keys(
+                "UPDATE transactions SET description = 'Local Description' \
+                WHERE transaction_id = 'tx001a2b3c4d5e6f7g8h9i01'",
+            )
+            .await
+            .unwrap();
 
+        let test_sheet = TestSheet::new(config.spreadsheet_id()).with_write_failure_after(1);
+        let attempt = || {
+            sync_up(
+                config.clone(),
+                Mode::Testing,
+                false,
+                FormulasMode::Ignore,
+                SyncUpStrategy::Replace,
+                Vec::new(),
+                false,
+            )
+        };
+        attempt().await.unwrap_err();
+
+        // The local change is kept rather than replaced by the partly written sheet
+        let e = sync_down(config.clone(), Mode::Testing).await.unwrap_err();
         assert!(
-            !write_calls.is_empty(),
-            "sync_up should write data to sheet ranges. Call history: {:?}",
-            history
+            e.to_string().contains("Finish it with sync up first"),
+            "{e}"
         );
+        let description = |data: TillerData| data.transactions.data()[0].description.clone();
+        let local = config.db().get_tiller_data().await.unwrap();
+        assert_eq!(description(local), "Local Description");
 
-        // Verify clear happens before write
-        let clear_idx = history
-            .iter()
-            .position(|c| matches!(c, SheetCall::ClearRanges { .. }));
-        let write_idx = history
-            .iter()
-            .position(|c| matches!(c, SheetCall::WriteRanges { .. }));
+        // Once the sync up is finished, sync down works again
+        test_sheet.clear_history();
+        attempt().await.unwrap();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        let local = config.db().get_tiller_data().await.unwrap();
+        assert_eq!(description(local), "Local Description");
+    }
+
+    #[tokio::test]
+    async fn test_sync_up_cannot_resume_after_local_changes_without_force() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
 
+        TestSheet::new(config.spreadsheet_id()).with_write_failure_after(1);
+        let attempt = |force| {
+            sync_up(
+                config.clone(),
+                Mode::Testing,
+                force,
+                FormulasMode::Ignore,
+                SyncUpStrategy::Replace,
+                Vec::new(),
+                false,
+            )
+        };
+        attempt(false).await.unwrap_err();
+
+        // The categories changed since the interrupted attempt, so the tab it wrote no longer
+        // matches and it cannot be resumed
+        let category = Category {
+            category: "Pets".to_string(),
+            category_group: "Living".to_string(),
+            r#type: "Expense".to_string(),
+            ..Default::default()
+        };
+        config.db().insert_category(&category).await.unwrap();
+        let e = attempt(false).await.unwrap_err();
+        assert!(e.to_string().contains("cannot be resumed"), "{e}");
+
+        // With force, every tab is written again
+        let out = attempt(true).await.unwrap();
         assert!(
-            clear_idx < write_idx,
-            "clear_ranges should be called before write_ranges"
+            out.warnings().iter().any(|w| w.contains("partly written")),
+            "{:?}",
+            out.warnings()
         );
+        assert_eq!(out.structure().unwrap().write_ranges.len(), 3);
+        assert!(!config.root().join(".sync-up-progress").exists());
     }
 
     #[tokio::test]
@@ -657,9 +2489,17 @@ mod tests {
         test_sheet.clear_history();
 
         // Run sync_up
-        sync_up(config.clone(), Mode::Testing, false, FormulasMode::Ignore)
-            .await
-            .unwrap();
+        sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap();
 
         // Check that verification occurred - Get calls should happen after WriteRanges
         let history = test_sheet.call_history();
@@ -674,7 +2514,8 @@ mod tests {
             .iter()
             .enumerate()
             .filter(|(idx, c)| {
-                matches!(c, SheetCall::Get { .. }) && write_idx.map_or(false, |w| *idx > w)
+                matches!(c, SheetCall::GetFormattedValues { .. })
+                    && write_idx.map_or(false, |w| *idx > w)
             })
             .collect();
 
@@ -701,7 +2542,16 @@ mod tests {
         );
 
         // Run sync_up with FormulasMode::Unknown - should error because formulas exist
-        let result = sync_up(config, Mode::Testing, false, FormulasMode::Unknown).await;
+        let result = sync_up(
+            config,
+            Mode::Testing,
+            false,
+            FormulasMode::Unknown,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await;
 
         assert!(
             result.is_err(),
@@ -715,4 +2565,642 @@ mod tests {
             err_msg
         );
     }
+
+    #[tokio::test]
+    async fn test_sync_down_uses_formatted_values() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+
+        // Simulate the raw values holding a date serial number while the sheet displays the date
+        let mut state = env.get_state();
+        let transactions = state.data.get_mut("Transactions").unwrap();
+        let formatted = transactions.clone();
+        // Row 0 is the header row and column 1 is Date
+        assert_eq!(transactions[1][1], "10/20/2025");
+        transactions[1][1] = "45950".to_string();
+        env.set_state(state);
+        let _ = TestSheet::new(config.spreadsheet_id())
+            .with_formatted_values("Transactions", formatted);
+
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        let txn = config
+            .db()
+            ._get_transaction("tx001a2b3c4d5e6f7g8h9i01")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(txn.date, "10/20/2025");
+    }
+
+    #[tokio::test]
+    async fn test_sync_up_append_only_writes_only_new_user_transactions() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        let before = env.get_state().data["Transactions"].clone();
+
+        // A locally edited Tiller transaction is not written, only new user- transactions are
+        let mut edited = config
+            .db()
+            ._get_transaction("tx001a2b3c4d5e6f7g8h9i01")
+            .await
+            .unwrap()
+            .unwrap();
+        edited.note = "edited locally".to_string();
+        config.db().update_transaction(&edited).await.unwrap();
+        for (id, description) in [
+            ("user-00000000000000000001", "Farmers Market"),
+            ("user-00000000000000000002", "Bakery"),
+        ] {
+            let mut t = Transaction::default();
+            t.transaction_id = id.to_string();
+            t.date = "10/22/2025".to_string();
+            t.description = description.to_string();
+            config.db().insert_transaction(&t).await.unwrap();
+        }
+
+        let test_sheet = TestSheet::new(config.spreadsheet_id());
+        test_sheet.clear_history();
+        let out = sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Unknown,
+            SyncUpStrategy::AppendOnly,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            out.message(),
+            "Appended 2 transactions from local datastore to sheet"
+        );
+
+        // Nothing is cleared and only the two new rows are written, below the 20 existing rows
+        let history = test_sheet.call_history();
+        assert!(!history
+            .iter()
+            .any(|c| matches!(c, SheetCall::ClearRanges { .. })));
+        let writes: Vec<_> = history
+            .iter()
+            .filter_map(|c| match c {
+                SheetCall::WriteRanges { ranges } => Some(ranges),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(writes.len(), 1);
+        assert_eq!(writes[0].len(), 1);
+        let (range, rows) = &writes[0][0];
+        assert_eq!(range, "Transactions!A22:Q23");
+        assert_eq!(rows.len(), 2);
+        let id_col = before[0]
+            .iter()
+            .position(|h| h == "Transaction ID")
+            .unwrap();
+        assert_eq!(rows[0][id_col], "user-00000000000000000001");
+        assert_eq!(rows[1][id_col], "user-00000000000000000002");
+
+        // The existing rows are untouched
+        let after = env.get_state().data["Transactions"].clone();
+        assert_eq!(after.len(), before.len() + 2);
+        assert_eq!(after[..before.len()], before[..]);
+
+        // The appended transactions are now in the sheet, so another run writes nothing
+        test_sheet.clear_history();
+        let out = sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Unknown,
+            SyncUpStrategy::AppendOnly,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(out.message().starts_with("No new transactions to append"));
+        assert!(!test_sheet
+            .call_history()
+            .iter()
+            .any(|c| matches!(c, SheetCall::WriteRanges { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_sync_up_append_only_adds_missing_categories_first() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        let before = env.get_state().data["Categories"].clone();
+
+        let category = Category {
+            category: "Pets".to_string(),
+            category_group: "Living".to_string(),
+            r#type: "Expense".to_string(),
+            ..Default::default()
+        };
+        config.db().insert_category(&category).await.unwrap();
+        let mut t = Transaction::default();
+        t.transaction_id = "user-00000000000000000001".to_string();
+        t.date = "10/22/2025".to_string();
+        t.description = "Pet Store".to_string();
+        t.category = "Pets".to_string();
+        config.db().insert_transaction(&t).await.unwrap();
+
+        let test_sheet = TestSheet::new(config.spreadsheet_id());
+        test_sheet.clear_history();
+        let out = sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Unknown,
+            SyncUpStrategy::AppendOnly,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(
+            out.message().contains("the category it uses"),
+            "{}",
+            out.message()
+        );
+
+        // The category is written below the existing ones, before the transaction
+        let written: Vec<String> = test_sheet
+            .call_history()
+            .into_iter()
+            .filter_map(|c| match c {
+                SheetCall::WriteRanges { ranges } => Some(ranges),
+                _ => None,
+            })
+            .flatten()
+            .map(|(range, _)| range)
+            .collect();
+        let first = before.len() + 1;
+        assert_eq!(
+            written,
+            vec![
+                format!("Categories!A{first}:P{first}"),
+                "Transactions!A22:Q22".to_string()
+            ]
+        );
+        let after = env.get_state().data["Categories"].clone();
+        assert_eq!(after.len(), before.len() + 1);
+        assert_eq!(after[..before.len()], before[..]);
+        let name_col = before[0].iter().position(|h| h == "Category").unwrap();
+        assert_eq!(after[before.len()][name_col], "Pets");
+    }
+
+    #[tokio::test]
+    async fn test_sync_up_append_only_errors_on_an_unknown_category() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        config
+            .db()
+            .execute_without_foreign_keys(
+                "INSERT INTO transactions (transaction_id, date, description, amount, account, \
+                account_number, institution, account_id, category) VALUES \
+                ('user-00000000000000000001', '10/22/2025', 'Pet Store', '-5.00', '', '', '', '', \
+                'Nope')",
+            )
+            .await
+            .unwrap();
+        let before = env.get_state();
+
+        let err = sync_up(
+            config,
+            Mode::Testing,
+            false,
+            FormulasMode::Unknown,
+            SyncUpStrategy::AppendOnly,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("user-00000000000000000001 ('Nope')"), "{err}");
+        assert_eq!(env.get_state().data, before.data);
+    }
+
+    #[tokio::test]
+    async fn test_sync_uploads_local_changes_then_downloads() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        let mut t = Transaction::default();
+        t.transaction_id = "user-00000000000000000001".to_string();
+        t.date = "10/22/2025".to_string();
+        t.description = "Farmers Market".to_string();
+        config.db().insert_transaction(&t).await.unwrap();
+
+        sync(config.clone(), Mode::Testing, false, FormulasMode::Ignore)
+            .await
+            .unwrap();
+
+        // The local insert reached the sheet and survived the download that followed
+        let sheet = &env.get_state().data["Transactions"];
+        assert_eq!(sheet.len(), 22);
+        assert!(sheet
+            .iter()
+            .any(|row| row.iter().any(|cell| cell == "Farmers Market")));
+        assert!(config
+            .db()
+            ._get_transaction("user-00000000000000000001")
+            .await
+            .unwrap()
+            .is_some());
+
+        // Both phases ran, newest first
+        let history = config.db().sync_history(2).await.unwrap();
+        assert!(matches!(history[0].direction, UpDown::Down));
+        assert!(matches!(history[1].direction, UpDown::Up));
+    }
+
+    #[tokio::test]
+    async fn test_sync_without_local_changes_only_downloads() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        let test_sheet = TestSheet::new(config.spreadsheet_id());
+        test_sheet.clear_history();
+        let out = sync(config.clone(), Mode::Testing, false, FormulasMode::Ignore)
+            .await
+            .unwrap();
+
+        assert!(out.message().starts_with("No local changes to upload"));
+        assert!(!test_sheet
+            .call_history()
+            .iter()
+            .any(|c| matches!(c, SheetCall::WriteRanges { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_sync_stops_when_sheet_changed() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        let mut local = config
+            .db()
+            ._get_transaction("tx001a2b3c4d5e6f7g8h9i01")
+            .await
+            .unwrap()
+            .unwrap();
+        local.note = "edited locally".to_string();
+        config.db().update_transaction(&local).await.unwrap();
+        let mut state = env.get_state();
+        // Row 0 is the header row and column 3 is Category
+        state.data.get_mut("Transactions").unwrap()[1][3] = "Coffee Shops".to_string();
+        state.formatted.remove("Transactions");
+        env.set_state(state);
+
+        let result = sync(config.clone(), Mode::Testing, false, FormulasMode::Ignore).await;
+        assert!(result.is_err());
+
+        // Nothing was downloaded over the local edit
+        let local = config
+            .db()
+            ._get_transaction("tx001a2b3c4d5e6f7g8h9i01")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(local.note, "edited locally");
+        assert_eq!(local.category, "Groceries");
+    }
+
+    #[tokio::test]
+    async fn test_sync_up_preserves_column_a() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+
+        // The seeded Column A has an empty header and empty cells, give some of them values
+        let mut state = env.get_state();
+        let transactions = state.data.get_mut("Transactions").unwrap();
+        assert_eq!(transactions[0][0], "");
+        transactions[1][0] = "x".to_string();
+        transactions[3][0] = " leading and trailing ".to_string();
+        transactions[5][0] = "Ünïcödé ✓".to_string();
+        let column_a: Vec<String> = transactions.iter().map(|row| row[0].clone()).collect();
+        state.formatted.remove("Transactions");
+        env.set_state(state);
+
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        let txn = config
+            .db()
+            ._get_transaction("tx001a2b3c4d5e6f7g8h9i01")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(txn.no_name, "x");
+
+        let test_sheet = TestSheet::new(config.spreadsheet_id());
+        test_sheet.clear_history();
+        sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap();
+
+        let (range, rows) = written_range(&test_sheet.call_history(), "Transactions");
+        assert_eq!(range, "Transactions!A1:Q");
+        let written_column_a: Vec<String> = rows.iter().map(|row| row[0].clone()).collect();
+        assert_eq!(written_column_a, column_a);
+    }
+
+    #[tokio::test]
+    async fn test_sync_with_and_without_column_a() {
+        // The same seeded sheet, as is and without its empty Column A
+        let with = TestEnv::new().await;
+        let without = TestEnv::new().await;
+        TestSheet::new(without.config().spreadsheet_id()).without_column_a();
+        sync_down(with.config(), Mode::Testing).await.unwrap();
+        sync_down(without.config(), Mode::Testing).await.unwrap();
+
+        // Every field is read by its header, wherever its column is
+        let with_data = with.config().db().get_tiller_data().await.unwrap();
+        let without_data = without.config().db().get_tiller_data().await.unwrap();
+        assert_eq!(with_data.transactions.mapping().headers()[0].as_ref(), "");
+        assert_eq!(
+            without_data.transactions.mapping().headers()[0].as_ref(),
+            "Date"
+        );
+        assert_eq!(
+            with_data.transactions.data(),
+            without_data.transactions.data()
+        );
+
+        // The formulas are found one column to the left, and refer to the Amount column there
+        let shifted: Vec<(RowCol, String)> = with_data
+            .transactions
+            .formulas()
+            .iter()
+            .map(|(RowCol(row, col), formula)| {
+                (
+                    RowCol::new(*row, col - 1),
+                    formula.replace("=ABS(E", "=ABS(D"),
+                )
+            })
+            .collect();
+        let without_formulas: Vec<(RowCol, String)> = without_data
+            .transactions
+            .formulas()
+            .iter()
+            .map(|(rc, formula)| (*rc, formula.clone()))
+            .collect();
+        assert!(!shifted.is_empty());
+        assert_eq!(without_formulas, shifted);
+
+        // Both are written back with the same values, in their own columns
+        let mut written = Vec::new();
+        for env in [&with, &without] {
+            let test_sheet = TestSheet::new(env.config().spreadsheet_id());
+            test_sheet.clear_history();
+            sync_up(
+                env.config(),
+                Mode::Testing,
+                false,
+                FormulasMode::Preserve,
+                SyncUpStrategy::Replace,
+                Vec::new(),
+                false,
+            )
+            .await
+            .unwrap();
+            written.push(written_range(&test_sheet.call_history(), "Transactions"));
+        }
+        let (with_range, mut with_values) = written.remove(0);
+        let (without_range, without_values) = written.remove(0);
+        assert_eq!(with_range, "Transactions!A1:Q");
+        assert_eq!(without_range, "Transactions!A1:P");
+        for row in &mut with_values {
+            row.remove(0);
+        }
+        let with_values: Vec<Vec<String>> = with_values
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|cell| cell.replace("=ABS(E", "=ABS(D"))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(with_values, without_values);
+    }
+
+    /// Puts the rows of the `tab` tab in an order that differs from the seeded order, then asserts
+    /// that `sync down` stores them in that order and that `sync up` writes them back in that order,
+    /// followed by the rows named in `added`, which are inserted locally, in the order they were
+    /// inserted.
+    async fn assert_sync_up_preserves_order(tab: &str, added: &[&str]) {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        // The column that names a row of the tab
+        let name_col = match tab {
+            "Categories" => 0,
+            "AutoCat" => 1,
+            other => panic!("No row order for the '{other}' tab"),
+        };
+
+        let mut state = env.get_state();
+        let rows = state.data.get_mut(tab).unwrap();
+        let seeded: Vec<String> = rows[1..].iter().map(|row| row[name_col].clone()).collect();
+        rows[1..].reverse();
+        let mut expected: Vec<String> = rows[1..].iter().map(|row| row[name_col].clone()).collect();
+        assert_ne!(expected, seeded);
+        state.formulas.remove(tab);
+        state.formatted.remove(tab);
+        env.set_state(state);
+
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        let db = config.db();
+        let stored: Vec<String> = match tab {
+            "Categories" => db
+                .get_categories()
+                .await
+                .unwrap()
+                .data()
+                .iter()
+                .map(|c| c.category.clone())
+                .collect(),
+            _ => db
+                .get_autocats()
+                .await
+                .unwrap()
+                .data()
+                .iter()
+                .map(|r| r.description_contains.clone())
+                .collect(),
+        };
+        assert_eq!(stored, expected);
+
+        for name in added {
+            if tab == "Categories" {
+                let category = Category {
+                    category: name.to_string(),
+                    ..Default::default()
+                };
+                db.insert_category(&category).await.unwrap();
+            } else {
+                let rule = AutoCat {
+                    category: "Groceries".to_string(),
+                    description_contains: name.to_string(),
+                    ..Default::default()
+                };
+                db.insert_autocat(&rule).await.unwrap();
+            }
+            expected.push(name.to_string());
+        }
+
+        let test_sheet = TestSheet::new(config.spreadsheet_id());
+        test_sheet.clear_history();
+        sync_up(
+            config.clone(),
+            Mode::Testing,
+            false,
+            FormulasMode::Ignore,
+            SyncUpStrategy::Replace,
+            Vec::new(),
+            false,
+        )
+        .await
+        .unwrap();
+
+        let (_, rows) = written_range(&test_sheet.call_history(), tab);
+        let written: Vec<String> = rows[1..].iter().map(|row| row[name_col].clone()).collect();
+        assert_eq!(written, expected);
+    }
+
+    #[tokio::test]
+    async fn test_sync_up_preserves_category_order() {
+        assert_sync_up_preserves_order("Categories", &["Zoo", "Aardvark"]).await;
+    }
+
+    #[tokio::test]
+    async fn test_sync_up_preserves_autocat_order() {
+        // Rule order decides which rule wins
+        assert_sync_up_preserves_order("AutoCat", &["Trader Joe"]).await;
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_transactions() {
+        let headers = vec![
+            "",
+            "Date",
+            "Description",
+            "Category",
+            "Amount",
+            "Account",
+            "Account #",
+            "Institution",
+            "Month",
+            "Week",
+            "Transaction ID",
+            "Account ID",
+            "Check Number",
+            "Full Description",
+            "Date Added",
+            "Categorized Date",
+            "Note",
+            "Tags",
+            "Custom Column",
+        ];
+        let rows = vec![
+            headers,
+            vec![
+                "",
+                "10/20/2025",
+                "Whole Foods Market",
+                "Groceries",
+                "-$1,234.50",
+                "Credit Card 1",
+                "xxxx1234",
+                "Bank A",
+                "10/1/25",
+                "10/19/25",
+                "tx-round-trip-01",
+                "acct001",
+                "",
+                "WHOLE FOODS MARKET",
+                "10/21/25",
+                "10/21/2025 9:15:30 AM",
+                "Party supplies",
+                "party, food",
+                "custom",
+            ],
+            vec![
+                "",
+                "10/19/2025",
+                "Paycheck",
+                "",
+                "$2,500.00",
+                "Checking 1",
+                "xxxx5678",
+                "Bank A",
+                "10/1/25",
+                "10/19/25",
+                "tx-round-trip-02",
+                "acct002",
+                "1001",
+                "PAYROLL DEPOSIT",
+                "10/20/25",
+            ],
+        ];
+        assert_round_trip("Transactions", &rows, &["Custom Column"]).await;
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_categories() {
+        // Transactions in the seed data reference all of these categories
+        let rows = vec![
+            vec![
+                "Category",
+                "Group",
+                "Type",
+                "Hide From Reports",
+                "Jan 2024",
+                "Feb 2024",
+            ],
+            vec!["Groceries", "Food", "Expense", "", "$500.00", "$450.00"],
+            vec!["Coffee Shops", "Food", "Expense", "Hide", "$0.00", "$0.00"],
+            vec!["Gas & Fuel", "Auto", "Expense", "", "$100.00", ""],
+            vec!["Restaurants", "Food", "Expense"],
+            vec!["Utilities", "Home", "Expense", "Hide", "$200.00", "$200.00"],
+        ];
+        assert_round_trip("Categories", &rows, &["Jan 2024", "Feb 2024"]).await;
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_autocats() {
+        let rows = vec![
+            vec![
+                "Category",
+                "Description Contains",
+                "Account Contains",
+                "Institution Contains",
+                "Amount Min",
+                "Amount Max",
+                "Amount Equals",
+                "Description Equals",
+                "Description",
+                "Full Description Contains",
+                "Amount Contains",
+            ],
+            vec!["Groceries", "Whole Foods", "Credit Card", "Bank A"],
+            vec!["Coffee Shops", "", "", "", "2.5", "10", "", "", "Coffee"],
+            vec!["Utilities", "", "", "", "", "", "89.99", "Comcast Internet"],
+            vec!["", "", "", "", "", "", "", "", "", "SHELL", "52"],
+        ];
+        assert_round_trip("AutoCat", &rows, &[]).await;
+    }
 }
@@ -0,0 +1,85 @@
+//! Implements listing of the sync history.
+
+use crate::args::SyncHistoryArgs;
+use crate::commands::Out;
+use crate::error::{ErrorType, IntoResult};
+use crate::model::SyncRecord;
+use crate::{Config, Result};
+
+/// Lists the most recent `sync up` and `sync down` outcomes, newest first.
+///
+/// # Arguments
+///
+/// - `config` - The application configuration containing the database connection.
+/// - `args` - The maximum number of entries to return.
+///
+/// # Returns
+///
+/// On success, returns an `Out` containing a message and the sync history entries.
+///
+/// # Errors
+///
+/// - Returns an error if a database operation fails.
+pub async fn sync_history(config: Config, args: SyncHistoryArgs) -> Result<Out<Vec<SyncRecord>>> {
+    let records = config
+        .db()
+        .sync_history(args.limit)
+        .await
+        .pub_result(ErrorType::Database)?;
+
+    let message = match records.first() {
+        None => "No syncs have been recorded".to_string(),
+        Some(last) => format!(
+            "Listed {} syncs, the most recent was sync {} at {}",
+            records.len(),
+            last.direction,
+            last.finished_at
+        ),
+    };
+
+    Ok(Out::new(message, records))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::UpDown;
+    use crate::model::SyncCounts;
+    use crate::test::TestEnv;
+
+    #[tokio::test]
+    async fn test_sync_history_newest_first_and_limited() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        let db = config.db();
+
+        let out = sync_history(config.clone(), SyncHistoryArgs::default())
+            .await
+            .unwrap();
+        assert!(out.structure().unwrap().is_empty());
+
+        let counts = SyncCounts {
+            transactions: 2,
+            ..SyncCounts::default()
+        };
+        db.record_sync(UpDown::Down, counts, true, "first")
+            .await
+            .unwrap();
+        db.record_sync(UpDown::Up, SyncCounts::default(), false, "second")
+            .await
+            .unwrap();
+        db.record_sync(UpDown::Down, counts, true, "third")
+            .await
+            .unwrap();
+
+        let out = sync_history(config.clone(), SyncHistoryArgs { limit: 2 })
+            .await
+            .unwrap();
+        let records = out.structure().unwrap();
+        let messages: Vec<_> = records.iter().map(|r| r.message.as_str()).collect();
+        assert_eq!(messages, vec!["third", "second"]);
+        assert!(matches!(records[1].direction, UpDown::Up));
+        assert!(!records[1].success);
+        assert_eq!(records[0].counts, counts);
+    }
+}
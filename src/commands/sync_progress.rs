@@ -0,0 +1,98 @@
+//! Records which tabs a `sync up` has finished writing so that, if it fails part way through, a
+//! retry can pick up where it left off instead of starting over.
+
+use crate::api::WritePlan;
+use crate::error::Res;
+use crate::{utils, Config};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+
+/// The name of the progress marker file in the tiller home directory.
+const SYNC_UP_PROGRESS: &str = ".sync-up-progress";
+
+/// The tabs that a `sync up` has finished writing, saved to `.sync-up-progress` in the tiller home
+/// directory. The marker is created before the first tab is written, updated after each tab and
+/// removed once the whole write has been verified, so its presence means that a `sync up` was
+/// interrupted.
+///
+/// Each tab is stored with a fingerprint of the ranges written to it, so that a tab is only skipped
+/// on a retry that would write exactly the same data to it again.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub(super) struct SyncUpProgress {
+    /// The fingerprint of what was written to each finished tab, keyed by tab name.
+    written: BTreeMap<String, u64>,
+}
+
+impl SyncUpProgress {
+    /// Loads the marker left by an interrupted `sync up`, if there is one.
+    pub(super) async fn load(config: &Config) -> Res<Option<Self>> {
+        let path = path(config);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(utils::deserialize(&path).await?))
+    }
+
+    /// Saves the marker, replacing any earlier one.
+    pub(super) async fn save(&self, config: &Config) -> Res<()> {
+        utils::write(path(config), serde_json::to_string_pretty(self)?).await
+    }
+
+    /// Removes the marker, if there is one.
+    pub(super) async fn remove(config: &Config) -> Res<()> {
+        let path = path(config);
+        if path.exists() {
+            utils::remove(path).await?;
+        }
+        Ok(())
+    }
+
+    /// True if every finished tab was written with the same ranges and data that `plan` has for
+    /// it, i.e. the interrupted `sync up` can be resumed with `plan`.
+    pub(super) fn matches(&self, plan: &WritePlan) -> bool {
+        let tabs = plan.tabs();
+        self.written.iter().all(|(tab, fingerprint)| {
+            tabs.contains(&tab.as_str()) && *fingerprint == fingerprint_of(plan, tab)
+        })
+    }
+
+    /// The tabs of `plan` that have been written, in the order they are written.
+    pub(super) fn finished<'a>(&self, plan: &'a WritePlan) -> Vec<&'a str> {
+        plan.tabs()
+            .into_iter()
+            .filter(|tab| self.written.contains_key(*tab))
+            .collect()
+    }
+
+    /// The part of `plan` that has not been written yet.
+    pub(super) fn remaining(&self, plan: &WritePlan) -> WritePlan {
+        plan.filter_tabs(|tab| !self.written.contains_key(tab))
+    }
+
+    /// Records that `tab` has been written as given by `plan`.
+    pub(super) fn record(&mut self, plan: &WritePlan, tab: &str) {
+        self.written
+            .insert(tab.to_string(), fingerprint_of(plan, tab));
+    }
+}
+
+fn path(config: &Config) -> PathBuf {
+    config.root().join(SYNC_UP_PROGRESS)
+}
+
+/// A hash of the ranges that `plan` clears and writes for `tab`, and of the data written. It only
+/// needs to be stable between an interrupted `sync up` and its retry; if it changes, e.g. with a
+/// new version of Rust, the marker is treated as stale and the retry starts over.
+fn fingerprint_of(plan: &WritePlan, tab: &str) -> u64 {
+    let part = plan.filter_tabs(|t| t == tab);
+    let mut hasher = DefaultHasher::new();
+    part.clear_ranges.hash(&mut hasher);
+    for range in &part.write_ranges {
+        range.range.hash(&mut hasher);
+        range.values.hash(&mut hasher);
+    }
+    hasher.finish()
+}
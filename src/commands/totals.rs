@@ -0,0 +1,91 @@
+//! Commands that summarize transaction amounts.
+
+use crate::args::InstitutionTotalsArgs;
+use crate::commands::Out;
+use crate::error::{ErrorType, IntoResult};
+use crate::model::Amount;
+use crate::{utils, Config, Result};
+use chrono::NaiveDate;
+use std::collections::BTreeMap;
+
+/// Sums transaction amounts grouped by institution.
+///
+/// Transactions with a blank institution are grouped under `"(unknown)"`. If `date_from` or
+/// `date_to` are given, only transactions within that (inclusive) date range are counted.
+///
+/// # Arguments
+///
+/// - `config` - The application configuration containing the database connection.
+/// - `args` - The optional date range to restrict the totals to.
+///
+/// # Returns
+///
+/// On success, returns an `Out` containing:
+/// - A message listing the total for each institution.
+/// - A map of institution name to the summed amount of its transactions.
+///
+/// # Errors
+///
+/// - Returns an error if `date_from` or `date_to` cannot be parsed as a date.
+/// - Returns an error if a database operation fails.
+pub async fn institution_totals(
+    config: Config,
+    args: InstitutionTotalsArgs,
+) -> Result<Out<BTreeMap<String, Amount>>> {
+    let date_from = parse_optional_date(args.date_from.as_deref())?;
+    let date_to = parse_optional_date(args.date_to.as_deref())?;
+    let totals = config
+        .db()
+        .institution_totals(date_from, date_to)
+        .await
+        .pub_result(ErrorType::Database)?;
+    let count = totals.len();
+    let mut message = format!(
+        "Found totals for {} institution{}",
+        count,
+        if count == 1 { "" } else { "s" }
+    );
+    for (institution, total) in &totals {
+        message.push_str(&format!("\n  {institution}: {total}"));
+    }
+    Ok(Out::new(message, totals))
+}
+
+fn parse_optional_date(s: Option<&str>) -> Result<Option<NaiveDate>> {
+    s.map(utils::parse_date)
+        .transpose()
+        .pub_result(ErrorType::Request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::TestEnv;
+
+    #[tokio::test]
+    async fn test_institution_totals() {
+        let env = TestEnv::new().await;
+        env.insert_test_transaction("test-txn-001").await;
+
+        let out = institution_totals(env.config(), InstitutionTotalsArgs::default())
+            .await
+            .unwrap();
+        assert_eq!(
+            out.message(),
+            "Found totals for 1 institution\n  Test Bank: -$4.50"
+        );
+        let totals = out.structure().unwrap();
+        assert_eq!(totals.get("Test Bank").unwrap().to_string(), "-$4.50");
+    }
+
+    #[tokio::test]
+    async fn test_institution_totals_bad_date() {
+        let env = TestEnv::new().await;
+        let args = InstitutionTotalsArgs {
+            date_from: Some("not a date".to_string()),
+            date_to: None,
+        };
+        let result = institution_totals(env.config(), args).await;
+        assert!(result.is_err());
+    }
+}
@@ -1,16 +1,28 @@
 //! Update command handlers.
 
-use crate::args::{UpdateAutoCatsArgs, UpdateCategoriesArgs, UpdateTransactionsArgs};
+use crate::args::{
+    TransactionFilter, UpdateAutoCatsArgs, UpdateCategoriesArgs, UpdateTransactionsArgs,
+};
+use crate::commands::batch::{self, BatchResult};
 use crate::commands::Out;
 use crate::db::_Row;
 use crate::error::{ErrorType, IntoResult};
 use crate::model::{AutoCat, Category, Transaction};
+use crate::utils;
 use crate::{Config, Result};
+use anyhow::anyhow;
+use chrono::{Local, NaiveDateTime};
+use serde::Serialize;
+use std::collections::HashSet;
 
 /// Updates one or more transactions by ID with the specified field changes.
 ///
-/// Transactions are updated one-by-one. If an error occurs partway through, some transactions may
-/// have been updated while others were not.
+/// Transactions are updated atomically within a database transaction. If any transaction is not
+/// found, the entire operation is rolled back. With `keep_going`, each transaction is updated on
+/// its own and those that fail are reported instead.
+///
+/// When the updates assign a non-empty `category` without a `categorized_date`, the
+/// `categorized_date` is set to now, e.g. `10/21/2025 9:15:30 AM`.
 ///
 /// # Arguments
 ///
@@ -20,35 +32,59 @@ use crate::{Config, Result};
 /// # Returns
 ///
 /// On success, returns an `Out` containing:
-/// - A message indicating how many transactions were updated.
-/// - A vector of the updated `Transaction` objects.
+/// - A message indicating how many transactions were updated, and how many failed.
+/// - A `BatchResult` of the updated `Transaction` objects and, with `keep_going`, the failures.
 ///
 /// # Errors
 ///
-/// - Returns an error if any specified transaction ID is not found.
-/// - Returns an error if a database operation fails.
+/// - Returns an error if any specified transaction ID is not found, unless `keep_going` is set.
+/// - Returns an error if a database operation fails, unless `keep_going` is set.
 pub async fn update_transactions(
     config: Config,
     args: UpdateTransactionsArgs,
-) -> Result<Out<Vec<Transaction>>> {
-    let updated = config
-        .db()
-        .update_transactions(args)
+) -> Result<Out<BatchResult<Transaction>>> {
+    update_transactions_at(config, args, Local::now().naive_local()).await
+}
+
+/// Updates transactions as `update_transactions` does, with `now` as the current time.
+async fn update_transactions_at(
+    config: Config,
+    mut args: UpdateTransactionsArgs,
+    now: NaiveDateTime,
+) -> Result<Out<BatchResult<Transaction>>> {
+    // Assigning a category stamps when the transaction was categorized, as Tiller does
+    let updates = args.updates_mut();
+    let categorizes = updates
+        .category
+        .as_deref()
+        .is_some_and(|category| !category.trim().is_empty());
+    if categorizes && updates.categorized_date.is_none() {
+        updates.categorized_date = Some(utils::tiller_timestamp(now));
+    }
+
+    let db = config.db();
+    let result = if args.keep_going() {
+        batch::keep_going(args.ids(), |id| {
+            let (db, args) = (db.clone(), args.single(id));
+            async move { db.update_transactions(args).await }
+        })
         .await
-        .pub_result(ErrorType::Database)?;
-    let count = updated.len();
-    let message = format!(
-        "Updated {} transaction{}",
-        count,
-        if count == 1 { "" } else { "s" }
-    );
-    Ok(Out::new(message, updated))
+    } else {
+        let updated = db
+            .update_transactions(args)
+            .await
+            .pub_result(ErrorType::Database)?;
+        BatchResult::atomic(updated)
+    };
+    let message = result.message("Updated", "transaction", "transactions");
+    Ok(Out::new(message, result).with_dry_run(config.dry_run()))
 }
 
 /// Updates one or more categories by name with the specified field changes.
 ///
 /// Categories are updated atomically within a database transaction. If any category is not
-/// found, the entire operation is rolled back.
+/// found, the entire operation is rolled back. With `keep_going`, each category is updated on its
+/// own and those that fail are reported instead.
 ///
 /// The category name is the primary key. To rename a category, provide the current name and
 /// include the new name in the updates. Due to `ON UPDATE CASCADE` foreign key constraints,
@@ -62,35 +98,46 @@ pub async fn update_transactions(
 /// # Returns
 ///
 /// On success, returns an `Out` containing:
-/// - A message indicating how many categories were updated.
-/// - A vector of the updated `Category` objects.
+/// - A message indicating how many categories were updated, and how many failed.
+/// - A `BatchResult` of the updated `Category` objects and, with `keep_going`, the failures.
 ///
 /// # Errors
 ///
-/// - Returns an error if any specified category is not found.
-/// - Returns an error if a database operation fails.
+/// - Returns an error if `type` is not one of "Expense", "Income" or "Transfer" (or empty).
+/// - Returns an error if any specified category is not found, unless `keep_going` is set.
+/// - Returns an error if a database operation fails, unless `keep_going` is set.
 pub async fn update_categories(
     config: Config,
     args: UpdateCategoriesArgs,
-) -> Result<Out<Vec<Category>>> {
-    let updated = config
-        .db()
-        .update_categories(args)
+) -> Result<Out<BatchResult<Category>>> {
+    // Only the new type is checked so that categories synced down with an unexpected type can
+    // still have their other fields updated.
+    if let Some(r#type) = &args.updates().r#type {
+        Category::validate_type(r#type).pub_result(ErrorType::Request)?;
+    }
+    let db = config.db();
+    let result = if args.keep_going() {
+        batch::keep_going(args.names(), |name| {
+            let (db, args) = (db.clone(), args.single(name));
+            async move { db.update_categories(args).await }
+        })
         .await
-        .pub_result(ErrorType::Database)?;
-    let count = updated.len();
-    let message = format!(
-        "Updated {} categor{}",
-        count,
-        if count == 1 { "y" } else { "ies" }
-    );
-    Ok(Out::new(message, updated))
+    } else {
+        let updated = db
+            .update_categories(args)
+            .await
+            .pub_result(ErrorType::Database)?;
+        BatchResult::atomic(updated)
+    };
+    let message = result.message("Updated", "category", "categories");
+    Ok(Out::new(message, result).with_dry_run(config.dry_run()))
 }
 
 /// Updates one or more AutoCat rules by ID with the specified field changes.
 ///
 /// AutoCat rules are updated atomically within a database transaction. If any rule is not
-/// found, the entire operation is rolled back.
+/// found, the entire operation is rolled back. With `keep_going`, each rule is updated on its own
+/// and those that fail are reported instead.
 ///
 /// AutoCat rules have a synthetic auto-increment primary key that is assigned when first synced
 /// down or inserted locally.
@@ -103,37 +150,316 @@ pub async fn update_categories(
 /// # Returns
 ///
 /// On success, returns an `Out` containing:
-/// - A message indicating how many rules were updated.
-/// - A vector of the updated `AutoCat` objects wrapped in `_Row` (includes the ID).
+/// - A message indicating how many rules were updated, and how many failed.
+/// - A `BatchResult` of the updated `AutoCat` objects wrapped in `_Row` (includes the ID) and,
+///   with `keep_going`, the failures.
 ///
 /// # Errors
 ///
-/// - Returns an error if any specified AutoCat rule is not found.
-/// - Returns an error if a database operation fails.
+/// - Returns an error if any specified AutoCat rule is not found, unless `keep_going` is set.
+/// - Returns an error if a database operation fails, unless `keep_going` is set.
 pub async fn update_autocats(
     config: Config,
     args: UpdateAutoCatsArgs,
-) -> Result<Out<Vec<_Row<AutoCat>>>> {
-    let updated = config
+) -> Result<Out<BatchResult<_Row<AutoCat>>>> {
+    let db = config.db();
+    let result = if args.keep_going() {
+        batch::keep_going(args.ids(), |id| {
+            let (db, args) = (db.clone(), args.single(id));
+            async move { db.update_autocats(args).await }
+        })
+        .await
+    } else {
+        let updated = db
+            .update_autocats(args)
+            .await
+            .pub_result(ErrorType::Database)?;
+        BatchResult::atomic(updated)
+    };
+    let message = result.message("Updated", "AutoCat rule", "AutoCat rules");
+    Ok(Out::new(message, result).with_dry_run(config.dry_run()))
+}
+
+/// Moves every transaction whose category is `from` to the category `to`.
+///
+/// All transactions are updated atomically within a single database transaction.
+///
+/// # Arguments
+///
+/// - `config` - The application configuration containing the database connection.
+/// - `from` - The category that transactions are currently assigned to.
+/// - `to` - The category to assign the transactions to. This category must exist.
+/// - `dry_run` - When true, count the transactions that would be changed without changing them.
+///
+/// # Returns
+///
+/// On success, returns an `Out` containing:
+/// - A message describing the change.
+/// - The number of transactions affected (or that would be affected for a dry run).
+///
+/// # Errors
+///
+/// - Returns an error if the `to` category does not exist.
+/// - Returns an error if a database operation fails.
+pub async fn recategorize(
+    config: Config,
+    from: String,
+    to: String,
+    dry_run: bool,
+) -> Result<Out<u64>> {
+    let dry_run = dry_run || config.dry_run();
+    let count = config
         .db()
-        .update_autocats(args)
+        .recategorize_transactions(&from, &to, dry_run)
+        .await
+        .pub_result(ErrorType::Database)?;
+    let plural = if count == 1 { "" } else { "s" };
+    let message = if dry_run {
+        format!("Dry run: would recategorize {count} transaction{plural} from '{from}' to '{to}'")
+    } else {
+        format!("Recategorized {count} transaction{plural} from '{from}' to '{to}'")
+    };
+    Ok(Out::new(message, count))
+}
+
+/// What `merge_categories` changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MergeReport {
+    /// The categories that were merged, and so deleted.
+    pub merged: Vec<String>,
+    /// The category they were merged into.
+    pub into: String,
+    /// The number of transactions moved to `into`.
+    pub transactions: u64,
+    /// The number of AutoCat rules moved to `into`.
+    pub autocats: u64,
+}
+
+/// Merges the `from` categories into the category `into`. Their transactions and AutoCat rules
+/// are moved to `into` and then they are deleted.
+///
+/// All changes are made atomically within a single database transaction.
+///
+/// # Arguments
+///
+/// - `config` - The application configuration containing the database connection.
+/// - `from` - The categories to merge. Repeated names are merged once.
+/// - `into` - The category to merge them into. This category must exist.
+///
+/// # Returns
+///
+/// On success, returns an `Out` containing:
+/// - A message describing the change.
+/// - A `MergeReport` with the number of transactions and AutoCat rules moved.
+///
+/// # Errors
+///
+/// - Returns an error if `from` is empty or contains `into`.
+/// - Returns an error if any of the categories does not exist.
+/// - Returns an error if a database operation fails.
+pub async fn merge_categories(
+    config: Config,
+    from: Vec<String>,
+    into: String,
+) -> Result<Out<MergeReport>> {
+    let mut seen = HashSet::new();
+    let from: Vec<String> = from
+        .into_iter()
+        .filter(|c| seen.insert(c.clone()))
+        .collect();
+    if from.is_empty() {
+        return Err(anyhow!("At least one category to merge is required"))
+            .pub_result(ErrorType::Request);
+    }
+    if from.contains(&into) {
+        return Err(anyhow!("Cannot merge category '{into}' into itself"))
+            .pub_result(ErrorType::Request);
+    }
+    let (transactions, autocats) = config
+        .db()
+        .merge_categories(&from, &into)
         .await
         .pub_result(ErrorType::Database)?;
-    let count = updated.len();
     let message = format!(
-        "Updated {} AutoCat rule{}",
-        count,
-        if count == 1 { "" } else { "s" }
+        "Merged {} into '{into}', moving {transactions} transaction{} and {autocats} AutoCat \
+        rule{}",
+        from.iter()
+            .map(|c| format!("'{c}'"))
+            .collect::<Vec<_>>()
+            .join(", "),
+        if transactions == 1 { "" } else { "s" },
+        if autocats == 1 { "" } else { "s" },
     );
-    Ok(Out::new(message, updated))
+    let report = MergeReport {
+        merged: from,
+        into,
+        transactions,
+        autocats,
+    };
+    Ok(Out::new(message, report).with_dry_run(config.dry_run()))
+}
+
+/// A transaction that `categorize_uncategorized` gave a category.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CategoryAssignment {
+    /// The ID of the transaction.
+    pub transaction_id: String,
+    /// The category it was given.
+    pub category: String,
+    /// The ID of the AutoCat rule that matched it, as listed by `list-autocats`.
+    pub autocat_id: u64,
+}
+
+/// The outcome of `categorize_uncategorized`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CategorizeReport {
+    /// The transactions that were given a category, in sheet order.
+    pub categorized: Vec<CategoryAssignment>,
+    /// The number of transactions that are still uncategorized.
+    pub remaining: u64,
+}
+
+/// Applies the AutoCat rules to the transactions that have no category, as Tiller would.
+///
+/// Rules are tried in the order Tiller processes them and the first rule that matches a
+/// transaction and has a category wins, see `AutoCat::matches`. Transactions that already have a
+/// category are never changed. Rules whose category is not in the categories table are skipped
+/// with a warning, since the category cannot be assigned. Each categorized transaction has its
+/// `categorized_date` set to now. All transactions are updated in a single database transaction.
+///
+/// Changes are made locally only. Use `sync up` to upload local changes to the Google Sheet.
+///
+/// # Arguments
+///
+/// - `config` - The application configuration containing the database connection.
+/// - `dry_run` - When true, report the transactions that would be categorized without changing
+///   them.
+///
+/// # Returns
+///
+/// On success, returns an `Out` containing a message and the transactions that were (or would be)
+/// categorized, and the number that remain uncategorized.
+///
+/// # Errors
+///
+/// - Returns an error if a database operation fails.
+pub async fn categorize_uncategorized(
+    config: Config,
+    dry_run: bool,
+) -> Result<Out<CategorizeReport>> {
+    let dry_run = dry_run || config.dry_run();
+    let db = config.db();
+    let rules = db.list_autocats().await.pub_result(ErrorType::Database)?;
+    let categories: HashSet<String> = db
+        .get_categories()
+        .await
+        .pub_result(ErrorType::Database)?
+        .data()
+        .iter()
+        .map(|c| c.category.clone())
+        .collect();
+    let filter = TransactionFilter {
+        only_uncategorized: true,
+        ..Default::default()
+    };
+    let (uncategorized, total) = db
+        .list_transactions(&filter, None, 0)
+        .await
+        .pub_result(ErrorType::Database)?;
+
+    let mut warnings = Vec::new();
+    let rules: Vec<&_Row<AutoCat>> = rules
+        .iter()
+        .filter(|rule| !rule.row.category.is_empty())
+        .filter(|rule| {
+            let known = categories.contains(&rule.row.category);
+            if !known {
+                warnings.push(format!(
+                    "Skipped AutoCat rule {} because its category '{}' does not exist",
+                    rule.id, rule.row.category
+                ));
+            }
+            known
+        })
+        .collect();
+    let categorized: Vec<CategoryAssignment> = uncategorized
+        .iter()
+        .filter_map(|transaction| {
+            let rule = rules.iter().find(|rule| rule.row.matches(transaction))?;
+            Some(CategoryAssignment {
+                transaction_id: transaction.transaction_id.clone(),
+                category: rule.row.category.clone(),
+                autocat_id: rule.id,
+            })
+        })
+        .collect();
+
+    if !dry_run {
+        let assignments: Vec<(String, String)> = categorized
+            .iter()
+            .map(|a| (a.transaction_id.clone(), a.category.clone()))
+            .collect();
+        let now = utils::tiller_timestamp(Local::now().naive_local());
+        db.assign_categories(&assignments, &now)
+            .await
+            .pub_result(ErrorType::Database)?;
+    }
+
+    let count = categorized.len();
+    let remaining = total - count as u64;
+    let plural = if count == 1 { "" } else { "s" };
+    let message = if dry_run {
+        format!(
+            "Dry run: would categorize {count} transaction{plural} with AutoCat, {remaining} would \
+            remain uncategorized"
+        )
+    } else {
+        format!(
+            "Categorized {count} transaction{plural} with AutoCat, {remaining} remain uncategorized"
+        )
+    };
+    Ok(Out::new(
+        message,
+        CategorizeReport {
+            categorized,
+            remaining,
+        },
+    )
+    .with_warnings(warnings))
+}
+
+/// Moves the AutoCat rule `id` to `new_position` (0-based) in the processing order. The rules in
+/// between shift by one and the rules are returned in their new order.
+///
+/// Changes are made locally only. Use `sync up` to upload local changes to the Google Sheet.
+pub async fn move_autocat(
+    config: Config,
+    id: String,
+    new_position: u32,
+) -> Result<Out<Vec<_Row<AutoCat>>>> {
+    let rules = config
+        .db()
+        .move_autocat(&id, new_position as usize)
+        .await
+        .pub_result(ErrorType::Database)?;
+    Ok(Out::new(
+        format!("Moved AutoCat rule {id} to position {new_position}"),
+        rules,
+    )
+    .with_dry_run(config.dry_run()))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::args::{UpdateAutoCatsArgs, UpdateCategoriesArgs, UpdateTransactionsArgs};
-    use crate::model::{AutoCatUpdates, CategoryUpdates, TransactionUpdates};
+    use crate::api::Mode;
+    use crate::args::{
+        TransactionFilter, UpdateAutoCatsArgs, UpdateCategoriesArgs, UpdateTransactionsArgs,
+    };
+    use crate::commands::sync_down;
+    use crate::model::{AutoCatUpdates, CategoryUpdates, RowCol, TransactionUpdates};
     use crate::test::TestEnv;
+    use std::collections::BTreeMap;
 
     #[tokio::test]
     async fn test_update_transactions_success() {
@@ -165,6 +491,42 @@ mod tests {
         assert_eq!(updated.note, "updated note");
     }
 
+    #[tokio::test]
+    async fn test_update_transactions_keep_going() {
+        let env = TestEnv::new().await;
+        let txn_id = "test-txn-003";
+        env.insert_test_transaction(txn_id).await;
+
+        let updates = TransactionUpdates {
+            note: Some("kept going".to_string()),
+            ..Default::default()
+        };
+        let args = UpdateTransactionsArgs::new(vec!["missing-1", txn_id, "missing-2"], updates)
+            .unwrap()
+            .with_keep_going(true);
+        let out = update_transactions(env.config(), args).await.unwrap();
+
+        let report = out.structure().unwrap();
+        assert_eq!(report.succeeded.len(), 1);
+        assert_eq!(report.succeeded[0].transaction_id, txn_id);
+        let failed: Vec<&str> = report.failed.iter().map(|f| f.id.as_str()).collect();
+        assert_eq!(failed, vec!["missing-1", "missing-2"]);
+        assert!(report
+            .failed
+            .iter()
+            .all(|f| f.reason.contains("Transaction not found")));
+        assert!(out.message().starts_with("Updated 1 transaction, 2 failed"));
+
+        let updated = env
+            .config()
+            .db()
+            ._get_transaction(txn_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.note, "kept going");
+    }
+
     #[tokio::test]
     async fn test_update_transactions_multiple_fields() {
         let env = TestEnv::new().await;
@@ -179,7 +541,7 @@ mod tests {
         let args = UpdateTransactionsArgs::new(vec![txn_id], updates).unwrap();
         let out = update_transactions(env.config(), args).await.unwrap();
 
-        let returned = out.structure().unwrap().first().unwrap();
+        let returned = out.structure().unwrap().succeeded.first().unwrap();
         assert_eq!(returned.note, "new note");
         assert_eq!(returned.category, "Entertainment");
         assert_eq!(returned.account_number, "1234");
@@ -196,6 +558,73 @@ mod tests {
         assert_eq!(updated.account_number, "1234");
     }
 
+    #[tokio::test]
+    async fn test_update_transactions_stamps_categorized_date() {
+        let env = TestEnv::new().await;
+        let txn_id = "test-txn-004";
+        env.insert_test_transaction(txn_id).await;
+        let now = chrono::NaiveDate::from_ymd_opt(2025, 10, 21)
+            .unwrap()
+            .and_hms_opt(9, 15, 30)
+            .unwrap();
+
+        // An update that does not touch the category leaves the categorized date alone
+        let updates = TransactionUpdates {
+            note: Some("no category".to_string()),
+            ..Default::default()
+        };
+        let args = UpdateTransactionsArgs::new(vec![txn_id], updates).unwrap();
+        let out = update_transactions_at(env.config(), args, now)
+            .await
+            .unwrap();
+        assert_eq!(out.structure().unwrap().succeeded[0].categorized_date, "");
+
+        // Assigning a category stamps it
+        let updates = TransactionUpdates {
+            category: Some("Entertainment".to_string()),
+            ..Default::default()
+        };
+        let args = UpdateTransactionsArgs::new(vec![txn_id], updates).unwrap();
+        update_transactions_at(env.config(), args, now)
+            .await
+            .unwrap();
+        let updated = env
+            .config()
+            .db()
+            ._get_transaction(txn_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.categorized_date, "10/21/2025 9:15:30 AM");
+
+        // A later update that does not touch the category keeps the stamp
+        let updates = TransactionUpdates {
+            note: Some("still categorized".to_string()),
+            ..Default::default()
+        };
+        let later = now + chrono::Duration::hours(1);
+        let args = UpdateTransactionsArgs::new(vec![txn_id], updates).unwrap();
+        let out = update_transactions_at(env.config(), args, later)
+            .await
+            .unwrap();
+        let returned = &out.structure().unwrap().succeeded[0];
+        assert_eq!(returned.categorized_date, "10/21/2025 9:15:30 AM");
+
+        // A given categorized date is kept, and clearing the category does not stamp it
+        let updates = TransactionUpdates {
+            category: Some(String::new()),
+            categorized_date: Some(String::new()),
+            ..Default::default()
+        };
+        let args = UpdateTransactionsArgs::new(vec![txn_id], updates).unwrap();
+        let out = update_transactions_at(env.config(), args, later)
+            .await
+            .unwrap();
+        let returned = &out.structure().unwrap().succeeded[0];
+        assert_eq!(returned.category, "");
+        assert_eq!(returned.categorized_date, "");
+    }
+
     #[tokio::test]
     async fn test_update_transactions_not_found_error() {
         let env = TestEnv::new().await;
@@ -276,6 +705,24 @@ mod tests {
         assert_eq!(updated.category_group, "Updated Group");
     }
 
+    #[tokio::test]
+    async fn test_update_categories_invalid_type_error() {
+        let env = TestEnv::new().await;
+        env.insert_test_transaction("txn-001").await;
+
+        let updates = CategoryUpdates {
+            r#type: Some("Expnse".to_string()),
+            ..Default::default()
+        };
+        let args = UpdateCategoriesArgs::new(vec!["Food"], updates).unwrap();
+        let result = update_categories(env.config(), args).await;
+        let err_msg = result.unwrap_err().to_string();
+        assert!(
+            err_msg.contains("Invalid category type 'Expnse'"),
+            "Unexpected error message: {err_msg}"
+        );
+    }
+
     #[tokio::test]
     async fn test_update_categories_multiple_fields() {
         let env = TestEnv::new().await;
@@ -292,7 +739,7 @@ mod tests {
 
         assert!(result.is_ok());
         let out = result.unwrap();
-        let returned = out.structure().unwrap().first().unwrap();
+        let returned = out.structure().unwrap().succeeded.first().unwrap();
         assert_eq!(returned.category_group, "New Group");
         assert_eq!(returned.r#type, "Income");
         assert_eq!(returned.hide_from_reports, "Hide");
@@ -346,6 +793,68 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_update_categories_rename_to_existing_name_error() {
+        let env = TestEnv::new().await;
+        env.insert_test_transaction("txn-001").await;
+
+        let updates = CategoryUpdates {
+            category: Some("Entertainment".to_string()),
+            ..Default::default()
+        };
+        let args = UpdateCategoriesArgs::new(vec!["Food"], updates).unwrap();
+        let err_msg = update_categories(env.config(), args)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err_msg.contains("target category already exists; use merge"),
+            "Unexpected error message: {err_msg}"
+        );
+
+        // Nothing changed
+        let db = env.config().db();
+        assert!(db._get_category("Food").await.unwrap().is_some());
+        let txn = db._get_transaction("txn-001").await.unwrap().unwrap();
+        assert_eq!(txn.category, "Food");
+    }
+
+    #[tokio::test]
+    async fn test_update_categories_rename_with_merge() {
+        let env = TestEnv::new().await;
+        env.insert_test_transaction("txn-001").await;
+        let db = env.config().db();
+        let autocat = AutoCat {
+            category: "Food".to_string(),
+            description_contains: "coffee".to_string(),
+            ..Default::default()
+        };
+        let autocat_id = db.insert_autocat(&autocat).await.unwrap().to_string();
+
+        let updates = CategoryUpdates {
+            category: Some("Entertainment".to_string()),
+            group: Some("Going Out".to_string()),
+            ..Default::default()
+        };
+        let args = UpdateCategoriesArgs::new(vec!["Food"], updates)
+            .unwrap()
+            .with_merge(true);
+        let out = update_categories(env.config(), args).await.unwrap();
+        let returned = out.structure().unwrap().succeeded.first().unwrap();
+        assert_eq!(returned.category, "Entertainment");
+        assert_eq!(returned.category_group, "Going Out");
+
+        // The references moved to the existing category and the renamed one is gone
+        assert!(db._get_category("Food").await.unwrap().is_none());
+        let txn = db._get_transaction("txn-001").await.unwrap().unwrap();
+        assert_eq!(txn.category, "Entertainment");
+        let autocat = db._get_autocat(&autocat_id).await.unwrap().unwrap();
+        assert_eq!(autocat.row.category, "Entertainment");
+        let target = db._get_category("Entertainment").await.unwrap().unwrap();
+        assert_eq!(target.category_group, "Going Out");
+        assert_eq!(target.r#type, "Expense");
+    }
+
     #[tokio::test]
     async fn test_update_categories_not_found_error() {
         let env = TestEnv::new().await;
@@ -408,7 +917,7 @@ mod tests {
 
         assert!(result.is_ok());
         let out = result.unwrap();
-        let returned = out.structure().unwrap().first().unwrap();
+        let returned = out.structure().unwrap().succeeded.first().unwrap();
         assert_eq!(returned.row.description_contains, "new-pattern");
         assert_eq!(returned.row.account_contains, "checking");
         assert_eq!(returned.row.category, "Transportation");
@@ -435,4 +944,379 @@ mod tests {
         let err_msg = result.unwrap_err().to_string();
         assert!(err_msg.contains("AutoCat rule not found"));
     }
+
+    #[tokio::test]
+    async fn test_recategorize_success() {
+        let env = TestEnv::new().await;
+        let txn_id = "test-txn-001";
+        env.insert_test_transaction(txn_id).await;
+
+        let out = recategorize(env.config(), "Food".into(), "Entertainment".into(), false)
+            .await
+            .unwrap();
+        assert_eq!(*out.structure().unwrap(), 1);
+        assert_eq!(
+            out.message(),
+            "Recategorized 1 transaction from 'Food' to 'Entertainment'"
+        );
+
+        let txn = env
+            .config()
+            .db()
+            ._get_transaction(txn_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(txn.category, "Entertainment");
+    }
+
+    #[tokio::test]
+    async fn test_recategorize_missing_target_category() {
+        let env = TestEnv::new().await;
+        let txn_id = "test-txn-001";
+        env.insert_test_transaction(txn_id).await;
+
+        let result = recategorize(env.config(), "Food".into(), "Nonexistent".into(), false).await;
+        assert!(result.is_err());
+        let err = result.unwrap_err().to_string();
+        assert!(
+            err.contains("Category not found"),
+            "Unexpected error message: {err}"
+        );
+
+        let txn = env
+            .config()
+            .db()
+            ._get_transaction(txn_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(txn.category, "Food");
+    }
+
+    #[tokio::test]
+    async fn test_recategorize_dry_run() {
+        let env = TestEnv::new().await;
+        let txn_id = "test-txn-001";
+        env.insert_test_transaction(txn_id).await;
+
+        let out = recategorize(env.config(), "Food".into(), "Entertainment".into(), true)
+            .await
+            .unwrap();
+        assert_eq!(*out.structure().unwrap(), 1);
+        assert!(out.message().starts_with("Dry run"));
+
+        let txn = env
+            .config()
+            .db()
+            ._get_transaction(txn_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(txn.category, "Food");
+    }
+
+    #[tokio::test]
+    async fn test_merge_categories_into_a_third() {
+        let env = TestEnv::new().await;
+        sync_down(env.config(), Mode::Testing).await.unwrap();
+        let db = env.config().db();
+        let from = ["Groceries", "Coffee Shops"];
+        let before = db.get_transactions().await.unwrap();
+        let moved: Vec<&str> = before
+            .data()
+            .iter()
+            .filter(|t| from.contains(&t.category.as_str()))
+            .map(|t| t.transaction_id.as_str())
+            .collect();
+        let restaurants = before
+            .data()
+            .iter()
+            .filter(|t| t.category == "Restaurants")
+            .count();
+
+        let out = merge_categories(
+            env.config(),
+            from.iter().map(|c| c.to_string()).collect(),
+            "Restaurants".into(),
+        )
+        .await
+        .unwrap();
+        let report = out.structure().unwrap();
+        assert_eq!(report.transactions, moved.len() as u64);
+        assert_eq!(report.autocats, 2);
+
+        // The sources are gone and everything that referenced them now uses the target
+        for name in from {
+            assert!(db._get_category(name).await.unwrap().is_none(), "{name}");
+        }
+        let after = db.get_transactions().await.unwrap();
+        for t in after.data() {
+            if moved.contains(&t.transaction_id.as_str()) {
+                assert_eq!(t.category, "Restaurants", "{}", t.transaction_id);
+            }
+        }
+        let count = after
+            .data()
+            .iter()
+            .filter(|t| t.category == "Restaurants")
+            .count();
+        assert_eq!(count, restaurants + moved.len());
+        let rules: Vec<String> = db
+            .list_autocats()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|r| r.row.category)
+            .collect();
+        assert_eq!(rules, ["Restaurants", "Restaurants", "Gas & Fuel"]);
+    }
+
+    #[tokio::test]
+    async fn test_merge_categories_missing_category_changes_nothing() {
+        let env = TestEnv::new().await;
+        sync_down(env.config(), Mode::Testing).await.unwrap();
+        let before = env.config().db().get_tiller_data().await.unwrap();
+
+        let err_msg = merge_categories(
+            env.config(),
+            vec!["Groceries".into(), "Nonexistent".into()],
+            "Restaurants".into(),
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+        assert!(
+            err_msg.contains("Category not found: Nonexistent"),
+            "Unexpected error message: {err_msg}"
+        );
+        assert_eq!(env.config().db().get_tiller_data().await.unwrap(), before);
+
+        let result = merge_categories(
+            env.config(),
+            vec!["Restaurants".into()],
+            "Restaurants".into(),
+        )
+        .await;
+        assert!(result.unwrap_err().to_string().contains("into itself"));
+    }
+
+    /// Syncs down the seed data and clears the category of a Whole Foods, a Starbucks and a
+    /// Chipotle transaction. The seed AutoCat rules match the first two. The last seed
+    /// transaction, from City Water District, is already uncategorized and matches no rule.
+    async fn env_with_uncategorized() -> TestEnv {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        for id in [
+            "tx001a2b3c4d5e6f7g8h9i01",
+            "tx001a2b3c4d5e6f7g8h9i02",
+            "tx001a2b3c4d5e6f7g8h9i04",
+        ] {
+            let mut txn = config.db()._get_transaction(id).await.unwrap().unwrap();
+            txn.category = String::new();
+            txn.categorized_date = String::new();
+            config.db().update_transaction(&txn).await.unwrap();
+        }
+        env
+    }
+
+    #[tokio::test]
+    async fn test_categorize_uncategorized() {
+        let env = env_with_uncategorized().await;
+        let config = env.config();
+
+        let out = categorize_uncategorized(config.clone(), false)
+            .await
+            .unwrap();
+        let report = out.structure().unwrap();
+        let assigned: Vec<(&str, &str)> = report
+            .categorized
+            .iter()
+            .map(|a| (a.transaction_id.as_str(), a.category.as_str()))
+            .collect();
+        assert_eq!(
+            assigned,
+            vec![
+                ("tx001a2b3c4d5e6f7g8h9i01", "Groceries"),
+                ("tx001a2b3c4d5e6f7g8h9i02", "Coffee Shops"),
+            ]
+        );
+        assert_eq!(report.remaining, 2);
+        assert_eq!(
+            out.message(),
+            "Categorized 2 transactions with AutoCat, 2 remain uncategorized"
+        );
+
+        let db = config.db();
+        let txn = db
+            ._get_transaction("tx001a2b3c4d5e6f7g8h9i02")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(txn.category, "Coffee Shops");
+        assert!(!txn.categorized_date.is_empty());
+        for id in ["tx001a2b3c4d5e6f7g8h9i04", "tx001a2b3c4d5e6f7g8h9i20"] {
+            let txn = db._get_transaction(id).await.unwrap().unwrap();
+            assert!(txn.category.is_empty(), "{id} has {}", txn.category);
+        }
+
+        // Categorized transactions are not touched again
+        let out = categorize_uncategorized(config, false).await.unwrap();
+        assert!(out.structure().unwrap().categorized.is_empty());
+        assert_eq!(out.structure().unwrap().remaining, 2);
+    }
+
+    #[tokio::test]
+    async fn test_categorize_uncategorized_dry_run() {
+        let env = env_with_uncategorized().await;
+        let config = env.config();
+
+        let out = categorize_uncategorized(config.clone(), true)
+            .await
+            .unwrap();
+        assert_eq!(out.structure().unwrap().categorized.len(), 2);
+        assert!(out.message().starts_with("Dry run"), "{}", out.message());
+        let txn = config
+            .db()
+            ._get_transaction("tx001a2b3c4d5e6f7g8h9i01")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(txn.category.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_categorize_uncategorized_skips_rules_with_unknown_category() {
+        let env = env_with_uncategorized().await;
+        let config = env.config();
+        // The foreign key on autocat.category rejects such a rule, so insert it with checks off
+        config
+            .db()
+            .execute_without_foreign_keys(
+                "INSERT INTO autocat (category, description_contains) \
+                 VALUES ('Farmers Markets', 'Chipotle')",
+            )
+            .await
+            .unwrap();
+
+        let out = categorize_uncategorized(config, false).await.unwrap();
+        assert_eq!(out.structure().unwrap().categorized.len(), 2);
+        assert_eq!(out.structure().unwrap().remaining, 2);
+        assert!(
+            out.warnings()
+                .iter()
+                .any(|w| w.contains("'Farmers Markets' does not exist")),
+            "{:?}",
+            out.warnings()
+        );
+    }
+
+    fn autocat_order(rules: &[_Row<AutoCat>]) -> Vec<&str> {
+        rules
+            .iter()
+            .map(|r| r.row.description_contains.as_str())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_move_autocat_up() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        let rules = config.db().list_autocats().await.unwrap();
+        let shell = rules[2].id.to_string();
+
+        let out = move_autocat(config.clone(), shell.clone(), 0)
+            .await
+            .unwrap();
+        assert_eq!(
+            out.message(),
+            format!("Moved AutoCat rule {shell} to position 0")
+        );
+        assert_eq!(
+            autocat_order(out.structure().unwrap()),
+            vec!["Shell", "Whole Foods", "Starbucks"]
+        );
+
+        // The new order is persisted
+        let rules = config.db().list_autocats().await.unwrap();
+        assert_eq!(
+            autocat_order(&rules),
+            vec!["Shell", "Whole Foods", "Starbucks"]
+        );
+        let orders: Vec<_> = rules.iter().map(|r| r.row.original_order).collect();
+        assert_eq!(orders, vec![Some(0), Some(1), Some(2)]);
+    }
+
+    #[tokio::test]
+    async fn test_move_autocat_down() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        let rules = config.db().list_autocats().await.unwrap();
+        let whole_foods = rules[0].id.to_string();
+
+        move_autocat(config.clone(), whole_foods, 1).await.unwrap();
+        let rules = config.db().list_autocats().await.unwrap();
+        assert_eq!(
+            autocat_order(&rules),
+            vec!["Starbucks", "Whole Foods", "Shell"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_move_autocat_moves_formulas() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+
+        // Give the Shell rule, the third row, a formula in its Description column
+        let mut state = env.get_state();
+        let mut formulas = state.data["AutoCat"].clone();
+        formulas[3][8] = "=\"Fuel\"".to_string();
+        state.formulas.insert("AutoCat".to_string(), formulas);
+        env.set_state(state);
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        let rules = config.db().list_autocats().await.unwrap();
+        let shell = rules[2].id.to_string();
+
+        move_autocat(config.clone(), shell, 0).await.unwrap();
+        let auto_cats = config.db().get_autocats().await.unwrap();
+        let expected = BTreeMap::from([(RowCol::new(0, 8), "=\"Fuel\"".to_string())]);
+        assert_eq!(auto_cats.formulas(), &expected);
+    }
+
+    #[tokio::test]
+    async fn test_move_autocat_errors() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+        let rules = config.db().list_autocats().await.unwrap();
+        let first = rules[0].id.to_string();
+
+        let err = move_autocat(config.clone(), first, 3)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("out of range"),
+            "Unexpected error message: {err}"
+        );
+
+        let err = move_autocat(config.clone(), "999".into(), 0)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("AutoCat rule not found"),
+            "Unexpected error message: {err}"
+        );
+
+        // Nothing moved
+        let rules = config.db().list_autocats().await.unwrap();
+        assert_eq!(
+            autocat_order(&rules),
+            vec!["Whole Foods", "Starbucks", "Shell"]
+        );
+    }
 }
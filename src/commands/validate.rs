@@ -0,0 +1,201 @@
+//! Implements a read-only health check of the local database and the last sync down.
+
+use crate::backup::SYNC_DOWN;
+use crate::commands::Out;
+use crate::error::{ErrorType, IntoResult};
+use crate::utils;
+use crate::{Config, Result};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// The kind of problem found by `validate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// A transaction's category is not in the categories table.
+    UnknownCategory,
+    /// A transaction ID appears more than once in the Transactions tab of the last sync down.
+    DuplicateId,
+    /// A transaction's amount is not a number.
+    InvalidAmount,
+    /// A transaction's date cannot be parsed.
+    InvalidDate,
+}
+
+serde_plain::derive_display_from_serialize!(IssueKind);
+
+/// A problem found by `validate`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationIssue {
+    /// The kind of problem.
+    pub kind: IssueKind,
+    /// The transaction with the problem.
+    pub transaction_id: String,
+    /// A description of the problem.
+    pub detail: String,
+}
+
+/// Checks the local database, and the Transactions tab as of the last sync down, for data that
+/// other commands may trip over. Nothing is changed.
+///
+/// The checks are:
+/// - Transactions whose category is not in the categories table.
+/// - Transaction IDs that appear more than once in the last sync down. The database cannot hold
+///   these, so a sync down of such a sheet fails.
+/// - Amounts that are not numbers.
+/// - Dates that cannot be parsed.
+///
+/// # Arguments
+///
+/// - `config` - The application configuration.
+///
+/// # Returns
+///
+/// On success, returns an `Out` containing a message and the problems found, which is empty if
+/// there are none.
+///
+/// # Errors
+///
+/// - Returns an error if the database or the last sync-down backup cannot be read.
+pub async fn validate(config: Config) -> Result<Out<Vec<ValidationIssue>>> {
+    let db = config.db();
+    let mut issues = Vec::new();
+
+    let unknown = db
+        .transactions_with_unknown_categories()
+        .await
+        .pub_result(ErrorType::Database)?;
+    for (transaction_id, category) in unknown {
+        issues.push(ValidationIssue {
+            kind: IssueKind::UnknownCategory,
+            transaction_id,
+            detail: format!("Category '{category}' does not exist"),
+        });
+    }
+
+    let last_sync_down = config
+        .backup()
+        .load_latest_json(SYNC_DOWN)
+        .await
+        .pub_result(ErrorType::Internal)?;
+    if let Some(data) = last_sync_down {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for transaction in data.transactions.data() {
+            *counts
+                .entry(transaction.transaction_id.as_str())
+                .or_default() += 1;
+        }
+        for (transaction_id, count) in counts.into_iter().filter(|(_, count)| *count > 1) {
+            issues.push(ValidationIssue {
+                kind: IssueKind::DuplicateId,
+                transaction_id: transaction_id.to_string(),
+                detail: format!("Appears {count} times in the last sync down"),
+            });
+        }
+    }
+
+    let rows = db
+        .raw_transaction_dates_and_amounts()
+        .await
+        .pub_result(ErrorType::Database)?;
+    for (transaction_id, date, amount) in rows {
+        if Decimal::from_str(&amount).is_err() {
+            issues.push(ValidationIssue {
+                kind: IssueKind::InvalidAmount,
+                transaction_id: transaction_id.clone(),
+                detail: format!("Amount '{amount}' is not a number"),
+            });
+        }
+        // Blank dates are allowed, e.g. for transactions inserted without one
+        if !date.trim().is_empty() && utils::parse_date(&date).is_err() {
+            issues.push(ValidationIssue {
+                kind: IssueKind::InvalidDate,
+                transaction_id,
+                detail: format!("Date '{date}' cannot be parsed"),
+            });
+        }
+    }
+
+    let mut message = match issues.len() {
+        0 => "No problems found".to_string(),
+        1 => "Found 1 problem".to_string(),
+        count => format!("Found {count} problems"),
+    };
+    for issue in &issues {
+        message.push_str(&format!(
+            "\n  {} {}: {}",
+            issue.kind, issue.transaction_id, issue.detail
+        ));
+    }
+    Ok(Out::new(message, issues))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::Mode;
+    use crate::commands::sync_down;
+    use crate::test::TestEnv;
+
+    #[tokio::test]
+    async fn test_validate_seed_data_has_no_problems() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+        let out = validate(config).await.unwrap();
+        assert!(out.structure().unwrap().is_empty(), "{}", out.message());
+        assert_eq!(out.message(), "No problems found");
+    }
+
+    #[tokio::test]
+    async fn test_validate_reports_each_kind_of_problem() {
+        let env = TestEnv::new().await;
+        let config = env.config();
+        env.insert_test_transaction("txn-good").await;
+        let db = config.db();
+
+        // A sheet in which a transaction ID was pasted twice
+        let mut data = db.get_tiller_data().await.unwrap();
+        let duplicate = crate::model::Transaction {
+            transaction_id: "txn-twice".to_string(),
+            ..Default::default()
+        };
+        data.transactions.data_mut().push(duplicate.clone());
+        data.transactions.data_mut().push(duplicate);
+        config.backup().save_json(SYNC_DOWN, &data).await.unwrap();
+
+        db.execute_without_foreign_keys(
+            "INSERT INTO transactions (transaction_id, date, description, amount, account, \
+            account_number, institution, account_id, category) VALUES \
+            ('txn-category', '1/2/2025', '', '-1.00', '', '', '', '', 'Nope'), \
+            ('txn-amount', '1/3/2025', '', '$abc', '', '', '', '', ''), \
+            ('txn-date', 'someday', '', '2.50', '', '', '', '', '')",
+        )
+        .await
+        .unwrap();
+
+        let out = validate(config).await.unwrap();
+        let found: Vec<(IssueKind, &str)> = out
+            .structure()
+            .unwrap()
+            .iter()
+            .map(|issue| (issue.kind, issue.transaction_id.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (IssueKind::UnknownCategory, "txn-category"),
+                (IssueKind::DuplicateId, "txn-twice"),
+                (IssueKind::InvalidAmount, "txn-amount"),
+                (IssueKind::InvalidDate, "txn-date"),
+            ]
+        );
+        assert!(out.message().starts_with("Found 4 problems"));
+        assert!(out
+            .message()
+            .contains("unknown_category txn-category: Category 'Nope' does not exist"));
+    }
+}
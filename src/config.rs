@@ -11,10 +11,15 @@ use crate::{utils, Result};
 use anyhow::{anyhow, Context};
 use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::warn;
 
 const APP_NAME: &str = "tiller";
 const CONFIG_VERSION: u8 = 1;
 const BACKUP_COPIES: u32 = 5;
+const REQUEST_TIMEOUT_SECS: u64 = 30;
+const TOKEN_REFRESH_BUFFER_SECS: u64 = 300;
+const MCP_CONCURRENCY: u32 = crate::db::POOL_SIZE;
 const SECRETS: &str = ".secrets";
 const BACKUPS: &str = ".backups";
 const CLIENT_SECRET_JSON: &str = "client_secret.json";
@@ -35,6 +40,14 @@ pub struct Config {
     config_file: ConfigFile,
     db: Db,
     spreadsheet_id: String,
+    /// The URL of the spreadsheet given by `with_spreadsheet_id`, if any.
+    sheet_url_override: Option<String>,
+    /// The limit given by `with_max_rows`, if any, which takes the place of `max_rows` in
+    /// `config.json`.
+    max_rows_override: Option<u64>,
+    /// Whether `with_no_drive_backup` turned off the Drive copy, whatever `drive_backup` in
+    /// `config.json` says.
+    no_drive_backup: bool,
     sqlite_path: PathBuf,
 }
 
@@ -43,6 +56,10 @@ impl Config {
     /// - Creates an initial `config.json` file using `sheet_url` along with default settings
     /// - Copies `secret_file` into its default location in the data dir.
     ///
+    /// If any step fails, the directories and files created by the earlier steps are removed and
+    /// the files they overwrote are restored, so that `create` can be run again once the problem is
+    /// fixed. `secret_file` is copied, not moved, so it is left where it was.
+    ///
     /// # Arguments
     /// - `dir` - The directory that will be the root of data directory, e.g. `$HOME/tiller`
     /// - `secret_file` - The downloaded OAuth 2.0 client credentials JSON needed to start the Google
@@ -53,9 +70,26 @@ impl Config {
     ///
     /// # Errors
     /// - Returns an error if any file operations fail.
+    /// - Returns an error if the spreadsheet ID cannot be extracted from `url`.
     pub async fn create(dir: impl Into<PathBuf>, secret_file: &Path, url: &str) -> Result<Self> {
+        let mut undo = CreateUndo::default();
+        let result = Self::create_inner(dir.into(), secret_file, url, &mut undo).await;
+        if result.is_err() {
+            undo.undo().await;
+        }
+        result
+    }
+
+    /// Does the work of `create`, recording each directory or file that it makes or overwrites in
+    /// `undo` before doing so.
+    async fn create_inner(
+        maybe_relative: PathBuf,
+        secret_file: &Path,
+        url: &str,
+        undo: &mut CreateUndo,
+    ) -> Result<Self> {
         // Create the directory if it does not exist
-        let maybe_relative = dir.into();
+        undo.new_path(&maybe_relative);
         utils::make_dir(&maybe_relative)
             .await
             .context("Unable to create the tiller home directory")
@@ -67,17 +101,18 @@ impl Config {
             .pub_result(ErrorType::Internal)?;
 
         // Create the subdirectories
-        let backups_dir = root.join(".backups");
-        utils::make_dir(&backups_dir)
-            .await
-            .pub_result(ErrorType::Internal)?;
-        let secrets_dir = root.join(".secrets");
-        utils::make_dir(&secrets_dir)
-            .await
-            .pub_result(ErrorType::Internal)?;
+        let backups_dir = root.join(BACKUPS);
+        let secrets_dir = root.join(SECRETS);
+        for dir in [&backups_dir, &secrets_dir] {
+            undo.new_path(dir);
+            utils::make_dir(dir).await.pub_result(ErrorType::Internal)?;
+        }
 
         // Copy the Google OAuth client credentials file to its default location in the data dir
         let secret_destination = secrets_dir.join(CLIENT_SECRET_JSON);
+        undo.file(&secret_destination)
+            .await
+            .pub_result(ErrorType::Internal)?;
         utils::copy(secret_file, &secret_destination)
             .await
             .pub_result(ErrorType::Internal)?;
@@ -91,15 +126,22 @@ impl Config {
             backup_copies: BACKUP_COPIES,
             client_secret_path: None,
             token_path: None,
+            sync_balances: false,
+            request_timeout_secs: REQUEST_TIMEOUT_SECS,
+            proxy: None,
+            compress_backups: false,
+            atomic_write: false,
+            verify_headers: true,
+            drive_backup: true,
+            token_refresh_buffer_secs: TOKEN_REFRESH_BUFFER_SECS,
+            conflict_scope: ConflictScope::All,
+            mcp_concurrency: MCP_CONCURRENCY,
+            max_rows: None,
         };
-        config_file.save(&config_path).await?;
-
-        // Initialize the SQLite database
-        let db_path = root.join(TILLER_SQLITE);
-        let db = Db::init(&db_path)
+        undo.file(&config_path)
             .await
-            .context("Unable to create SQLite DB")
-            .pub_result(ErrorType::Database)?;
+            .pub_result(ErrorType::Internal)?;
+        config_file.save(&config_path).await?;
 
         // Extract the spreadsheet ID from the URL
         let spreadsheet_id = extract_spreadsheet_id(url)
@@ -107,6 +149,15 @@ impl Config {
             .pub_result(ErrorType::Config)?
             .to_string();
 
+        // Initialize the SQLite database last, so that nothing can fail while it is open. It is
+        // pushed first since it may be left half made if it fails.
+        let db_path = root.join(TILLER_SQLITE);
+        undo.new_path(&db_path);
+        let db = Db::init(&db_path)
+            .await
+            .context("Unable to create SQLite DB")
+            .pub_result(ErrorType::Database)?;
+
         // Return a new `Config` object that represents a data directory that is ready to use
         Ok(Self {
             root,
@@ -116,20 +167,78 @@ impl Config {
             config_file,
             db,
             spreadsheet_id,
+            sheet_url_override: None,
+            max_rows_override: None,
+            no_drive_backup: false,
             sqlite_path: db_path,
         })
     }
 
+    /// Returns the paths that `create` would make for `dir`, in the order they are made, along with
+    /// the spreadsheet ID extracted from `url`. Nothing is created or written.
+    ///
+    /// # Errors
+    /// - Returns an error if the spreadsheet ID cannot be extracted from `url`.
+    pub(crate) fn plan_create(dir: &Path, url: &str) -> Result<(Vec<PathBuf>, String)> {
+        let spreadsheet_id = extract_spreadsheet_id(url)
+            .context("Failed to extract spreadsheet ID from sheet URL")
+            .pub_result(ErrorType::Config)?
+            .to_string();
+        let secrets_dir = dir.join(SECRETS);
+        let paths = vec![
+            dir.to_path_buf(),
+            dir.join(BACKUPS),
+            secrets_dir.clone(),
+            secrets_dir.join(CLIENT_SECRET_JSON),
+            dir.join(CONFIG_JSON),
+            dir.join(TILLER_SQLITE),
+        ];
+        Ok((paths, spreadsheet_id))
+    }
+
     /// This will
     /// - validate that the  `tiller_home` exists and that the config file exists
     /// - load the config file
     /// - validate that the backups and secrets directories exist
     /// - return the loaded configuration object
-    pub async fn load(tiller_home: impl Into<PathBuf>) -> Result<Self> {
-        let maybe_relative = tiller_home.into();
-        let root = utils::canonicalize(&maybe_relative)
-            .await
-            .pub_result(ErrorType::Internal)?;
+    ///
+    /// If `config_file` is given, it is loaded instead of `$TILLER_HOME/config.json` and the
+    /// directory containing it is used as the root from which all other paths are derived. In this
+    /// case `tiller_home` is ignored.
+    pub async fn load(tiller_home: impl Into<PathBuf>, config_file: Option<&Path>) -> Result<Self> {
+        let (root, config_path) = match config_file {
+            None => {
+                let maybe_relative = tiller_home.into();
+                let root = utils::canonicalize(&maybe_relative)
+                    .await
+                    .pub_result(ErrorType::Internal)?;
+                let config_path = root.join(CONFIG_JSON);
+                (root, config_path)
+            }
+            Some(config_file) => {
+                if !config_file.is_file() {
+                    return Err(anyhow!(
+                        "The config file is missing '{}'",
+                        config_file.display()
+                    ))
+                    .pub_result(ErrorType::Config);
+                }
+                let config_path = utils::canonicalize(config_file)
+                    .await
+                    .pub_result(ErrorType::Internal)?;
+                let root = config_path
+                    .parent()
+                    .with_context(|| {
+                        format!(
+                            "Unable to determine the directory of the config file '{}'",
+                            config_path.display()
+                        )
+                    })
+                    .pub_result(ErrorType::Config)?
+                    .to_path_buf();
+                (root, config_path)
+            }
+        };
 
         // Validate that the home directory exists.
         let _ = utils::read_dir(&root)
@@ -137,7 +246,6 @@ impl Config {
             .context("Tiller Home is missing")
             .pub_result(ErrorType::Internal)?;
 
-        let config_path = root.join("config.json");
         if !config_path.is_file() {
             return Err(anyhow!(
                 "The config file is missing '{}'",
@@ -168,6 +276,9 @@ impl Config {
             config_file,
             db,
             spreadsheet_id,
+            sheet_url_override: None,
+            max_rows_override: None,
+            no_drive_backup: false,
             sqlite_path: db_path,
         };
         if !config.backups.is_dir() {
@@ -187,6 +298,30 @@ impl Config {
         Ok(config)
     }
 
+    /// Re-reads `config.json`, e.g. after it was edited while the MCP server is running, and
+    /// returns a configuration with its settings. The database connection, dry-run mode and any
+    /// spreadsheet given by `with_spreadsheet_id`, limit given by `with_max_rows` and Drive copy
+    /// turned off by `with_no_drive_backup` are kept.
+    ///
+    /// # Errors
+    /// - Returns an error if the config file cannot be read or parsed.
+    /// - Returns an error if the spreadsheet ID cannot be extracted from its `sheet_url`.
+    pub async fn reload(&self) -> Result<Self> {
+        let config_file = ConfigFile::load(&self.config_path).await?;
+        let spreadsheet_id = match self.sheet_url_override {
+            Some(_) => self.spreadsheet_id.clone(),
+            None => extract_spreadsheet_id(&config_file.sheet_url)
+                .context("Failed to extract spreadsheet ID from sheet URL")
+                .pub_result(ErrorType::Config)?
+                .to_string(),
+        };
+        Ok(Self {
+            config_file,
+            spreadsheet_id,
+            ..self.clone()
+        })
+    }
+
     pub fn root(&self) -> &Path {
         &self.root
     }
@@ -199,6 +334,12 @@ impl Config {
         &self.db
     }
 
+    /// Closes the database, waiting for pending writes to be flushed. Clones of this `Config`
+    /// share the database, so they can no longer use it either.
+    pub async fn close(self) {
+        self.db.close().await;
+    }
+
     pub fn backups(&self) -> &Path {
         &self.backups
     }
@@ -207,14 +348,83 @@ impl Config {
         &self.secrets
     }
 
+    /// The URL of the spreadsheet, which is the one in `config.json` unless it was overridden with
+    /// `with_spreadsheet_id`.
     pub fn sheet_url(&self) -> &str {
-        &self.config_file.sheet_url
+        self.sheet_url_override
+            .as_deref()
+            .unwrap_or(&self.config_file.sheet_url)
     }
 
     pub fn spreadsheet_id(&self) -> &str {
         &self.spreadsheet_id
     }
 
+    /// Uses the spreadsheet `id_or_url` instead of the one in `config.json`, e.g. to work with a
+    /// copy of the sheet. `config.json` is not changed. Either a spreadsheet ID or the URL of a
+    /// Google Sheet is accepted.
+    ///
+    /// # Errors
+    /// - Returns an error if `id_or_url` is blank or the ID cannot be extracted from the URL.
+    pub fn with_spreadsheet_id(mut self, id_or_url: &str) -> Result<Self> {
+        let id_or_url = id_or_url.trim();
+        let id = if id_or_url.contains('/') {
+            extract_spreadsheet_id(id_or_url)
+                .context("Failed to extract spreadsheet ID from sheet URL")
+                .pub_result(ErrorType::Request)?
+        } else {
+            id_or_url
+        };
+        if id.is_empty() {
+            return Err(anyhow!("The spreadsheet ID is empty")).pub_result(ErrorType::Request);
+        }
+        self.sheet_url_override = Some(format!("https://docs.google.com/spreadsheets/d/{id}"));
+        self.spreadsheet_id = id.to_string();
+        Ok(self)
+    }
+
+    /// Puts the configuration in dry-run mode when `dry_run` is true. Commands that change data
+    /// then check and report what they would change without saving anything: database changes are
+    /// rolled back, backups are not written and the Google sheet is not modified.
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.db = self.db.with_dry_run(dry_run);
+        self
+    }
+
+    /// Whether commands only report what they would change, see `with_dry_run`.
+    pub fn dry_run(&self) -> bool {
+        self.db.dry_run()
+    }
+
+    /// Uses `max_rows` instead of the `max_rows` in `config.json`, e.g. from the command line, when
+    /// it is `Some`. `config.json` is not changed.
+    pub fn with_max_rows(mut self, max_rows: Option<u64>) -> Self {
+        if max_rows.is_some() {
+            self.max_rows_override = max_rows;
+        }
+        self
+    }
+
+    /// The most Transactions rows that `sync down` accepts from the sheet, if there is a limit.
+    pub fn max_rows(&self) -> Option<u64> {
+        self.max_rows_override.or(self.config_file.max_rows)
+    }
+
+    /// Skips the Drive copy of the sheet before `sync up`, e.g. from the command line, when
+    /// `no_drive_backup` is true, even if `drive_backup` is on in `config.json`. `config.json` is
+    /// not changed.
+    pub fn with_no_drive_backup(mut self, no_drive_backup: bool) -> Self {
+        if no_drive_backup {
+            self.no_drive_backup = true;
+        }
+        self
+    }
+
+    /// Whether `sync up` copies the spreadsheet with the Drive API before writing to it.
+    pub fn drive_backup(&self) -> bool {
+        !self.no_drive_backup && self.config_file.drive_backup
+    }
+
     pub fn sqlite_path(&self) -> &Path {
         &self.sqlite_path
     }
@@ -223,6 +433,52 @@ impl Config {
         self.config_file.backup_copies
     }
 
+    /// Whether the Balance History tab is included in `sync down` and `sync up`.
+    pub fn sync_balances(&self) -> bool {
+        self.config_file.sync_balances
+    }
+
+    /// How long to wait for any single request to Google before giving up.
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.config_file.request_timeout_secs)
+    }
+
+    /// The proxy for requests to Google, if one is configured in `config.json`.
+    pub fn proxy(&self) -> Option<&str> {
+        self.config_file.proxy.as_deref()
+    }
+
+    /// Whether JSON backup snapshots are gzip compressed.
+    pub fn compress_backups(&self) -> bool {
+        self.config_file.compress_backups
+    }
+
+    /// Whether `sync up` writes each tab before trimming it, so that a tab is never left empty.
+    pub fn atomic_write(&self) -> bool {
+        self.config_file.atomic_write
+    }
+
+    /// Whether `sync up` checks that clearing the rows of a tab left its header row as it was.
+    pub fn verify_headers(&self) -> bool {
+        self.config_file.verify_headers
+    }
+
+    /// How long before the OAuth access token expires it is refreshed, so that it does not expire
+    /// partway through a sync.
+    pub fn token_refresh_buffer(&self) -> Duration {
+        Duration::from_secs(self.config_file.token_refresh_buffer_secs)
+    }
+
+    /// Which differences between the sheet and the last sync down stop `sync up`.
+    pub(crate) fn conflict_scope(&self) -> ConflictScope {
+        self.config_file.conflict_scope
+    }
+
+    /// How many MCP tool calls may use the database at the same time. At least 1.
+    pub(crate) fn mcp_concurrency(&self) -> usize {
+        self.config_file.mcp_concurrency.max(1) as usize
+    }
+
     /// Returns the stored `client_secret_path` if it is absolute, otherwise resolves the relative path.
     pub fn client_secret_path(&self) -> PathBuf {
         self.resolve_secrets_file_path(self.config_file.client_secret_path())
@@ -257,7 +513,16 @@ impl Config {
 ///   "sheet_url": "https://docs.google.com/spreadsheets/d/7KpXm2RfZwNJgs84QhVYno5DU6iM9Wlr3bCzAv1txRpL",
 ///   "backup_copies": 5,
 ///   "client_secret_path": ".secrets/client_secret.json",
-///   "token_path": ".secrets/token.json"
+///   "token_path": ".secrets/token.json",
+///   "sync_balances": false,
+///   "request_timeout_secs": 30,
+///   "compress_backups": false,
+///   "atomic_write": false,
+///   "verify_headers": true,
+///   "drive_backup": true,
+///   "token_refresh_buffer_secs": 300,
+///   "conflict_scope": "all",
+///   "mcp_concurrency": 1
 /// }
 /// ```
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
@@ -283,6 +548,96 @@ struct ConfigFile {
     /// Defaults to $TILLER_HOME/.secrets/token.json if not specified
     #[serde(skip_serializing_if = "Option::is_none")]
     token_path: Option<PathBuf>,
+
+    /// Whether to sync the Balance History tab. Defaults to false so that existing config files
+    /// keep their current behavior.
+    #[serde(default)]
+    sync_balances: bool,
+
+    /// How long to wait for any single request to Google before giving up, in seconds.
+    #[serde(default = "default_request_timeout_secs")]
+    request_timeout_secs: u64,
+
+    /// The URL of a proxy for all requests to Google, e.g. `http://proxy.example.com:8080`. When
+    /// not set, the `HTTPS_PROXY`, `HTTP_PROXY` and `NO_PROXY` environment variables are used.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    proxy: Option<String>,
+
+    /// Whether JSON backup snapshots are gzip compressed and saved as `.json.gz`. Defaults to
+    /// false. Either kind of snapshot can be read regardless of this setting.
+    #[serde(default)]
+    compress_backups: bool,
+
+    /// Whether `sync up` writes each tab before trimming the rows left over below the new data,
+    /// instead of clearing the tab first. Defaults to false, which keeps the clear-then-write
+    /// behavior.
+    #[serde(default)]
+    atomic_write: bool,
+
+    /// Whether `sync up` reads the header row of each tab before and after clearing its rows, and
+    /// stops if clearing changed it. Defaults to true.
+    #[serde(default = "default_verify_headers")]
+    verify_headers: bool,
+
+    /// Whether `sync up` copies the spreadsheet with the Drive API before writing to it. The JSON
+    /// and SQLite backups are made either way. Defaults to true.
+    #[serde(default = "default_drive_backup")]
+    drive_backup: bool,
+
+    /// How many seconds before the OAuth access token expires it is refreshed. Defaults to 5
+    /// minutes.
+    #[serde(default = "default_token_refresh_buffer_secs")]
+    token_refresh_buffer_secs: u64,
+
+    /// Which differences between the sheet and the last sync down stop `sync up`. Defaults to
+    /// `all`.
+    #[serde(default)]
+    conflict_scope: ConflictScope,
+
+    /// How many MCP tool calls may use the database at the same time. Further calls wait for one
+    /// to finish. Defaults to the size of the database connection pool.
+    #[serde(default = "default_mcp_concurrency")]
+    mcp_concurrency: u32,
+
+    /// The most rows of the Transactions tab that `sync down` accepts. A sheet with more rows is
+    /// not saved, in case `sheet_url` points at the wrong, much larger sheet. No limit when not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_rows: Option<u64>,
+}
+
+/// Which differences between the sheet and the last sync down are treated as conflicts by
+/// `sync up`. Without `--force`, `sync up` refuses to overwrite a sheet that has conflicts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ConflictScope {
+    /// Any difference in any tab, including a formula that changed.
+    #[default]
+    All,
+    /// Any difference in the Transactions tab. Changes to the other tabs are overwritten.
+    TransactionsOnly,
+    /// Any difference in the values of any tab. A formula that changed is overwritten if the
+    /// values in the sheet did not change.
+    ValuesOnly,
+}
+
+fn default_request_timeout_secs() -> u64 {
+    REQUEST_TIMEOUT_SECS
+}
+
+fn default_verify_headers() -> bool {
+    true
+}
+
+fn default_drive_backup() -> bool {
+    true
+}
+
+fn default_token_refresh_buffer_secs() -> u64 {
+    TOKEN_REFRESH_BUFFER_SECS
+}
+
+fn default_mcp_concurrency() -> u32 {
+    MCP_CONCURRENCY
 }
 
 impl Default for ConfigFile {
@@ -294,6 +649,17 @@ impl Default for ConfigFile {
             backup_copies: 5,
             client_secret_path: None,
             token_path: None,
+            sync_balances: false,
+            request_timeout_secs: REQUEST_TIMEOUT_SECS,
+            proxy: None,
+            compress_backups: false,
+            atomic_write: false,
+            verify_headers: true,
+            drive_backup: true,
+            token_refresh_buffer_secs: TOKEN_REFRESH_BUFFER_SECS,
+            conflict_scope: ConflictScope::All,
+            mcp_concurrency: MCP_CONCURRENCY,
+            max_rows: None,
         }
     }
 }
@@ -360,6 +726,17 @@ impl ConfigFile {
             backup_copies,
             client_secret_path,
             token_path,
+            sync_balances: false,
+            request_timeout_secs: REQUEST_TIMEOUT_SECS,
+            proxy: None,
+            compress_backups: false,
+            atomic_write: false,
+            verify_headers: true,
+            drive_backup: true,
+            token_refresh_buffer_secs: TOKEN_REFRESH_BUFFER_SECS,
+            conflict_scope: ConflictScope::All,
+            mcp_concurrency: MCP_CONCURRENCY,
+            max_rows: None,
         }
     }
 
@@ -384,8 +761,64 @@ impl ConfigFile {
     }
 }
 
+/// What a failed `Config::create` has to undo: the directories and files it made, and the original
+/// contents of the files it overwrote.
+#[derive(Default)]
+struct CreateUndo {
+    created: Vec<PathBuf>,
+    overwritten: Vec<(PathBuf, Vec<u8>)>,
+}
+
+impl CreateUndo {
+    /// Records `path`, which is about to be made, so that it is removed on failure. Nothing is
+    /// recorded if `path` already exists.
+    fn new_path(&mut self, path: &Path) {
+        if !path.exists() {
+            self.created.push(path.to_path_buf());
+        }
+    }
+
+    /// Records the file at `path`, which is about to be written. It is removed on failure if it
+    /// does not exist yet, otherwise its current contents are restored.
+    async fn file(&mut self, path: &Path) -> Res<()> {
+        if path.exists() {
+            let contents = utils::read_bytes(path).await?;
+            self.overwritten.push((path.to_path_buf(), contents));
+        } else {
+            self.created.push(path.to_path_buf());
+        }
+        Ok(())
+    }
+
+    /// Restores the overwritten files, then removes what was made, newest first. Failures are
+    /// logged rather than returned, since the error that caused the clean up is the one to report.
+    async fn undo(&self) {
+        for (path, contents) in &self.overwritten {
+            if let Err(e) = utils::write(path, contents).await {
+                warn!("Unable to restore a file after a failed init: {e:#}");
+            }
+        }
+        for path in self.created.iter().rev() {
+            let removed = if path.is_dir() {
+                utils::remove_dir_all(path).await
+            } else if path.exists() {
+                utils::remove(path).await
+            } else {
+                Ok(())
+            };
+            if let Err(e) = removed {
+                warn!("Unable to clean up after a failed init: {e:#}");
+            }
+        }
+    }
+}
+
 /// Extracts the spreadsheet ID from a Google Sheets URL
 ///
+/// The ID is the path segment after `d`. URLs copied from a browser signed in to more than one
+/// Google account have an account index before it, e.g. `/spreadsheets/u/1/d/SPREADSHEET_ID`,
+/// which is ignored. Any query, e.g. `?usp=sharing`, and fragment, e.g. `#gid=123`, are dropped.
+///
 /// # Arguments
 /// * `url` - The Google Sheets URL (e.g., "https://docs.google.com/spreadsheets/d/SPREADSHEET_ID/...")
 ///
@@ -398,25 +831,16 @@ fn extract_spreadsheet_id(url: &str) -> Res<&str> {
     }
 
     // URL format: https://docs.google.com/spreadsheets/d/SPREADSHEET_ID/...
+    // or: https://docs.google.com/spreadsheets/u/0/d/SPREADSHEET_ID#gid=0
     // or: https://docs.google.com/spreadsheets/d/SPREADSHEET_ID?foo=bar
-    let parts: Vec<&str> = url.split('/').collect();
-    for (i, part) in parts.iter().enumerate() {
-        if *part == "d" && i + 1 < parts.len() {
-            // Extract the ID and remove any query parameters or fragments
-            let id_part = parts[i + 1];
-            let id = id_part
-                .split('?')
-                .next()
-                .unwrap_or(id_part)
-                .split('#')
-                .next()
-                .unwrap_or(id_part);
-            return Ok(id);
-        }
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    let mut parts = path.split('/').skip_while(|part| *part != "d").skip(1);
+    match parts.next() {
+        Some(id) if !id.is_empty() => Ok(id),
+        _ => Err(anyhow::anyhow!(
+            "Invalid Google Sheets URL format. Expected: https://docs.google.com/spreadsheets/d/SPREADSHEET_ID"
+        )),
     }
-    Err(anyhow::anyhow!(
-        "Invalid Google Sheets URL format. Expected: https://docs.google.com/spreadsheets/d/SPREADSHEET_ID"
-    ))
 }
 
 #[cfg(test)]
@@ -425,6 +849,16 @@ mod tests {
     use tempfile::TempDir;
     use utils;
 
+    #[tokio::test]
+    async fn test_close_closes_the_database_of_every_clone() {
+        let env = crate::test::TestEnv::new().await;
+        let config = env.config();
+        assert_eq!(config.db().count_transactions().await.unwrap(), 0);
+
+        env.finish().await;
+        assert!(config.db().count_transactions().await.is_err());
+    }
+
     #[tokio::test]
     async fn test_config_create() {
         use tempfile::TempDir;
@@ -471,6 +905,104 @@ mod tests {
         assert_eq!("MySheetIDX", config.spreadsheet_id());
     }
 
+    #[tokio::test]
+    async fn test_with_spreadsheet_id() {
+        let dir = TempDir::new().unwrap();
+        let secret_file = dir.path().join("foo.json");
+        utils::write(&secret_file, "{}").await.unwrap();
+        let url = "https://docs.google.com/spreadsheets/d/ConfiguredID";
+        let home_dir = dir.path().join("tiller_home");
+        let config = Config::create(&home_dir, &secret_file, url).await.unwrap();
+
+        let copy = config.clone().with_spreadsheet_id("CopyID").unwrap();
+        assert_eq!("CopyID", copy.spreadsheet_id());
+        assert_eq!(
+            "https://docs.google.com/spreadsheets/d/CopyID",
+            copy.sheet_url()
+        );
+
+        let copy = config
+            .clone()
+            .with_spreadsheet_id("https://docs.google.com/spreadsheets/d/UrlID/edit#gid=0")
+            .unwrap();
+        assert_eq!("UrlID", copy.spreadsheet_id());
+
+        assert!(config.clone().with_spreadsheet_id(" ").is_err());
+        assert!(config.clone().with_spreadsheet_id("https://x/y").is_err());
+
+        // config.json is unchanged
+        let loaded = Config::load(&home_dir, None).await.unwrap();
+        assert_eq!("ConfiguredID", loaded.spreadsheet_id());
+        assert_eq!(url, loaded.sheet_url());
+    }
+
+    #[tokio::test]
+    async fn test_config_load_default_config_file() {
+        let dir = TempDir::new().unwrap();
+        let home_dir = dir.path().join("tiller_home");
+        let secret_file = dir.path().join("foo.json");
+        utils::write(&secret_file, "{}").await.unwrap();
+        let url = "https://docs.google.com/spreadsheets/d/DefaultSheetID";
+        let created = Config::create(&home_dir, &secret_file, url).await.unwrap();
+
+        let loaded = Config::load(&home_dir, None).await.unwrap();
+        assert_eq!(created.root(), loaded.root());
+        assert_eq!(created.config_path(), loaded.config_path());
+        assert_eq!("DefaultSheetID", loaded.spreadsheet_id());
+    }
+
+    #[tokio::test]
+    async fn test_config_load_config_file_override() {
+        let dir = TempDir::new().unwrap();
+        let home_dir = dir.path().join("tiller_home");
+        let secret_file = dir.path().join("foo.json");
+        utils::write(&secret_file, "{}").await.unwrap();
+        let url = "https://docs.google.com/spreadsheets/d/DefaultSheetID";
+        let created = Config::create(&home_dir, &secret_file, url).await.unwrap();
+
+        // Write a second config file, with a different sheet, alongside the default one
+        let alt_config_path = created.root().join("alt.json");
+        let alt = ConfigFile::new(
+            "https://docs.google.com/spreadsheets/d/AltSheetID".to_string(),
+            3,
+            None,
+            None,
+        );
+        alt.save(&alt_config_path).await.unwrap();
+
+        // The tiller_home argument points somewhere else entirely and should be ignored
+        let other_home = dir.path().join("does_not_exist");
+        let loaded = Config::load(&other_home, Some(&alt_config_path))
+            .await
+            .unwrap();
+
+        let root = created.root();
+        assert_eq!(root, loaded.root());
+        assert_eq!(alt_config_path, loaded.config_path());
+        assert_eq!("AltSheetID", loaded.spreadsheet_id());
+        assert_eq!(3, loaded.backup_copies());
+        assert_eq!(root.join(TILLER_SQLITE), loaded.sqlite_path());
+        assert_eq!(root.join(BACKUPS), loaded.backups());
+        assert_eq!(root.join(SECRETS), loaded.secrets());
+        assert_eq!(
+            root.join(SECRETS).join(CLIENT_SECRET_JSON),
+            loaded.client_secret_path()
+        );
+        assert_eq!(root.join(SECRETS).join(TOKEN_JSON), loaded.token_path());
+    }
+
+    #[tokio::test]
+    async fn test_config_load_config_file_override_missing() {
+        let dir = TempDir::new().unwrap();
+        let missing = dir.path().join("missing.json");
+        let result = Config::load(dir.path(), Some(&missing)).await;
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("The config file is missing"));
+    }
+
     #[test]
     fn test_config_file_new() {
         let config = ConfigFile::new(
@@ -546,6 +1078,16 @@ mod tests {
             PathBuf::from(SECRETS).join(CLIENT_SECRET_JSON)
         );
         assert_eq!(config.token_path(), PathBuf::from(SECRETS).join(TOKEN_JSON));
+        assert!(!config.sync_balances);
+        assert_eq!(config.request_timeout_secs, REQUEST_TIMEOUT_SECS);
+        assert!(config.proxy.is_none());
+        assert!(!config.compress_backups);
+        assert!(!config.atomic_write);
+        assert!(config.verify_headers);
+        assert!(config.drive_backup);
+        assert_eq!(config.token_refresh_buffer_secs, TOKEN_REFRESH_BUFFER_SECS);
+        assert_eq!(config.conflict_scope, ConflictScope::All);
+        assert_eq!(config.mcp_concurrency, MCP_CONCURRENCY);
     }
 
     #[tokio::test]
@@ -636,4 +1178,33 @@ mod tests {
         let id_empty = extract_spreadsheet_id(empty).unwrap();
         assert_eq!(id_empty, "");
     }
+
+    #[test]
+    fn test_extract_spreadsheet_id_with_account_index() {
+        let id = "7KpXm2RfZwNJgs84QhVYno5DU6iM9Wlr3bCzAv1txRpL";
+        for url in [
+            format!("https://docs.google.com/spreadsheets/u/0/d/{id}/edit"),
+            format!("https://docs.google.com/spreadsheets/u/2/d/{id}/edit"),
+            format!("https://docs.google.com/spreadsheets/u/1/d/{id}"),
+        ] {
+            assert_eq!(extract_spreadsheet_id(&url).unwrap(), id, "{url}");
+        }
+    }
+
+    #[test]
+    fn test_extract_spreadsheet_id_with_gid() {
+        let id = "7KpXm2RfZwNJgs84QhVYno5DU6iM9Wlr3bCzAv1txRpL";
+        for url in [
+            format!("https://docs.google.com/spreadsheets/d/{id}/edit#gid=123"),
+            format!("https://docs.google.com/spreadsheets/d/{id}#gid=123"),
+            format!("https://docs.google.com/spreadsheets/d/{id}/edit?gid=123#gid=123"),
+            format!("https://docs.google.com/spreadsheets/u/0/d/{id}/edit#gid=0"),
+        ] {
+            assert_eq!(extract_spreadsheet_id(&url).unwrap(), id, "{url}");
+        }
+
+        // A URL without an ID after `d` is an error
+        assert!(extract_spreadsheet_id("https://docs.google.com/spreadsheets/d/").is_err());
+        assert!(extract_spreadsheet_id("https://docs.google.com/spreadsheets/d#gid=1").is_err());
+    }
 }
@@ -0,0 +1,3 @@
+-- Migration 02 (down): Drop the balances table
+
+DROP TABLE IF EXISTS balances;
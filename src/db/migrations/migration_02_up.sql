@@ -0,0 +1,18 @@
+-- Migration 02: Create the balances table for the Balance History sheet
+--
+-- Balance History rows have no natural primary key (the same account appears once per snapshot),
+-- so, like autocat, rows are identified by a surrogate id. Balances are only synced when
+-- `sync_balances` is enabled in config.json; otherwise this table stays empty.
+
+CREATE TABLE balances (
+    id             INTEGER PRIMARY KEY AUTOINCREMENT,
+    account        TEXT    NOT NULL,
+    date           TEXT    NOT NULL,
+    balance        NUMERIC NOT NULL,
+    account_id     TEXT    NOT NULL,
+    original_order INTEGER,
+    other_fields   TEXT
+);
+
+CREATE INDEX idx_balances_account_id ON balances (account_id);
+CREATE INDEX idx_balances_date ON balances (date);
@@ -0,0 +1,112 @@
+-- Migration 03: Store amounts as decimal strings
+--
+-- transactions.amount and balances.balance were NUMERIC, which SQLite stores as a floating point
+-- value whenever the amount has cents, so -87.43 could be read back as -87.42999... Both columns
+-- become TEXT holding the exact decimal string (e.g. '-87.43'), the same way the autocat amount
+-- columns are stored. SQLite cannot change a column's type in place, so each table is rebuilt and
+-- its rows copied over.
+
+CREATE TABLE transactions_new (
+    transaction_id   TEXT PRIMARY KEY,
+    date             TEXT NOT NULL,
+    description      TEXT NOT NULL,
+    amount           TEXT NOT NULL,
+    account          TEXT NOT NULL,
+    account_number   TEXT NOT NULL,
+    institution      TEXT NOT NULL,
+    account_id       TEXT NOT NULL,
+    month            TEXT,
+    week             TEXT,
+    full_description TEXT,
+    check_number     TEXT,
+    date_added       TEXT,
+    merchant_name    TEXT,
+    category_hint    TEXT,
+    category         TEXT REFERENCES categories(category) ON UPDATE CASCADE ON DELETE RESTRICT,
+    note             TEXT,
+    tags             TEXT,
+    categorized_date TEXT,
+    statement        TEXT,
+    metadata         TEXT,
+    original_order   INTEGER,
+    other_fields     TEXT
+);
+
+INSERT INTO transactions_new (
+    transaction_id,
+    date,
+    description,
+    amount,
+    account,
+    account_number,
+    institution,
+    account_id,
+    month,
+    week,
+    full_description,
+    check_number,
+    date_added,
+    merchant_name,
+    category_hint,
+    category,
+    note,
+    tags,
+    categorized_date,
+    statement,
+    metadata,
+    original_order,
+    other_fields
+)
+SELECT
+    transaction_id,
+    date,
+    description,
+    CAST(amount AS TEXT),
+    account,
+    account_number,
+    institution,
+    account_id,
+    month,
+    week,
+    full_description,
+    check_number,
+    date_added,
+    merchant_name,
+    category_hint,
+    category,
+    note,
+    tags,
+    categorized_date,
+    statement,
+    metadata,
+    original_order,
+    other_fields
+FROM transactions;
+
+DROP TABLE transactions;
+ALTER TABLE transactions_new RENAME TO transactions;
+
+CREATE INDEX idx_transactions_date ON transactions (date);
+CREATE INDEX idx_transactions_account ON transactions (account);
+CREATE INDEX idx_transactions_category ON transactions (category);
+CREATE INDEX idx_transactions_description ON transactions (description);
+
+CREATE TABLE balances_new (
+    id             INTEGER PRIMARY KEY AUTOINCREMENT,
+    account        TEXT    NOT NULL,
+    date           TEXT    NOT NULL,
+    balance        TEXT    NOT NULL,
+    account_id     TEXT    NOT NULL,
+    original_order INTEGER,
+    other_fields   TEXT
+);
+
+INSERT INTO balances_new (id, account, date, balance, account_id, original_order, other_fields)
+SELECT id, account, date, CAST(balance AS TEXT), account_id, original_order, other_fields
+FROM balances;
+
+DROP TABLE balances;
+ALTER TABLE balances_new RENAME TO balances;
+
+CREATE INDEX idx_balances_account_id ON balances (account_id);
+CREATE INDEX idx_balances_date ON balances (date);
@@ -0,0 +1,3 @@
+-- Migration 04 (down): Drop the autocat category index
+
+DROP INDEX IF EXISTS idx_autocat_category;
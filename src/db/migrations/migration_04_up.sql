@@ -0,0 +1,7 @@
+-- Migration 04: Index autocat rules by category
+--
+-- The transactions table is already indexed on date, account, category and description. Autocat
+-- rules are looked up by category when categories are renamed or deleted, and when listing the
+-- rules for a category.
+
+CREATE INDEX idx_autocat_category ON autocat (category);
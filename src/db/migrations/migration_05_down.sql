@@ -0,0 +1,3 @@
+-- Migration 05 (down): Drop the sync_history table
+
+DROP TABLE IF EXISTS sync_history;
@@ -0,0 +1,16 @@
+-- Migration 05: Record each sync up and sync down
+--
+-- One row is written when a sync finishes, whether it succeeded or failed. The counts are the
+-- number of rows synced from or to each tab, and are 0 when the sync failed.
+
+CREATE TABLE sync_history (
+    id           INTEGER PRIMARY KEY AUTOINCREMENT,
+    finished_at  TEXT    NOT NULL,
+    direction    TEXT    NOT NULL,
+    transactions INTEGER NOT NULL,
+    categories   INTEGER NOT NULL,
+    autocats     INTEGER NOT NULL,
+    balances     INTEGER NOT NULL,
+    success      INTEGER NOT NULL,
+    message      TEXT    NOT NULL
+);
@@ -0,0 +1,3 @@
+-- Migration 06 (down): Drop the transactions no_name column
+
+ALTER TABLE transactions DROP COLUMN no_name;
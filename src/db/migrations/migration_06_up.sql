@@ -0,0 +1,7 @@
+-- Migration 06: Store the unnamed Column A of the Transactions tab
+--
+-- Tiller sheets can have a column with an empty header at Column A, which Tiller's own formulas
+-- may reference. It was parsed into the `no_name` field but not stored, so `sync up` wrote it back
+-- empty. Existing rows get an empty value until the next `sync down`.
+
+ALTER TABLE transactions ADD COLUMN no_name TEXT;
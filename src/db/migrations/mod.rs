@@ -20,11 +20,38 @@ struct Migration {
 }
 
 /// All available migrations in order.
-const MIGRATIONS: &[Migration] = &[Migration {
-    version: 1,
-    up_sql: include_str!("migration_01_up.sql"),
-    down_sql: include_str!("migration_01_down.sql"),
-}];
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        up_sql: include_str!("migration_01_up.sql"),
+        down_sql: include_str!("migration_01_down.sql"),
+    },
+    Migration {
+        version: 2,
+        up_sql: include_str!("migration_02_up.sql"),
+        down_sql: include_str!("migration_02_down.sql"),
+    },
+    Migration {
+        version: 3,
+        up_sql: include_str!("migration_03_up.sql"),
+        down_sql: include_str!("migration_03_down.sql"),
+    },
+    Migration {
+        version: 4,
+        up_sql: include_str!("migration_04_up.sql"),
+        down_sql: include_str!("migration_04_down.sql"),
+    },
+    Migration {
+        version: 5,
+        up_sql: include_str!("migration_05_up.sql"),
+        down_sql: include_str!("migration_05_down.sql"),
+    },
+    Migration {
+        version: 6,
+        up_sql: include_str!("migration_06_up.sql"),
+        down_sql: include_str!("migration_06_down.sql"),
+    },
+];
 
 /// Runs migrations to bring the database from `current_version` to `target_version`.
 ///
@@ -167,6 +194,17 @@ mod tests {
         Ok(row.0)
     }
 
+    /// Helper to check if an index exists.
+    async fn index_exists(pool: &SqlitePool, index_name: &str) -> Res<bool> {
+        let row: (i32,) =
+            sqlx::query_as("SELECT COUNT(*) FROM sqlite_master WHERE type='index' AND name=?")
+                .bind(index_name)
+                .fetch_one(pool)
+                .await
+                .context("Failed to check index existence")?;
+        Ok(row.0 > 0)
+    }
+
     /// Helper to check if a table exists.
     async fn table_exists(pool: &SqlitePool, table_name: &str) -> Res<bool> {
         let row: (i32,) =
@@ -231,17 +269,171 @@ mod tests {
         assert_eq!(get_schema_version(&pool).await.unwrap(), 1);
     }
 
+    #[tokio::test]
+    async fn test_migration_02_balances_table() {
+        let (_temp_dir, pool) = create_test_db().await.unwrap();
+
+        run(&pool, 0, 2).await.unwrap();
+        assert_eq!(get_schema_version(&pool).await.unwrap(), 2);
+        assert!(table_exists(&pool, "balances").await.unwrap());
+
+        // Going back to version 1 drops only the balances table
+        run(&pool, 2, 1).await.unwrap();
+        assert_eq!(get_schema_version(&pool).await.unwrap(), 1);
+        assert!(!table_exists(&pool, "balances").await.unwrap());
+        assert!(table_exists(&pool, "transactions").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_migration_03_preserves_amounts_as_text() {
+        let (_temp_dir, pool) = create_test_db().await.unwrap();
+        run(&pool, 0, 2).await.unwrap();
+
+        // At version 2 amounts are NUMERIC, so SQLite stores them as REAL
+        sqlx::query(
+            "INSERT INTO transactions (transaction_id, date, description, amount, account, \
+            account_number, institution, account_id) \
+            VALUES ('t1', '10/20/2025', 'Coffee', -87.43, 'Checking', 'xxxx', 'Bank', 'a1')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO balances (account, date, balance, account_id) \
+            VALUES ('Checking', '10/20/2025', 1234.5, 'a1')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        run(&pool, 2, 3).await.unwrap();
+        assert_eq!(get_schema_version(&pool).await.unwrap(), 3);
+
+        let (amount_type, amount): (String, String) =
+            sqlx::query_as("SELECT typeof(amount), amount FROM transactions")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(amount_type, "text");
+        assert_eq!(amount, "-87.43");
+
+        let (balance,): (String,) = sqlx::query_as("SELECT balance FROM balances")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(balance, "1234.5");
+
+        // Going back to version 2 restores numeric storage
+        run(&pool, 3, 2).await.unwrap();
+        let (amount_type,): (String,) = sqlx::query_as("SELECT typeof(amount) FROM transactions")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(amount_type, "real");
+    }
+
+    #[tokio::test]
+    async fn test_migration_04_indexes() {
+        let (_temp_dir, pool) = create_test_db().await.unwrap();
+        run(&pool, 0, 4).await.unwrap();
+        assert_eq!(get_schema_version(&pool).await.unwrap(), 4);
+
+        for index in [
+            "idx_transactions_date",
+            "idx_transactions_account",
+            "idx_transactions_category",
+            "idx_autocat_category",
+        ] {
+            assert!(index_exists(&pool, index).await.unwrap(), "{index}");
+        }
+
+        // A category filter is answered from the index rather than a full scan
+        let plan: Vec<(i64, i64, i64, String)> = sqlx::query_as(
+            "EXPLAIN QUERY PLAN SELECT * FROM transactions WHERE category = 'Groceries'",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert!(
+            plan.iter()
+                .any(|(_, _, _, detail)| detail.contains("idx_transactions_category")),
+            "{plan:?}"
+        );
+
+        run(&pool, 4, 3).await.unwrap();
+        assert!(!index_exists(&pool, "idx_autocat_category").await.unwrap());
+        assert!(index_exists(&pool, "idx_transactions_category")
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_migration_05_sync_history_table() {
+        let (_temp_dir, pool) = create_test_db().await.unwrap();
+        run(&pool, 0, 5).await.unwrap();
+        assert!(table_exists(&pool, "sync_history").await.unwrap());
+
+        run(&pool, 5, 4).await.unwrap();
+        assert!(!table_exists(&pool, "sync_history").await.unwrap());
+        assert!(table_exists(&pool, "transactions").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_migration_06_transactions_no_name_column() {
+        let (_temp_dir, pool) = create_test_db().await.unwrap();
+        run(&pool, 0, 5).await.unwrap();
+        sqlx::query(
+            "INSERT INTO transactions
+                (transaction_id, date, description, amount, account, account_number,
+                 institution, account_id)
+             VALUES ('txn-001', '10/1/2025', 'Coffee', '-4.50', 'Checking', 'xxxx1234',
+                     'Bank A', 'acct-001')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        run(&pool, 5, 6).await.unwrap();
+        let (no_name,): (Option<String>,) =
+            sqlx::query_as("SELECT no_name FROM transactions WHERE transaction_id = 'txn-001'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(no_name, None);
+
+        run(&pool, 6, 5).await.unwrap();
+        assert!(sqlx::query("SELECT no_name FROM transactions")
+            .fetch_all(&pool)
+            .await
+            .is_err());
+        let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM transactions")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
     #[test]
     fn testvalidate_migrations_succeeds_for_valid_range() {
-        // Migration 1 exists, so this should succeed
+        // Migrations 1 through 6 exist, so this should succeed
         assert!(validate_migrations(0, 1).is_ok());
         assert!(validate_migrations(1, 0).is_ok());
+        assert!(validate_migrations(0, 2).is_ok());
+        assert!(validate_migrations(2, 0).is_ok());
+        assert!(validate_migrations(0, 3).is_ok());
+        assert!(validate_migrations(3, 0).is_ok());
+        assert!(validate_migrations(0, 4).is_ok());
+        assert!(validate_migrations(4, 0).is_ok());
+        assert!(validate_migrations(0, 5).is_ok());
+        assert!(validate_migrations(5, 0).is_ok());
+        assert!(validate_migrations(0, 6).is_ok());
+        assert!(validate_migrations(6, 0).is_ok());
     }
 
     #[test]
     fn testvalidate_migrations_fails_for_missing_migration() {
-        // Migration 2 doesn't exist
-        assert!(validate_migrations(0, 2).is_err());
-        assert!(validate_migrations(1, 3).is_err());
+        // Migration 7 doesn't exist
+        assert!(validate_migrations(0, 7).is_err());
+        assert!(validate_migrations(6, 8).is_err());
     }
 }
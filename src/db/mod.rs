@@ -4,25 +4,83 @@
 //! TODO: This file is an absolute vibe-coding disaster. Sorry. At least try to deduplicate code.
 mod migrations;
 
-use crate::api::{AUTO_CAT, CATEGORIES, TRANSACTIONS};
+use crate::api::{AUTO_CAT, BALANCES, CATEGORIES, TRANSACTIONS};
 use crate::args::{
-    DeleteAutoCatsArgs, DeleteCategoriesArgs, DeleteTransactionsArgs, UpdateAutoCatsArgs,
-    UpdateCategoriesArgs, UpdateTransactionsArgs,
+    DeleteAutoCatsArgs, DeleteCategoriesArgs, DeleteTransactionsArgs, TransactionFilter, UpDown,
+    UpdateAutoCatsArgs, UpdateCategoriesArgs, UpdateTransactionsArgs,
 };
 use crate::error::Res;
-use crate::model::{Amount, AutoCat, Category, Item, Mapping, TillerData, Transaction};
+use crate::model::{
+    Amount, AutoCat, AutoCats, Balance, Balances, Categories, Category, Item, Mapping, RowCol,
+    SyncCounts, SyncRecord, TillerData, Transaction, Transactions,
+};
+use crate::utils;
 use anyhow::{bail, Context};
-use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use chrono::{NaiveDate, SecondsFormat, Utc};
 use rust_decimal::Decimal;
-use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions, SqliteRow};
 use sqlx::{Column, SqlitePool};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::path::Path;
 use std::str::FromStr;
+use tracing::{info, warn};
+
+/// The target schema version for the database. This equals the highest migration number available:
+/// adding `migration_NN_up.sql` means raising this to `NN`.
+pub(crate) const CURRENT_VERSION: i32 = 6;
 
-/// The target schema version for the database. This equals the highest migration number available.
-/// When `migration_05_up.sql` is the highest numbered migration, this should be `5`.
-pub(crate) const CURRENT_VERSION: i32 = 1;
+/// The number of connections in each of the read-write and read-only pools.
+pub(crate) const POOL_SIZE: u32 = 1;
+
+/// PRAGMAs applied to every read-write connection when it is opened. These settings are not stored
+/// in the database file, so each connection needs them.
+///
+/// - `foreign_keys`: SQLite only enforces the `ON DELETE RESTRICT` and `ON UPDATE CASCADE`
+///   clauses of the schema when this is on.
+/// - `journal_mode`: The rollback journal keeps all committed data in the single `.sqlite` file,
+///   which is what backups copy. WAL mode would leave recent commits in a separate `-wal` file.
+/// - `busy_timeout`: Milliseconds to wait for a lock held by another connection before failing.
+const CONNECTION_PRAGMAS: &[(&str, &str)] = &[
+    ("foreign_keys", "ON"),
+    ("journal_mode", "DELETE"),
+    ("busy_timeout", "5000"),
+];
+
+/// PRAGMAs applied to every read-only connection when it is opened. A read-only connection cannot
+/// change the journal mode, and foreign keys only matter for writes.
+const READ_ONLY_CONNECTION_PRAGMAS: &[(&str, &str)] = &[("busy_timeout", "5000")];
+
+/// How many transactions `save_tiller_data` saves between logging its progress.
+const SAVE_PROGRESS_INTERVAL: usize = 1000;
+
+/// The columns selected when reading a full `Transaction` from the `transactions` table.
+const TRANSACTION_COLUMNS: &str = "transaction_id, date, description, amount, account, \
+    account_number, institution, month, week, full_description, account_id, check_number, \
+    date_added, merchant_name, category_hint, category, note, tags, categorized_date, statement, \
+    metadata, no_name, other_fields, original_order";
+
+/// The order in which transactions are listed, which matches their order in the sheet.
+const TRANSACTION_ORDER: &str = "original_order ASC NULLS LAST, transaction_id ASC";
+
+/// The order in which categories are listed, which matches their order in the sheet. Categories
+/// added locally have no `original_order` and follow, in the order they were added, since the
+/// `rowid` of a row does not change when it is updated or renamed.
+const CATEGORY_ORDER: &str = "original_order ASC NULLS LAST, rowid ASC";
+
+/// The columns selected when reading a full AutoCat rule, with its ID, from the `autocat` table.
+const AUTOCAT_COLUMNS: &str = "id, category, description, description_contains, \
+    account_contains, institution_contains, amount_min, amount_max, amount_equals, \
+    description_equals, description_full, full_description_contains, amount_contains, \
+    other_fields, original_order";
+
+/// The order in which AutoCat rules are listed, which matches their order in the sheet. Tiller
+/// applies the rules from top to bottom, so this order decides which rule wins. Rules added
+/// locally have no `original_order` and follow, in the order they were added.
+const AUTOCAT_ORDER: &str = "original_order ASC NULLS LAST, id ASC";
+
+/// The key under which transactions with a blank institution are grouped in
+/// [`Db::institution_totals`].
+pub(crate) const UNKNOWN_INSTITUTION: &str = "(unknown)";
 
 /// Represents a row in the database in a table for which the primary key is not known in
 /// `TillerData`. Namely, rows from the `categories` and `autocats` tables.
@@ -59,6 +117,8 @@ pub(crate) struct Db {
     pool: SqlitePool,
     /// Read-only connection pool for queries. Uses SQLite's `?mode=ro` to enforce read-only access.
     ro_pool: SqlitePool,
+    /// When true, every change is rolled back instead of committed, see `with_dry_run`.
+    dry_run: bool,
 }
 
 impl Db {
@@ -73,36 +133,60 @@ impl Db {
         }
 
         // Read-write connection pool
-        let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", path.display()))
-            .context("Failed to parse SQLite connection string")?
-            .create_if_missing(false)
-            // Enable foreign key constraints by default
-            .pragma("foreign_keys", "ON");
+        let options = rw_connect_options(path, false)?;
 
-        let pool = SqlitePoolOptions::new()
-            .max_connections(1)
+        // Opening a connection sets the journal mode, which reads the file, so a file that is not a
+        // database is already found here.
+        let pool = match pool_options(POOL_SIZE, CONNECTION_PRAGMAS)
             .connect_with(options)
             .await
-            .context("Failed to connect to SQLite database")?;
+        {
+            Ok(pool) => pool,
+            Err(e) if is_corrupt(&e) => return Err(e).context(corrupt_message(path)),
+            Err(e) => return Err(e).context("Failed to connect to SQLite database"),
+        };
 
         // Read-only connection pool using SQLite's mode=ro
         let ro_options =
             SqliteConnectOptions::from_str(&format!("sqlite:{}?mode=ro", path.display()))
                 .context("Failed to parse read-only SQLite connection string")?;
 
-        let ro_pool = SqlitePoolOptions::new()
-            .max_connections(1)
+        let ro_pool = pool_options(POOL_SIZE, READ_ONLY_CONNECTION_PRAGMAS)
             .connect_with(ro_options)
             .await
             .context("Failed to connect to read-only SQLite database")?;
 
-        let db = Self { pool, ro_pool };
+        let db = Self {
+            pool,
+            ro_pool,
+            dry_run: false,
+        };
+        db.check_integrity(path).await?;
         db.migrate().await?;
 
         Ok(db)
     }
 
+    /// Asks SQLite to check the database at `path` for damage, so that a corrupt or truncated file
+    /// is reported as such instead of failing later with an obscure error. `quick_check` is used
+    /// rather than `integrity_check` because it runs on every load and skips the slow index checks.
+    async fn check_integrity(&self, path: &Path) -> Res<()> {
+        let problems: Vec<String> = match sqlx::query_scalar::<_, String>("PRAGMA quick_check")
+            .fetch_all(&self.ro_pool)
+            .await
+        {
+            Ok(rows) => rows.into_iter().filter(|row| row != "ok").collect(),
+            Err(e) if is_corrupt(&e) => return Err(e).context(corrupt_message(path)),
+            Err(e) => return Err(e).context("Unable to check the SQLite database for damage"),
+        };
+        if problems.is_empty() {
+            return Ok(());
+        }
+        Err(anyhow::anyhow!(problems.join("\n"))).context(corrupt_message(path))
+    }
+
     /// - Validates that no file currently exists at `path`
+    /// - Creates the directories above `path` that do not exist yet
     /// - Creates a new SQLite file at `path`
     /// - Initializes the database schema
     /// - Returns a constructed `Datastore` object for further operations
@@ -111,16 +195,15 @@ impl Db {
         if path.exists() {
             bail!("SQLite database already exists at {}", path.display());
         }
+        // A relative path with a single component has an empty parent, which is the current dir
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            utils::make_dir(parent).await?;
+        }
 
         // Read-write connection pool (creates the database)
-        let options = SqliteConnectOptions::from_str(&format!("sqlite:{}", path.display()))
-            .context("Failed to parse SQLite connection string")?
-            .create_if_missing(true)
-            // Enable foreign key constraints by default
-            .pragma("foreign_keys", "ON");
+        let options = rw_connect_options(path, true)?;
 
-        let pool = SqlitePoolOptions::new()
-            .max_connections(1)
+        let pool = pool_options(POOL_SIZE, CONNECTION_PRAGMAS)
             .connect_with(options)
             .await
             .context("Failed to create SQLite database")?;
@@ -130,19 +213,81 @@ impl Db {
             SqliteConnectOptions::from_str(&format!("sqlite:{}?mode=ro", path.display()))
                 .context("Failed to parse read-only SQLite connection string")?;
 
-        let ro_pool = SqlitePoolOptions::new()
-            .max_connections(1)
+        let ro_pool = pool_options(POOL_SIZE, READ_ONLY_CONNECTION_PRAGMAS)
             .connect_with(ro_options)
             .await
             .context("Failed to connect to read-only SQLite database")?;
 
-        let db = Self { pool, ro_pool };
+        let db = Self {
+            pool,
+            ro_pool,
+            dry_run: false,
+        };
         db.bootstrap().await?;
         db.migrate().await?;
 
         Ok(db)
     }
 
+    /// When `dry_run` is true, every change made through the returned `Db` is made in a database
+    /// transaction that is rolled back instead of committed. The changes are checked and applied as
+    /// usual, so errors such as a missing row or a foreign key violation are still reported, but
+    /// nothing is saved. Syncs are not recorded in the sync history.
+    pub(crate) fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Whether changes are rolled back instead of committed, see `with_dry_run`.
+    pub(crate) fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Commits `db_txn`, or rolls it back in dry-run mode.
+    async fn commit(&self, db_txn: sqlx::Transaction<'_, sqlx::Sqlite>) -> Res<()> {
+        if self.dry_run {
+            db_txn
+                .rollback()
+                .await
+                .context("Unable to roll back db transaction")?;
+        } else {
+            db_txn
+                .commit()
+                .await
+                .context("Unable to commit db transaction")?;
+        }
+        Ok(())
+    }
+
+    /// Closes both connection pools, waiting for their connections to finish and close. Any
+    /// further operation on this `Db`, or on any clone of it, returns an error.
+    pub(crate) async fn close(&self) {
+        self.pool.close().await;
+        self.ro_pool.close().await;
+    }
+
+    /// Rebuilds the database file, reclaiming the space left behind by deleted rows.
+    pub(crate) async fn vacuum(&self) -> Res<()> {
+        sqlx::query("VACUUM")
+            .execute(&self.pool)
+            .await
+            .context("Failed to vacuum the database")?;
+        Ok(())
+    }
+
+    /// Refreshes the statistics that SQLite's query planner uses to choose indexes.
+    pub(crate) async fn analyze(&self) -> Res<()> {
+        sqlx::query("ANALYZE")
+            .execute(&self.pool)
+            .await
+            .context("Failed to analyze the database")?;
+        sqlx::query("PRAGMA optimize")
+            .execute(&self.pool)
+            .await
+            .context("Failed to optimize the database")?;
+        Ok(())
+    }
+
     /// Returns the number of rows in the transactions table.
     pub(crate) async fn count_transactions(&self) -> Res<u64> {
         let row: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM transactions")
@@ -151,13 +296,250 @@ impl Db {
         Ok(row.0 as u64)
     }
 
+    /// Returns the ID and category of each transaction whose category is not in the categories
+    /// table. The foreign key on `category` prevents this, but rows written before foreign keys
+    /// were enforced can still have it.
+    pub(crate) async fn transactions_with_unknown_categories(&self) -> Res<Vec<(String, String)>> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            r#"SELECT t.transaction_id, t.category FROM transactions t
+               LEFT JOIN categories c ON c.category = t.category
+               WHERE t.category IS NOT NULL AND t.category != '' AND c.category IS NULL
+               ORDER BY t.original_order ASC NULLS LAST, t.transaction_id ASC"#,
+        )
+        .fetch_all(&self.ro_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Returns the ID, date and amount of each transaction as they are stored, without parsing the
+    /// date or the amount.
+    pub(crate) async fn raw_transaction_dates_and_amounts(
+        &self,
+    ) -> Res<Vec<(String, String, String)>> {
+        let rows: Vec<(String, String, String)> = sqlx::query_as(&format!(
+            "SELECT transaction_id, date, CAST(amount AS TEXT) FROM transactions \
+            ORDER BY {TRANSACTION_ORDER}"
+        ))
+        .fetch_all(&self.ro_pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Runs `sql` with foreign key enforcement turned off, to set up data that the schema would
+    /// otherwise reject.
+    #[cfg(test)]
+    pub(crate) async fn execute_without_foreign_keys(&self, sql: &str) -> Res<()> {
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query("PRAGMA foreign_keys = OFF")
+            .execute(&mut *conn)
+            .await?;
+        let result = sqlx::query(sql).execute(&mut *conn).await;
+        sqlx::query("PRAGMA foreign_keys = ON")
+            .execute(&mut *conn)
+            .await?;
+        result?;
+        Ok(())
+    }
+
+    /// Records the outcome of a `sync up` or `sync down` in the `sync_history` table, timestamped
+    /// with the current time. Nothing is recorded in dry-run mode.
+    pub(crate) async fn record_sync(
+        &self,
+        direction: UpDown,
+        counts: SyncCounts,
+        success: bool,
+        message: &str,
+    ) -> Res<()> {
+        if self.dry_run {
+            return Ok(());
+        }
+        let finished_at = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        sqlx::query(
+            "INSERT INTO sync_history \
+             (finished_at, direction, transactions, categories, autocats, balances, success, \
+             message) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(finished_at)
+        .bind(direction.to_string())
+        .bind(counts.transactions as i64)
+        .bind(counts.categories as i64)
+        .bind(counts.autocats as i64)
+        .bind(counts.balances as i64)
+        .bind(success)
+        .bind(message)
+        .execute(&self.pool)
+        .await
+        .context("Failed to record the sync in sync_history")?;
+        Ok(())
+    }
+
+    /// Returns the most recent `limit` rows of the `sync_history` table, newest first.
+    pub(crate) async fn sync_history(&self, limit: u32) -> Res<Vec<SyncRecord>> {
+        use sqlx::Row;
+
+        let rows = sqlx::query(
+            "SELECT id, finished_at, direction, transactions, categories, autocats, balances, \
+             success, message FROM sync_history ORDER BY id DESC LIMIT ?",
+        )
+        .bind(i64::from(limit))
+        .fetch_all(&self.ro_pool)
+        .await
+        .context("Failed to read sync_history")?;
+
+        rows.iter()
+            .map(|row| -> Res<SyncRecord> {
+                let direction: String = row.try_get("direction")?;
+                Ok(SyncRecord {
+                    id: row.try_get("id")?,
+                    finished_at: row.try_get("finished_at")?,
+                    direction: UpDown::from_str(&direction)?,
+                    counts: SyncCounts {
+                        transactions: row.try_get::<i64, _>("transactions")? as u64,
+                        categories: row.try_get::<i64, _>("categories")? as u64,
+                        autocats: row.try_get::<i64, _>("autocats")? as u64,
+                        balances: row.try_get::<i64, _>("balances")? as u64,
+                    },
+                    success: row.try_get("success")?,
+                    message: row.try_get("message")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Lists the transactions matching `filter`, in sheet order, skipping the first `offset` matches
+    /// and returning at most `limit` of them. Also returns the total number of transactions that
+    /// match `filter`, regardless of `limit` and `offset`, so callers can tell whether there are
+    /// more pages.
+    pub(crate) async fn list_transactions(
+        &self,
+        filter: &TransactionFilter,
+        limit: Option<u32>,
+        offset: u32,
+    ) -> Res<(Vec<Transaction>, u64)> {
+        let (where_clause, binds) = transaction_filter_sql(filter);
+
+        let count_sql = format!("SELECT COUNT(*) FROM transactions{where_clause}");
+        let mut count_query = sqlx::query_as::<_, (i64,)>(&count_sql);
+        for value in &binds {
+            count_query = count_query.bind(value);
+        }
+        let (total,) = count_query
+            .fetch_one(&self.ro_pool)
+            .await
+            .context("Failed to count transactions")?;
+
+        // SQLite requires a LIMIT in order to use OFFSET, -1 means no limit
+        let list_sql = format!(
+            "SELECT {TRANSACTION_COLUMNS} FROM transactions{where_clause} \
+             ORDER BY {TRANSACTION_ORDER} LIMIT ? OFFSET ?"
+        );
+        let mut list_query = sqlx::query(&list_sql);
+        for value in &binds {
+            list_query = list_query.bind(value);
+        }
+        let rows = list_query
+            .bind(limit.map(i64::from).unwrap_or(-1))
+            .bind(i64::from(offset))
+            .fetch_all(&self.ro_pool)
+            .await
+            .context("Failed to list transactions")?;
+
+        let transactions = rows
+            .iter()
+            .map(transaction_from_row)
+            .collect::<Res<Vec<_>>>()?;
+        Ok((transactions, total as u64))
+    }
+
+    /// Lists the transactions of the account identified by `account_id`, in sheet order.
+    #[cfg_attr(not(test), expect(dead_code))]
+    pub(crate) async fn list_transactions_by_account_id(
+        &self,
+        account_id: &str,
+    ) -> Res<Vec<Transaction>> {
+        let filter = TransactionFilter {
+            account_id: Some(account_id.to_string()),
+            ..Default::default()
+        };
+        let (transactions, _) = self.list_transactions(&filter, None, 0).await?;
+        Ok(transactions)
+    }
+
+    /// Lists the transactions, in sheet order, whose custom column `key` has a value containing
+    /// `value_contains` (case-insensitive). Custom columns are those not part of the standard
+    /// Tiller schema, which are stored in `other_fields`.
+    #[cfg_attr(not(test), expect(dead_code))]
+    pub(crate) async fn list_transactions_by_other_field(
+        &self,
+        key: &str,
+        value_contains: &str,
+    ) -> Res<Vec<Transaction>> {
+        let filter = TransactionFilter {
+            other_field: Some(key.to_string()),
+            other_field_contains: Some(value_contains.to_string()),
+            ..Default::default()
+        };
+        let (transactions, _) = self.list_transactions(&filter, None, 0).await?;
+        Ok(transactions)
+    }
+
+    /// Sums transaction amounts grouped by institution. Transactions with a blank institution are
+    /// grouped under [`UNKNOWN_INSTITUTION`] rather than being dropped.
+    ///
+    /// When `date_from` or `date_to` is given, only transactions dated within the (inclusive) range
+    /// are counted. Transactions whose date cannot be parsed are skipped in that case.
+    pub(crate) async fn institution_totals(
+        &self,
+        date_from: Option<NaiveDate>,
+        date_to: Option<NaiveDate>,
+    ) -> Res<BTreeMap<String, Amount>> {
+        use sqlx::Row;
+
+        let rows = sqlx::query("SELECT date, institution, amount FROM transactions")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to query transactions")?;
+
+        let mut groups: BTreeMap<String, Vec<Amount>> = BTreeMap::new();
+        for r in rows {
+            if date_from.is_some() || date_to.is_some() {
+                let date: String = r.get("date");
+                let Ok(date) = utils::parse_date(&date) else {
+                    continue;
+                };
+                if date_from.is_some_and(|from| date < from) || date_to.is_some_and(|to| date > to)
+                {
+                    continue;
+                }
+            }
+
+            let institution: String = r.get("institution");
+            let institution = if institution.trim().is_empty() {
+                UNKNOWN_INSTITUTION.to_string()
+            } else {
+                institution
+            };
+            groups
+                .entry(institution)
+                .or_default()
+                .push(amount_from_row(&r, "amount")?);
+        }
+
+        Ok(groups
+            .into_iter()
+            .map(|(institution, amounts)| (institution, amounts.iter().sum()))
+            .collect())
+    }
+
     /// Saves TillerData into the database.
+    /// - Categories: upsert, then delete removed once nothing refers to them
     /// - Transactions: upsert (insert new, update existing, delete removed)
-    /// - Categories: delete all, then insert all
     /// - AutoCat: delete all, then insert all
+    /// - Balances: delete all, then insert all
     ///
-    /// Note: Foreign key constraints are temporarily disabled during this operation
-    /// to allow the delete-all-then-insert pattern for categories and autocat.
+    /// Categories cannot be deleted and re-inserted like the other tabs. SQLite applies
+    /// `ON DELETE RESTRICT` as soon as a row is deleted, even when foreign key checks are deferred,
+    /// so deleting a category that a stored transaction still uses would fail.
     pub(crate) async fn save_tiller_data(&self, data: &TillerData) -> Res<()> {
         let mut db_txn = self
             .pool
@@ -176,15 +558,12 @@ impl Db {
         Self::save_tiller_data_inner(&mut db_txn, data).await?;
 
         // Commit - FK constraints are checked here. defer_foreign_keys auto-resets.
-        db_txn
-            .commit()
-            .await
-            .context("Unable to commit database transaction")?;
+        self.commit(db_txn).await?;
 
         Ok(())
     }
 
-    /// Inner implementation of save_tiller_data, called with FK constraints disabled.
+    /// Inner implementation of save_tiller_data, called with FK constraint checks deferred.
     ///
     /// Takes a mutable reference to a transaction. Each query uses `&mut **tx` to create
     /// a reborrow of the underlying connection, allowing the transaction to be reused.
@@ -194,6 +573,30 @@ impl Db {
     ) -> Res<()> {
         use sqlx::Row;
 
+        // Categories: upsert first, so that transactions and AutoCat rules can refer to them
+        let existing_categories: Vec<String> = sqlx::query("SELECT category FROM categories")
+            .fetch_all(&mut **tx)
+            .await?
+            .iter()
+            .map(|row| row.get("category"))
+            .collect();
+        for category in data.categories.data() {
+            Self::upsert_category_impl(&mut **tx, category).await?;
+        }
+        let missing = missing_categories(data);
+        for name in &missing {
+            warn!(
+                "Category '{name}' is used but missing from the {CATEGORIES} tab, perhaps it was \
+                renamed in the sheet. Adding it locally, it will be added to the {CATEGORIES} tab \
+                on the next sync up"
+            );
+            let placeholder = Category {
+                category: name.to_string(),
+                ..Default::default()
+            };
+            Self::upsert_category_impl(&mut **tx, &placeholder).await?;
+        }
+
         // Get existing transaction IDs for upsert logic
         let existing_ids: Vec<String> = sqlx::query("SELECT transaction_id FROM transactions")
             .fetch_all(&mut **tx)
@@ -219,23 +622,20 @@ impl Db {
             }
         }
 
-        // Upsert transactions
+        // Upsert transactions, reporting progress since there can be many thousands of them
         let existing_set: std::collections::HashSet<&str> =
             existing_ids.iter().map(|s| s.as_str()).collect();
-        for transaction in data.transactions.data() {
+        let total = data.transactions.data().len();
+        for (ix, transaction) in data.transactions.data().iter().enumerate() {
             if existing_set.contains(transaction.transaction_id.as_str()) {
                 Self::update_transaction_impl(&mut **tx, transaction).await?;
             } else {
                 Self::insert_transaction_impl(&mut **tx, transaction).await?;
             }
-        }
-
-        // Categories: delete all, then insert all
-        sqlx::query("DELETE FROM categories")
-            .execute(&mut **tx)
-            .await?;
-        for category in data.categories.data() {
-            Self::insert_category_impl(&mut **tx, category).await?;
+            let saved = ix + 1;
+            if saved % SAVE_PROGRESS_INTERVAL == 0 || saved == total {
+                info!("Saving to the database ({saved}/{total} transactions)");
+            }
         }
 
         // AutoCat: delete all, then insert all
@@ -246,6 +646,31 @@ impl Db {
             Self::insert_autocat_impl(&mut **tx, autocat).await?;
         }
 
+        // Categories: delete removed, now that no transaction or AutoCat rule refers to them
+        let incoming_categories: HashSet<&str> = data
+            .categories
+            .data()
+            .iter()
+            .map(|c| c.category.as_str())
+            .chain(missing.iter().copied())
+            .collect();
+        for name in &existing_categories {
+            if !incoming_categories.contains(name.as_str()) {
+                sqlx::query("DELETE FROM categories WHERE category = ?")
+                    .bind(name)
+                    .execute(&mut **tx)
+                    .await?;
+            }
+        }
+
+        // Balances: delete all, then insert all
+        sqlx::query("DELETE FROM balances")
+            .execute(&mut **tx)
+            .await?;
+        for balance in data.balances.data() {
+            Self::insert_balance_impl(&mut **tx, balance).await?;
+        }
+
         // Save formulas from all sheets
         Self::save_formulas_impl(tx, data).await?;
 
@@ -279,76 +704,42 @@ impl Db {
 
     /// Retrieves all data from the database as TillerData.
     pub(crate) async fn get_tiller_data(&self) -> Res<TillerData> {
-        use crate::model::{AutoCats, Categories, Transactions};
-        use sqlx::Row;
+        Ok(TillerData {
+            transactions: self.get_transactions().await?,
+            categories: self.get_categories().await?,
+            auto_cats: self.get_autocats().await?,
+            balances: self.get_balances().await?,
+        })
+    }
 
-        // Query all transactions
-        let rows = sqlx::query(
-            r#"SELECT
-                transaction_id, date, description, amount, account, account_number,
-                institution, month, week, full_description, account_id, check_number,
-                date_added, merchant_name, category_hint, category, note, tags,
-                categorized_date, statement, metadata, other_fields, original_order
-            FROM transactions ORDER BY original_order ASC NULLS LAST, transaction_id ASC"#,
-        )
+    /// Retrieves the transactions, in sheet order, along with their formulas and mapping.
+    pub(crate) async fn get_transactions(&self) -> Res<Transactions> {
+        let rows = sqlx::query(&format!(
+            "SELECT {TRANSACTION_COLUMNS} FROM transactions ORDER BY {TRANSACTION_ORDER}"
+        ))
         .fetch_all(&self.pool)
         .await?;
 
         let mut transactions_data = Vec::new();
         for r in rows {
-            let other_fields_json: Option<String> = r.get("other_fields");
-            let other_fields: BTreeMap<String, String> = match other_fields_json {
-                Some(json) => serde_json::from_str(&json)?,
-                None => BTreeMap::new(),
-            };
-
-            let amount_val: f64 = r
-                .try_get::<f64, _>("amount")
-                .or_else(|_| r.try_get::<i64, _>("amount").map(|i| i as f64))
-                .unwrap_or(0.0);
+            transactions_data.push(transaction_from_row(&r)?);
+        }
 
-            transactions_data.push(Transaction {
-                transaction_id: r.get("transaction_id"),
-                date: r.get("date"),
-                description: r.get("description"),
-                amount: Amount::new(Decimal::from_f64(amount_val).unwrap_or_default()),
-                account: r.get("account"),
-                account_number: r.get("account_number"),
-                institution: r.get("institution"),
-                month: r.get::<Option<String>, _>("month").unwrap_or_default(),
-                week: r.get::<Option<String>, _>("week").unwrap_or_default(),
-                full_description: r
-                    .get::<Option<String>, _>("full_description")
-                    .unwrap_or_default(),
-                account_id: r.get("account_id"),
-                check_number: r
-                    .get::<Option<String>, _>("check_number")
-                    .unwrap_or_default(),
-                date_added: r.get::<Option<String>, _>("date_added").unwrap_or_default(),
-                merchant_name: r
-                    .get::<Option<String>, _>("merchant_name")
-                    .unwrap_or_default(),
-                category_hint: r
-                    .get::<Option<String>, _>("category_hint")
-                    .unwrap_or_default(),
-                category: r.get::<Option<String>, _>("category").unwrap_or_default(),
-                note: r.get::<Option<String>, _>("note").unwrap_or_default(),
-                tags: r.get::<Option<String>, _>("tags").unwrap_or_default(),
-                categorized_date: r
-                    .get::<Option<String>, _>("categorized_date")
-                    .unwrap_or_default(),
-                statement: r.get::<Option<String>, _>("statement").unwrap_or_default(),
-                metadata: r.get::<Option<String>, _>("metadata").unwrap_or_default(),
-                other_fields,
-                original_order: r.get::<Option<u64>, _>("original_order"),
-                ..Default::default()
-            });
+        let formulas = self.load_formulas(TRANSACTIONS).await?;
+        match self.load_sheet_metadata(TRANSACTIONS).await? {
+            Some(mapping) => Transactions::new(transactions_data, formulas, mapping),
+            None => Transactions::from_data(transactions_data, formulas),
         }
+    }
 
-        // Query all categories
-        let rows = sqlx::query(
-            "SELECT category, category_group, type, hide_from_reports, other_fields, original_order FROM categories ORDER BY original_order ASC NULLS LAST, category ASC",
-        )
+    /// Retrieves the categories, in sheet order, along with their formulas and mapping.
+    pub(crate) async fn get_categories(&self) -> Res<Categories> {
+        use sqlx::Row;
+
+        let rows = sqlx::query(&format!(
+            "SELECT category, category_group, type, hide_from_reports, other_fields, \
+            original_order FROM categories ORDER BY {CATEGORY_ORDER}"
+        ))
         .fetch_all(&self.pool)
         .await?;
 
@@ -374,18 +765,61 @@ impl Db {
             });
         }
 
-        // Query all autocat rules
+        let formulas = self.load_formulas(CATEGORIES).await?;
+        let mapping = self
+            .load_sheet_metadata(CATEGORIES)
+            .await?
+            .unwrap_or_default();
+        Categories::new(categories_data, formulas, mapping)
+    }
+
+    /// Retrieves the AutoCat rules, in sheet order, along with their formulas and mapping.
+    pub(crate) async fn get_autocats(&self) -> Res<AutoCats> {
+        let autocat_data = self
+            .list_autocats()
+            .await?
+            .into_iter()
+            .map(|autocat| autocat.row)
+            .collect();
+
+        let formulas = self.load_formulas(AUTO_CAT).await?;
+        let mapping = self
+            .load_sheet_metadata(AUTO_CAT)
+            .await?
+            .unwrap_or_default();
+        AutoCats::new(autocat_data, formulas, mapping)
+    }
+
+    /// Lists the AutoCat rules with their IDs in the order Tiller processes them, which is their
+    /// order in the sheet followed by the rules added locally.
+    pub(crate) async fn list_autocats(&self) -> Res<Vec<_Row<AutoCat>>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {AUTOCAT_COLUMNS} FROM autocat ORDER BY {AUTOCAT_ORDER}"
+        ))
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to list autocats")?;
+
+        rows.iter().map(autocat_from_row).collect()
+    }
+
+    /// Retrieves the balances, in sheet order, along with their formulas and mapping. Balances are
+    /// optional; there is no mapping unless they have been synced down.
+    async fn get_balances(&self) -> Res<Balances> {
+        use sqlx::Row;
+
+        let Some(mapping) = self.load_sheet_metadata(BALANCES).await? else {
+            return Ok(Balances::default());
+        };
+
         let rows = sqlx::query(
-            r#"SELECT id, category, description, description_contains, account_contains,
-                institution_contains, amount_min, amount_max, amount_equals,
-                description_equals, description_full, full_description_contains,
-                amount_contains, other_fields, original_order
-            FROM autocat ORDER BY original_order ASC NULLS LAST, id ASC"#,
+            r#"SELECT account, date, balance, account_id, other_fields, original_order
+            FROM balances ORDER BY original_order ASC NULLS LAST, id ASC"#,
         )
         .fetch_all(&self.pool)
         .await?;
 
-        let mut autocat_data = Vec::new();
+        let mut balances_data = Vec::new();
         for r in rows {
             let other_fields_json: Option<String> = r.get("other_fields");
             let other_fields: BTreeMap<String, String> = match other_fields_json {
@@ -393,102 +827,54 @@ impl Db {
                 None => BTreeMap::new(),
             };
 
-            let amount_min: Option<String> = r.get("amount_min");
-            let amount_max: Option<String> = r.get("amount_max");
-            let amount_equals: Option<String> = r.get("amount_equals");
-
-            autocat_data.push(AutoCat {
-                category: r.get::<Option<String>, _>("category").unwrap_or_default(),
-                description: r
-                    .get::<Option<String>, _>("description")
-                    .unwrap_or_default(),
-                description_contains: r
-                    .get::<Option<String>, _>("description_contains")
-                    .unwrap_or_default(),
-                account_contains: r
-                    .get::<Option<String>, _>("account_contains")
-                    .unwrap_or_default(),
-                institution_contains: r
-                    .get::<Option<String>, _>("institution_contains")
-                    .unwrap_or_default(),
-                amount_min: amount_min.and_then(|v| v.parse().ok()),
-                amount_max: amount_max.and_then(|v| v.parse().ok()),
-                amount_equals: amount_equals.and_then(|v| v.parse().ok()),
-                description_equals: r
-                    .get::<Option<String>, _>("description_equals")
-                    .unwrap_or_default(),
-                description_full: r
-                    .get::<Option<String>, _>("description_full")
-                    .unwrap_or_default(),
-                full_description_contains: r
-                    .get::<Option<String>, _>("full_description_contains")
-                    .unwrap_or_default(),
-                amount_contains: r
-                    .get::<Option<String>, _>("amount_contains")
-                    .unwrap_or_default(),
+            balances_data.push(Balance {
+                account: r.get("account"),
+                date: r.get("date"),
+                balance: amount_from_row(&r, "balance")?,
+                account_id: r.get("account_id"),
                 other_fields,
                 original_order: r.get::<Option<u64>, _>("original_order"),
             });
         }
 
-        // Query formulas for all sheets
-        let formula_rows: Vec<(String, i64, i64, String)> = sqlx::query_as(
-            "SELECT sheet, row, col, formula FROM formulas ORDER BY sheet, row, col",
-        )
-        .fetch_all(&self.pool)
-        .await?;
-
-        // Build formula maps for each sheet
-        use crate::model::RowCol;
-        use std::collections::BTreeMap;
-
-        let mut txn_formulas: BTreeMap<RowCol, String> = BTreeMap::new();
-        let mut cat_formulas: BTreeMap<RowCol, String> = BTreeMap::new();
-        let mut autocat_formulas: BTreeMap<RowCol, String> = BTreeMap::new();
+        let formulas = self.load_formulas(BALANCES).await?;
+        Balances::new(balances_data, formulas, mapping)
+    }
 
-        for (sheet, row, col, formula) in formula_rows {
-            let key = RowCol::new(row as usize, col as usize);
-            match sheet.as_str() {
-                s if s == TRANSACTIONS => {
-                    txn_formulas.insert(key, formula);
-                }
-                s if s == CATEGORIES => {
-                    cat_formulas.insert(key, formula);
-                }
-                s if s == AUTO_CAT => {
-                    autocat_formulas.insert(key, formula);
-                }
-                _ => {} // Ignore unknown sheets
-            }
-        }
+    /// Loads the formulas stored for `sheet`, keyed by their position in the sheet.
+    async fn load_formulas(&self, sheet: &str) -> Res<BTreeMap<RowCol, String>> {
+        Self::load_formulas_impl(&self.pool, sheet).await
+    }
 
-        // Load mappings for each sheet
-        let txn_mapping = self
-            .load_sheet_metadata(TRANSACTIONS)
-            .await?
-            .unwrap_or_default();
-        let cat_mapping = self
-            .load_sheet_metadata(CATEGORIES)
-            .await?
-            .unwrap_or_default();
-        let autocat_mapping = self
-            .load_sheet_metadata(AUTO_CAT)
-            .await?
-            .unwrap_or_default();
+    async fn load_formulas_impl<'e, E>(ex: E, sheet: &str) -> Res<BTreeMap<RowCol, String>>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+    {
+        let rows: Vec<(i64, i64, String)> = sqlx::query_as(
+            "SELECT row, col, formula FROM formulas WHERE sheet = ? ORDER BY row, col",
+        )
+        .bind(sheet)
+        .fetch_all(ex)
+        .await?;
 
-        Ok(TillerData {
-            transactions: Transactions::new(transactions_data, txn_formulas, txn_mapping)?,
-            categories: Categories::new(categories_data, cat_formulas, cat_mapping)?,
-            auto_cats: AutoCats::new(autocat_data, autocat_formulas, autocat_mapping)?,
-        })
+        Ok(rows
+            .into_iter()
+            .map(|(row, col, formula)| (RowCol::new(row as usize, col as usize), formula))
+            .collect())
     }
 
     /// Inserts a new transaction into the database.
     pub(crate) async fn insert_transaction(&self, transaction: &Transaction) -> Res<()> {
-        Self::insert_transaction_impl(&self.pool, transaction).await
-    }
-
-    /// Updates multiple transactions atomically.
+        let mut db_txn = self
+            .pool
+            .begin()
+            .await
+            .context("Unable to begin db transaction")?;
+        Self::insert_transaction_impl(&mut *db_txn, transaction).await?;
+        self.commit(db_txn).await
+    }
+
+    /// Updates multiple transactions atomically.
     ///
     /// This operation is all-or-nothing: either all specified transactions are updated, or none
     /// are. If any transaction ID is not found, the entire operation is rolled back.
@@ -521,14 +907,126 @@ impl Db {
             results.push(txn);
         }
 
-        db_txn
-            .commit()
-            .await
-            .context("Unable to commit db transaction")?;
+        self.commit(db_txn).await?;
 
         Ok(results)
     }
 
+    /// Replaces the fields of the existing transaction that has the same ID as `transaction`.
+    pub(crate) async fn update_transaction(&self, transaction: &Transaction) -> Res<()> {
+        let mut db_txn = self
+            .pool
+            .begin()
+            .await
+            .context("Unable to begin db transaction")?;
+        Self::update_transaction_impl(&mut *db_txn, transaction).await?;
+        self.commit(db_txn).await
+    }
+
+    /// For each `(merged, remove)` pair, replaces the fields of the existing transaction that has
+    /// the same ID as `merged` and deletes the transaction with the ID `remove`, all in a single
+    /// database transaction.
+    pub(crate) async fn merge_transactions(&self, merges: &[(Transaction, String)]) -> Res<()> {
+        let mut db_txn = self
+            .pool
+            .begin()
+            .await
+            .context("Unable to begin db transaction")?;
+        for (merged, remove) in merges {
+            Self::update_transaction_impl(&mut *db_txn, merged).await?;
+            let result = sqlx::query("DELETE FROM transactions WHERE transaction_id = ?")
+                .bind(remove)
+                .execute(&mut *db_txn)
+                .await
+                .context("Failed to delete transaction")?;
+            if result.rows_affected() == 0 {
+                bail!("Transaction not found: {}", remove);
+            }
+        }
+        self.commit(db_txn).await
+    }
+
+    /// Moves every transaction whose category is `from` to the category `to` in a single database
+    /// transaction. Returns the number of transactions affected.
+    ///
+    /// When `dry_run` is true, the number of transactions that would be affected is returned and
+    /// nothing is changed.
+    ///
+    /// Fails if the `to` category does not exist.
+    pub(crate) async fn recategorize_transactions(
+        &self,
+        from: &str,
+        to: &str,
+        dry_run: bool,
+    ) -> Res<u64> {
+        let mut db_txn = self
+            .pool
+            .begin()
+            .await
+            .context("Unable to begin db transaction")?;
+
+        if Self::get_category_impl(&mut *db_txn, to).await?.is_none() {
+            bail!("Category not found: {}", to);
+        }
+
+        let count = if dry_run {
+            let row: (i64,) =
+                sqlx::query_as("SELECT COUNT(*) FROM transactions WHERE category = ?")
+                    .bind(from)
+                    .fetch_one(&mut *db_txn)
+                    .await
+                    .context("Failed to count transactions")?;
+            row.0 as u64
+        } else {
+            sqlx::query("UPDATE transactions SET category = ? WHERE category = ?")
+                .bind(to)
+                .bind(from)
+                .execute(&mut *db_txn)
+                .await
+                .context("Failed to recategorize transactions")?
+                .rows_affected()
+        };
+
+        self.commit(db_txn).await?;
+
+        Ok(count)
+    }
+
+    /// Sets the category of each uncategorized transaction in `assignments`, given as
+    /// `(transaction_id, category)` pairs, and its categorized date to `categorized_date`, in a
+    /// single database transaction. Transactions that already have a category are left alone.
+    /// Returns the number of transactions changed.
+    pub(crate) async fn assign_categories(
+        &self,
+        assignments: &[(String, String)],
+        categorized_date: &str,
+    ) -> Res<u64> {
+        let mut db_txn = self
+            .pool
+            .begin()
+            .await
+            .context("Unable to begin db transaction")?;
+
+        let mut count = 0;
+        for (id, category) in assignments {
+            count += sqlx::query(
+                "UPDATE transactions SET category = ?, categorized_date = ? \
+                 WHERE transaction_id = ? AND (category IS NULL OR category = '')",
+            )
+            .bind(category)
+            .bind(categorized_date)
+            .bind(id)
+            .execute(&mut *db_txn)
+            .await
+            .context("Failed to categorize transaction")?
+            .rows_affected();
+        }
+
+        self.commit(db_txn).await?;
+
+        Ok(count)
+    }
+
     /// Updates an existing transaction using the provided executor.
     async fn update_transaction_impl<'e, E>(executor: E, txn: &Transaction) -> Res<()>
     where
@@ -553,12 +1051,12 @@ impl Db {
                 institution = ?, month = ?, week = ?, full_description = ?, account_id = ?,
                 check_number = ?, date_added = ?, merchant_name = ?, category_hint = ?,
                 category = ?, note = ?, tags = ?, categorized_date = ?, statement = ?,
-                metadata = ?, other_fields = ?, original_order = ?
+                metadata = ?, no_name = ?, other_fields = ?, original_order = ?
             WHERE transaction_id = ?"#,
         )
         .bind(&txn.date)
         .bind(&txn.description)
-        .bind(txn.amount.value().to_f64().unwrap_or(0.0))
+        .bind(amount_to_sql(&txn.amount))
         .bind(&txn.account)
         .bind(&txn.account_number)
         .bind(&txn.institution)
@@ -576,6 +1074,7 @@ impl Db {
         .bind(&txn.categorized_date)
         .bind(&txn.statement)
         .bind(&txn.metadata)
+        .bind(&txn.no_name)
         .bind(&other_fields_json)
         .bind(txn.original_order.map(|i| i as i64))
         .bind(&txn.transaction_id)
@@ -597,79 +1096,26 @@ impl Db {
     where
         E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
     {
-        use sqlx::Row;
-
-        let row = sqlx::query(
-            r#"SELECT
-                transaction_id, date, description, amount, account, account_number,
-                institution, month, week, full_description, account_id, check_number,
-                date_added, merchant_name, category_hint, category, note, tags,
-                categorized_date, statement, metadata, other_fields
-            FROM transactions WHERE transaction_id = ?"#,
-        )
+        let row = sqlx::query(&format!(
+            "SELECT {TRANSACTION_COLUMNS} FROM transactions WHERE transaction_id = ?"
+        ))
         .bind(id)
         .fetch_optional(executor)
         .await
         .context("Failed to get transaction")?;
 
-        match row {
-            None => Ok(None),
-            Some(r) => {
-                let other_fields_json: Option<String> = r.get("other_fields");
-                let other_fields: BTreeMap<String, String> = match other_fields_json {
-                    Some(json) => serde_json::from_str(&json)?,
-                    None => BTreeMap::new(),
-                };
-
-                // SQLite may store numeric values as INTEGER or REAL depending on value
-                // Use try_get to handle both cases
-                let amount_val: f64 = r
-                    .try_get::<f64, _>("amount")
-                    .or_else(|_| r.try_get::<i64, _>("amount").map(|i| i as f64))
-                    .unwrap_or(0.0);
-
-                Ok(Some(Transaction {
-                    transaction_id: r.get("transaction_id"),
-                    date: r.get("date"),
-                    description: r.get("description"),
-                    amount: Amount::new(Decimal::from_f64(amount_val).unwrap_or_default()),
-                    account: r.get("account"),
-                    account_number: r.get("account_number"),
-                    institution: r.get("institution"),
-                    month: r.get::<Option<String>, _>("month").unwrap_or_default(),
-                    week: r.get::<Option<String>, _>("week").unwrap_or_default(),
-                    full_description: r
-                        .get::<Option<String>, _>("full_description")
-                        .unwrap_or_default(),
-                    account_id: r.get("account_id"),
-                    check_number: r
-                        .get::<Option<String>, _>("check_number")
-                        .unwrap_or_default(),
-                    date_added: r.get::<Option<String>, _>("date_added").unwrap_or_default(),
-                    merchant_name: r
-                        .get::<Option<String>, _>("merchant_name")
-                        .unwrap_or_default(),
-                    category_hint: r
-                        .get::<Option<String>, _>("category_hint")
-                        .unwrap_or_default(),
-                    category: r.get::<Option<String>, _>("category").unwrap_or_default(),
-                    note: r.get::<Option<String>, _>("note").unwrap_or_default(),
-                    tags: r.get::<Option<String>, _>("tags").unwrap_or_default(),
-                    categorized_date: r
-                        .get::<Option<String>, _>("categorized_date")
-                        .unwrap_or_default(),
-                    statement: r.get::<Option<String>, _>("statement").unwrap_or_default(),
-                    metadata: r.get::<Option<String>, _>("metadata").unwrap_or_default(),
-                    other_fields,
-                    ..Default::default()
-                }))
-            }
-        }
+        row.map(|r| transaction_from_row(&r)).transpose()
     }
 
     /// Inserts a new category into the database. Returns the category name (primary key).
     pub(crate) async fn insert_category(&self, category: &Category) -> Res<String> {
-        Self::insert_category_impl(&self.pool, category).await?;
+        let mut db_txn = self
+            .pool
+            .begin()
+            .await
+            .context("Unable to begin db transaction")?;
+        Self::insert_category_impl(&mut *db_txn, category).await?;
+        self.commit(db_txn).await?;
         Ok(category.category.clone())
     }
 
@@ -680,6 +1126,11 @@ impl Db {
     ///
     /// Due to `ON UPDATE CASCADE` foreign key constraints, renaming a category automatically
     /// updates all references in transactions and autocat.
+    ///
+    /// Renaming a category to the name of another existing category is an error unless
+    /// `args.merge()` is set. Then the transactions and autocat rules of the renamed category are
+    /// moved to the existing one, the other updates are applied to it, and the renamed category is
+    /// deleted.
     pub(crate) async fn update_categories(&self, args: UpdateCategoriesArgs) -> Res<Vec<Category>> {
         let mut db_txn = self
             .pool
@@ -703,10 +1154,30 @@ impl Db {
                 .with_context(|| format!("Category '{name}' not found"))?;
 
             // Track original name for update (in case of rename)
-            let old_name = category.category.clone();
+            let mut old_name = category.category.clone();
 
             category.merge_updates(args.updates().clone());
 
+            if category.category != old_name {
+                if let Some(mut target) =
+                    Self::get_category_impl(&mut *db_txn, &category.category).await?
+                {
+                    if !args.merge() {
+                        bail!(
+                            "Cannot rename category '{old_name}' to '{}' because the target \
+                            category already exists; use merge to move the transactions and \
+                            AutoCat rules of '{old_name}' into it",
+                            category.category
+                        );
+                    }
+                    Self::merge_category_impl(&mut db_txn, &old_name, &target.category).await?;
+                    // The renamed category is gone, so the updates go to the one it merged into
+                    old_name = target.category.clone();
+                    target.merge_updates(args.updates().clone());
+                    category = target;
+                }
+            }
+
             Self::update_category_impl(&mut *db_txn, &old_name, &category).await?;
 
             // Re-fetch to get the updated category (especially if renamed)
@@ -719,14 +1190,67 @@ impl Db {
             results.push(updated);
         }
 
-        db_txn
-            .commit()
-            .await
-            .context("Unable to commit db transaction")?;
+        self.commit(db_txn).await?;
 
         Ok(results)
     }
 
+    /// Moves the transactions and autocat rules of each category in `from` to the category `into`
+    /// and deletes the `from` categories, all in a single database transaction. Every category
+    /// must exist. Returns the number of transactions and autocat rules that were moved.
+    pub(crate) async fn merge_categories(&self, from: &[String], into: &str) -> Res<(u64, u64)> {
+        let mut db_txn = self
+            .pool
+            .begin()
+            .await
+            .context("Unable to begin db transaction")?;
+
+        for name in from.iter().map(String::as_str).chain([into]) {
+            if Self::get_category_impl(&mut *db_txn, name).await?.is_none() {
+                bail!("Category not found: {}", name);
+            }
+        }
+
+        let (mut transactions, mut autocats) = (0, 0);
+        for name in from {
+            let (t, a) = Self::merge_category_impl(&mut db_txn, name, into).await?;
+            transactions += t;
+            autocats += a;
+        }
+
+        self.commit(db_txn).await?;
+
+        Ok((transactions, autocats))
+    }
+
+    /// Moves the transactions and autocat rules of category `from` to category `into`, then
+    /// deletes `from`. Both categories must exist. Returns the number of transactions and autocat
+    /// rules that were moved.
+    async fn merge_category_impl(
+        tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+        from: &str,
+        into: &str,
+    ) -> Res<(u64, u64)> {
+        let mut moved = [0; 2];
+        for (count, table) in moved.iter_mut().zip(["transactions", "autocat"]) {
+            *count = sqlx::query(&format!(
+                "UPDATE {table} SET category = ? WHERE category = ?"
+            ))
+            .bind(into)
+            .bind(from)
+            .execute(&mut **tx)
+            .await
+            .with_context(|| format!("Failed to move the {table} of category '{from}'"))?
+            .rows_affected();
+        }
+        sqlx::query("DELETE FROM categories WHERE category = ?")
+            .bind(from)
+            .execute(&mut **tx)
+            .await
+            .with_context(|| format!("Failed to delete category '{from}'"))?;
+        Ok((moved[0], moved[1]))
+    }
+
     /// Updates a category using the provided executor.
     async fn update_category_impl<'e, E>(
         executor: E,
@@ -839,17 +1363,64 @@ impl Db {
             deleted.push(name.to_string());
         }
 
-        db_txn
-            .commit()
-            .await
-            .context("Unable to commit db transaction")?;
+        self.commit(db_txn).await?;
 
         Ok(deleted)
     }
 
     /// Inserts a new autocat rule into the database. Returns the primary key ID.
     pub(crate) async fn insert_autocat(&self, autocat: &AutoCat) -> Res<u64> {
-        Self::insert_autocat_impl(&self.pool, autocat).await
+        let mut db_txn = self
+            .pool
+            .begin()
+            .await
+            .context("Unable to begin db transaction")?;
+        let id = Self::insert_autocat_impl(&mut *db_txn, autocat).await?;
+        self.commit(db_txn).await?;
+        Ok(id)
+    }
+
+    /// Returns the AutoCat rule with the lowest ID that has the same category, overrides and
+    /// filter criteria as `rule`, if there is one. Custom columns and the row order are not
+    /// compared.
+    pub(crate) async fn find_autocat_by_criteria(
+        &self,
+        rule: &AutoCat,
+    ) -> Res<Option<_Row<AutoCat>>> {
+        // Blank text may be stored as NULL or as an empty string, so both are treated the same
+        let row: Option<(i64,)> = sqlx::query_as(
+            r#"SELECT id FROM autocat
+            WHERE COALESCE(category, '') = ? AND COALESCE(description, '') = ?
+                AND COALESCE(description_contains, '') = ?
+                AND COALESCE(account_contains, '') = ?
+                AND COALESCE(institution_contains, '') = ?
+                AND amount_min IS ? AND amount_max IS ? AND amount_equals IS ?
+                AND COALESCE(description_equals, '') = ?
+                AND COALESCE(description_full, '') = ?
+                AND COALESCE(full_description_contains, '') = ?
+                AND COALESCE(amount_contains, '') = ?
+            ORDER BY id ASC LIMIT 1"#,
+        )
+        .bind(&rule.category)
+        .bind(&rule.description)
+        .bind(&rule.description_contains)
+        .bind(&rule.account_contains)
+        .bind(&rule.institution_contains)
+        .bind(rule.amount_min.as_ref().map(amount_to_sql))
+        .bind(rule.amount_max.as_ref().map(amount_to_sql))
+        .bind(rule.amount_equals.as_ref().map(amount_to_sql))
+        .bind(&rule.description_equals)
+        .bind(&rule.description_full)
+        .bind(&rule.full_description_contains)
+        .bind(&rule.amount_contains)
+        .fetch_optional(&self.ro_pool)
+        .await
+        .context("Failed to find autocat")?;
+
+        match row {
+            None => Ok(None),
+            Some((id,)) => Self::get_autocat_impl(&self.ro_pool, &id.to_string()).await,
+        }
     }
 
     /// Updates one or more autocat rules atomically.
@@ -893,10 +1464,7 @@ impl Db {
             results.push(updated);
         }
 
-        db_txn
-            .commit()
-            .await
-            .context("Unable to commit db transaction")?;
+        self.commit(db_txn).await?;
 
         Ok(results)
     }
@@ -932,27 +1500,9 @@ impl Db {
         .bind(&autocat.row.description_contains)
         .bind(&autocat.row.account_contains)
         .bind(&autocat.row.institution_contains)
-        .bind(
-            autocat
-                .row
-                .amount_min
-                .as_ref()
-                .map(|a| a.value().to_f64().unwrap_or(0.0)),
-        )
-        .bind(
-            autocat
-                .row
-                .amount_max
-                .as_ref()
-                .map(|a| a.value().to_f64().unwrap_or(0.0)),
-        )
-        .bind(
-            autocat
-                .row
-                .amount_equals
-                .as_ref()
-                .map(|a| a.value().to_f64().unwrap_or(0.0)),
-        )
+        .bind(autocat.row.amount_min.as_ref().map(amount_to_sql))
+        .bind(autocat.row.amount_max.as_ref().map(amount_to_sql))
+        .bind(autocat.row.amount_equals.as_ref().map(amount_to_sql))
         .bind(&autocat.row.description_equals)
         .bind(&autocat.row.description_full)
         .bind(&autocat.row.full_description_contains)
@@ -976,72 +1526,94 @@ impl Db {
     where
         E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
     {
-        use sqlx::Row;
-
         let id_num: i64 = id.parse().context("Invalid autocat ID")?;
 
-        let row = sqlx::query(
-            r#"SELECT id, category, description, description_contains, account_contains,
-                institution_contains, amount_min, amount_max, amount_equals,
-                description_equals, description_full, full_description_contains,
-                amount_contains, other_fields, original_order
-            FROM autocat WHERE id = ?"#,
-        )
+        let row = sqlx::query(&format!(
+            "SELECT {AUTOCAT_COLUMNS} FROM autocat WHERE id = ?"
+        ))
         .bind(id_num)
         .fetch_optional(executor)
         .await
         .context("Failed to get autocat")?;
 
-        match row {
-            None => Ok(None),
-            Some(r) => {
-                let other_fields_json: Option<String> = r.get("other_fields");
-                let other_fields: BTreeMap<String, String> = match other_fields_json {
-                    Some(json) => serde_json::from_str(&json)?,
-                    None => BTreeMap::new(),
-                };
+        row.map(|r| autocat_from_row(&r)).transpose()
+    }
 
-                let amount_min: Option<String> = r.get("amount_min");
-                let amount_max: Option<String> = r.get("amount_max");
-                let amount_equals: Option<String> = r.get("amount_equals");
-
-                Ok(Some(_Row {
-                    id: r.get::<i64, _>("id") as u64,
-                    row: AutoCat {
-                        category: r.get::<Option<String>, _>("category").unwrap_or_default(),
-                        description: r
-                            .get::<Option<String>, _>("description")
-                            .unwrap_or_default(),
-                        description_contains: r
-                            .get::<Option<String>, _>("description_contains")
-                            .unwrap_or_default(),
-                        account_contains: r
-                            .get::<Option<String>, _>("account_contains")
-                            .unwrap_or_default(),
-                        institution_contains: r
-                            .get::<Option<String>, _>("institution_contains")
-                            .unwrap_or_default(),
-                        amount_min: amount_min.and_then(|v| v.parse().ok()),
-                        amount_max: amount_max.and_then(|v| v.parse().ok()),
-                        amount_equals: amount_equals.and_then(|v| v.parse().ok()),
-                        description_equals: r
-                            .get::<Option<String>, _>("description_equals")
-                            .unwrap_or_default(),
-                        description_full: r
-                            .get::<Option<String>, _>("description_full")
-                            .unwrap_or_default(),
-                        full_description_contains: r
-                            .get::<Option<String>, _>("full_description_contains")
-                            .unwrap_or_default(),
-                        amount_contains: r
-                            .get::<Option<String>, _>("amount_contains")
-                            .unwrap_or_default(),
-                        other_fields,
-                        original_order: r.get::<Option<u64>, _>("original_order"),
-                    },
-                }))
-            }
+    /// Moves the AutoCat rule `id` to `position` (0-based) in the processing order, shifting the
+    /// rules in between by one. Every rule's `original_order` is renumbered to its new position and
+    /// the AutoCat formulas move with their rows. Returns the rules in their new order.
+    pub(crate) async fn move_autocat(&self, id: &str, position: usize) -> Res<Vec<_Row<AutoCat>>> {
+        let id_num: u64 = id.parse().context("Invalid autocat ID")?;
+
+        let mut db_txn = self
+            .pool
+            .begin()
+            .await
+            .context("Unable to begin db transaction")?;
+
+        let mut rules = sqlx::query(&format!(
+            "SELECT {AUTOCAT_COLUMNS} FROM autocat ORDER BY {AUTOCAT_ORDER}"
+        ))
+        .fetch_all(&mut *db_txn)
+        .await
+        .context("Failed to list autocats")?
+        .iter()
+        .map(autocat_from_row)
+        .collect::<Res<Vec<_>>>()?;
+
+        let Some(from) = rules.iter().position(|r| r.id == id_num) else {
+            bail!("AutoCat rule not found: {id}");
+        };
+        if position >= rules.len() {
+            bail!(
+                "Position {position} is out of range, there are {} AutoCat rules so the last \
+                position is {}",
+                rules.len(),
+                rules.len() - 1
+            );
+        }
+        let rule = rules.remove(from);
+        rules.insert(position, rule);
+
+        // Formulas are stored by row, so they follow the rules to their new rows. Formulas in rows
+        // whose rule has been deleted are dropped.
+        let old_to_new: HashMap<usize, usize> = rules
+            .iter()
+            .enumerate()
+            .filter_map(|(new, r)| r.row.original_order.map(|old| (old as usize, new)))
+            .collect();
+        // Only the formulas are remapped, so the rules themselves are left out
+        let mut auto_cats = AutoCats::default();
+        *auto_cats.formulas_mut() = Self::load_formulas_impl(&mut *db_txn, AUTO_CAT).await?;
+        auto_cats.remap_formulas(&old_to_new);
+        sqlx::query("DELETE FROM formulas WHERE sheet = ?")
+            .bind(AUTO_CAT)
+            .execute(&mut *db_txn)
+            .await?;
+        for (row_col, formula) in auto_cats.formulas() {
+            sqlx::query("INSERT INTO formulas (sheet, row, col, formula) VALUES (?, ?, ?, ?)")
+                .bind(AUTO_CAT)
+                .bind(row_col.0 as i64)
+                .bind(row_col.1 as i64)
+                .bind(formula)
+                .execute(&mut *db_txn)
+                .await
+                .context("Failed to move autocat formula")?;
+        }
+
+        for (ix, rule) in rules.iter_mut().enumerate() {
+            rule.row.original_order = Some(ix as u64);
+            sqlx::query("UPDATE autocat SET original_order = ? WHERE id = ?")
+                .bind(ix as i64)
+                .bind(rule.id as i64)
+                .execute(&mut *db_txn)
+                .await
+                .context("Failed to update autocat order")?;
         }
+
+        self.commit(db_txn).await?;
+
+        Ok(rules)
     }
 
     /// Deletes multiple autocat rules atomically.
@@ -1071,10 +1643,7 @@ impl Db {
             deleted.push(id.to_string());
         }
 
-        db_txn
-            .commit()
-            .await
-            .context("Unable to commit db transaction")?;
+        self.commit(db_txn).await?;
 
         Ok(deleted)
     }
@@ -1142,10 +1711,7 @@ impl Db {
             deleted.push(id.to_string());
         }
 
-        db_txn
-            .commit()
-            .await
-            .context("Unable to commit db transaction")?;
+        self.commit(db_txn).await?;
 
         Ok(deleted)
     }
@@ -1172,13 +1738,13 @@ impl Db {
                 transaction_id, date, description, amount, account, account_number,
                 institution, month, week, full_description, account_id, check_number,
                 date_added, merchant_name, category_hint, category, note, tags,
-                categorized_date, statement, metadata, other_fields, original_order
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+                categorized_date, statement, metadata, no_name, other_fields, original_order
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
         )
         .bind(&txn.transaction_id)
         .bind(&txn.date)
         .bind(&txn.description)
-        .bind(txn.amount.value().to_f64().unwrap_or(0.0))
+        .bind(amount_to_sql(&txn.amount))
         .bind(&txn.account)
         .bind(&txn.account_number)
         .bind(&txn.institution)
@@ -1196,6 +1762,7 @@ impl Db {
         .bind(&txn.categorized_date)
         .bind(&txn.statement)
         .bind(&txn.metadata)
+        .bind(&txn.no_name)
         .bind(&other_fields_json)
         .bind(txn.original_order.map(|i| i as i64))
         .execute(ex)
@@ -1232,6 +1799,39 @@ impl Db {
         Ok(())
     }
 
+    /// Inserts a category, or replaces the details of the category with the same name. Unlike a
+    /// delete followed by an insert, this keeps the rows that refer to the category valid.
+    async fn upsert_category_impl<'e, E>(ex: E, category: &Category) -> Res<()>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+    {
+        let other_fields_json = if category.other_fields.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(&category.other_fields)?)
+        };
+
+        sqlx::query(
+            r#"INSERT INTO categories (category, category_group, type, hide_from_reports, other_fields, original_order)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(category) DO UPDATE SET
+                category_group = excluded.category_group, type = excluded.type,
+                hide_from_reports = excluded.hide_from_reports,
+                other_fields = excluded.other_fields, original_order = excluded.original_order"#,
+        )
+        .bind(&category.category)
+        .bind(&category.category_group)
+        .bind(&category.r#type)
+        .bind(&category.hide_from_reports)
+        .bind(&other_fields_json)
+        .bind(category.original_order.map(|i| i as i64))
+        .execute(ex)
+        .await
+        .context("Failed to upsert category")?;
+
+        Ok(())
+    }
+
     async fn insert_autocat_impl<'e, E>(ex: E, autocat: &AutoCat) -> Res<u64>
     where
         E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
@@ -1262,24 +1862,9 @@ impl Db {
         .bind(&autocat.description_contains)
         .bind(&autocat.account_contains)
         .bind(&autocat.institution_contains)
-        .bind(
-            autocat
-                .amount_min
-                .as_ref()
-                .map(|a| a.value().to_f64().unwrap_or(0.0)),
-        )
-        .bind(
-            autocat
-                .amount_max
-                .as_ref()
-                .map(|a| a.value().to_f64().unwrap_or(0.0)),
-        )
-        .bind(
-            autocat
-                .amount_equals
-                .as_ref()
-                .map(|a| a.value().to_f64().unwrap_or(0.0)),
-        )
+        .bind(autocat.amount_min.as_ref().map(amount_to_sql))
+        .bind(autocat.amount_max.as_ref().map(amount_to_sql))
+        .bind(autocat.amount_equals.as_ref().map(amount_to_sql))
         .bind(&autocat.description_equals)
         .bind(&autocat.description_full)
         .bind(&autocat.full_description_contains)
@@ -1293,6 +1878,33 @@ impl Db {
         Ok(result.last_insert_rowid() as u64)
     }
 
+    async fn insert_balance_impl<'e, E>(ex: E, balance: &Balance) -> Res<()>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+    {
+        let other_fields_json = if balance.other_fields.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(&balance.other_fields)?)
+        };
+
+        sqlx::query(
+            r#"INSERT INTO balances (account, date, balance, account_id, other_fields, original_order)
+            VALUES (?, ?, ?, ?, ?, ?)"#,
+        )
+        .bind(&balance.account)
+        .bind(&balance.date)
+        .bind(amount_to_sql(&balance.balance))
+        .bind(&balance.account_id)
+        .bind(&other_fields_json)
+        .bind(balance.original_order.map(|i| i as i64))
+        .execute(ex)
+        .await
+        .context("Failed to insert balance")?;
+
+        Ok(())
+    }
+
     /// Saves formulas using the provided transaction.
     async fn save_formulas_impl(
         tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
@@ -1339,6 +1951,18 @@ impl Db {
                 .context("Failed to insert autocat formula")?;
         }
 
+        // Save balance formulas
+        for (row_col, formula) in data.balances.formulas() {
+            sqlx::query("INSERT INTO formulas (sheet, row, col, formula) VALUES (?, ?, ?, ?)")
+                .bind(BALANCES)
+                .bind(row_col.0 as i64)
+                .bind(row_col.1 as i64)
+                .bind(formula)
+                .execute(&mut **tx)
+                .await
+                .context("Failed to insert balance formula")?;
+        }
+
         Ok(())
     }
 
@@ -1385,6 +2009,7 @@ impl Db {
         save_mapping(tx, TRANSACTIONS, data.transactions.mapping()).await?;
         save_mapping(tx, CATEGORIES, data.categories.mapping()).await?;
         save_mapping(tx, AUTO_CAT, data.auto_cats.mapping()).await?;
+        save_mapping(tx, BALANCES, data.balances.mapping()).await?;
 
         Ok(())
     }
@@ -1518,8 +2143,13 @@ impl Db {
         use crate::commands::{ColumnInfo, ForeignKeyInfo, IndexInfo, Out, Schema, TableInfo};
 
         // Tables to query - data tables or all tables including metadata
-        let data_tables = ["transactions", "categories", "autocat"];
-        let metadata_tables = ["sheet_metadata", "formulas", "schema_version"];
+        let data_tables = ["transactions", "categories", "autocat", "balances"];
+        let metadata_tables = [
+            "sheet_metadata",
+            "formulas",
+            "schema_version",
+            "sync_history",
+        ];
 
         // Query the list of tables from sqlite_master
         let table_rows: Vec<(String,)> = sqlx::query_as(
@@ -1554,6 +2184,7 @@ impl Db {
                 "transactions" => Transaction::field_descriptions(),
                 "categories" => Category::field_descriptions(),
                 "autocat" => AutoCat::field_descriptions(),
+                "balances" => Balance::field_descriptions(),
                 _ => BTreeMap::new(),
             };
 
@@ -1682,15 +2313,304 @@ impl Db {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::model::{AutoCat, AutoCats, Categories, Category, Transaction, Transactions};
-    use tempfile::TempDir;
+/// Builds the `WHERE` clause (with a leading space, or empty if nothing is filtered) and the values
+/// to bind to it, in order, for `filter`.
+fn transaction_filter_sql(filter: &TransactionFilter) -> (String, Vec<String>) {
+    let mut conditions = Vec::new();
+    let mut binds = Vec::new();
 
-    #[tokio::test]
-    async fn test_bootstrap_creates_schema_version_table() {
-        let temp_dir = TempDir::new().unwrap();
+    if let Some(category) = &filter.category {
+        conditions.push("category = ?");
+        binds.push(category.clone());
+    }
+    if filter.only_uncategorized {
+        conditions.push("(category IS NULL OR category = '')");
+    }
+    if let Some(account) = &filter.account {
+        conditions.push("account = ?");
+        binds.push(account.clone());
+    }
+    if let Some(account_id) = &filter.account_id {
+        conditions.push("account_id = ?");
+        binds.push(account_id.clone());
+    }
+    if let Some(institution) = &filter.institution {
+        conditions.push("institution = ?");
+        binds.push(institution.clone());
+    }
+    if let Some(description) = &filter.description_contains {
+        conditions.push("description LIKE '%' || ? || '%' ESCAPE '\\'");
+        binds.push(escape_like(description));
+    }
+    // Custom columns are stored as a JSON object in other_fields
+    match (&filter.other_field, &filter.other_field_contains) {
+        (Some(key), contains) => {
+            conditions.push(
+                "EXISTS (SELECT 1 FROM json_each(transactions.other_fields) \
+                 WHERE key = ? AND value != '' AND value LIKE '%' || ? || '%' ESCAPE '\\')",
+            );
+            binds.push(key.clone());
+            binds.push(escape_like(contains.as_deref().unwrap_or_default()));
+        }
+        (None, Some(contains)) => {
+            conditions.push(
+                "EXISTS (SELECT 1 FROM json_each(transactions.other_fields) \
+                 WHERE value LIKE '%' || ? || '%' ESCAPE '\\')",
+            );
+            binds.push(escape_like(contains));
+        }
+        (None, None) => {}
+    }
+
+    if conditions.is_empty() {
+        (String::new(), binds)
+    } else {
+        (format!(" WHERE {}", conditions.join(" AND ")), binds)
+    }
+}
+
+/// Escapes `s` with backslashes so that a `LIKE ... ESCAPE '\'` pattern matches its `%` and `_`
+/// literally, instead of as wildcards.
+fn escape_like(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Returns the categories that transactions or AutoCat rules in `data` refer to but that are not in
+/// `data.categories`, e.g. because a category was renamed in the sheet without updating every row
+/// that uses it. Saving these references as is would violate the foreign keys on `category`.
+fn missing_categories(data: &TillerData) -> BTreeSet<&str> {
+    let known: HashSet<&str> = data
+        .categories
+        .data()
+        .iter()
+        .map(|c| c.category.as_str())
+        .collect();
+    let transactions = data.transactions.data().iter().map(|t| t.category.as_str());
+    let auto_cats = data.auto_cats.data().iter().map(|a| a.category.as_str());
+    transactions
+        .chain(auto_cats)
+        .filter(|c| !c.is_empty() && !known.contains(c))
+        .collect()
+}
+
+/// Converts a row selected with [`TRANSACTION_COLUMNS`] into a `Transaction`.
+fn transaction_from_row(r: &SqliteRow) -> Res<Transaction> {
+    use sqlx::Row;
+
+    let other_fields_json: Option<String> = r.get("other_fields");
+    let other_fields: BTreeMap<String, String> = match other_fields_json {
+        Some(json) => serde_json::from_str(&json)?,
+        None => BTreeMap::new(),
+    };
+
+    Ok(Transaction {
+        transaction_id: r.get("transaction_id"),
+        date: r.get("date"),
+        description: r.get("description"),
+        amount: amount_from_row(r, "amount")?,
+        account: r.get("account"),
+        account_number: r.get("account_number"),
+        institution: r.get("institution"),
+        month: r.get::<Option<String>, _>("month").unwrap_or_default(),
+        week: r.get::<Option<String>, _>("week").unwrap_or_default(),
+        full_description: r
+            .get::<Option<String>, _>("full_description")
+            .unwrap_or_default(),
+        account_id: r.get("account_id"),
+        check_number: r
+            .get::<Option<String>, _>("check_number")
+            .unwrap_or_default(),
+        date_added: r.get::<Option<String>, _>("date_added").unwrap_or_default(),
+        merchant_name: r
+            .get::<Option<String>, _>("merchant_name")
+            .unwrap_or_default(),
+        category_hint: r
+            .get::<Option<String>, _>("category_hint")
+            .unwrap_or_default(),
+        category: r.get::<Option<String>, _>("category").unwrap_or_default(),
+        note: r.get::<Option<String>, _>("note").unwrap_or_default(),
+        tags: r.get::<Option<String>, _>("tags").unwrap_or_default(),
+        categorized_date: r
+            .get::<Option<String>, _>("categorized_date")
+            .unwrap_or_default(),
+        statement: r.get::<Option<String>, _>("statement").unwrap_or_default(),
+        metadata: r.get::<Option<String>, _>("metadata").unwrap_or_default(),
+        no_name: r.get::<Option<String>, _>("no_name").unwrap_or_default(),
+        other_fields,
+        original_order: r.get::<Option<u64>, _>("original_order"),
+        ..Default::default()
+    })
+}
+
+/// Converts a row selected with [`AUTOCAT_COLUMNS`] into an AutoCat rule and its ID.
+fn autocat_from_row(r: &SqliteRow) -> Res<_Row<AutoCat>> {
+    use sqlx::Row;
+
+    let other_fields_json: Option<String> = r.get("other_fields");
+    let other_fields: BTreeMap<String, String> = match other_fields_json {
+        Some(json) => serde_json::from_str(&json)?,
+        None => BTreeMap::new(),
+    };
+
+    let amount_min: Option<String> = r.get("amount_min");
+    let amount_max: Option<String> = r.get("amount_max");
+    let amount_equals: Option<String> = r.get("amount_equals");
+
+    Ok(_Row {
+        id: r.get::<i64, _>("id") as u64,
+        row: AutoCat {
+            category: r.get::<Option<String>, _>("category").unwrap_or_default(),
+            description: r
+                .get::<Option<String>, _>("description")
+                .unwrap_or_default(),
+            description_contains: r
+                .get::<Option<String>, _>("description_contains")
+                .unwrap_or_default(),
+            account_contains: r
+                .get::<Option<String>, _>("account_contains")
+                .unwrap_or_default(),
+            institution_contains: r
+                .get::<Option<String>, _>("institution_contains")
+                .unwrap_or_default(),
+            amount_min: amount_min.and_then(|v| v.parse().ok()),
+            amount_max: amount_max.and_then(|v| v.parse().ok()),
+            amount_equals: amount_equals.and_then(|v| v.parse().ok()),
+            description_equals: r
+                .get::<Option<String>, _>("description_equals")
+                .unwrap_or_default(),
+            description_full: r
+                .get::<Option<String>, _>("description_full")
+                .unwrap_or_default(),
+            full_description_contains: r
+                .get::<Option<String>, _>("full_description_contains")
+                .unwrap_or_default(),
+            amount_contains: r
+                .get::<Option<String>, _>("amount_contains")
+                .unwrap_or_default(),
+            other_fields,
+            original_order: r.get::<Option<u64>, _>("original_order"),
+        },
+    })
+}
+
+/// Returns the options for read-write connections to the SQLite file at `path`. Per-connection
+/// settings are not made here, they are applied by [`pool_options`].
+fn rw_connect_options(path: &Path, create_if_missing: bool) -> Res<SqliteConnectOptions> {
+    Ok(
+        SqliteConnectOptions::from_str(&format!("sqlite:{}", path.display()))
+            .context("Failed to parse SQLite connection string")?
+            .create_if_missing(create_if_missing),
+    )
+}
+
+/// SQLite's result codes for a damaged database file and for a file that is not a database.
+const SQLITE_CORRUPT: i32 = 11;
+const SQLITE_NOTADB: i32 = 26;
+
+/// Whether `e` is SQLite reporting that the database file is damaged or is not a database.
+fn is_corrupt(e: &sqlx::Error) -> bool {
+    e.as_database_error()
+        .and_then(|e| e.code())
+        .and_then(|code| code.parse::<i32>().ok())
+        // Extended result codes keep the primary result code in the low byte
+        .is_some_and(|code| matches!(code & 0xff, SQLITE_CORRUPT | SQLITE_NOTADB))
+}
+
+/// The error message for a database file at `path` that SQLite cannot read.
+fn corrupt_message(path: &Path) -> String {
+    format!(
+        "The SQLite database at {} appears to be corrupt. Restore it by copying one of the \
+        'tiller.sqlite.*' backups in the .backups directory over it",
+        path.display()
+    )
+}
+
+/// Returns pool options with an `after_connect` hook that applies each of `pragmas` to every new
+/// connection, so that all connections in a pool behave the same regardless of the pool's size.
+fn pool_options(
+    max_connections: u32,
+    pragmas: &'static [(&'static str, &'static str)],
+) -> SqlitePoolOptions {
+    SqlitePoolOptions::new()
+        .max_connections(max_connections)
+        .after_connect(move |conn, _meta| {
+            Box::pin(async move {
+                for (name, value) in pragmas {
+                    let sql = format!("PRAGMA {name} = {value}");
+                    sqlx::query(&sql).execute(&mut *conn).await?;
+                }
+                Ok(())
+            })
+        })
+}
+
+/// Converts an amount to the decimal string that is stored in SQLite, e.g. `-87.43`.
+///
+/// Amounts are stored as TEXT rather than as a number so that SQLite never converts them to a
+/// floating point value. The value is normalized so that equal amounts are stored identically,
+/// which keeps exact-match comparisons in SQL working.
+fn amount_to_sql(amount: &Amount) -> String {
+    amount.value().normalize().to_string()
+}
+
+/// Reads an amount column, such as `transactions.amount` or `balances.balance`, from a row.
+///
+/// Amounts are stored as decimal strings, see [`amount_to_sql`].
+fn amount_from_row(r: &SqliteRow, column: &str) -> Res<Amount> {
+    use sqlx::Row;
+
+    let value: String = r
+        .try_get(column)
+        .with_context(|| format!("Failed to read the {column} column as text"))?;
+    let decimal = Decimal::from_str(&value)
+        .with_context(|| format!("Invalid amount '{value}' in the {column} column"))?;
+    Ok(Amount::new(decimal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{AutoCat, AutoCats, Categories, Category, Transaction, Transactions};
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_load_reports_a_corrupt_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.sqlite");
+        std::fs::write(
+            &db_path,
+            b"this is not a SQLite database, just some garbage bytes",
+        )
+        .unwrap();
+
+        let e = Db::load(&db_path).await.err().unwrap();
+        let message = format!("{e:#}");
+        assert!(message.contains("appears to be corrupt"), "{message}");
+        assert!(message.contains(".backups"), "{message}");
+    }
+
+    #[tokio::test]
+    async fn test_init_creates_parent_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        let parent = temp_dir.path().join("a").join("b");
+        let db_path = parent.join("test.sqlite");
+
+        let db = Db::init(&db_path).await.unwrap();
+        assert!(parent.is_dir());
+        assert!(db_path.is_file());
+        assert!(db.schema_version().await.unwrap() >= 0);
+    }
+
+    #[tokio::test]
+    async fn test_bootstrap_creates_schema_version_table() {
+        let temp_dir = TempDir::new().unwrap();
         let db_path = temp_dir.path().join("test.sqlite");
 
         let db = Db::init(&db_path).await.unwrap();
@@ -1760,6 +2680,7 @@ mod tests {
             transactions,
             categories,
             auto_cats,
+            ..Default::default()
         };
 
         db.save_tiller_data(&data).await.unwrap();
@@ -1948,6 +2869,42 @@ mod tests {
         assert_eq!(row.1, "Food");
     }
 
+    #[tokio::test]
+    async fn test_update_category_merges_other_fields() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.sqlite");
+        let db = Db::init(&db_path).await.unwrap();
+
+        sqlx::query(
+            r#"INSERT INTO categories
+                (category, category_group, type, hide_from_reports, other_fields)
+             VALUES ('Groceries', 'Food', 'Expense', '',
+                     '{"Jan 2024":"$500.00","Feb 2024":"$450.00","Mar 2024":"$400.00"}')"#,
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+        let updates = crate::model::CategoryUpdates {
+            other_fields: BTreeMap::from([("Feb 2024".to_string(), "$600.00".to_string())]),
+            ..Default::default()
+        };
+        let args = crate::args::UpdateCategoriesArgs::new(vec!["Groceries"], updates).unwrap();
+        let updated = db.update_categories(args).await.unwrap();
+        assert_eq!(updated[0].other_fields["Feb 2024"], "$600.00");
+
+        let category = db._get_category("Groceries").await.unwrap().unwrap();
+        assert_eq!(category.category_group, "Food");
+        assert_eq!(
+            category.other_fields,
+            BTreeMap::from([
+                ("Jan 2024".to_string(), "$500.00".to_string()),
+                ("Feb 2024".to_string(), "$600.00".to_string()),
+                ("Mar 2024".to_string(), "$400.00".to_string()),
+            ])
+        );
+    }
+
     #[tokio::test]
     async fn test_update_category() {
         let temp_dir = TempDir::new().unwrap();
@@ -2296,6 +3253,7 @@ mod tests {
             transactions,
             categories,
             auto_cats,
+            ..Default::default()
         };
 
         // Save the data (should save formulas too)
@@ -2422,6 +3380,7 @@ mod tests {
             transactions,
             categories,
             auto_cats,
+            ..Default::default()
         };
 
         // Save the data (including formulas)
@@ -2447,6 +3406,191 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_formulas_survive_reopening_the_db() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.sqlite");
+
+        let categories = Categories::parse(
+            vec![
+                vec!["Category", "Group", "Type", "Hide From Reports", "Budget"],
+                vec!["Groceries", "Food", "Expense", "", "400"],
+                vec!["Coffee Shops", "Food", "Expense", "", "50"],
+            ],
+            vec![
+                vec!["Category", "Group", "Type", "Hide From Reports", "Budget"],
+                vec!["Groceries", "Food", "Expense", "", "=200*2"],
+                vec!["Coffee Shops", "Food", "Expense", "", "=E2/8"],
+            ],
+        )
+        .unwrap();
+        let auto_cats = AutoCats::parse(
+            vec![
+                vec!["Category", "Description Contains"],
+                vec!["Groceries", "grocery"],
+            ],
+            Vec::<Vec<&str>>::new(),
+        )
+        .unwrap();
+        let data = TillerData {
+            transactions: create_transactions_with_formulas(),
+            categories,
+            auto_cats,
+            ..Default::default()
+        };
+        assert_eq!(data.categories.formulas().len(), 2);
+
+        let db = Db::init(&db_path).await.unwrap();
+        db.save_tiller_data(&data).await.unwrap();
+        db.close().await;
+
+        // A new connection has only the database to go on
+        let loaded = Db::load(&db_path)
+            .await
+            .unwrap()
+            .get_tiller_data()
+            .await
+            .unwrap();
+        assert_eq!(loaded.transactions.formulas(), data.transactions.formulas());
+        assert_eq!(loaded.categories.formulas(), data.categories.formulas());
+        assert!(loaded.auto_cats.formulas().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_save_tiller_data_adds_missing_categories() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.sqlite");
+        let db = Db::init(&db_path).await.unwrap();
+
+        // "Food" was renamed to "Dining Out" in the Categories tab, but one transaction and the
+        // AutoCat rule still say "Food"
+        let transactions = Transactions::parse(
+            vec![
+                vec!["Transaction ID", "Date", "Description", "Category"],
+                vec!["txn-001", "10/1/2025", "Cafe", "Food"],
+                vec!["txn-002", "10/2/2025", "Bistro", "Dining Out"],
+                vec!["txn-003", "10/3/2025", "Transfer", ""],
+            ],
+            Vec::<Vec<&str>>::new(),
+        )
+        .unwrap();
+        let categories = Categories::parse(
+            vec![
+                vec!["Category", "Group", "Type", "Hide From Reports"],
+                vec!["Dining Out", "Food", "Expense", ""],
+            ],
+            Vec::<Vec<&str>>::new(),
+        )
+        .unwrap();
+        let auto_cats = AutoCats::parse(
+            vec![
+                vec!["Category", "Description Contains"],
+                vec!["Food", "cafe"],
+            ],
+            Vec::<Vec<&str>>::new(),
+        )
+        .unwrap();
+        let data = TillerData {
+            transactions,
+            categories,
+            auto_cats,
+            ..Default::default()
+        };
+        assert_eq!(missing_categories(&data), BTreeSet::from(["Food"]));
+
+        db.save_tiller_data(&data).await.unwrap();
+
+        let food = db._get_category("Food").await.unwrap().unwrap();
+        assert_eq!(food.category_group, "");
+        assert_eq!(food.original_order, None);
+        let txn = db._get_transaction("txn-001").await.unwrap().unwrap();
+        assert_eq!(txn.category, "Food");
+        let categories = db.get_categories().await.unwrap();
+        let names: Vec<&str> = categories
+            .data()
+            .iter()
+            .map(|c| c.category.as_str())
+            .collect();
+        assert_eq!(names, vec!["Dining Out", "Food"]);
+    }
+
+    /// Saving over data whose transactions already refer to categories must not trip the
+    /// `ON DELETE RESTRICT` foreign keys, which SQLite enforces immediately even when deferred.
+    #[tokio::test]
+    async fn test_save_tiller_data_with_cross_references() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.sqlite");
+        let db = Db::init(&db_path).await.unwrap();
+        let (foreign_keys,): (i64,) = sqlx::query_as("PRAGMA foreign_keys")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(foreign_keys, 1);
+
+        let data =
+            |rows: Vec<Vec<&str>>, categories: Vec<Vec<&str>>, rules: Vec<Vec<&str>>| TillerData {
+                transactions: Transactions::parse(rows, Vec::<Vec<&str>>::new()).unwrap(),
+                categories: Categories::parse(categories, Vec::<Vec<&str>>::new()).unwrap(),
+                auto_cats: AutoCats::parse(rules, Vec::<Vec<&str>>::new()).unwrap(),
+                ..Default::default()
+            };
+        let first = data(
+            vec![
+                vec!["Transaction ID", "Date", "Description", "Category"],
+                vec!["txn-001", "10/1/2025", "Cafe", "Food"],
+                vec!["txn-002", "10/2/2025", "Paycheck", "Income"],
+            ],
+            vec![
+                vec!["Category", "Group", "Type"],
+                vec!["Food", "Living", "Expense"],
+                vec!["Income", "Income", "Income"],
+                vec!["Travel", "Fun", "Expense"],
+            ],
+            vec![
+                vec!["Category", "Description Contains"],
+                vec!["Food", "cafe"],
+            ],
+        );
+        db.save_tiller_data(&first).await.unwrap();
+
+        // Travel is removed, Gifts is added and used, and the other categories are still in use
+        let second = data(
+            vec![
+                vec!["Transaction ID", "Date", "Description", "Category"],
+                vec!["txn-001", "10/1/2025", "Cafe", "Food"],
+                vec!["txn-002", "10/2/2025", "Paycheck", "Income"],
+                vec!["txn-003", "10/3/2025", "Flowers", "Gifts"],
+            ],
+            vec![
+                vec!["Category", "Group", "Type"],
+                vec!["Food", "Everyday", "Expense"],
+                vec!["Income", "Income", "Income"],
+                vec!["Gifts", "Fun", "Expense"],
+            ],
+            vec![
+                vec!["Category", "Description Contains"],
+                vec!["Gifts", "flowers"],
+                vec!["Food", "cafe"],
+            ],
+        );
+        db.save_tiller_data(&second).await.unwrap();
+
+        let names: Vec<String> = db
+            .get_categories()
+            .await
+            .unwrap()
+            .data()
+            .iter()
+            .map(|c| c.category.clone())
+            .collect();
+        assert_eq!(names, vec!["Food", "Income", "Gifts"]);
+        let food = db._get_category("Food").await.unwrap().unwrap();
+        assert_eq!(food.category_group, "Everyday");
+        let txn = db._get_transaction("txn-003").await.unwrap().unwrap();
+        assert_eq!(txn.category, "Gifts");
+        assert_eq!(db.get_autocats().await.unwrap().data().len(), 2);
+    }
+
     /// Test that save_tiller_data saves the header mapping to the sheet_metadata table.
     /// This test is expected to FAIL until we implement saving mappings.
     #[tokio::test]
@@ -2507,6 +3651,7 @@ mod tests {
             transactions,
             categories,
             auto_cats,
+            ..Default::default()
         };
 
         // Save the data
@@ -2627,4 +3772,581 @@ mod tests {
             mapping.headers().len()
         );
     }
+
+    #[tokio::test]
+    async fn test_institution_totals() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.sqlite");
+        let db = Db::init(&db_path).await.unwrap();
+
+        let rows = [
+            ("txn-001", "10/1/2025", "Bank A", "-10.25"),
+            ("txn-002", "10/5/2025", "Bank A", "-4.75"),
+            ("txn-003", "10/10/2025", "Bank B", "100.00"),
+            ("txn-004", "10/15/2025", "Bank B", "-0.10"),
+            ("txn-005", "10/20/2025", "", "-7.00"),
+        ];
+        for (id, date, institution, amount) in rows {
+            let mut transaction = Transaction::default();
+            transaction.transaction_id = id.to_string();
+            transaction.date = date.to_string();
+            transaction.institution = institution.to_string();
+            transaction.amount = amount.parse().unwrap();
+            transaction.account_id = "acct-001".to_string();
+            db.insert_transaction(&transaction).await.unwrap();
+        }
+
+        let totals = db.institution_totals(None, None).await.unwrap();
+        assert_eq!(totals.len(), 3);
+        assert_eq!(
+            totals.get("Bank A").unwrap().value(),
+            Decimal::from_str("-15.00").unwrap()
+        );
+        assert_eq!(
+            totals.get("Bank B").unwrap().value(),
+            Decimal::from_str("99.90").unwrap()
+        );
+        assert_eq!(
+            totals.get(UNKNOWN_INSTITUTION).unwrap().value(),
+            Decimal::from_str("-7.00").unwrap()
+        );
+
+        // Restrict to a date range that excludes the first and last transactions
+        let from = NaiveDate::from_ymd_opt(2025, 10, 2);
+        let to = NaiveDate::from_ymd_opt(2025, 10, 15);
+        let totals = db.institution_totals(from, to).await.unwrap();
+        assert_eq!(totals.len(), 2);
+        assert_eq!(
+            totals.get("Bank A").unwrap().value(),
+            Decimal::from_str("-4.75").unwrap()
+        );
+        assert_eq!(
+            totals.get("Bank B").unwrap().value(),
+            Decimal::from_str("99.90").unwrap()
+        );
+        assert!(totals.get(UNKNOWN_INSTITUTION).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_save_and_get_balances() {
+        use crate::model::Balances;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.sqlite");
+        let db = Db::init(&db_path).await.unwrap();
+
+        let values = vec![
+            vec!["Date", "Account", "Account ID", "Balance", "Class"],
+            vec!["10/20/2025", "Checking", "acct-001", "$1,200.50", "Asset"],
+            vec!["10/20/2025", "Visa", "acct-002", "-$300.00", "Liability"],
+            vec!["10/13/2025", "Checking", "acct-001", "$1,150.00", "Asset"],
+        ];
+        let mut formulas = values.clone();
+        formulas[3][4] = "=B2";
+        let balances = Balances::parse(values, formulas).unwrap();
+
+        // The other sheets need headers, even when they have no rows
+        let data = TillerData {
+            transactions: Transactions::parse(
+                vec![vec!["Transaction ID", "Date", "Amount"]],
+                Vec::<Vec<&str>>::new(),
+            )
+            .unwrap(),
+            categories: Categories::parse(vec![vec!["Category"]], Vec::<Vec<&str>>::new()).unwrap(),
+            auto_cats: AutoCats::parse(vec![vec!["Category"]], Vec::<Vec<&str>>::new()).unwrap(),
+            balances: balances.clone(),
+        };
+        db.save_tiller_data(&data).await.unwrap();
+
+        let loaded = db.get_tiller_data().await.unwrap();
+        assert_eq!(loaded.balances.mapping(), balances.mapping());
+        assert_eq!(loaded.balances.formulas(), balances.formulas());
+        assert_eq!(loaded.balances.data().len(), 3);
+        for (loaded, original) in loaded.balances.data().iter().zip(balances.data()) {
+            assert_eq!(loaded.account, original.account);
+            assert_eq!(loaded.date, original.date);
+            assert_eq!(loaded.account_id, original.account_id);
+            assert_eq!(loaded.balance.value(), original.balance.value());
+            assert_eq!(loaded.other_fields, original.other_fields);
+            assert_eq!(loaded.original_order, original.original_order);
+        }
+
+        // Saving data without balances clears them, and no mapping is loaded
+        let data = TillerData {
+            balances: Balances::default(),
+            ..data
+        };
+        db.save_tiller_data(&data).await.unwrap();
+        let loaded = db.get_tiller_data().await.unwrap();
+        assert!(loaded.balances.data().is_empty());
+        assert!(loaded.balances.mapping().headers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_transactions_pagination() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.sqlite");
+        let db = Db::init(&db_path).await.unwrap();
+
+        for ix in 0..5u64 {
+            let mut transaction = Transaction::default();
+            transaction.transaction_id = format!("txn-{ix:03}");
+            transaction.date = "10/1/2025".to_string();
+            transaction.description = if ix % 2 == 0 { "Coffee" } else { "Gas" }.to_string();
+            transaction.account = "Checking".to_string();
+            transaction.account_id = "acct-001".to_string();
+            transaction.original_order = Some(ix);
+            db.insert_transaction(&transaction).await.unwrap();
+        }
+
+        // No filter, no limit
+        let (rows, total) = db
+            .list_transactions(&TransactionFilter::default(), None, 0)
+            .await
+            .unwrap();
+        assert_eq!(total, 5);
+        assert_eq!(rows.len(), 5);
+
+        // The limit truncates the rows but not the total
+        let filter = TransactionFilter {
+            description_contains: Some("off".to_string()),
+            ..Default::default()
+        };
+        let (rows, total) = db.list_transactions(&filter, Some(2), 0).await.unwrap();
+        assert_eq!(total, 3);
+        let ids: Vec<&str> = rows.iter().map(|t| t.transaction_id.as_str()).collect();
+        assert_eq!(ids, vec!["txn-000", "txn-002"]);
+
+        // The next page holds the remainder
+        let (rows, total) = db.list_transactions(&filter, Some(2), 2).await.unwrap();
+        assert_eq!(total, 3);
+        let ids: Vec<&str> = rows.iter().map(|t| t.transaction_id.as_str()).collect();
+        assert_eq!(ids, vec!["txn-004"]);
+
+        // An exact-match filter that matches nothing
+        let filter = TransactionFilter {
+            account: Some("Savings".to_string()),
+            ..Default::default()
+        };
+        let (rows, total) = db.list_transactions(&filter, Some(2), 0).await.unwrap();
+        assert_eq!(total, 0);
+        assert!(rows.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_list_transactions_contains_matches_wildcards_literally() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.sqlite");
+        let db = Db::init(&db_path).await.unwrap();
+
+        let rows = [
+            ("txn-001", "50% off", "a_b"),
+            ("txn-002", "500 off", "axb"),
+            ("txn-003", "C:\\Temp", "100%"),
+        ];
+        for (ix, (id, description, code)) in rows.into_iter().enumerate() {
+            let mut transaction = Transaction::default();
+            transaction.transaction_id = id.to_string();
+            transaction.date = "10/1/2025".to_string();
+            transaction.description = description.to_string();
+            transaction.original_order = Some(ix as u64);
+            transaction
+                .other_fields
+                .insert("Code".to_string(), code.to_string());
+            db.insert_transaction(&transaction).await.unwrap();
+        }
+
+        let db = &db;
+        let ids = |filter: TransactionFilter| async move {
+            let (found, _) = db.list_transactions(&filter, None, 0).await.unwrap();
+            found
+                .into_iter()
+                .map(|t| t.transaction_id)
+                .collect::<Vec<_>>()
+        };
+        let description = |s: &str| TransactionFilter {
+            description_contains: Some(s.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(description("0%")).await, vec!["txn-001"]);
+        assert_eq!(ids(description("\\T")).await, vec!["txn-003"]);
+        let field = |key: Option<&str>, s: &str| TransactionFilter {
+            other_field: key.map(str::to_string),
+            other_field_contains: Some(s.to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(field(Some("Code"), "_")).await, vec!["txn-001"]);
+        assert_eq!(ids(field(None, "%")).await, vec!["txn-003"]);
+    }
+
+    /// Returns a test environment whose database holds the `TestSheet` seed data.
+    async fn seeded_env() -> crate::test::TestEnv {
+        let env = crate::test::TestEnv::new().await;
+        crate::commands::sync_down(env.config(), crate::api::Mode::Testing)
+            .await
+            .unwrap();
+        env
+    }
+
+    #[tokio::test]
+    async fn test_get_transactions() {
+        let env = seeded_env().await;
+        let db = env.config().db().clone();
+
+        let transactions = db.get_transactions().await.unwrap();
+        assert_eq!(transactions.data().len(), 20);
+        assert_eq!(
+            transactions.data()[0].transaction_id,
+            "tx001a2b3c4d5e6f7g8h9i01"
+        );
+        assert_eq!(transactions.mapping().headers().len(), 17);
+        assert!(!transactions.formulas().is_empty());
+        assert_eq!(
+            transactions,
+            db.get_tiller_data().await.unwrap().transactions
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_categories() {
+        let env = seeded_env().await;
+        let db = env.config().db().clone();
+
+        let categories = db.get_categories().await.unwrap();
+        let names: Vec<&str> = categories
+            .data()
+            .iter()
+            .map(|c| c.category.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "Groceries",
+                "Coffee Shops",
+                "Gas & Fuel",
+                "Restaurants",
+                "Utilities"
+            ]
+        );
+        assert_eq!(categories.mapping().headers().len(), 16);
+        assert_eq!(categories, db.get_tiller_data().await.unwrap().categories);
+    }
+
+    #[tokio::test]
+    async fn test_get_autocats() {
+        let env = seeded_env().await;
+        let db = env.config().db().clone();
+
+        let autocats = db.get_autocats().await.unwrap();
+        let rules: Vec<(&str, &str)> = autocats
+            .data()
+            .iter()
+            .map(|a| (a.category.as_str(), a.description_contains.as_str()))
+            .collect();
+        assert_eq!(
+            rules,
+            vec![
+                ("Groceries", "Whole Foods"),
+                ("Coffee Shops", "Starbucks"),
+                ("Gas & Fuel", "Shell")
+            ]
+        );
+        assert_eq!(autocats.mapping().headers().len(), 11);
+        assert_eq!(autocats, db.get_tiller_data().await.unwrap().auto_cats);
+    }
+
+    #[tokio::test]
+    async fn test_list_transactions_by_account_id() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.sqlite");
+        let db = Db::init(&db_path).await.unwrap();
+
+        // Both accounts share a display name, only the account ID tells them apart
+        let rows = [
+            ("txn-001", "acct-001"),
+            ("txn-002", "acct-002"),
+            ("txn-003", "acct-001"),
+        ];
+        for (ix, (id, account_id)) in rows.into_iter().enumerate() {
+            let mut transaction = Transaction::default();
+            transaction.transaction_id = id.to_string();
+            transaction.date = "10/1/2025".to_string();
+            transaction.account = "Checking".to_string();
+            transaction.account_id = account_id.to_string();
+            transaction.original_order = Some(ix as u64);
+            db.insert_transaction(&transaction).await.unwrap();
+        }
+
+        let ids = |transactions: Vec<Transaction>| -> Vec<String> {
+            transactions.into_iter().map(|t| t.transaction_id).collect()
+        };
+        let found = db
+            .list_transactions_by_account_id("acct-001")
+            .await
+            .unwrap();
+        assert_eq!(ids(found), vec!["txn-001", "txn-003"]);
+        let found = db
+            .list_transactions_by_account_id("acct-002")
+            .await
+            .unwrap();
+        assert_eq!(ids(found), vec!["txn-002"]);
+        let found = db
+            .list_transactions_by_account_id("acct-003")
+            .await
+            .unwrap();
+        assert!(found.is_empty());
+
+        let filter = TransactionFilter {
+            account: Some("Checking".to_string()),
+            account_id: Some("acct-002".to_string()),
+            ..Default::default()
+        };
+        let (found, total) = db.list_transactions(&filter, None, 0).await.unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(ids(found), vec!["txn-002"]);
+    }
+
+    #[tokio::test]
+    async fn test_list_transactions_only_uncategorized() {
+        let env = seeded_env().await;
+        let config = env.config();
+        let db = config.db();
+        async fn uncategorized(db: &Db) -> Vec<String> {
+            let filter = TransactionFilter {
+                only_uncategorized: true,
+                ..Default::default()
+            };
+            let (found, total) = db.list_transactions(&filter, None, 0).await.unwrap();
+            assert_eq!(total as usize, found.len());
+            assert!(found.iter().all(|t| t.category.is_empty()));
+            found.into_iter().map(|t| t.transaction_id).collect()
+        }
+
+        // Only the last seed transaction has no category
+        assert_eq!(uncategorized(db).await, vec!["tx001a2b3c4d5e6f7g8h9i20"]);
+
+        // Clear the category of two more, one is stored as NULL and one as ''
+        let mut transaction = db
+            ._get_transaction("tx001a2b3c4d5e6f7g8h9i02")
+            .await
+            .unwrap()
+            .unwrap();
+        transaction.category = String::new();
+        db.update_transaction(&transaction).await.unwrap();
+        db.execute_without_foreign_keys(
+            "UPDATE transactions SET category = '' \
+             WHERE transaction_id = 'tx001a2b3c4d5e6f7g8h9i05'",
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            uncategorized(db).await,
+            vec![
+                "tx001a2b3c4d5e6f7g8h9i02",
+                "tx001a2b3c4d5e6f7g8h9i05",
+                "tx001a2b3c4d5e6f7g8h9i20"
+            ]
+        );
+
+        // Combined with another criterion, both must match
+        let filter = TransactionFilter {
+            only_uncategorized: true,
+            description_contains: Some("starbucks".to_string()),
+            ..Default::default()
+        };
+        let (found, total) = db.list_transactions(&filter, None, 0).await.unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(found[0].transaction_id, "tx001a2b3c4d5e6f7g8h9i02");
+    }
+
+    #[tokio::test]
+    async fn test_list_transactions_by_other_field() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.sqlite");
+        let db = Db::init(&db_path).await.unwrap();
+
+        let rows = [
+            ("txn-001", Some(("Location", "Portland, OR"))),
+            ("txn-002", Some(("Location", "Seattle, WA"))),
+            ("txn-003", Some(("City", "Portland"))),
+            ("txn-004", None),
+        ];
+        for (ix, (id, field)) in rows.into_iter().enumerate() {
+            let mut transaction = Transaction::default();
+            transaction.transaction_id = id.to_string();
+            transaction.date = "10/1/2025".to_string();
+            transaction.original_order = Some(ix as u64);
+            if let Some((key, value)) = field {
+                transaction
+                    .other_fields
+                    .insert(key.to_string(), value.to_string());
+            }
+            db.insert_transaction(&transaction).await.unwrap();
+        }
+
+        let ids = |transactions: Vec<Transaction>| -> Vec<String> {
+            transactions.into_iter().map(|t| t.transaction_id).collect()
+        };
+        let found = db
+            .list_transactions_by_other_field("Location", "portland")
+            .await
+            .unwrap();
+        assert_eq!(ids(found), vec!["txn-001"]);
+        let found = db
+            .list_transactions_by_other_field("Location", "")
+            .await
+            .unwrap();
+        assert_eq!(ids(found), vec!["txn-001", "txn-002"]);
+        let found = db
+            .list_transactions_by_other_field("Location", "Boise")
+            .await
+            .unwrap();
+        assert!(found.is_empty());
+
+        // Without a key, the value may be in any custom column
+        let filter = TransactionFilter {
+            other_field_contains: Some("Portland".to_string()),
+            ..Default::default()
+        };
+        let (found, total) = db.list_transactions(&filter, None, 0).await.unwrap();
+        assert_eq!(total, 2);
+        assert_eq!(ids(found), vec!["txn-001", "txn-003"]);
+    }
+
+    #[tokio::test]
+    async fn test_amounts_are_stored_exactly() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.sqlite");
+        let db = Db::init(&db_path).await.unwrap();
+
+        sqlx::query("INSERT INTO categories (category) VALUES ('Groceries')")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        let rows = [
+            ("txn-001", "Bank A", "0.1", ""),
+            ("txn-002", "Bank A", "0.2", ""),
+            ("txn-003", "Bank B", "-87.43", "Groceries"),
+            ("txn-004", "Bank C", "1234567.8901", ""),
+        ];
+        for (id, institution, amount, category) in rows {
+            let mut transaction = Transaction::default();
+            transaction.transaction_id = id.to_string();
+            transaction.date = "10/1/2025".to_string();
+            transaction.institution = institution.to_string();
+            transaction.amount = amount.parse().unwrap();
+            transaction.category = category.to_string();
+            transaction.account_id = "acct-001".to_string();
+            db.insert_transaction(&transaction).await.unwrap();
+        }
+
+        // Each amount is read back exactly as it was written
+        for (id, _, amount, _) in rows {
+            let transaction = db._get_transaction(id).await.unwrap().unwrap();
+            assert_eq!(
+                transaction.amount.value(),
+                Decimal::from_str(amount).unwrap()
+            );
+        }
+
+        // Summing 0.1 and 0.2 gives exactly 0.3
+        let totals = db.institution_totals(None, None).await.unwrap();
+        assert_eq!(
+            totals.get("Bank A").unwrap().value(),
+            Decimal::from_str("0.3").unwrap()
+        );
+
+        // An AutoCat rule with an exact amount matches the transaction in SQL
+        let mut autocat = AutoCat::default();
+        autocat.category = "Groceries".to_string();
+        autocat.amount_equals = Some("-$87.43".parse().unwrap());
+        db.insert_autocat(&autocat).await.unwrap();
+        let (count,): (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM transactions t \
+            JOIN autocat a ON t.amount = a.amount_equals",
+        )
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_foreign_keys_are_enforced_after_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.sqlite");
+        let db = Db::init(&db_path).await.unwrap();
+        db.pool.close().await;
+
+        // Reopen the file, which opens new connections
+        let db = Db::load(&db_path).await.unwrap();
+        let (enabled,): (i64,) = sqlx::query_as("PRAGMA foreign_keys")
+            .fetch_one(&db.pool)
+            .await
+            .unwrap();
+        assert_eq!(enabled, 1);
+
+        sqlx::query("INSERT INTO categories (category) VALUES ('Groceries')")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+        let mut transaction = Transaction::default();
+        transaction.transaction_id = "txn-001".to_string();
+        transaction.category = "Groceries".to_string();
+        db.insert_transaction(&transaction).await.unwrap();
+
+        // ON DELETE RESTRICT prevents deleting a category that is in use
+        let args = DeleteCategoriesArgs::new(vec!["Groceries"]).unwrap();
+        assert!(db.delete_categories(args).await.is_err());
+        assert!(db._get_category("Groceries").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_pool_applies_pragmas_to_every_connection() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.sqlite");
+        let pool = pool_options(3, CONNECTION_PRAGMAS)
+            .connect_with(rw_connect_options(&db_path, true).unwrap())
+            .await
+            .unwrap();
+
+        // Hold all three connections at once so that each one is a distinct connection
+        let mut conns = Vec::new();
+        for _ in 0..3 {
+            conns.push(pool.acquire().await.unwrap());
+        }
+        for conn in conns.iter_mut() {
+            let (foreign_keys,): (i64,) = sqlx::query_as("PRAGMA foreign_keys")
+                .fetch_one(&mut **conn)
+                .await
+                .unwrap();
+            assert_eq!(foreign_keys, 1);
+
+            let (journal_mode,): (String,) = sqlx::query_as("PRAGMA journal_mode")
+                .fetch_one(&mut **conn)
+                .await
+                .unwrap();
+            assert_eq!(journal_mode, "delete");
+
+            let (busy_timeout,): (i64,) = sqlx::query_as("PRAGMA busy_timeout")
+                .fetch_one(&mut **conn)
+                .await
+                .unwrap();
+            assert_eq!(busy_timeout, 5000);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_close_rejects_further_operations() {
+        let dir = TempDir::new().unwrap();
+        let db = Db::init(dir.path().join("test.sqlite")).await.unwrap();
+        assert_eq!(db.count_transactions().await.unwrap(), 0);
+
+        let clone = db.clone();
+        db.close().await;
+        assert!(db.count_transactions().await.is_err());
+        assert!(clone.count_transactions().await.is_err());
+        assert!(clone.ro_pool.is_closed());
+    }
 }
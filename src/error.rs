@@ -52,6 +52,25 @@ pub enum ErrorType {
 serde_plain::derive_display_from_serialize!(ErrorType);
 serde_plain::derive_fromstr_from_deserialize!(ErrorType);
 
+impl ErrorType {
+    /// A distinct error code for each type, from the -32000 to -32099 range that JSON-RPC leaves
+    /// for server-defined errors. MCP tool failures report it so that agents can tell the types
+    /// apart without parsing the message. The codes are documented in the MCP instructions and
+    /// must not change.
+    pub fn code(&self) -> i32 {
+        match self {
+            ErrorType::Other => -32000,
+            ErrorType::Request => -32001,
+            ErrorType::Service => -32002,
+            ErrorType::Internal => -32003,
+            ErrorType::Sync => -32004,
+            ErrorType::Auth => -32005,
+            ErrorType::Config => -32006,
+            ErrorType::Database => -32007,
+        }
+    }
+}
+
 /// This library's public error type
 #[derive(Debug)]
 pub struct TillerError {
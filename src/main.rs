@@ -1,21 +1,24 @@
 use clap::Parser;
+use std::io::Write;
 use std::process::ExitCode;
 use tiller_sync::args::{
-    Args, Command, DeleteSubcommand, InsertSubcommand, UpDown, UpdateSubcommand,
+    Args, Command, DeleteSubcommand, Format, InsertSubcommand, LogFormat, UpDown, UpdateSubcommand,
 };
 use tiller_sync::{commands, Config, Mode, Result};
-use tracing::{debug, error, trace};
+use tracing::{debug, error, trace, Subscriber};
 use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
 async fn main() -> ExitCode {
     let args = Args::parse();
     let log_level = args.common().log_level();
-    init_logger(log_level);
+    init_logger(log_level, args.common().log_format(), args.common().color());
     debug!("Log level set to {}", log_level.to_string().to_lowercase());
 
-    match main_inner(args).await {
+    match main_inner(args, &mut std::io::stdout()).await {
         Ok(_) => ExitCode::SUCCESS,
         Err(e) => {
             error!("Exiting with error: {e}");
@@ -24,25 +27,64 @@ async fn main() -> ExitCode {
     }
 }
 
-pub async fn main_inner(args: Args) -> Result<()> {
+/// Runs the command given by `args`, writing list and query results to `stdout`.
+pub async fn main_inner(args: Args, stdout: &mut dyn Write) -> Result<()> {
     trace!("{args:?}");
-    let home = args.common().tiller_home().path();
+    let home = args.common().tiller_home();
+    let config_file = args.common().config_file();
 
     // This allows for testing the program without hitting the Google APIs. When
     // TILLER_SYNC_IN_TEST_MODE is set and non-zero in length, then the mode will be Mode::Test,
     // otherwise it will be Mode::Google.
     let mode = Mode::from_env();
 
-    // Route to appropriate command handler
-    let _: () = match args.command() {
-        Command::Init(init_args) => {
-            commands::init(home, init_args.client_secret(), init_args.sheet_url())
-                .await?
-                .print()
-        }
+    // `init` creates the configuration that every other command loads
+    if let Command::Init(init_args) = args.command() {
+        commands::init(
+            &home,
+            init_args.client_secret(),
+            init_args.sheet_url(),
+            init_args.dry_run() || args.common().dry_run(),
+        )
+        .await?
+        .print();
+        return Ok(());
+    }
+
+    let mut config = Config::load(home.path(), config_file)
+        .await?
+        .with_dry_run(args.common().dry_run());
+    if let Some(spreadsheet_id) = args.common().spreadsheet_id() {
+        config = config.with_spreadsheet_id(spreadsheet_id)?;
+    }
+    let output = args.common().output();
+    // --quiet limits output to errors, so results are not printed either
+    let mut sink = std::io::sink();
+    let stdout: &mut dyn Write = if args.common().quiet() {
+        &mut sink
+    } else {
+        stdout
+    };
+    let result = run_command(args.command(), config.clone(), mode, output, stdout).await;
+    // Close the database once the command is done so that every write is flushed before exit
+    config.close().await;
+    result
+}
+
+/// Routes `command` to the appropriate command handler. List and query results are written to
+/// `stdout` in `output`.
+async fn run_command(
+    command: &Command,
+    config: Config,
+    mode: Mode,
+    output: Format,
+    stdout: &mut dyn Write,
+) -> Result<()> {
+    let _: () = match command {
+        // Handled by `main_inner` before the configuration exists
+        Command::Init(_) => {}
 
         Command::Auth(auth_args) => {
-            let config = Config::load(home).await?;
             if auth_args.verify() {
                 commands::auth_verify(&config).await?.print()
             } else {
@@ -50,92 +92,181 @@ pub async fn main_inner(args: Args) -> Result<()> {
             }
         }
 
-        Command::Sync(sync_args) => {
-            let config = Config::load(home).await?;
-            match sync_args.direction() {
-                UpDown::Up => {
-                    commands::sync_up(config, mode, sync_args.force(), sync_args.formulas())
-                        .await?
-                        .print()
-                }
-                UpDown::Down => commands::sync_down(config, mode).await?.print(),
-            }
-        }
+        Command::Whoami(_) => commands::whoami(config, mode).await?.print(),
 
-        Command::Mcp(_mcp_args) => commands::mcp(Config::load(home).await?, mode)
+        Command::Sync(sync_args) => match sync_args.direction() {
+            UpDown::Up => commands::sync_up(
+                config.with_no_drive_backup(sync_args.no_drive_backup()),
+                mode,
+                sync_args.force(),
+                sync_args.formulas(),
+                sync_args.strategy(),
+                sync_args.only().to_vec(),
+                sync_args.dry_run(),
+            )
             .await?
             .print(),
+            UpDown::Down => commands::sync_down(config.with_max_rows(sync_args.max_rows()), mode)
+                .await?
+                .print(),
+            UpDown::Both => commands::sync(
+                config
+                    .with_max_rows(sync_args.max_rows())
+                    .with_no_drive_backup(sync_args.no_drive_backup()),
+                mode,
+                sync_args.force(),
+                sync_args.formulas(),
+            )
+            .await?
+            .print(),
+        },
+
+        Command::Mcp(_mcp_args) => commands::mcp(config, mode).await?.print(),
 
-        Command::Update(update_args) => {
-            let config = Config::load(home).await?;
-            match update_args.entity() {
-                UpdateSubcommand::Transactions(args) => {
-                    commands::update_transactions(config, *args.clone())
-                        .await?
-                        .print()
-                }
-                UpdateSubcommand::Categories(args) => {
-                    commands::update_categories(config, args.clone())
-                        .await?
-                        .print()
-                }
-                UpdateSubcommand::Autocats(args) => commands::update_autocats(config, args.clone())
+        Command::Update(update_args) => match update_args.entity() {
+            UpdateSubcommand::Transactions(args) => {
+                commands::update_transactions(config, *args.clone())
                     .await?
-                    .print(),
+                    .print()
             }
-        }
+            UpdateSubcommand::Categories(args) => commands::update_categories(config, args.clone())
+                .await?
+                .print(),
+            UpdateSubcommand::Autocats(args) => commands::update_autocats(config, args.clone())
+                .await?
+                .print(),
+        },
 
-        Command::Delete(delete_args) => {
-            let config = Config::load(home).await?;
-            match delete_args.entity() {
-                DeleteSubcommand::Transactions(args) => {
-                    commands::delete_transactions(config, args.clone())
-                        .await?
-                        .print()
-                }
-                DeleteSubcommand::Categories(args) => {
-                    commands::delete_categories(config, args.clone())
-                        .await?
-                        .print()
-                }
-                DeleteSubcommand::Autocats(args) => commands::delete_autocats(config, args.clone())
+        Command::Delete(delete_args) => match delete_args.entity() {
+            DeleteSubcommand::Transactions(args) => {
+                commands::delete_transactions(config, args.clone())
                     .await?
-                    .print(),
+                    .print()
             }
-        }
+            DeleteSubcommand::Categories(args) => commands::delete_categories(config, args.clone())
+                .await?
+                .print(),
+            DeleteSubcommand::Autocats(args) => commands::delete_autocats(config, args.clone())
+                .await?
+                .print(),
+        },
 
-        Command::Insert(insert_args) => {
-            let config = Config::load(home).await?;
-            match insert_args.entity() {
-                InsertSubcommand::Transaction(args) => {
-                    commands::insert_transaction(config, *args.clone())
-                        .await?
-                        .print()
-                }
-                InsertSubcommand::Category(args) => commands::insert_category(config, args.clone())
+        Command::Insert(insert_args) => match insert_args.entity() {
+            InsertSubcommand::Transaction(args) => {
+                commands::insert_transaction(config, *args.clone())
                     .await?
-                    .print(),
-                InsertSubcommand::Autocat(args) => commands::insert_autocat(config, *args.clone())
-                    .await?
-                    .print(),
+                    .print()
             }
-        }
+            InsertSubcommand::Category(args) => commands::insert_category(config, args.clone())
+                .await?
+                .print(),
+            InsertSubcommand::Autocat(args) => commands::insert_autocat(config, *args.clone())
+                .await?
+                .print(),
+        },
 
-        Command::Query(query_args) => {
-            let config = Config::load(home).await?;
-            commands::query(config, query_args.clone()).await?.print()
-        }
+        Command::Query(query_args) => commands::query(config, query_args.clone())
+            .await?
+            .print_as(output, stdout)?,
 
         Command::Schema(schema_args) => {
-            let config = Config::load(home).await?;
             commands::schema(config, schema_args.clone()).await?.print()
         }
+
+        Command::InstitutionTotals(totals_args) => {
+            commands::institution_totals(config, totals_args.clone())
+                .await?
+                .print_as(output, stdout)?
+        }
+
+        Command::ListTransactions(list_args) => {
+            commands::list_transactions(config, list_args.clone())
+                .await?
+                .print_as(output, stdout)?
+        }
+
+        Command::ListAutocats(_) => commands::list_autocats(config)
+            .await?
+            .print_as(output, stdout)?,
+
+        Command::Recategorize(recategorize_args) => commands::recategorize(
+            config,
+            recategorize_args.from.clone(),
+            recategorize_args.to.clone(),
+            recategorize_args.dry_run,
+        )
+        .await?
+        .print(),
+
+        Command::MergeCategories(merge_args) => {
+            commands::merge_categories(config, merge_args.from.clone(), merge_args.into.clone())
+                .await?
+                .print_as(output, stdout)?
+        }
+
+        Command::CategorizeUncategorized(categorize_args) => {
+            commands::categorize_uncategorized(config, categorize_args.dry_run)
+                .await?
+                .print_as(output, stdout)?
+        }
+
+        Command::MoveAutocat(move_args) => {
+            commands::move_autocat(config, move_args.id.clone(), move_args.position)
+                .await?
+                .print()
+        }
+
+        Command::DiagnoseFormulas(_) => commands::diagnose_formulas(config, mode)
+            .await?
+            .print_as(output, stdout)?,
+
+        Command::CreateBackup(_) => commands::create_backup(config).await?.print(),
+
+        Command::Diff(diff_args) => commands::diff_backups(config, diff_args.clone())
+            .await?
+            .print(),
+
+        Command::Maintenance(_) => commands::maintenance(config).await?.print(),
+
+        Command::Validate(_) => commands::validate(config).await?.print_as(output, stdout)?,
+
+        Command::ReconcileIds(_) => commands::reconcile_ids(config)
+            .await?
+            .print_as(output, stdout)?,
+
+        Command::SyncHistory(history_args) => commands::sync_history(config, history_args.clone())
+            .await?
+            .print_as(output, stdout)?,
+
+        Command::ListConflicts(_) => commands::list_conflicts(config, mode)
+            .await?
+            .print_as(output, stdout)?,
+
+        Command::ResolveConflict(resolve_args) => {
+            commands::resolve_conflict(config, mode, resolve_args.clone())
+                .await?
+                .print()
+        }
     };
     Ok(())
 }
 
 /// Initializes the tracing subscriber.
-pub fn init_logger(level: LevelFilter) {
+pub fn init_logger(level: LevelFilter, format: LogFormat, color: bool) {
+    subscriber(level, format, color, std::io::stderr).init();
+}
+
+/// Creates a subscriber that writes log lines in the given `format` to `writer`, with ANSI color
+/// only if `color` is true.
+fn subscriber<W>(
+    level: LevelFilter,
+    format: LogFormat,
+    color: bool,
+    writer: W,
+) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
     let filter = match std::env::var("RUST_LOG").ok() {
         Some(_) => {
             // RUST_LOG exists; use it.
@@ -153,8 +284,102 @@ pub fn init_logger(level: LevelFilter) {
         }
     };
 
-    tracing_subscriber::fmt()
+    let builder = tracing_subscriber::fmt()
         .with_env_filter(filter)
-        .with_writer(std::io::stderr)
-        .init();
+        .with_ansi(color)
+        .with_writer(writer);
+    match format {
+        LogFormat::Text => Box::new(builder.finish()),
+        LogFormat::Json => Box::new(builder.json().finish()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+    use tracing::info;
+
+    /// A writer that collects log output in memory.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for Buffer {
+        type Writer = Buffer;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    /// Runs `list-autocats` against `home`, with JSON output, and returns what it wrote to stdout.
+    async fn list_autocats_stdout(home: &std::path::Path, quiet: bool) -> Vec<u8> {
+        let home = home.to_str().unwrap();
+        let mut args = vec!["tiller", "--tiller-home", home, "--output", "json"];
+        if quiet {
+            args.push("--quiet");
+        }
+        args.push("list-autocats");
+        let mut stdout = Vec::new();
+        main_inner(Args::parse_from(args), &mut stdout)
+            .await
+            .unwrap();
+        stdout
+    }
+
+    #[tokio::test]
+    async fn test_quiet_prints_nothing_to_stdout() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let home = dir.path().join("tiller");
+        let secret = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testing")
+            .join("client-secret.fake.json");
+        let url =
+            "https://docs.google.com/spreadsheets/d/7KpXm2RfZwNJgs84QhVYno5DU6iM9Wlr3bCzAv1txRpL";
+        commands::init(&home, &secret, url, false).await.unwrap();
+
+        let stdout = list_autocats_stdout(&home, false).await;
+        assert_eq!(String::from_utf8(stdout).unwrap().trim(), "[]");
+        assert!(list_autocats_stdout(&home, true).await.is_empty());
+    }
+
+    #[test]
+    fn test_json_log_lines_parse() {
+        let buffer = Buffer::default();
+        let subscriber = subscriber(LevelFilter::INFO, LogFormat::Json, false, buffer.clone());
+        tracing::subscriber::with_default(subscriber, || info!("Synced 20 transactions"));
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let line = output.lines().next().unwrap();
+        let json: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(json["level"], "INFO");
+        assert_eq!(json["fields"]["message"], "Synced 20 transactions");
+    }
+
+    #[test]
+    fn test_text_log_lines_without_color() {
+        let log = |color: bool| {
+            let buffer = Buffer::default();
+            let subscriber = subscriber(LevelFilter::INFO, LogFormat::Text, color, buffer.clone());
+            tracing::subscriber::with_default(subscriber, || info!("Synced 20 transactions"));
+            String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap()
+        };
+
+        let plain = log(false);
+        assert!(plain.contains("Synced 20 transactions"));
+        assert!(!plain.contains('\x1b'));
+        assert!(log(true).contains('\x1b'));
+    }
 }
@@ -1,4 +1,5 @@
 use crate::commands::Out;
+use crate::error::{ErrorType, TillerError};
 use rmcp::model::{CallToolResult, Content};
 use rmcp::ErrorData;
 use serde::Serialize;
@@ -10,6 +11,9 @@ where
     T: Debug + Clone + Serialize,
 {
     let mut content = vec![Content::text(out.message())];
+    for warning in out.warnings() {
+        content.push(Content::text(format!("Warning: {warning}")));
+    }
     if let Some(object) = out.structure() {
         match Content::json(object) {
             Ok(json) => content.push(json),
@@ -19,12 +23,87 @@ where
     content
 }
 
+/// The JSON content of a failed tool call, which identifies the type of error so that agents can
+/// tell, e.g., a database constraint failure from an authentication problem.
+#[derive(Debug, Serialize)]
+struct ToolError {
+    error_type: ErrorType,
+    code: i32,
+    message: String,
+}
+
+fn error_content(e: &TillerError) -> Vec<Content> {
+    let message = e.to_string();
+    let tool_error = ToolError {
+        error_type: e.error_type(),
+        code: e.error_type().code(),
+        message: message.clone(),
+    };
+    let mut content = vec![Content::text(message)];
+    match Content::json(tool_error) {
+        Ok(json) => content.push(json),
+        Err(e) => error!("Unable to serialize JSON error output: {e}"),
+    };
+    content
+}
+
 pub(super) fn tool_result<T>(result: crate::Result<Out<T>>) -> Result<CallToolResult, ErrorData>
 where
     T: Debug + Clone + Serialize,
 {
     Ok(match result {
         Ok(out) => CallToolResult::success(to_content(out)),
-        Err(e) => CallToolResult::error(vec![Content::text(e.to_string())]),
+        Err(e) => CallToolResult::error(error_content(&e)),
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::IntoResult;
+    use anyhow::anyhow;
+
+    /// The JSON content of the failed tool call for an error of type `error_type`.
+    fn error_json(error_type: ErrorType) -> serde_json::Value {
+        let result: crate::Result<Out<()>> =
+            Err(anyhow!("FOREIGN KEY constraint failed")).pub_result(error_type);
+        let result = tool_result(result).unwrap();
+        assert!(result.is_error.unwrap_or(false));
+        assert_eq!(result.content.len(), 2);
+        let json = result.content[1].as_text().unwrap();
+        serde_json::from_str(&json.text).unwrap()
+    }
+
+    #[test]
+    fn test_tool_errors_report_their_type() {
+        let database = error_json(ErrorType::Database);
+        assert_eq!(database["error_type"], "Database");
+        assert_eq!(database["code"], -32007);
+        assert!(database["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Database error: FOREIGN KEY constraint failed"));
+
+        let service = error_json(ErrorType::Service);
+        assert_eq!(service["error_type"], "Service");
+        assert_eq!(service["code"], -32002);
+        assert_ne!(database["code"], service["code"]);
+    }
+
+    #[test]
+    fn test_error_codes_are_distinct() {
+        let types = [
+            ErrorType::Request,
+            ErrorType::Service,
+            ErrorType::Internal,
+            ErrorType::Sync,
+            ErrorType::Auth,
+            ErrorType::Config,
+            ErrorType::Database,
+            ErrorType::Other,
+        ];
+        let codes: std::collections::BTreeSet<i32> = types.iter().map(ErrorType::code).collect();
+        assert_eq!(codes.len(), types.len());
+        assert!(codes.iter().all(|code| (-32099..=-32000).contains(code)));
+    }
+}
@@ -3,12 +3,14 @@
 //! This module provides an MCP server that exposes tiller functionality as tools
 //! for AI agent integration. The server communicates via JSON-RPC over stdio.
 
-/// Checks if the server has been initialized and returns an error if not.
+/// Checks if the server has been initialized and returns an error if not. Then waits for a permit
+/// to use the database, which is held until the tool returns.
 macro_rules! require_init {
     ($self:expr) => {
         if !$self.check_initialized().await {
             return Self::uninitialized();
         }
+        let _permit = $self.permit().await?;
     };
 }
 
@@ -24,7 +26,7 @@ use rmcp::transport::stdio;
 use rmcp::ErrorData as McpError;
 use rmcp::{tool_handler, ServerHandler, ServiceExt};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, RwLock, Semaphore, SemaphorePermit};
 use tracing::info;
 
 /// The tiller MCP server.
@@ -34,7 +36,10 @@ use tracing::info;
 pub struct TillerServer {
     initialized: Arc<Mutex<bool>>,
     mode: Mode,
-    config: Arc<Config>,
+    /// Replaced by the `reload_config` tool, so tool calls take a copy with `config()`.
+    config: Arc<RwLock<Config>>,
+    /// Bounds how many tool calls use the database at the same time, see `mcp_concurrency`.
+    permits: Arc<Semaphore>,
     tool_router: ToolRouter<TillerServer>,
 }
 
@@ -44,15 +49,29 @@ impl TillerServer {
         Self {
             initialized: Arc::new(Mutex::new(false)),
             mode,
-            config: Arc::new(config),
+            permits: Arc::new(Semaphore::new(config.mcp_concurrency())),
+            config: Arc::new(RwLock::new(config)),
             tool_router: Self::tool_router(),
         }
     }
 
+    /// The current configuration, as of the last `reload_config`.
+    async fn config(&self) -> Config {
+        self.config.read().await.clone()
+    }
+
     async fn check_initialized(&self) -> bool {
         *self.initialized.lock().await
     }
 
+    /// Waits until fewer than `mcp_concurrency` tool calls are using the database.
+    async fn permit(&self) -> Result<SemaphorePermit<'_>, McpError> {
+        self.permits
+            .acquire()
+            .await
+            .map_err(|e| McpError::internal_error(format!("Unable to wait for a turn: {e}"), None))
+    }
+
     fn uninitialized() -> Result<CallToolResult, McpError> {
         Ok(CallToolResult::error(vec![rmcp::model::Content::text(
             "You have not yet initialized the service. Please call __initialize_service__ first.",
@@ -139,6 +158,7 @@ mod tests {
     use crate::args::UpdateTransactionsArgs;
     use crate::test::TestEnv;
     use rmcp::ServiceExt;
+    use std::sync::atomic::{AtomicBool, Ordering};
     use tokio::io::duplex;
 
     /// Integration test for the MCP server using an in-memory transport.
@@ -215,8 +235,8 @@ mod tests {
 
         // Test 4: Call update_transaction tool
         // After sync_down, we have transactions in the database. Get one to update.
-        let tiller_data = env.config().db().get_tiller_data().await.unwrap();
-        let first_txn = &tiller_data.transactions.data()[0];
+        let transactions = env.config().db().get_transactions().await.unwrap();
+        let first_txn = &transactions.data()[0];
         let txn_id = first_txn.transaction_id.clone();
         let updates = crate::model::TransactionUpdates {
             note: Some("Updated via MCP".to_string()),
@@ -259,6 +279,239 @@ mod tests {
         );
     }
 
+    /// Tool calls beyond `mcp_concurrency` wait for a permit instead of failing, and no more than
+    /// `mcp_concurrency` of them run at the same time.
+    #[tokio::test]
+    async fn test_mcp_concurrency_limit() {
+        const LIMIT: usize = 2;
+        let (client_io, server_io) = duplex(4096);
+        let mut env = TestEnv::new().await;
+        env.set_config_value("mcp_concurrency", serde_json::json!(LIMIT))
+            .await;
+
+        let server = TillerServer::new(env.config(), Mode::Testing);
+        let permits = server.permits.clone();
+        assert_eq!(permits.available_permits(), LIMIT);
+        let server_handle = tokio::spawn(async move {
+            let service = server.serve(server_io).await.unwrap();
+            service.waiting().await.unwrap();
+        });
+        let client = ().serve(client_io).await.expect("Failed to create client");
+        let call = |name: &'static str| {
+            let peer = client.peer().clone();
+            tokio::spawn(async move {
+                peer.call_tool(rmcp::model::CallToolRequestParam {
+                    name: name.into(),
+                    arguments: None,
+                })
+                .await
+            })
+        };
+        let init_result = call("initialize_service").await.unwrap().unwrap();
+        assert!(!init_result.is_error.unwrap_or(false));
+
+        // While every permit is taken, none of the LIMIT + 1 calls gets to run
+        let held = permits
+            .clone()
+            .acquire_many_owned(LIMIT as u32)
+            .await
+            .unwrap();
+        let calls: Vec<_> = (0..=LIMIT).map(|_| call("validate")).collect();
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+        assert!(calls.iter().all(|call| !call.is_finished()));
+
+        // Once the permits are returned, watch how many of them the calls hold at once until every
+        // call has finished
+        drop(held);
+        let done = Arc::new(AtomicBool::new(false));
+        let watcher = {
+            let (permits, done) = (permits.clone(), done.clone());
+            tokio::spawn(async move {
+                let mut peak = 0;
+                while !done.load(Ordering::SeqCst) {
+                    peak = peak.max(LIMIT - permits.available_permits());
+                    tokio::task::yield_now().await;
+                }
+                peak
+            })
+        };
+        for call in calls {
+            let result = call.await.unwrap().expect("validate call failed");
+            assert!(
+                !result.is_error.unwrap_or(false),
+                "validate returned error: {:?}",
+                result.content
+            );
+        }
+        done.store(true, Ordering::SeqCst);
+        assert_eq!(watcher.await.unwrap(), LIMIT);
+        assert_eq!(permits.available_permits(), LIMIT);
+
+        drop(client);
+        tokio::time::timeout(std::time::Duration::from_secs(5), server_handle)
+            .await
+            .expect("Server timed out")
+            .expect("Server task panicked");
+    }
+
+    /// The get_instructions tool works whether or not the service is initialized, and does not
+    /// initialize it.
+    #[tokio::test]
+    async fn test_mcp_get_instructions() {
+        let (client_io, server_io) = duplex(4096);
+        let env = TestEnv::new().await;
+
+        let server = TillerServer::new(env.config(), Mode::Testing);
+        let initialized = server.initialized.clone();
+        let server_handle = tokio::spawn(async move {
+            let service = server.serve(server_io).await.unwrap();
+            service.waiting().await.unwrap();
+        });
+        let client = ().serve(client_io).await.expect("Failed to create client");
+        let call = |name: &'static str| {
+            client.call_tool(rmcp::model::CallToolRequestParam {
+                name: name.into(),
+                arguments: None,
+            })
+        };
+        let instructions = serde_json::json!([rmcp::model::Content::text(include_str!(
+            "docs/INSTRUCTIONS.md"
+        ))]);
+
+        let result = call("get_instructions").await.expect("call_tool failed");
+        assert!(!result.is_error.unwrap_or(false));
+        assert_eq!(serde_json::json!(result.content), instructions);
+        assert!(!*initialized.lock().await);
+
+        // Other tools still require initialize_service
+        let result = call("validate").await.expect("call_tool failed");
+        assert!(result.is_error.unwrap_or(false));
+
+        let result = call("initialize_service").await.expect("call_tool failed");
+        assert!(!result.is_error.unwrap_or(false));
+        assert!(*initialized.lock().await);
+
+        let result = call("get_instructions").await.expect("call_tool failed");
+        assert_eq!(serde_json::json!(result.content), instructions);
+        assert!(*initialized.lock().await);
+
+        drop(client);
+        tokio::time::timeout(std::time::Duration::from_secs(5), server_handle)
+            .await
+            .expect("Server timed out")
+            .expect("Server task panicked");
+    }
+
+    /// Edits to `config.json` take effect once the reload_config tool is called.
+    #[tokio::test]
+    async fn test_mcp_reload_config() {
+        let (client_io, server_io) = duplex(4096);
+        let mut env = TestEnv::new().await;
+        let backup_copies = env.config().backup_copies();
+
+        let server = TillerServer::new(env.config(), Mode::Testing);
+        let config = server.config.clone();
+        let server_handle = tokio::spawn(async move {
+            let service = server.serve(server_io).await.unwrap();
+            service.waiting().await.unwrap();
+        });
+        let client = ().serve(client_io).await.expect("Failed to create client");
+
+        env.set_config_value("backup_copies", serde_json::json!(backup_copies + 3))
+            .await;
+        assert_eq!(config.read().await.backup_copies(), backup_copies);
+
+        for name in ["initialize_service", "reload_config"] {
+            let result = client
+                .call_tool(rmcp::model::CallToolRequestParam {
+                    name: name.into(),
+                    arguments: None,
+                })
+                .await
+                .expect("call_tool failed");
+            assert!(
+                !result.is_error.unwrap_or(false),
+                "{name} returned error: {:?}",
+                result.content
+            );
+        }
+        assert_eq!(config.read().await.backup_copies(), backup_copies + 3);
+
+        // A config file that cannot be parsed is reported and the previous config stays in use
+        std::fs::write(env.config().config_path(), "{").unwrap();
+        let result = client
+            .call_tool(rmcp::model::CallToolRequestParam {
+                name: "reload_config".into(),
+                arguments: None,
+            })
+            .await
+            .expect("call_tool failed");
+        assert!(result.is_error.unwrap_or(false));
+        assert_eq!(config.read().await.backup_copies(), backup_copies + 3);
+
+        drop(client);
+        tokio::time::timeout(std::time::Duration::from_secs(5), server_handle)
+            .await
+            .expect("Server timed out")
+            .expect("Server task panicked");
+    }
+
+    /// Calls the create_backup tool and checks that the backup files are written to the
+    /// configured backups directory.
+    #[tokio::test]
+    async fn test_mcp_create_backup() {
+        let (client_io, server_io) = duplex(4096);
+
+        let env = TestEnv::new().await;
+        env.insert_test_transaction("txn-001").await;
+        let config = env.config();
+        let backups_dir = config.backups().to_path_buf();
+
+        let server_handle =
+            tokio::spawn(
+                async move { run_server(config, Mode::Testing, Io::Mock(server_io)).await },
+            );
+        let client = ().serve(client_io).await.expect("Failed to create client");
+
+        for name in ["initialize_service", "create_backup"] {
+            let result = client
+                .call_tool(rmcp::model::CallToolRequestParam {
+                    name: name.into(),
+                    arguments: None,
+                })
+                .await
+                .expect("call_tool failed");
+            assert!(
+                !result.is_error.unwrap_or(false),
+                "{name} returned error: {:?}",
+                result.content
+            );
+        }
+
+        let backup_files: Vec<String> = std::fs::read_dir(&backups_dir)
+            .unwrap()
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect();
+        assert!(
+            backup_files.iter().any(|f| f.starts_with("tiller.sqlite.")),
+            "SQLite backup should be created. Found: {backup_files:?}"
+        );
+        assert!(
+            backup_files
+                .iter()
+                .any(|f| f.starts_with("snapshot.") && f.ends_with(".json")),
+            "JSON snapshot should be created. Found: {backup_files:?}"
+        );
+
+        drop(client);
+        let server_result = tokio::time::timeout(std::time::Duration::from_secs(5), server_handle)
+            .await
+            .expect("Server timed out")
+            .expect("Server task panicked");
+        assert!(server_result.is_ok());
+    }
+
     /// Queries MCP tool definitions and writes them to `.ignore/mcp_tools.txt`.
     /// This provides a human-readable dump of the tool schemas for inspection.
     #[tokio::test]
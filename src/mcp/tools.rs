@@ -1,13 +1,16 @@
 //! Implementation of the sync_up and sync_down commands for MCP
 
 use crate::args::{
-    DeleteAutoCatsArgs, DeleteCategoriesArgs, DeleteTransactionsArgs, InsertAutoCatArgs,
-    InsertCategoryArgs, InsertTransactionArgs, QueryArgs, SchemaArgs, UpdateAutoCatsArgs,
-    UpdateCategoriesArgs, UpdateTransactionsArgs,
+    CategorizeUncategorizedArgs, DeleteAutoCatsArgs, DeleteCategoriesArgs, DeleteTransactionsArgs,
+    InsertAutoCatArgs, InsertCategoryArgs, InsertTransactionArgs, InstitutionTotalsArgs,
+    ListTransactionsArgs, MergeCategoriesArgs, MoveAutocatArgs, QueryArgs, RecategorizeArgs,
+    ResolveConflictArgs, SchemaArgs, SyncHistoryArgs, UpdateAutoCatsArgs, UpdateCategoriesArgs,
+    UpdateTransactionsArgs,
 };
-use crate::commands::{self, FormulasMode};
+use crate::commands::{self, FormulasMode, Out, SyncUpStrategy};
 use crate::mcp::mcp_utils::tool_result;
 use crate::mcp::TillerServer;
+use crate::model::TransactionColumn;
 use rmcp::handler::server::wrapper::Parameters;
 use rmcp::model::CallToolResult;
 use rmcp::ErrorData as McpError;
@@ -29,6 +32,25 @@ pub struct SyncUpParams {
     /// back), or 'ignore' (skip formulas, write values only). Default is 'unknown'.
     #[serde(default)]
     pub formulas: FormulasMode,
+
+    /// How to write to the sheet: 'replace' (clear each tab and rewrite it from the local
+    /// database) or 'append_only' (only add transactions created locally, with `user-` IDs, below
+    /// the last row, leaving existing rows and formulas untouched). Default is 'replace'.
+    #[serde(default)]
+    pub strategy: SyncUpStrategy,
+
+    /// Only write these columns of the Transactions tab, e.g. `["category", "note"]`. The sheet
+    /// is read first and only these columns of its rows are overwritten with the local values, so
+    /// the other columns, rows that are not in the local database and the other tabs keep what the
+    /// sheet has. New transactions are not added. Cannot be combined with 'append_only'. Default
+    /// is every column.
+    #[serde(default)]
+    pub only: Vec<TransactionColumn>,
+
+    /// Perform all checks and return the ranges that would be cleared and written, without
+    /// changing the sheet. Default is false.
+    #[serde(default)]
+    pub dry_run: bool,
 }
 
 #[tool_router(vis = "pub(super)")]
@@ -36,8 +58,7 @@ impl TillerServer {
     #[tool]
     /// Initialize the tiller MCP service for this session and return usage instructions. You
     /// **MUST** call this **ONCE** before using other tools so that you have the full usage
-    /// instructions. You **MAY** call it more than once if you have forgotten the usage
-    /// instructions.
+    /// instructions. If you have forgotten the usage instructions, call `get_instructions`.
     async fn initialize_service(&self) -> Result<CallToolResult, McpError> {
         let mut initialized = self.initialized.lock().await;
         *initialized = true;
@@ -46,6 +67,16 @@ impl TillerServer {
         )]))
     }
 
+    #[tool]
+    /// Return the usage instructions again without changing the state of the service. Unlike
+    /// `initialize_service`, this may be called at any time and does not initialize the service.
+    async fn get_instructions(&self) -> Result<CallToolResult, McpError> {
+        info!("MCP: get_instructions called");
+        Ok(CallToolResult::success(vec![rmcp::model::Content::text(
+            include_str!("docs/INSTRUCTIONS.md"),
+        )]))
+    }
+
     /// Download Transactions, Categories, and AutoCat data from the configured Tiller Google
     /// Sheet to the local SQLite database. Creates a backup first.
     ///
@@ -77,7 +108,7 @@ impl TillerServer {
     async fn sync_down(&self) -> Result<CallToolResult, McpError> {
         require_init!(self);
         info!("MCP: sync_down called");
-        let config = (*self.config).clone();
+        let config = self.config().await;
         let out = commands::sync_down(config, self.mode).await;
         tool_result(out)
     }
@@ -100,7 +131,8 @@ impl TillerServer {
     /// 2. **SQLite backup** (`tiller.sqlite.YYYY-MM-DD-NNN`): Creates a timestamped copy of the
     ///    local database.
     /// 3. **Google Sheet copy**: Uses the Drive API to create a full copy of the spreadsheet
-    ///    named `tiller-backup-YYYY-MM-DD-HHMMSS`.
+    ///    named `tiller-backup-YYYY-MM-DD-HHMMSS`. Skipped when `drive_backup` is false in
+    ///    `config.json`.
     ///
     /// # Conflict Detection
     ///
@@ -141,6 +173,19 @@ impl TillerServer {
     /// - The local database must contain transactions. Run `sync down` first if empty.
     /// - Authentication must be valid.
     ///
+    /// # Append Only
+    ///
+    /// With `strategy="append_only"`, only transactions created locally with `insert_transaction`
+    /// (IDs starting with `user-`) that are not yet in the sheet are written, below the last row of
+    /// the Transactions tab. Nothing is cleared, so conflict detection and the formula checks are
+    /// skipped and rows edited in the sheet are kept.
+    ///
+    /// # Dry Run
+    ///
+    /// With `dry_run=true`, the tool runs every check above and returns the exact ranges it would
+    /// clear (e.g. `Transactions!A2:Q`) and the rows and columns it would write to each range,
+    /// without backing up, clearing or writing anything.
+    ///
     /// # Verification
     ///
     /// After writing, the tool re-fetches row counts from each sheet tab and verifies they match
@@ -153,12 +198,21 @@ impl TillerServer {
         require_init!(self);
 
         info!(
-            "MCP: sync_up called with force={}, formulas={}",
-            params.force, params.formulas
+            "MCP: sync_up called with force={}, formulas={}, strategy={}, only={:?}, dry_run={}",
+            params.force, params.formulas, params.strategy, params.only, params.dry_run
         );
 
-        let config = (*self.config).clone();
-        let out = commands::sync_up(config, self.mode, params.force, params.formulas).await;
+        let config = self.config().await;
+        let out = commands::sync_up(
+            config,
+            self.mode,
+            params.force,
+            params.formulas,
+            params.strategy,
+            params.only,
+            params.dry_run,
+        )
+        .await;
         tool_result(out)
     }
 
@@ -173,12 +227,19 @@ impl TillerServer {
     /// - `ids`: One or more transaction IDs to update. All specified transactions will receive the
     ///   same field updates.
     /// - `updates`: The fields to update. Only fields with values will be modified; unspecified
-    ///   fields remain unchanged. See `TransactionUpdates` for available fields.
+    ///   fields remain unchanged. See `TransactionUpdates` for available fields. Assigning a
+    ///   non-empty `category` sets `categorized_date` to now, unless it is also given. Use
+    ///   `append_note` instead of `note` to add a line to the existing note rather than replace it.
+    /// - `keep_going`: Optional. When true, update each transaction on its own and report the ones
+    ///   that fail, instead of rolling back every change when one fails. Defaults to false.
     ///
     /// # Returns
     ///
-    /// On success, returns a message indicating how many transactions were updated and a JSON array
-    /// of the updated transaction objects.
+    /// On success, returns a message indicating how many transactions were updated and a JSON
+    /// object with:
+    /// - `succeeded`: The updated transaction objects.
+    /// - `failed`: The `id` and `reason` of each transaction that could not be updated. Always
+    ///   empty unless `keep_going` is true.
     ///
     /// # Example
     ///
@@ -207,7 +268,7 @@ impl TillerServer {
     ) -> Result<CallToolResult, McpError> {
         require_init!(self);
 
-        let config = (*self.config).clone();
+        let config = self.config().await;
         let out = commands::update_transactions(config, args).await;
         tool_result(out)
     }
@@ -226,6 +287,10 @@ impl TillerServer {
     /// updates all references in transactions and autocat rules. This is a safe operation that
     /// maintains data integrity.
     ///
+    /// Renaming a category to the name of another existing category fails with "target category
+    /// already exists; use merge". Set `merge` to true to move the transactions and autocat rules
+    /// of the renamed category to the existing one and delete the renamed category instead.
+    ///
     /// # Parameters
     ///
     /// - `name`: The name of the category to update (this is the primary key).
@@ -235,11 +300,20 @@ impl TillerServer {
     ///   - `group`: The group this category belongs to (e.g., "Food", "Transportation")
     ///   - `type`: Category type ("Expense", "Income", or "Transfer")
     ///   - `hide_from_reports`: Set to "Hide" to exclude from reports
+    ///   - `other_fields`: Custom columns such as monthly budgets. Only the given columns are
+    ///     changed; the category's other custom columns keep their values.
+    /// - `merge`: Optional. When true, a rename onto an existing category merges into it, and the
+    ///   other updates are applied to that category. Defaults to false.
+    /// - `keep_going`: Optional. When true, update each category on its own and report the ones
+    ///   that fail, instead of rolling back every change when one fails. Defaults to false.
     ///
     /// # Returns
     ///
-    /// On success, returns a message indicating the category was updated and a JSON object of
-    /// the updated category.
+    /// On success, returns a message indicating how many categories were updated and a JSON object
+    /// with:
+    /// - `succeeded`: The updated category objects.
+    /// - `failed`: The `id` and `reason` of each category that could not be updated. Always empty
+    ///   unless `keep_going` is true.
     ///
     /// # Example
     ///
@@ -260,6 +334,25 @@ impl TillerServer {
     ///   "category": "Groceries"
     /// }
     /// ```
+    ///
+    /// Fold "Dining" into the existing "Restaurants" category:
+    ///
+    /// ```json
+    /// {
+    ///   "name": "Dining",
+    ///   "category": "Restaurants",
+    ///   "merge": true
+    /// }
+    /// ```
+    ///
+    /// Set one month's budget, leaving the other months unchanged:
+    ///
+    /// ```json
+    /// {
+    ///   "name": "Groceries",
+    ///   "other_fields": { "Feb 2024": "$600.00" }
+    /// }
+    /// ```
     #[tool]
     async fn update_categories(
         &self,
@@ -267,7 +360,7 @@ impl TillerServer {
     ) -> Result<CallToolResult, McpError> {
         require_init!(self);
 
-        let config = (*self.config).clone();
+        let config = self.config().await;
         let out = commands::update_categories(config, args).await;
         tool_result(out)
     }
@@ -305,11 +398,16 @@ impl TillerServer {
     ///   - `amount_equals`: Exact amount to match
     ///   - `description_equals`: Exact match for Description
     ///   - `full_description_contains`: Text to search for in Full Description
+    /// - `keep_going`: Optional. When true, update each rule on its own and report the ones
+    ///   that fail, instead of rolling back every change when one fails. Defaults to false.
     ///
     /// # Returns
     ///
-    /// On success, returns a message indicating the rule was updated and a JSON object of
-    /// the updated AutoCat rule (including its ID).
+    /// On success, returns a message indicating how many rules were updated and a JSON object
+    /// with:
+    /// - `succeeded`: The updated AutoCat rules, including their IDs.
+    /// - `failed`: The `id` and `reason` of each rule that could not be updated. Always empty
+    ///   unless `keep_going` is true.
     ///
     /// # Example
     ///
@@ -329,7 +427,7 @@ impl TillerServer {
     ) -> Result<CallToolResult, McpError> {
         require_init!(self);
 
-        let config = (*self.config).clone();
+        let config = self.config().await;
         let out = commands::update_autocats(config, args).await;
         tool_result(out)
     }
@@ -347,11 +445,16 @@ impl TillerServer {
     /// # Parameters
     ///
     /// - `ids`: One or more transaction IDs to delete. All specified transactions will be removed.
+    /// - `keep_going`: Optional. When true, delete each transaction on its own and report the ones
+    ///   that fail, instead of rolling back every change when one fails. Defaults to false.
     ///
     /// # Returns
     ///
-    /// On success, returns a message indicating how many transactions were deleted and a JSON array
-    /// of the deleted transaction IDs.
+    /// On success, returns a message indicating how many transactions were deleted and a JSON
+    /// object with:
+    /// - `succeeded`: The deleted transaction IDs.
+    /// - `failed`: The `id` and `reason` of each transaction that could not be deleted. Always
+    ///   empty unless `keep_going` is true.
     ///
     /// # Errors
     ///
@@ -383,7 +486,7 @@ impl TillerServer {
     ) -> Result<CallToolResult, McpError> {
         require_init!(self);
 
-        let config = (*self.config).clone();
+        let config = self.config().await;
         let out = commands::delete_transactions(config, args).await;
         tool_result(out)
     }
@@ -407,11 +510,16 @@ impl TillerServer {
     /// # Parameters
     ///
     /// - `names`: One or more category names to delete.
+    /// - `keep_going`: Optional. When true, delete each category on its own and report the ones
+    ///   that fail, instead of rolling back every change when one fails. Defaults to false.
     ///
     /// # Returns
     ///
-    /// On success, returns a message indicating how many categories were deleted and a JSON array
-    /// of the deleted category names.
+    /// On success, returns a message indicating how many categories were deleted and a JSON object
+    /// with:
+    /// - `succeeded`: The deleted category names.
+    /// - `failed`: The `id` and `reason` of each category that could not be deleted. Always empty
+    ///   unless `keep_going` is true.
     ///
     /// # Errors
     ///
@@ -444,7 +552,7 @@ impl TillerServer {
     ) -> Result<CallToolResult, McpError> {
         require_init!(self);
 
-        let config = (*self.config).clone();
+        let config = self.config().await;
         let out = commands::delete_categories(config, args).await;
         tool_result(out)
     }
@@ -465,11 +573,16 @@ impl TillerServer {
     /// # Parameters
     ///
     /// - `ids`: One or more AutoCat rule IDs to delete.
+    /// - `keep_going`: Optional. When true, delete each rule on its own and report the ones
+    ///   that fail, instead of rolling back every change when one fails. Defaults to false.
     ///
     /// # Returns
     ///
-    /// On success, returns a message indicating how many AutoCat rules were deleted and a JSON
-    /// array of the deleted rule IDs.
+    /// On success, returns a message indicating how many rules were deleted and a JSON object
+    /// with:
+    /// - `succeeded`: The deleted rule IDs.
+    /// - `failed`: The `id` and `reason` of each rule that could not be deleted. Always empty
+    ///   unless `keep_going` is true.
     ///
     /// # Errors
     ///
@@ -501,7 +614,7 @@ impl TillerServer {
     ) -> Result<CallToolResult, McpError> {
         require_init!(self);
 
-        let config = (*self.config).clone();
+        let config = self.config().await;
         let out = commands::delete_autocats(config, args).await;
         tool_result(out)
     }
@@ -519,6 +632,8 @@ impl TillerServer {
     /// - `amount`: Transaction value where income and credits are positive; expenses and debits
     ///   are negative. **Required.**
     /// - All other fields are optional. See the `InsertTransactionArgs` schema for the full list.
+    ///   When left blank, `month` and `week` are derived from `date`, `date_added` is set to
+    ///   today, and `categorized_date` is set to now if a `category` is given.
     ///
     /// # Foreign Key Constraints
     ///
@@ -561,7 +676,7 @@ impl TillerServer {
     ) -> Result<CallToolResult, McpError> {
         require_init!(self);
 
-        let config = (*self.config).clone();
+        let config = self.config().await;
         let out = commands::insert_transaction(config, args).await;
         tool_result(out)
     }
@@ -614,7 +729,7 @@ impl TillerServer {
     ) -> Result<CallToolResult, McpError> {
         require_init!(self);
 
-        let config = (*self.config).clone();
+        let config = self.config().await;
         let out = commands::insert_category(config, args).await;
         tool_result(out)
     }
@@ -655,6 +770,11 @@ impl TillerServer {
     /// - `full_description_contains`: Text to search for in Full Description.
     /// - `amount_contains`: Text pattern to search for in Amount column.
     ///
+    /// **Options**:
+    /// - `idempotent`: When true, and a rule with the same category, overrides and filter criteria
+    ///   already exists, return that rule's ID instead of inserting a duplicate. Defaults to
+    ///   false.
+    ///
     /// # Foreign Key Constraints
     ///
     /// If a `category` is specified, it must reference an existing category in the database.
@@ -663,8 +783,8 @@ impl TillerServer {
     ///
     /// # Returns
     ///
-    /// On success, returns a message indicating the AutoCat rule was inserted and the generated
-    /// rule ID.
+    /// On success, returns a message indicating the AutoCat rule was inserted, or already existed,
+    /// and the rule ID.
     ///
     /// # Example
     ///
@@ -694,7 +814,7 @@ impl TillerServer {
     ) -> Result<CallToolResult, McpError> {
         require_init!(self);
 
-        let config = (*self.config).clone();
+        let config = self.config().await;
         let out = commands::insert_autocat(config, args).await;
         tool_result(out)
     }
@@ -741,7 +861,7 @@ impl TillerServer {
     ///
     /// ```json
     /// {
-    ///   "sql": "SELECT category, SUM(amount) as total FROM transactions WHERE amount < 0 GROUP BY category ORDER BY total",
+    ///   "sql": "SELECT category, SUM(amount) as total FROM transactions WHERE CAST(amount AS REAL) < 0 GROUP BY category ORDER BY total",
     ///   "format": "markdown"
     /// }
     /// ```
@@ -752,7 +872,7 @@ impl TillerServer {
     ) -> Result<CallToolResult, McpError> {
         require_init!(self);
 
-        let config = (*self.config).clone();
+        let config = self.config().await;
         let out = commands::query(config, args).await;
         tool_result(out)
     }
@@ -822,10 +942,480 @@ impl TillerServer {
     ) -> Result<CallToolResult, McpError> {
         require_init!(self);
 
-        let config = (*self.config).clone();
+        let config = self.config().await;
         let out = commands::schema(config, args).await;
         tool_result(out)
     }
+
+    /// List transactions from the local database, one page at a time, in the same order as they
+    /// appear in the sheet.
+    ///
+    /// Prefer this over `query` when you want whole transaction records. The result includes the
+    /// `total` number of transactions matching the filter, so you can tell whether more pages
+    /// remain: there are more when `offset` plus the number of returned rows is less than `total`.
+    ///
+    /// # Parameters
+    ///
+    /// - `category`: Optional. Only include transactions with exactly this category.
+    /// - `only_uncategorized`: Optional, default `false`. When `true`, only include transactions
+    ///   that have no category.
+    /// - `account`: Optional. Only include transactions with exactly this account name.
+    /// - `account_id`: Optional. Only include transactions with exactly this account ID. Prefer it
+    ///   over `account`, since account names can be changed in the sheet.
+    /// - `institution`: Optional. Only include transactions with exactly this institution.
+    /// - `description_contains`: Optional. Only include transactions whose description contains
+    ///   this text (case-insensitive).
+    /// - `other_field`: Optional. Only include transactions with a value in this custom column,
+    ///   i.e. a column that is not part of the standard Tiller schema, such as `Location`.
+    /// - `other_field_contains`: Optional. Only include transactions whose custom column value
+    ///   contains this text (case-insensitive). Without `other_field`, any custom column may match.
+    /// - `limit`: Optional. The maximum number of transactions to return. Omit to return all.
+    /// - `offset`: Optional. The number of matching transactions to skip. Defaults to 0.
+    /// - `columns`: Optional. Only include these columns in each row, e.g.
+    ///   `["date", "description", "amount", "category"]`. `transaction_id` is always included.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns a JSON object with:
+    /// - `rows`: The transactions in this page. When `columns` is set, each row is an object of
+    ///   only those columns, keyed by header, e.g. `{"Transaction ID": "...", "Date": "..."}`.
+    /// - `total`: The number of transactions matching the filter, across all pages.
+    /// - `offset`: The offset that was used.
+    /// - `limit`: The limit that was used, if any.
+    ///
+    /// # Example
+    ///
+    /// ```json
+    /// {
+    ///   "category": "Groceries",
+    ///   "limit": 50,
+    ///   "offset": 100
+    /// }
+    /// ```
+    #[tool]
+    async fn list_transactions(
+        &self,
+        Parameters(args): Parameters<ListTransactionsArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        require_init!(self);
+
+        let config = self.config().await;
+        let out = commands::list_transactions(config, args).await;
+        tool_result(out)
+    }
+
+    /// List the AutoCat rules from the local database in the order Tiller processes them.
+    ///
+    /// Tiller applies AutoCat rules from top to bottom, and when more than one rule matches a
+    /// transaction, the first one wins. Rules are listed in their order in the AutoCat tab as of
+    /// the last `sync_down`, followed by the rules added locally in the order they were added.
+    /// `sync_up` writes them to the sheet in the same order.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns a JSON array of rules, each with:
+    /// - `id`: The rule's ID, for use with `update_autocats` and `delete_autocats`. IDs are
+    ///   reassigned by each `sync_down`.
+    /// - `row`: The rule's category, overrides and filter criteria.
+    #[tool]
+    async fn list_autocats(&self) -> Result<CallToolResult, McpError> {
+        require_init!(self);
+        info!("MCP: list_autocats called");
+        let config = self.config().await;
+        let out = commands::list_autocats(config).await;
+        tool_result(out)
+    }
+
+    /// Sum transaction amounts grouped by institution (bank) from the local database.
+    ///
+    /// Use this for a quick view of where money sits or flows by financial institution.
+    /// Transactions with a blank institution are grouped under the key `"(unknown)"` rather than
+    /// being dropped.
+    ///
+    /// # Parameters
+    ///
+    /// - `date_from`: Optional. Only include transactions on or after this date.
+    /// - `date_to`: Optional. Only include transactions on or before this date.
+    ///
+    /// Dates may be given in the sheet's format (e.g. `10/20/2025`) or as ISO 8601
+    /// (e.g. `2025-10-20`).
+    ///
+    /// # Returns
+    ///
+    /// On success, returns a message listing each institution's total and a JSON object mapping
+    /// institution names to summed amounts.
+    ///
+    /// # Example
+    ///
+    /// ```json
+    /// {
+    ///   "date_from": "2025-10-01",
+    ///   "date_to": "2025-10-31"
+    /// }
+    /// ```
+    #[tool]
+    async fn institution_totals(
+        &self,
+        Parameters(args): Parameters<InstitutionTotalsArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        require_init!(self);
+
+        let config = self.config().await;
+        let out = commands::institution_totals(config, args).await;
+        tool_result(out)
+    }
+
+    /// Move every transaction from one category to another in the local database.
+    ///
+    /// Use this to merge categories, e.g. moving all "Coffee Shops" transactions into
+    /// "Restaurants". Every transaction whose category is `from` is changed to `to` in a single
+    /// database transaction: either all are changed or none are. Changes are NOT automatically
+    /// synced to the Google Sheet - call `sync_up` to upload local changes.
+    ///
+    /// This does not change AutoCat rules or delete the `from` category.
+    ///
+    /// # Parameters
+    ///
+    /// - `from`: The category that transactions are currently assigned to.
+    /// - `to`: The category to assign the transactions to. It must already exist.
+    /// - `dry_run`: Optional, default `false`. When `true`, report how many transactions would be
+    ///   changed without changing anything.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns a message describing the change and the number of transactions
+    /// affected.
+    ///
+    /// # Example
+    ///
+    /// ```json
+    /// {
+    ///   "from": "Coffee Shops",
+    ///   "to": "Restaurants",
+    ///   "dry_run": true
+    /// }
+    /// ```
+    #[tool]
+    async fn recategorize(
+        &self,
+        Parameters(args): Parameters<RecategorizeArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        require_init!(self);
+
+        let config = self.config().await;
+        let out = commands::recategorize(config, args.from, args.to, args.dry_run).await;
+        tool_result(out)
+    }
+
+    /// Merge one or more categories into another category in the local database.
+    ///
+    /// Moves the transactions and AutoCat rules of each `from` category to the `into` category,
+    /// then deletes the `from` categories. Everything is changed in a single database
+    /// transaction: either all categories are merged or none are. Changes are NOT automatically
+    /// synced to the Google Sheet - call `sync_up` to upload local changes.
+    ///
+    /// Use this instead of `recategorize` when the old categories should go away. To fold a
+    /// single category into another while renaming it, `update_categories` with `merge` also
+    /// works.
+    ///
+    /// # Parameters
+    ///
+    /// - `from`: The categories to merge. They must exist and are deleted afterwards.
+    /// - `into`: The category to merge them into. It must already exist and cannot be one of the
+    ///   `from` categories.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns a message describing the change and a JSON object with:
+    /// - `merged`: The categories that were merged and deleted.
+    /// - `into`: The category they were merged into.
+    /// - `transactions`: The number of transactions moved.
+    /// - `autocats`: The number of AutoCat rules moved.
+    ///
+    /// # Example
+    ///
+    /// ```json
+    /// {
+    ///   "from": ["Coffee Shops", "Dining"],
+    ///   "into": "Restaurants"
+    /// }
+    /// ```
+    #[tool]
+    async fn merge_categories(
+        &self,
+        Parameters(args): Parameters<MergeCategoriesArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        require_init!(self);
+
+        let config = self.config().await;
+        let out = commands::merge_categories(config, args.from, args.into).await;
+        tool_result(out)
+    }
+
+    /// Move an AutoCat rule to a new position in the processing order in the local database.
+    ///
+    /// Tiller applies AutoCat rules from top to bottom and the first matching rule wins, so moving
+    /// a rule up gives it priority over the rules it passes. The rules in between shift by one.
+    /// Changes are NOT automatically synced to the Google Sheet - call `sync_up` to upload local
+    /// changes.
+    ///
+    /// # Parameters
+    ///
+    /// - `id`: The ID of the AutoCat rule to move, as returned by `list_autocats`.
+    /// - `position`: The 0-based position to move the rule to. Position 0 is processed first.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns a message describing the change and the AutoCat rules in their new
+    /// order.
+    ///
+    /// # Example
+    ///
+    /// ```json
+    /// {
+    ///   "id": "3",
+    ///   "position": 0
+    /// }
+    /// ```
+    #[tool]
+    async fn reorder_autocat(
+        &self,
+        Parameters(args): Parameters<MoveAutocatArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        require_init!(self);
+
+        let config = self.config().await;
+        let out = commands::move_autocat(config, args.id, args.position).await;
+        tool_result(out)
+    }
+
+    /// Apply the AutoCat rules to every transaction in the local database that has no category.
+    ///
+    /// Rules are tried in the order Tiller processes them, as listed by `list_autocats`, and the
+    /// first rule with a category that matches a transaction gives it that category. Transactions
+    /// that already have a category are never changed. All transactions are updated in a single
+    /// database transaction. Changes are NOT automatically synced to the Google Sheet - call
+    /// `sync_up` to upload local changes.
+    ///
+    /// # Parameters
+    ///
+    /// - `dry_run`: Optional, default `false`. When `true`, report the transactions that would be
+    ///   categorized without changing anything.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns a JSON object with:
+    /// - `categorized`: The transactions that were categorized, each with its `transaction_id`,
+    ///   the `category` it was given and the `autocat_id` of the rule that matched.
+    /// - `remaining`: The number of transactions that are still uncategorized.
+    #[tool]
+    async fn categorize_uncategorized(
+        &self,
+        Parameters(args): Parameters<CategorizeUncategorizedArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        require_init!(self);
+        info!("MCP: categorize_uncategorized called");
+        let config = self.config().await;
+        let out = commands::categorize_uncategorized(config, args.dry_run).await;
+        tool_result(out)
+    }
+
+    /// Back up the local SQLite database on demand, without syncing. Use this to take a safety
+    /// snapshot before making risky changes.
+    ///
+    /// # Backups Created
+    ///
+    /// 1. **SQLite backup** (`tiller.sqlite.YYYY-MM-DD-NNN`): Timestamped copy of the database.
+    /// 2. **JSON snapshot** (`snapshot.YYYY-MM-DD-NNN.json`): The current contents of the local
+    ///    database (not the Google Sheet).
+    ///
+    /// Old backups are rotated according to the `backup_copies` setting, just as they are during
+    /// syncs. Neither the Google Sheet nor the database is modified.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns the backups directory and the file names of the two backups.
+    #[tool]
+    async fn create_backup(&self) -> Result<CallToolResult, McpError> {
+        require_init!(self);
+        info!("MCP: create_backup called");
+        let config = self.config().await;
+        let out = commands::create_backup(config).await;
+        tool_result(out)
+    }
+
+    /// Check the local database for problems, without changing anything. Use this when other
+    /// tools fail on odd data, or before a `sync_up`.
+    ///
+    /// # Checks
+    ///
+    /// - `unknown_category`: A transaction's category does not exist in the categories table.
+    /// - `duplicate_id`: A transaction ID appears more than once in the Transactions tab as of the
+    ///   last `sync_down`. The database cannot hold duplicates, so `sync_down` fails until the
+    ///   sheet is fixed.
+    /// - `invalid_amount`: A transaction's amount is not a number.
+    /// - `invalid_date`: A transaction's date cannot be parsed.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns a JSON array of problems, empty if none were found, each with:
+    /// - `kind`: One of the checks above.
+    /// - `transaction_id`: The transaction with the problem.
+    /// - `detail`: A description of the problem.
+    #[tool]
+    async fn validate(&self) -> Result<CallToolResult, McpError> {
+        require_init!(self);
+        info!("MCP: validate called");
+        let config = self.config().await;
+        let out = commands::validate(config).await;
+        tool_result(out)
+    }
+
+    /// Merge transactions inserted locally (IDs starting with `user-`) into the transactions that
+    /// Tiller later added to the sheet for the same bank activity. Call this after a `sync_down`
+    /// when a transaction entered with `insert_transaction` has since been imported by Tiller, so
+    /// that it is not counted twice.
+    ///
+    /// A locally inserted transaction is merged when exactly one transaction from Tiller has the
+    /// same date, amount, description and account. The merged transaction keeps Tiller's ID and
+    /// fields, plus the category, note and tags entered locally where Tiller left them blank. The
+    /// locally inserted transaction is deleted. Ambiguous matches are left alone and returned as
+    /// warnings. Changes are NOT automatically synced to the Google Sheet - call `sync_up` to
+    /// upload local changes.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns a JSON array of the merged transactions, each with:
+    /// - `user_id`: The `user-` ID of the locally inserted transaction, which no longer exists.
+    /// - `transaction_id`: The ID that Tiller assigned, which the merged transaction keeps.
+    #[tool]
+    async fn reconcile_ids(&self) -> Result<CallToolResult, McpError> {
+        require_init!(self);
+        info!("MCP: reconcile_ids called");
+        let config = self.config().await;
+        let out = commands::reconcile_ids(config).await;
+        tool_result(out)
+    }
+
+    /// List transaction fields that were changed in the Google Sheet since the last `sync_down`.
+    ///
+    /// These are the changes that make `sync_up` refuse to run without `force`, or that `sync_up`
+    /// with `force` would overwrite. Use this with `resolve_conflict` to walk the user through
+    /// each change instead of forcing.
+    ///
+    /// Only transactions present both in the sheet and in the last sync-down are compared; rows
+    /// added to or removed from the sheet are not reported.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns a JSON array of conflicts, each with:
+    /// - `transaction_id`: The transaction with the changed field.
+    /// - `field`: The sheet header of the field, e.g. `Category`.
+    /// - `base`: The value at the last sync down.
+    /// - `remote`: The value now in the sheet.
+    /// - `local`: The value in the local database, or null if the transaction was deleted locally.
+    #[tool]
+    async fn list_conflicts(&self) -> Result<CallToolResult, McpError> {
+        require_init!(self);
+        info!("MCP: list_conflicts called");
+        let config = self.config().await;
+        let out = commands::list_conflicts(config, self.mode).await;
+        tool_result(out)
+    }
+
+    /// Resolve one conflict reported by `list_conflicts`.
+    ///
+    /// - `local`: Keep the value in the local database. The next `sync_up` writes it to the sheet.
+    /// - `remote`: Copy the sheet's value into the local database.
+    ///
+    /// Either way the conflict is no longer reported. Once every conflict is resolved, `sync_up`
+    /// runs without `force`, unless categories or AutoCat rules were also changed in the sheet.
+    ///
+    /// # Parameters
+    ///
+    /// - `transaction_id`: The ID of the transaction, as reported by `list_conflicts`.
+    /// - `field`: The field, exactly as reported by `list_conflicts`.
+    /// - `choose`: Either `"local"` or `"remote"`.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns the resolved conflict, where `local` is the value the local database
+    /// now holds.
+    ///
+    /// # Example
+    ///
+    /// ```json
+    /// {
+    ///   "transaction_id": "tx001a2b3c4d5e6f7g8h9i01",
+    ///   "field": "Category",
+    ///   "choose": "local"
+    /// }
+    /// ```
+    #[tool]
+    async fn resolve_conflict(
+        &self,
+        Parameters(args): Parameters<ResolveConflictArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        require_init!(self);
+
+        let config = self.config().await;
+        let out = commands::resolve_conflict(config, self.mode, args).await;
+        tool_result(out)
+    }
+
+    /// List the most recent `sync_up` and `sync_down` outcomes, newest first.
+    ///
+    /// Use this to find out when the local database was last synced with the Google Sheet, and
+    /// whether that sync succeeded. Dry runs of `sync_up` are not recorded.
+    ///
+    /// # Parameters
+    ///
+    /// - `limit`: Optional. The maximum number of entries to return. Defaults to 20.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns a JSON array of entries, each with:
+    /// - `id`: Increases with each sync.
+    /// - `finished_at`: When the sync finished, in RFC 3339 format.
+    /// - `direction`: `up` or `down`.
+    /// - `transactions`, `categories`, `autocats`, `balances`: The number of rows synced for each
+    ///   tab. All 0 when the sync failed.
+    /// - `success`: Whether the sync succeeded.
+    /// - `message`: The message of the sync, or its error if it failed.
+    #[tool]
+    async fn sync_history(
+        &self,
+        Parameters(args): Parameters<SyncHistoryArgs>,
+    ) -> Result<CallToolResult, McpError> {
+        require_init!(self);
+
+        let config = self.config().await;
+        let out = commands::sync_history(config, args).await;
+        tool_result(out)
+    }
+
+    /// Re-read the tiller `config.json` file so that edits made to it since the server started,
+    /// e.g. to `backup_copies`, take effect without restarting the server. Call this when the
+    /// user says they changed the configuration. `mcp_concurrency` still requires a restart.
+    ///
+    /// # Returns
+    ///
+    /// On success, returns a message naming the config file that was loaded. On failure, e.g.
+    /// when the file is not valid JSON, the previous configuration stays in use.
+    #[tool]
+    async fn reload_config(&self) -> Result<CallToolResult, McpError> {
+        require_init!(self);
+        info!("MCP: reload_config called");
+        let mut config = self.config.write().await;
+        let out = config.reload().await.map(|reloaded| {
+            *config = reloaded;
+            Out::<()>::new_message(format!(
+                "Reloaded the configuration from '{}'",
+                config.config_path().display()
+            ))
+        });
+        tool_result(out)
+    }
 }
 
 #[cfg(test)]
@@ -3,7 +3,6 @@
 //! This module provides the `Amount` type which wraps `Decimal` and handles
 //! parsing values that may or may not include a dollar and commas.
 
-use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -11,21 +10,96 @@ use std::borrow::Cow;
 use std::error::Error;
 use std::fmt;
 use std::fmt::{Debug, Display, Formatter};
+use std::iter::Sum;
 use std::str::FromStr;
 
 /// Represents how dollar amounts were (or should be) formatted.
 ///
 /// # Examples
-///  - `AmountFormat{ dollar: true, commas: true }` -> `-$60,000.00`
-///  - `AmountFormat{ dollar: false, commas: true }` -> `-60,000.00`
-///  - `AmountFormat{ dollar: false, commas: false }` -> `-60000.00`
-///  - `AmountFormat{ dollar: true, commas: false }` -> `-$60000.00`
+///  - `AmountFormat{ dollar: true, commas: true, parens: false }` -> `-$60,000.00`
+///  - `AmountFormat{ dollar: false, commas: true, parens: false }` -> `-60,000.00`
+///  - `AmountFormat{ dollar: false, commas: false, parens: false }` -> `-60000.00`
+///  - `AmountFormat{ dollar: true, commas: false, parens: false }` -> `-$60000.00`
+///  - `AmountFormat{ dollar: true, commas: true, parens: true }` -> `($60,000.00)`
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct AmountFormat {
     /// Whether a dollar sign is present in the formatting.
     dollar: bool,
     /// Whether commas are present as thousands separators in the formatting.
     commas: bool,
+    /// Whether negative amounts are written in parentheses, e.g. `($5.00)`, instead of with a
+    /// leading minus sign.
+    parens: bool,
+}
+
+impl AmountFormat {
+    /// Detects the format of an amount written as `s`, e.g. `-$87.43`, `($1,234.56)` or `12.50`.
+    /// Only the presence of a dollar sign, thousands separators and parentheses is considered, so
+    /// `s` does not need to be a valid amount.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tiller_sync::model::{Amount, AmountFormat};
+    /// # use std::str::FromStr;
+    /// let format = AmountFormat::detect("($1,234.56)");
+    /// let amount = Amount::from_str("-1234.56").unwrap();
+    /// assert_eq!(format.render(&amount), "($1,234.56)");
+    /// ```
+    pub fn detect(s: &str) -> AmountFormat {
+        let trimmed = s.trim();
+        AmountFormat {
+            dollar: trimmed.contains('$'),
+            commas: trimmed.contains(','),
+            parens: trimmed.len() > 1 && trimmed.starts_with('(') && trimmed.ends_with(')'),
+        }
+    }
+
+    /// Writes the value of `amount` using this format, ignoring the format held by `amount`.
+    ///
+    /// When the format has thousands separators, the value is written with exactly two decimal
+    /// places. Otherwise it is written with the precision it has.
+    pub fn render(&self, amount: &Amount) -> String {
+        let negative = amount.is_negative();
+        let num = amount.value().abs();
+
+        let dol = if self.dollar { "$" } else { "" };
+        let digits = if self.commas {
+            let mut rounded = num.round_dp(2);
+            rounded.rescale(2);
+            with_thousands_separators(&rounded.to_string())
+        } else {
+            num.to_string()
+        };
+
+        match (negative, self.parens) {
+            (false, _) => format!("{dol}{digits}"),
+            (true, false) => format!("-{dol}{digits}"),
+            (true, true) => format!("({dol}{digits})"),
+        }
+    }
+}
+
+/// Inserts a comma between each group of three digits in the integer part of `digits`, which must
+/// be an unsigned decimal number such as `1234567.89`.
+fn with_thousands_separators(digits: &str) -> String {
+    let (int_part, frac_part) = match digits.split_once('.') {
+        Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+        None => (digits, None),
+    };
+
+    let mut grouped = String::with_capacity(digits.len() + int_part.len() / 3);
+    for (ix, c) in int_part.chars().enumerate() {
+        if ix > 0 && (int_part.len() - ix) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+
+    match frac_part {
+        Some(frac_part) => format!("{grouped}.{frac_part}"),
+        None => grouped,
+    }
 }
 
 impl Default for AmountFormat {
@@ -38,6 +112,7 @@ impl Default for AmountFormat {
 const DEFAULT_FORMAT: AmountFormat = AmountFormat {
     dollar: true,
     commas: true,
+    parens: false,
 };
 
 /// Represents a dollar amount.
@@ -86,17 +161,19 @@ pub struct Amount {
 }
 
 impl Amount {
-    /// Creates a new Amount from a Decimal value with default `String` formatting.
+    /// Creates a new Amount from a Decimal value with default `String` formatting. A negative
+    /// zero, e.g. `-0.00`, is stored as zero.
     pub const fn new(value: Decimal) -> Self {
-        Self {
-            value,
-            format: DEFAULT_FORMAT,
-        }
+        Self::new_with_format(value, DEFAULT_FORMAT)
     }
 
-    /// Creates a new Amount from a Decimal value with default specified formatting.
+    /// Creates a new Amount from a Decimal value with default specified formatting. A negative
+    /// zero, e.g. `-0.00`, is stored as zero.
     pub const fn new_with_format(value: Decimal, format: AmountFormat) -> Self {
-        Self { value, format }
+        Self {
+            value: without_negative_zero(value),
+            format,
+        }
     }
 
     /// Returns the underlying Decimal value.
@@ -104,6 +181,11 @@ impl Amount {
         self.value
     }
 
+    /// Returns the format that the amount was parsed from, or will be written with.
+    pub fn format(&self) -> AmountFormat {
+        self.format
+    }
+
     /// Returns true if the amount is zero.
     pub fn is_zero(&self) -> bool {
         self.value().is_zero()
@@ -118,11 +200,52 @@ impl Amount {
     pub fn is_negative(&self) -> bool {
         self.value().is_sign_negative()
     }
+
+    /// Writes the amount for display in a column of amounts: exactly two decimal places, a leading
+    /// minus sign if it is negative, and no dollar sign or thousands separators, e.g. `-87.40`.
+    /// Unlike `to_string`, this ignores the format the amount was parsed from, so that amounts from
+    /// different rows line up when right-aligned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use tiller_sync::model::Amount;
+    /// # use std::str::FromStr;
+    /// let amount = Amount::from_str("-$1,234.5").unwrap();
+    /// assert_eq!(amount.to_display_string(), "-1234.50");
+    /// ```
+    pub fn to_display_string(&self) -> String {
+        let mut rounded = self.value().round_dp(2);
+        rounded.rescale(2);
+        if rounded.is_zero() {
+            rounded.set_sign_positive(true);
+        }
+        rounded.to_string()
+    }
 }
 
-/// An error that can occur when parsing strings into `Decimal` values.
+/// Returns `value`, except that a negative zero such as `-0` or `-0.00` becomes zero with the same
+/// scale. `Decimal` keeps the sign of zero, which would otherwise be written as `-$0.00`.
+const fn without_negative_zero(value: Decimal) -> Decimal {
+    if value.is_zero() {
+        Decimal::from_parts(0, 0, 0, false, value.scale())
+    } else {
+        value
+    }
+}
+
+/// An error that can occur when parsing strings into `Amount` values.
 pub struct AmountError(rust_decimal::Error);
 
+impl AmountError {
+    /// Creates an error for the amount written as `s`, which is invalid because of `reason`.
+    fn invalid(s: &str, reason: &str) -> Self {
+        AmountError(rust_decimal::Error::from(format!(
+            "Invalid amount '{s}': {reason}"
+        )))
+    }
+}
+
 impl Debug for AmountError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         Debug::fmt(&self.0, f)
@@ -145,8 +268,6 @@ impl FromStr for Amount {
     type Err = AmountError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut dollar_sign = false;
-
         // Remove whitespace
         let trimmed = s.trim();
 
@@ -155,63 +276,79 @@ impl FromStr for Amount {
             return Ok(Amount::default());
         }
 
-        // Remove dollar sign if present
-        let without_dollar = if let Some(after_minus) = trimmed.strip_prefix('-') {
-            // Negative number: could be "-$50.00" or "-50.00"
-            if let Some(after_dollar) = after_minus.strip_prefix('$') {
-                dollar_sign = true;
-                format!("-{after_dollar}")
-            } else {
-                trimmed.to_string()
-            }
-        } else if let Some(after_dollar) = trimmed.strip_prefix('$') {
-            // Positive number with dollar sign: "$50.00"
-            dollar_sign = true;
-            after_dollar.to_string()
+        let format = AmountFormat::detect(trimmed);
+
+        // Parentheses and a leading minus sign both denote a negative amount
+        let (negative, unsigned) = if format.parens {
+            let inner = trimmed
+                .strip_prefix('(')
+                .and_then(|t| t.strip_suffix(')'))
+                .unwrap_or(trimmed);
+            (true, inner.trim())
+        } else if let Some(rest) = trimmed.strip_prefix('-') {
+            (true, rest)
         } else {
-            // No dollar sign
-            trimmed.to_string()
+            (false, trimmed.strip_prefix('+').unwrap_or(trimmed))
         };
 
-        // Remove commas (thousand separators)
-        let without_commas = without_dollar.replace(',', "");
-        let commas = without_commas.len() < without_dollar.len();
-
-        // Parse the decimal value
-        let value = Decimal::from_str(&without_commas).map_err(AmountError)?;
-        Ok(Amount {
-            value,
-            format: AmountFormat {
-                dollar: dollar_sign,
-                commas,
-            },
-        })
-    }
-}
+        // A dollar sign may only come before the number, after any sign
+        let number = unsigned.strip_prefix('$').unwrap_or(unsigned);
+        if number.contains('$') {
+            return Err(AmountError::invalid(s, "'$' must come before the number"));
+        }
+        if number.starts_with(['-', '+']) {
+            return Err(AmountError::invalid(
+                s,
+                "the sign must come first and cannot be inside parentheses",
+            ));
+        }
 
-impl fmt::Display for Amount {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let (sign, num) = if self.is_negative() {
-            (String::from("-"), self.value().abs())
+        // Remove the commas, which may only separate the thousands of the integer part
+        let digits = if number.contains(',') {
+            without_thousands_separators(number).ok_or_else(|| {
+                AmountError::invalid(s, "',' must separate groups of three digits")
+            })?
         } else {
-            (String::new(), self.value())
+            number.to_string()
         };
 
-        let dol = if self.format.dollar {
-            String::from("$")
-        } else {
-            String::new()
-        };
+        let sign = if negative { "-" } else { "" };
+        let value = Decimal::from_str(&format!("{sign}{digits}")).map_err(AmountError)?;
+        Ok(Amount::new_with_format(value, format))
+    }
+}
 
-        if self.format.commas {
-            write!(
-                f,
-                "{sign}{dol}{}",
-                format_num::format_num!(",.2", num.to_f64().unwrap_or_default())
-            )
-        } else {
-            write!(f, "{sign}{dol}{num}")
+/// Removes the commas from `number`, e.g. `1,234,567.89` becomes `1234567.89`. Returns `None` if a
+/// comma does not separate a group of three digits in the integer part.
+fn without_thousands_separators(number: &str) -> Option<String> {
+    let (int_part, frac_part) = match number.split_once('.') {
+        Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+        None => (number, None),
+    };
+
+    let mut groups = int_part.split(',');
+    let first = groups.next().unwrap_or_default();
+    if first.is_empty() || first.len() > 3 {
+        return None;
+    }
+    let mut digits = first.to_string();
+    for group in groups {
+        if group.len() != 3 {
+            return None;
         }
+        digits.push_str(group);
+    }
+
+    match frac_part {
+        Some(frac_part) if frac_part.contains(',') => None,
+        Some(frac_part) => Some(format!("{digits}.{frac_part}")),
+        None => Some(digits),
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format.render(self))
     }
 }
 
@@ -230,8 +367,37 @@ impl<'de> Deserialize<'de> for Amount {
     where
         D: Deserializer<'de>,
     {
-        let s = String::deserialize(deserializer)?;
-        Amount::from_str(&s).map_err(serde::de::Error::custom)
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
+/// Accepts an amount as a string, e.g. `"-$87.43"`, or as a JSON number, e.g. `-87.43`.
+struct AmountVisitor;
+
+impl serde::de::Visitor<'_> for AmountVisitor {
+    type Value = Amount;
+
+    fn expecting(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("a decimal amount as a string or a number")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Amount, E> {
+        Amount::from_str(v).map_err(E::custom)
+    }
+
+    fn visit_i64<E: serde::de::Error>(self, v: i64) -> Result<Amount, E> {
+        Ok(Amount::new(Decimal::from(v)))
+    }
+
+    fn visit_u64<E: serde::de::Error>(self, v: u64) -> Result<Amount, E> {
+        Ok(Amount::new(Decimal::from(v)))
+    }
+
+    fn visit_f64<E: serde::de::Error>(self, v: f64) -> Result<Amount, E> {
+        // The shortest representation of the float, e.g. 12.5 rather than 12.4999999...
+        Decimal::from_str(&v.to_string())
+            .map(Amount::new)
+            .map_err(E::custom)
     }
 }
 
@@ -247,6 +413,20 @@ impl From<Amount> for Decimal {
     }
 }
 
+impl Sum for Amount {
+    /// Sums the values of the amounts. The result has the default formatting.
+    fn sum<I: Iterator<Item = Amount>>(iter: I) -> Self {
+        Amount::new(iter.map(|a| a.value()).sum())
+    }
+}
+
+impl<'a> Sum<&'a Amount> for Amount {
+    /// Sums the values of the amounts. The result has the default formatting.
+    fn sum<I: Iterator<Item = &'a Amount>>(iter: I) -> Self {
+        Amount::new(iter.map(|a| a.value()).sum())
+    }
+}
+
 impl JsonSchema for Amount {
     fn schema_name() -> Cow<'static, str> {
         "Amount".into()
@@ -319,6 +499,25 @@ mod tests {
         assert_eq!(amount.to_string(), "$0.00");
     }
 
+    #[test]
+    fn test_to_display_string() {
+        let display = |s: &str| Amount::from_str(s).unwrap().to_display_string();
+        assert_eq!(display("-87.4"), "-87.40");
+        assert_eq!(display("0"), "0.00");
+        assert_eq!(display("1234.5"), "1234.50");
+        assert_eq!(display("$1,234.567"), "1234.57");
+        assert_eq!(display("($5)"), "-5.00");
+    }
+
+    #[test]
+    fn test_to_display_string_of_rounded_to_zero_has_no_sign() {
+        assert_eq!(
+            Amount::from_str("-0.001").unwrap().to_display_string(),
+            "0.00"
+        );
+        assert_eq!(Amount::from_str("-0").unwrap().to_display_string(), "0.00");
+    }
+
     #[test]
     fn test_serialize() {
         let amount = Amount::new(Decimal::from_str("50.00").unwrap());
@@ -326,6 +525,26 @@ mod tests {
         assert_eq!(json, "\"$50.00\"");
     }
 
+    #[test]
+    fn test_serialize_is_quoted_decimal() {
+        let amount = Amount::from_str("-87.43").unwrap();
+        let json = serde_json::to_string(&amount).unwrap();
+        assert_eq!(json, "\"-87.43\"");
+        let value = serde_json::to_value(amount).unwrap();
+        assert!(value.is_string());
+    }
+
+    #[test]
+    fn test_deserialize_string_or_number() {
+        let from_string: Amount = serde_json::from_str("\"12.50\"").unwrap();
+        let from_number: Amount = serde_json::from_str("12.50").unwrap();
+        let from_integer: Amount = serde_json::from_str("-12").unwrap();
+        assert_eq!(from_string.value(), Decimal::from_str("12.50").unwrap());
+        assert_eq!(from_number.value(), Decimal::from_str("12.50").unwrap());
+        assert_eq!(from_integer.value(), Decimal::from_str("-12").unwrap());
+        assert_eq!(from_string.to_string(), "12.50");
+    }
+
     #[test]
     fn test_deserialize_with_dollar() {
         let json = "\"$50.00\"";
@@ -421,6 +640,21 @@ mod tests {
         assert_eq!(amount.value(), Decimal::from_str("1000.00").unwrap());
     }
 
+    #[test]
+    fn test_sum() {
+        let amounts = vec![
+            Amount::from_str("-$1,000.10").unwrap(),
+            Amount::from_str("250.05").unwrap(),
+            Amount::from_str("$0.05").unwrap(),
+        ];
+        let total: Amount = amounts.iter().sum();
+        assert_eq!(total.value(), Decimal::from_str("-750.00").unwrap());
+        assert_eq!(total.to_string(), "-$750.00");
+
+        let empty: Amount = Vec::<Amount>::new().into_iter().sum();
+        assert!(empty.is_zero());
+    }
+
     #[test]
     fn test_parse_retain_commas_no_dollarsign() {
         let s = "1,000,000.00";
@@ -436,4 +670,96 @@ mod tests {
         let actual = amount.to_string();
         assert_eq!(actual, s);
     }
+
+    #[test]
+    fn test_detect_then_render_round_trips() {
+        for s in [
+            "-$87.43",
+            "($1,234.56)",
+            "12.50",
+            "$1,234,567.89",
+            "-1,000.00",
+            "(12.50)",
+            "$0.00",
+        ] {
+            let format = AmountFormat::detect(s);
+            let amount = Amount::from_str(s).unwrap();
+            assert_eq!(format, amount.format());
+            assert_eq!(format.render(&amount), s);
+        }
+    }
+
+    #[test]
+    fn test_detect() {
+        let format = AmountFormat::detect("($1,234.56)");
+        assert!(format.dollar);
+        assert!(format.commas);
+        assert!(format.parens);
+
+        let format = AmountFormat::detect("12.50");
+        assert!(!format.dollar);
+        assert!(!format.commas);
+        assert!(!format.parens);
+    }
+
+    #[test]
+    fn test_parse_parentheses_is_negative() {
+        let amount = Amount::from_str("($1,234.56)").unwrap();
+        assert_eq!(amount.value(), Decimal::from_str("-1234.56").unwrap());
+        assert!(amount.is_negative());
+    }
+
+    #[test]
+    fn test_parse_dollar_sign_only_before_the_number() {
+        assert_eq!(Amount::from_str("-$5").unwrap().to_string(), "-$5");
+        for s in ["1$2", "5$", "$$5", "$-5", "-5$"] {
+            assert!(Amount::from_str(s).is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn test_parse_commas_only_as_thousands_separators() {
+        for s in [
+            "1,2",
+            "1,2345",
+            ",123",
+            "1234,567",
+            "1,,234",
+            "1,234.5,6",
+            "$,5",
+        ] {
+            assert!(Amount::from_str(s).is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn test_parse_sign_inside_parentheses_is_an_error() {
+        for s in ["(-5)", "(+5)", "(-$5.00)", "($-5.00)"] {
+            assert!(Amount::from_str(s).is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn test_render_with_another_format() {
+        let amount = Amount::from_str("-87.43").unwrap();
+        assert_eq!(AmountFormat::detect("($1.00)").render(&amount), "($87.43)");
+        assert_eq!(AmountFormat::detect("$1.00").render(&amount), "-$87.43");
+        assert_eq!(AmountFormat::detect("1,000.00").render(&amount), "-87.43");
+    }
+
+    #[test]
+    fn test_negative_zero_is_zero() {
+        for s in ["-0", "($0.00)", "-0.00", "-$0", "(0)"] {
+            let amount = Amount::from_str(s).unwrap();
+            assert_eq!(amount.value(), Decimal::ZERO, "{s}");
+            assert!(amount.value().is_sign_positive(), "{s}");
+            assert!(!amount.is_negative(), "{s}");
+            assert_eq!(amount.to_display_string(), "0.00", "{s}");
+            assert!(!amount.to_string().contains(['-', '(']), "{s}");
+        }
+
+        let amount = Amount::new(-Decimal::new(0, 2));
+        assert!(amount.value().is_sign_positive());
+        assert_eq!(amount.to_string(), "$0.00");
+    }
 }
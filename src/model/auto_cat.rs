@@ -1,6 +1,6 @@
 use crate::error::Res;
 use crate::model::items::{Item, Items};
-use crate::model::Amount;
+use crate::model::{Amount, Transaction};
 use crate::utils;
 use anyhow::{bail, Context};
 use clap::Parser;
@@ -110,6 +110,48 @@ impl AutoCat {
             self.other_fields.insert(key, val);
         }
     }
+
+    /// True if `transaction` meets every filter criterion of this rule. A rule without any
+    /// criteria matches nothing.
+    ///
+    /// The "contains" criteria are case-insensitive and match if any of their keywords is found,
+    /// see `parse_contains_keywords`. `description_equals` is case-insensitive. The amount
+    /// criteria compare the absolute value of the transaction amount, and the range given by
+    /// `amount_min` and `amount_max` is inclusive.
+    pub fn matches(&self, transaction: &Transaction) -> bool {
+        let contains = |criterion: &str, value: &str| {
+            let value = value.to_lowercase();
+            parse_contains_keywords(criterion)
+                .iter()
+                .any(|keyword| value.contains(&keyword.to_lowercase()))
+        };
+        let amount = transaction.amount.value().abs();
+        let text = |criterion: &str, value: &str| {
+            (!criterion.is_empty()).then(|| contains(criterion, value))
+        };
+
+        // Each criterion that is set gives whether it is met
+        let criteria = [
+            text(&self.description_contains, &transaction.description),
+            text(&self.account_contains, &transaction.account),
+            text(&self.institution_contains, &transaction.institution),
+            text(
+                &self.full_description_contains,
+                &transaction.full_description,
+            ),
+            text(&self.amount_contains, &transaction.amount.to_string()),
+            (!self.description_equals.is_empty()).then(|| {
+                self.description_equals.trim().to_lowercase()
+                    == transaction.description.trim().to_lowercase()
+            }),
+            self.amount_min.map(|min| amount >= min.value().abs()),
+            self.amount_max.map(|max| amount <= max.value().abs()),
+            self.amount_equals
+                .map(|equals| amount == equals.value().abs()),
+        ];
+        let mut criteria = criteria.into_iter().flatten().peekable();
+        criteria.peek().is_some() && criteria.all(|met| met)
+    }
 }
 
 impl Item for AutoCat {
@@ -337,3 +379,120 @@ pub struct AutoCatUpdates {
     #[arg(long = "other-field", value_parser = utils::parse_key_val)]
     pub other_fields: BTreeMap<String, String>,
 }
+
+/// Splits a "contains" filter, such as `description_contains`, into the keywords that are OR-ed
+/// together. Keywords are separated by commas, and a keyword in double quotes may itself contain
+/// commas, e.g. `"coffee shop, downtown",starbucks` yields `coffee shop, downtown` and
+/// `starbucks`. Whitespace around each keyword is trimmed and empty keywords are dropped.
+pub(crate) fn parse_contains_keywords(field: &str) -> Vec<String> {
+    let mut keywords = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut push = |current: &mut String| {
+        let keyword = current.trim();
+        if !keyword.is_empty() {
+            keywords.push(keyword.to_string());
+        }
+        current.clear();
+    };
+
+    for c in field.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => push(&mut current),
+            _ => current.push(c),
+        }
+    }
+    push(&mut current);
+    keywords
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_contains_keywords() {
+        assert_eq!(parse_contains_keywords("starbucks"), vec!["starbucks"]);
+        assert_eq!(
+            parse_contains_keywords("\"coffee shop\",starbucks"),
+            vec!["coffee shop", "starbucks"]
+        );
+        assert_eq!(
+            parse_contains_keywords("\"coffee, tea\", \"bakery\""),
+            vec!["coffee, tea", "bakery"]
+        );
+        assert_eq!(
+            parse_contains_keywords("amazon, \"whole foods\" ,target"),
+            vec!["amazon", "whole foods", "target"]
+        );
+        assert_eq!(parse_contains_keywords("a,,b, "), vec!["a", "b"]);
+        assert!(parse_contains_keywords("").is_empty());
+    }
+
+    #[test]
+    fn test_matches() {
+        let mut transaction = Transaction::default();
+        transaction.description = "STARBUCKS #2847".to_string();
+        transaction.account = "Credit Card 1".to_string();
+        transaction.amount = Amount::from_str("-$6.75").unwrap();
+
+        let rule = |f: fn(&mut AutoCat)| {
+            let mut rule = AutoCat {
+                category: "Coffee Shops".to_string(),
+                ..Default::default()
+            };
+            f(&mut rule);
+            rule
+        };
+        let amount = |s: &str| Some(Amount::from_str(s).unwrap());
+
+        // A rule without criteria matches nothing
+        assert!(!rule(|_| {}).matches(&transaction));
+
+        assert!(rule(|r| r.description_contains = "starbucks".into()).matches(&transaction));
+        assert!(
+            rule(|r| r.description_contains = "\"peet's\",Starbucks".into()).matches(&transaction)
+        );
+        assert!(!rule(|r| r.description_contains = "peet's".into()).matches(&transaction));
+        assert!(rule(|r| r.description_equals = "starbucks #2847".into()).matches(&transaction));
+        assert!(!rule(|r| r.description_equals = "starbucks".into()).matches(&transaction));
+        assert!(rule(|r| r.amount_contains = "6.75".into()).matches(&transaction));
+
+        // Amounts are compared by absolute value, and the range is inclusive
+        assert!(rule(|r| r.amount_equals = amount("6.75")).matches(&transaction));
+        assert!(rule(|r| {
+            r.amount_min = amount("6.75");
+            r.amount_max = amount("10");
+        })
+        .matches(&transaction));
+        assert!(!rule(|r| r.amount_min = amount("7")).matches(&transaction));
+        assert!(!rule(|r| r.amount_max = amount("5")).matches(&transaction));
+
+        // Every criterion that is set must be met
+        assert!(rule(|r| {
+            r.description_contains = "starbucks".into();
+            r.account_contains = "credit".into();
+        })
+        .matches(&transaction));
+        assert!(!rule(|r| {
+            r.description_contains = "starbucks".into();
+            r.account_contains = "checking".into();
+        })
+        .matches(&transaction));
+    }
+
+    #[test]
+    fn test_header_only_is_empty_not_an_error() {
+        let auto_cats = AutoCats::parse(
+            vec![vec!["Category", "Description Contains"]],
+            Vec::<Vec<&str>>::new(),
+        )
+        .unwrap();
+        assert!(auto_cats.data().is_empty());
+        assert_eq!(auto_cats.mapping().len(), 2);
+
+        let err = AutoCats::parse(Vec::<Vec<&str>>::new(), Vec::<Vec<&str>>::new());
+        assert!(err.is_err());
+    }
+}
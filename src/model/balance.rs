@@ -0,0 +1,169 @@
+use crate::error::Res;
+use crate::model::items::{Item, Items};
+use crate::model::Amount;
+use anyhow::bail;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// Represents a collection of balance snapshots from a Balance History sheet, including the header
+/// mapping. See tiller documentation for more information about the Balance History sheet:
+/// https://help.tiller.com/en/articles/2218587-balance-history-sheet
+pub type Balances = Items<Balance>;
+
+/// Represents a single row from the Balance History sheet. Each row is a snapshot of an account's
+/// balance on a given date.
+#[derive(Default, Debug, Clone, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Balance {
+    /// The account name as it appears on your bank's website or your custom nickname from Tiller
+    /// Console.
+    pub(crate) account: String,
+
+    /// The date on which the balance was recorded.
+    pub(crate) date: String,
+
+    /// The balance of the account on `date`. Liabilities, such as credit cards, are negative.
+    pub(crate) balance: Amount,
+
+    /// A unique ID assigned to your accounts by Tiller's systems. Matches the `account_id` of
+    /// transactions from the same account.
+    pub(crate) account_id: String,
+
+    /// Columns not modeled explicitly, e.g. Time, Institution, Type and Class.
+    pub(crate) other_fields: BTreeMap<String, String>,
+
+    /// Row position from last sync down (0-indexed); None for locally-added rows.
+    /// Used for formula preservation during sync up.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) original_order: Option<u64>,
+}
+
+impl Item for Balance {
+    fn set_with_header<S1, S2>(&mut self, header: S1, value: S2) -> Res<()>
+    where
+        S1: AsRef<str>,
+        S2: Into<String>,
+    {
+        let header = header.as_ref();
+        let value = value.into();
+
+        match BalanceColumn::from_header(header) {
+            Ok(col) => match col {
+                BalanceColumn::Account => self.account = value,
+                BalanceColumn::Date => self.date = value,
+                BalanceColumn::Balance => self.balance = Amount::from_str(&value)?,
+                BalanceColumn::AccountId => self.account_id = value,
+            },
+            Err(_) => {
+                let _ = self.other_fields.insert(header.to_string(), value);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_by_header(&self, header: &str) -> String {
+        match BalanceColumn::from_header(header) {
+            Ok(col) => match col {
+                BalanceColumn::Account => self.account.clone(),
+                BalanceColumn::Date => self.date.clone(),
+                BalanceColumn::Balance => self.balance.to_string(),
+                BalanceColumn::AccountId => self.account_id.clone(),
+            },
+            Err(_) => self.other_fields.get(header).cloned().unwrap_or_default(),
+        }
+    }
+
+    fn set_original_order(&mut self, original_order: u64) {
+        self.original_order = Some(original_order)
+    }
+
+    fn get_original_order(&self) -> Option<u64> {
+        self.original_order
+    }
+}
+
+/// Represents the known columns that should be found in the Balance History sheet.
+#[derive(Default, Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BalanceColumn {
+    /// The account name as it appears on your bank's website or your custom nickname from Tiller
+    /// Console.
+    #[default]
+    Account,
+    /// The date on which the balance was recorded.
+    Date,
+    /// The balance of the account on the recorded date.
+    Balance,
+    /// A unique ID assigned to your accounts by Tiller's systems.
+    AccountId,
+}
+
+serde_plain::derive_display_from_serialize!(BalanceColumn);
+serde_plain::derive_fromstr_from_deserialize!(BalanceColumn);
+
+impl BalanceColumn {
+    pub fn from_header(header: impl AsRef<str>) -> Res<BalanceColumn> {
+        let header_str = header.as_ref();
+        match header_str {
+            ACCOUNT_STR => Ok(BalanceColumn::Account),
+            DATE_STR => Ok(BalanceColumn::Date),
+            BALANCE_STR => Ok(BalanceColumn::Balance),
+            ACCOUNT_ID_STR => Ok(BalanceColumn::AccountId),
+            bad => bail!("Invalid balance column name '{bad}'"),
+        }
+    }
+}
+
+pub(super) const ACCOUNT_STR: &str = "Account";
+pub(super) const DATE_STR: &str = "Date";
+pub(super) const BALANCE_STR: &str = "Balance";
+pub(super) const ACCOUNT_ID_STR: &str = "Account ID";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_parse_balances() {
+        let values = vec![
+            vec!["Date", "Time", "Account", "Account ID", "Balance", "Type"],
+            vec![
+                "10/20/2025",
+                "9:15 AM",
+                "Checking 1",
+                "acct002",
+                "$1,234.56",
+                "Depository",
+            ],
+            vec![
+                "10/20/2025",
+                "9:15 AM",
+                "Credit Card 1",
+                "acct001",
+                "-$512.10",
+                "Credit",
+            ],
+        ];
+        let balances = Balances::parse(values.clone(), values).unwrap();
+        assert_eq!(balances.data().len(), 2);
+
+        let checking = &balances.data()[0];
+        assert_eq!(checking.account, "Checking 1");
+        assert_eq!(checking.date, "10/20/2025");
+        assert_eq!(checking.account_id, "acct002");
+        assert_eq!(checking.balance.value(), Decimal::new(123456, 2));
+        assert_eq!(checking.other_fields.get("Time").unwrap(), "9:15 AM");
+        assert_eq!(checking.other_fields.get("Type").unwrap(), "Depository");
+        assert_eq!(checking.original_order, Some(0));
+
+        // Writing the rows back out reproduces the sheet, including the original amount format
+        let rows = balances.to_rows().unwrap();
+        assert_eq!(rows[1][4], "$1,234.56");
+        assert_eq!(rows[2][4], "-$512.10");
+        assert_eq!(rows[2][5], "Credit");
+    }
+}
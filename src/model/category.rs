@@ -40,7 +40,27 @@ pub struct Category {
     pub(crate) original_order: Option<u64>,
 }
 
+/// The values allowed in the `type` column of the Categories sheet, in addition to an empty value.
+pub(crate) const CATEGORY_TYPES: &[&str] = &["Expense", "Income", "Transfer"];
+
 impl Category {
+    /// Checks that the fields of `self` hold values that Tiller understands. Currently this checks
+    /// that `type` is one of [`CATEGORY_TYPES`] (ignoring case) or empty.
+    pub(crate) fn validate(&self) -> Res<()> {
+        Self::validate_type(&self.r#type)
+    }
+
+    /// Checks that `value` is allowed in the `type` column, see [`Category::validate`].
+    pub(crate) fn validate_type(value: &str) -> Res<()> {
+        if value.is_empty() || CATEGORY_TYPES.iter().any(|t| t.eq_ignore_ascii_case(value)) {
+            return Ok(());
+        }
+        bail!(
+            "Invalid category type '{value}'. The type must be one of {} or empty",
+            CATEGORY_TYPES.join(", ")
+        )
+    }
+
     /// Set any of the fields on `self` that are set in `update`.
     pub fn merge_updates(&mut self, update: CategoryUpdates) {
         if let Some(x) = update.category {
@@ -144,7 +164,7 @@ impl CategoryColumn {
 pub(super) const CATEGORY_STR: &str = "Category";
 pub(super) const GROUP_STR: &str = "Group";
 pub(super) const TYPE_STR: &str = "Type";
-pub(super) const HIDE_FROM_REPORTS_STR: &str = "Hide from Reports";
+pub(super) const HIDE_FROM_REPORTS_STR: &str = "Hide From Reports";
 
 /// The fields to update in a category row. Only set values will be changed, unset values will
 /// not be changed.
@@ -180,8 +200,81 @@ pub struct CategoryUpdates {
     #[arg(long)]
     pub hide_from_reports: Option<String>,
 
-    /// Custom columns not part of the standard Tiller schema.
+    /// Custom columns not part of the standard Tiller schema, such as the monthly budget columns
+    /// (e.g. "Jan 2024"). Each entry is merged into the category's stored custom columns: the
+    /// given columns are set and all other columns keep their values.
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     #[arg(long = "other-field", value_parser = utils::parse_key_val)]
     pub other_fields: BTreeMap<String, String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn category_with_type(r#type: &str) -> Category {
+        Category {
+            category: "Groceries".to_string(),
+            r#type: r#type.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_allowed_types() {
+        for r#type in [
+            "Expense", "Income", "Transfer", "expense", "INCOME", "transfer", "",
+        ] {
+            assert!(
+                category_with_type(r#type).validate().is_ok(),
+                "'{type}' should be valid"
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_invalid_type() {
+        let err = category_with_type("Expnse").validate().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid category type 'Expnse'. The type must be one of Expense, Income, Transfer or \
+            empty"
+        );
+    }
+
+    #[test]
+    fn test_merge_updates_patches_other_fields() {
+        let mut category = Category {
+            category: "Groceries".to_string(),
+            other_fields: BTreeMap::from([
+                ("Jan 2024".to_string(), "$500.00".to_string()),
+                ("Feb 2024".to_string(), "$450.00".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let updates = CategoryUpdates {
+            other_fields: BTreeMap::from([("Feb 2024".to_string(), "$600.00".to_string())]),
+            ..Default::default()
+        };
+
+        category.merge_updates(updates);
+
+        assert_eq!(category.other_fields.len(), 2);
+        assert_eq!(category.other_fields["Jan 2024"], "$500.00");
+        assert_eq!(category.other_fields["Feb 2024"], "$600.00");
+    }
+
+    #[test]
+    fn test_header_only_is_empty_not_an_error() {
+        let categories = Categories::parse(
+            vec![vec!["Category", "Group", "Type", "Hide From Reports"]],
+            Vec::<Vec<&str>>::new(),
+        )
+        .unwrap();
+        assert!(categories.data().is_empty());
+        assert_eq!(categories.mapping().len(), 4);
+
+        let err = Categories::parse(Vec::<Vec<&str>>::new(), Vec::<Vec<&str>>::new());
+        assert!(err.is_err());
+    }
+}
@@ -4,7 +4,7 @@ use anyhow::{bail, Context};
 use schemars::JsonSchema;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Debug;
 
 /// Represents the row data from a sheet, including the header mapping.
@@ -94,6 +94,9 @@ where
     ///
     /// These generics are confusing, but think of it like this: both `sheet_data` and
     /// `formula_data` are iterators into something that looks like `Vec<Vec<String>>`, i.e. rows.
+    ///
+    /// A sheet with a header row and no data rows, e.g. a new sheet, parses to an empty `Items`.
+    /// It is an error for `sheet_data` to have no header row at all.
     pub(crate) fn parse<S, R, I1, I2>(sheet_data: I1, formula_data: I2) -> Res<Self>
     where
         S: Into<String>,
@@ -193,6 +196,10 @@ where
         &self.data
     }
 
+    pub(crate) fn data_mut(&mut self) -> &mut Vec<I> {
+        &mut self.data
+    }
+
     pub fn mapping(&self) -> &Mapping {
         &self.mapping
     }
@@ -200,4 +207,29 @@ where
     pub fn formulas(&self) -> &BTreeMap<RowCol, String> {
         &self.formulas
     }
+
+    /// Returns true if `self` and `other` have the same headers and rows, whether or not their
+    /// formulas are the same.
+    pub(crate) fn values_eq(&self, other: &Self) -> bool {
+        self.mapping == other.mapping && self.data == other.data
+    }
+
+    pub(crate) fn formulas_mut(&mut self) -> &mut BTreeMap<RowCol, String> {
+        &mut self.formulas
+    }
+
+    /// Moves each formula from its row to the row that `old_to_new` maps it to, for when rows have
+    /// been removed or reordered. Formulas in rows that are missing from `old_to_new` are dropped,
+    /// since their rows no longer exist. Columns are unchanged.
+    pub(crate) fn remap_formulas(&mut self, old_to_new: &HashMap<usize, usize>) {
+        let formulas = std::mem::take(self.formulas_mut());
+        self.formulas = formulas
+            .into_iter()
+            .filter_map(|(RowCol(row, col), formula)| {
+                old_to_new
+                    .get(&row)
+                    .map(|&new_row| (RowCol::new(new_row, col), formula))
+            })
+            .collect();
+    }
 }
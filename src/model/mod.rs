@@ -1,19 +1,26 @@
 //! Types that represent the core data model, such as `Transaction` and `Category`.
 mod amount;
 mod auto_cat;
+mod balance;
 mod category;
 mod items;
 mod mapping;
 mod row_col;
+mod sync_history;
 mod transaction;
 
+use crate::error::Res;
 pub use amount::{Amount, AmountFormat};
+use anyhow::bail;
 pub use auto_cat::{AutoCat, AutoCatUpdates, AutoCats};
+pub use balance::{Balance, BalanceColumn, Balances};
 pub use category::{Categories, Category, CategoryUpdates};
-pub(crate) use items::Item;
+pub(crate) use items::{Item, Items};
 pub(crate) use mapping::Mapping;
 pub(crate) use row_col::RowCol;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+pub use sync_history::{SyncCounts, SyncRecord};
 pub use transaction::{Transaction, TransactionColumn, TransactionUpdates, Transactions};
 
 /// Represents all the sheets of interest from a tiller Google sheet.
@@ -27,6 +34,10 @@ pub struct TillerData {
     pub(crate) categories: Categories,
     /// Rows of data from the AutoCat sheet.
     pub(crate) auto_cats: AutoCats,
+    /// Rows of data from the Balance History sheet. Empty unless `sync_balances` is enabled in the
+    /// config file.
+    #[serde(default)]
+    pub(crate) balances: Balances,
 }
 
 impl TillerData {
@@ -35,6 +46,7 @@ impl TillerData {
         !self.transactions.formulas().is_empty()
             || !self.categories.formulas().is_empty()
             || !self.auto_cats.formulas().is_empty()
+            || !self.balances.formulas().is_empty()
     }
 
     /// Checks if any of the sheets have gaps in their `original_order` sequences.
@@ -54,9 +66,51 @@ impl TillerData {
         if Self::check_gaps(self.auto_cats.data().iter().map(|c| c.original_order)) {
             return true;
         }
+        // Check balances
+        if Self::check_gaps(self.balances.data().iter().map(|b| b.original_order)) {
+            return true;
+        }
         false
     }
 
+    /// Checks that every transaction and AutoCat rule with a category refers to a category that is
+    /// in the Categories sheet. The database rejects such a reference, so finding it here keeps a
+    /// write from failing part way through.
+    ///
+    /// # Errors
+    /// - Returns an error listing each transaction and AutoCat rule whose category does not exist.
+    pub(crate) fn validate_references(&self) -> Res<()> {
+        let known: HashSet<&str> = self
+            .categories
+            .data()
+            .iter()
+            .map(|c| c.category.as_str())
+            .collect();
+        let is_dangling = |category: &str| !category.is_empty() && !known.contains(category);
+
+        let transactions = self
+            .transactions
+            .data()
+            .iter()
+            .filter(|t| is_dangling(&t.category))
+            .map(|t| format!("transaction {} ('{}')", t.transaction_id, t.category));
+        let auto_cats = self
+            .auto_cats
+            .data()
+            .iter()
+            .enumerate()
+            .filter(|(_, a)| is_dangling(&a.category))
+            .map(|(ix, a)| format!("AutoCat rule {} ('{}')", ix + 1, a.category));
+        let offenders: Vec<String> = transactions.chain(auto_cats).collect();
+        if !offenders.is_empty() {
+            bail!(
+                "These refer to categories that do not exist: {}",
+                offenders.join(", ")
+            );
+        }
+        Ok(())
+    }
+
     /// Helper to check for gaps in a sequence of original_order values.
     fn check_gaps(orders: impl Iterator<Item = Option<u64>>) -> bool {
         let mut orders: Vec<u64> = orders.flatten().collect();
@@ -76,3 +130,76 @@ impl TillerData {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tiller_data(transaction_category: &str, auto_cat_category: &str) -> TillerData {
+        TillerData {
+            transactions: Transactions::parse(
+                vec![
+                    vec![
+                        "Transaction ID",
+                        "Date",
+                        "Description",
+                        "Amount",
+                        "Category",
+                    ],
+                    vec!["txn-001", "2025-01-15", "Coffee Shop", "-4.50", "Food"],
+                    vec!["txn-002", "2025-01-16", "Paycheck", "100.00", ""],
+                    vec![
+                        "txn-003",
+                        "2025-01-17",
+                        "Cinema",
+                        "-12.00",
+                        transaction_category,
+                    ],
+                ],
+                Vec::<Vec<&str>>::new(),
+            )
+            .unwrap(),
+            categories: Categories::parse(
+                vec![
+                    vec!["Category", "Group", "Type"],
+                    vec!["Food", "Living", "Expense"],
+                    vec!["Entertainment", "Fun", "Expense"],
+                ],
+                Vec::<Vec<&str>>::new(),
+            )
+            .unwrap(),
+            auto_cats: AutoCats::parse(
+                vec![
+                    vec!["Category", "Description Contains"],
+                    vec!["Food", "coffee"],
+                    vec![auto_cat_category, "cinema"],
+                ],
+                Vec::<Vec<&str>>::new(),
+            )
+            .unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_references_ok() {
+        tiller_data("Entertainment", "Entertainment")
+            .validate_references()
+            .unwrap();
+        tiller_data("", "").validate_references().unwrap();
+    }
+
+    #[test]
+    fn test_validate_references_lists_dangling_categories() {
+        let message = tiller_data("Movies", "Films")
+            .validate_references()
+            .unwrap_err()
+            .to_string();
+        assert!(
+            message.contains("transaction txn-003 ('Movies')"),
+            "{message}"
+        );
+        assert!(message.contains("AutoCat rule 2 ('Films')"), "{message}");
+        assert!(!message.contains("txn-001"), "{message}");
+    }
+}
@@ -0,0 +1,33 @@
+use crate::args::UpDown;
+use serde::Serialize;
+
+/// The number of rows synced from or to each tab of the sheet.
+#[derive(Default, Debug, Clone, Copy, Eq, PartialEq, Serialize)]
+pub struct SyncCounts {
+    /// Rows of the Transactions tab.
+    pub transactions: u64,
+    /// Rows of the Categories tab.
+    pub categories: u64,
+    /// Rows of the AutoCat tab.
+    pub autocats: u64,
+    /// Rows of the Balance History tab, 0 unless `sync_balances` is enabled.
+    pub balances: u64,
+}
+
+/// A row of the `sync_history` table, recording one `sync up` or `sync down`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncRecord {
+    /// The ID of the row, which increases with each sync.
+    pub id: i64,
+    /// When the sync finished, in RFC 3339 format, e.g. `2025-10-21T09:15:30Z`.
+    pub finished_at: String,
+    /// Whether the sync was `up` or `down`.
+    pub direction: UpDown,
+    /// The number of rows synced. All 0 when the sync failed.
+    #[serde(flatten)]
+    pub counts: SyncCounts,
+    /// Whether the sync succeeded.
+    pub success: bool,
+    /// The message of the sync, or its error if it failed.
+    pub message: String,
+}
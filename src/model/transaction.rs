@@ -1,12 +1,12 @@
 use crate::error::Res;
 use crate::model::items::{Item, Items};
-use crate::model::Amount;
+use crate::model::{Amount, Mapping, RowCol};
 use crate::utils;
-use anyhow::bail;
+use anyhow::{bail, Context};
 use clap::Parser;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::str::FromStr;
 
 /// Represents a collection of transactions from a Transactions sheet, including the header mapping.
@@ -185,7 +185,41 @@ impl Item for Transaction {
     }
 }
 
+impl Transactions {
+    /// Returns true if `self` and `other` have the same headers and their rows have the same
+    /// content, see `Transaction::content_eq`. Formulas are only compared when `formulas` is true.
+    pub(crate) fn content_eq(&self, other: &Self, formulas: bool) -> bool {
+        self.mapping() == other.mapping()
+            && self.data().len() == other.data().len()
+            && self
+                .data()
+                .iter()
+                .zip(other.data())
+                .all(|(a, b)| a.content_eq(b))
+            && (!formulas || self.formulas() == other.formulas())
+    }
+}
+
 impl Transaction {
+    /// Compares the user-meaningful content of two transactions, ignoring the bookkeeping fields
+    /// that Tiller changes on its own (`date_added`, `categorized_date`) and the row position
+    /// (`original_order`). Use `==` when an exact comparison is needed.
+    pub fn content_eq(&self, other: &Transaction) -> bool {
+        let content = |t: &Transaction| Transaction {
+            date_added: String::new(),
+            categorized_date: String::new(),
+            original_order: None,
+            ..t.clone()
+        };
+        content(self) == content(other)
+    }
+
+    /// The tags in the Tags column. Tiller separates tags with commas or spaces, e.g.
+    /// `vacation, business` or `vacation business`. Blank and repeated tags are left out.
+    pub fn tags_list(&self) -> Vec<String> {
+        parse_tags(&self.tags)
+    }
+
     /// Set any of the fields on `self` that are set in `update`.
     pub fn merge_updates(&mut self, update: TransactionUpdates) {
         if let Some(x) = update.date {
@@ -236,9 +270,19 @@ impl Transaction {
         if let Some(x) = update.note {
             self.note = x;
         }
+        if let Some(x) = update.append_note {
+            if self.note.is_empty() {
+                self.note = x;
+            } else {
+                self.note = format!("{}\n{x}", self.note);
+            }
+        }
         if let Some(x) = update.tags {
             self.tags = x;
         }
+        if let Some(x) = update.tag_list {
+            self.tags = join_tags(&x);
+        }
         if let Some(x) = update.categorized_date {
             self.categorized_date = x;
         }
@@ -258,6 +302,70 @@ impl Transaction {
     }
 }
 
+/// Splits a Tags cell into its tags, see [`Transaction::tags_list`].
+fn parse_tags(tags: &str) -> Vec<String> {
+    let mut list: Vec<String> = Vec::new();
+    for tag in tags.split(|c: char| c == ',' || c.is_whitespace()) {
+        if !tag.is_empty() && !list.iter().any(|t| t == tag) {
+            list.push(tag.to_string());
+        }
+    }
+    list
+}
+
+/// Writes tags the way they are stored in a Tags cell: separated by a comma and a space, e.g.
+/// `vacation, business`. Each tag is split like a Tags cell, so tags that contain separators become
+/// more than one tag.
+fn join_tags(tags: &[String]) -> String {
+    parse_tags(&tags.join(",")).join(", ")
+}
+
+impl Transactions {
+    /// Creates `Transactions` from rows that have no header row to go with them, e.g. rows read
+    /// from the database before the sheet's headers were saved. The mapping is synthesized from
+    /// the standard columns in [`TransactionColumn`] order, followed by every custom column found
+    /// in `other_fields`, sorted by name.
+    pub(crate) fn from_data(
+        data: Vec<Transaction>,
+        formulas: BTreeMap<RowCol, String>,
+    ) -> Res<Self> {
+        let custom: BTreeSet<&String> = data.iter().flat_map(|t| t.other_fields.keys()).collect();
+        let headers = STANDARD_COLUMNS
+            .iter()
+            .map(|c| c.to_header().to_string())
+            .chain(custom.into_iter().cloned());
+        let mapping =
+            Mapping::new(headers).context("Failed to synthesize the transaction headers")?;
+        Self::new(data, formulas, mapping)
+    }
+}
+
+/// The columns of a Transactions sheet, in the order used when there are no sheet headers to
+/// follow. [`TransactionColumn::NoName`] is left out since its header is blank.
+const STANDARD_COLUMNS: [TransactionColumn; 21] = [
+    TransactionColumn::TransactionId,
+    TransactionColumn::Date,
+    TransactionColumn::Description,
+    TransactionColumn::Amount,
+    TransactionColumn::Account,
+    TransactionColumn::AccountNumber,
+    TransactionColumn::Institution,
+    TransactionColumn::Month,
+    TransactionColumn::Week,
+    TransactionColumn::FullDescription,
+    TransactionColumn::AccountId,
+    TransactionColumn::CheckNumber,
+    TransactionColumn::DateAdded,
+    TransactionColumn::MerchantName,
+    TransactionColumn::CategoryHint,
+    TransactionColumn::Category,
+    TransactionColumn::Note,
+    TransactionColumn::Tags,
+    TransactionColumn::CategorizedDate,
+    TransactionColumn::Statement,
+    TransactionColumn::Metadata,
+];
+
 /// Represents the known columns that should be found in the transactions sheet.
 /// See tiller documentation for more information about the semantic meanings of transaction
 /// columns: https://help.tiller.com/en/articles/432681-transactions-sheet-columns
@@ -335,6 +443,15 @@ serde_plain::derive_display_from_serialize!(TransactionColumn);
 serde_plain::derive_fromstr_from_deserialize!(TransactionColumn);
 
 impl TransactionColumn {
+    /// Whether the column is bookkeeping that Tiller changes on its own, and which
+    /// [`Transaction::content_eq`] therefore ignores.
+    pub fn is_volatile(&self) -> bool {
+        matches!(
+            self,
+            TransactionColumn::DateAdded | TransactionColumn::CategorizedDate
+        )
+    }
+
     pub fn from_header(header: impl AsRef<str>) -> Res<TransactionColumn> {
         let header_str = header.as_ref();
         match header_str {
@@ -492,11 +609,24 @@ pub struct TransactionUpdates {
     #[arg(long)]
     pub note: Option<String>,
 
+    /// Text to add to the end of the existing note, on a new line, rather than replacing it. When
+    /// the note is empty, it becomes the note. When `note` is also given, `note` replaces the
+    /// existing note first and this is added to the end of it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long)]
+    pub append_note: Option<String>,
+
     /// User-defined tags for additional transaction categorization.
     #[serde(skip_serializing_if = "Option::is_none")]
     #[arg(long)]
     pub tags: Option<String>,
 
+    /// The tags as a list, e.g. `["vacation", "business"]`, which are written to the Tags column
+    /// as `vacation, business`. An empty list clears the tags. Takes precedence over `tags`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[arg(long, num_args = 0..)]
+    pub tag_list: Option<Vec<String>>,
+
     /// Date when AutoCat automatically categorized or updated a transaction. Google Sheets Add-on
     /// column.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -546,3 +676,196 @@ pub(super) const CATEGORIZED_DATE_STR: &str = "Categorized Date";
 pub(super) const STATEMENT_STR: &str = "Statement";
 pub(super) const METADATA_STR: &str = "Metadata";
 pub(super) const NO_NAME_STR: &str = "";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn transaction() -> Transaction {
+        Transaction {
+            transaction_id: "tx1".to_string(),
+            date: "10/20/2025".to_string(),
+            description: "Whole Foods Market".to_string(),
+            amount: Amount::from_str("-87.43").unwrap(),
+            category: "Groceries".to_string(),
+            date_added: "10/21/25".to_string(),
+            categorized_date: "10/21/2025 9:15:30 AM".to_string(),
+            original_order: Some(0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_content_eq_ignores_volatile_fields() {
+        let a = transaction();
+        let mut b = transaction();
+        b.date_added = "10/22/25".to_string();
+        assert_ne!(a, b);
+        assert!(a.content_eq(&b));
+
+        b.categorized_date = "10/22/2025 8:00:00 AM".to_string();
+        b.original_order = None;
+        assert!(a.content_eq(&b));
+    }
+
+    #[test]
+    fn test_content_eq_detects_meaningful_changes() {
+        let a = transaction();
+
+        let mut b = transaction();
+        b.category = "Restaurants".to_string();
+        assert!(!a.content_eq(&b));
+
+        let mut b = transaction();
+        b.amount = Amount::from_str("-87.44").unwrap();
+        assert!(!a.content_eq(&b));
+
+        let mut b = transaction();
+        b.other_fields
+            .insert("Custom Column".to_string(), "x".to_string());
+        assert!(!a.content_eq(&b));
+    }
+
+    #[test]
+    fn test_tags_list() {
+        let mut t = transaction();
+        assert!(t.tags_list().is_empty());
+
+        t.tags = "vacation, business".to_string();
+        assert_eq!(t.tags_list(), vec!["vacation", "business"]);
+        t.tags = "vacation business".to_string();
+        assert_eq!(t.tags_list(), vec!["vacation", "business"]);
+        t.tags = " vacation,,business  vacation\treimbursable ".to_string();
+        assert_eq!(t.tags_list(), vec!["vacation", "business", "reimbursable"]);
+    }
+
+    #[test]
+    fn test_tag_list_update_is_canonical() {
+        let mut t = transaction();
+        t.tags = "old".to_string();
+        let tags = |list: &[&str]| TransactionUpdates {
+            tag_list: Some(list.iter().map(|s| s.to_string()).collect()),
+            ..Default::default()
+        };
+
+        t.merge_updates(tags(&["vacation", " business ", "", "vacation"]));
+        assert_eq!(t.tags, "vacation, business");
+        assert_eq!(t.tags_list(), vec!["vacation", "business"]);
+
+        // A tag containing separators becomes more than one tag
+        t.merge_updates(tags(&["trip,work home"]));
+        assert_eq!(t.tags, "trip, work, home");
+
+        // The list takes precedence over the raw string, and an empty list clears the tags
+        t.merge_updates(TransactionUpdates {
+            tags: Some("ignored".to_string()),
+            ..tags(&[])
+        });
+        assert_eq!(t.tags, "");
+    }
+
+    #[test]
+    fn test_append_note() {
+        let append = |text: &str| TransactionUpdates {
+            append_note: Some(text.to_string()),
+            ..Default::default()
+        };
+
+        // Appending to an empty note does not start with a blank line
+        let mut t = transaction();
+        t.note = String::new();
+        t.merge_updates(append("first"));
+        assert_eq!(t.note, "first");
+
+        t.merge_updates(append("second"));
+        assert_eq!(t.note, "first\nsecond");
+
+        // `note` replaces the existing note before `append_note` is added to it
+        t.merge_updates(TransactionUpdates {
+            note: Some("replaced".to_string()),
+            ..append("third")
+        });
+        assert_eq!(t.note, "replaced\nthird");
+    }
+
+    #[test]
+    fn test_is_volatile() {
+        assert!(TransactionColumn::DateAdded.is_volatile());
+        assert!(TransactionColumn::CategorizedDate.is_volatile());
+        assert!(!TransactionColumn::Category.is_volatile());
+    }
+
+    #[test]
+    fn test_remap_formulas() {
+        let mut transactions = Transactions::parse(
+            vec![
+                vec!["Transaction ID", "Amount", "Custom Column"],
+                vec!["tx1", "-1.00", "1.00"],
+                vec!["tx2", "-2.00", "2.00"],
+                vec!["tx3", "-3.00", "3.00"],
+            ],
+            Vec::<Vec<&str>>::new(),
+        )
+        .unwrap();
+        let formulas = transactions.formulas_mut();
+        formulas.insert(RowCol::new(0, 2), "=ABS(B2)".to_string());
+        formulas.insert(RowCol::new(1, 2), "=ABS(B3)".to_string());
+        formulas.insert(RowCol::new(2, 2), "=ABS(B4)".to_string());
+
+        // The second row is deleted and the third moves up to take its place
+        let old_to_new = HashMap::from([(0, 0), (2, 1)]);
+        transactions.remap_formulas(&old_to_new);
+
+        let expected = BTreeMap::from([
+            (RowCol::new(0, 2), "=ABS(B2)".to_string()),
+            (RowCol::new(1, 2), "=ABS(B4)".to_string()),
+        ]);
+        assert_eq!(transactions.formulas(), &expected);
+    }
+
+    #[test]
+    fn test_from_data_synthesizes_mapping() {
+        let mut custom = transaction();
+        custom
+            .other_fields
+            .insert("Location".to_string(), "Portland".to_string());
+        let mut formulas = BTreeMap::new();
+        formulas.insert(RowCol::new(0, 15), "=\"Groceries\"".to_string());
+
+        let transactions =
+            Transactions::from_data(vec![transaction(), custom], formulas.clone()).unwrap();
+
+        let mapping = transactions.mapping();
+        assert_eq!(mapping.len(), STANDARD_COLUMNS.len() + 1);
+        assert_eq!(mapping._header_index("Transaction ID"), Some(0));
+        assert_eq!(mapping._header_index("Category"), Some(15));
+        assert_eq!(
+            mapping._header_index("Location"),
+            Some(STANDARD_COLUMNS.len())
+        );
+        assert_eq!(mapping._header_index(""), None);
+        assert_eq!(transactions.formulas(), &formulas);
+
+        // The synthesized headers are enough to write the rows back out
+        let rows = transactions.to_rows().unwrap();
+        assert_eq!(rows[1][15], "Groceries");
+        assert_eq!(rows[2][STANDARD_COLUMNS.len()], "Portland");
+    }
+
+    #[test]
+    fn test_header_only_is_empty_not_an_error() {
+        let transactions = Transactions::parse(
+            vec![vec!["Transaction ID", "Date", "Description", "Amount"]],
+            Vec::<Vec<&str>>::new(),
+        )
+        .unwrap();
+        assert!(transactions.data().is_empty());
+        assert!(transactions.formulas().is_empty());
+        assert_eq!(transactions.mapping().len(), 4);
+
+        // Without even a header row there is nothing to map the columns with
+        let err = Transactions::parse(Vec::<Vec<&str>>::new(), Vec::<Vec<&str>>::new());
+        assert!(err.is_err());
+    }
+}
@@ -2,11 +2,18 @@
 //!
 //! This module is only compiled when running tests (`#[cfg(test)]`).
 
-use crate::api::{TestSheet, TestSheetState};
+use crate::api::{Mode, SheetCall, TestSheet, TestSheetState};
+use crate::commands::{sync_down, sync_up, FormulasMode, SyncUpStrategy};
 use crate::model::TillerData;
 use crate::model::{AutoCats, Categories, Transactions};
 use crate::Config;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
 use tempfile::TempDir;
+use tracing::subscriber::DefaultGuard;
+use tracing_subscriber::fmt::MakeWriter;
 use uuid::Uuid;
 
 /// Test environment that sets up a tiller home directory with Config and database.
@@ -47,11 +54,37 @@ impl TestEnv {
         }
     }
 
+    /// Closes the database, waiting for pending writes to be flushed, and then deletes the
+    /// temporary directory. Clones of the Config can no longer use the database.
+    pub async fn finish(self) {
+        self.config.close().await;
+    }
+
     /// Returns a clone of the Config.
     pub fn config(&self) -> Config {
         self.config.clone()
     }
 
+    /// Turns on `sync_balances` in the config file and reloads the Config.
+    pub async fn enable_sync_balances(&mut self) {
+        self.set_config_value("sync_balances", serde_json::Value::Bool(true))
+            .await;
+    }
+
+    /// Sets `key` to `value` in the config file and reloads the Config.
+    pub async fn set_config_value(&mut self, key: &str, value: serde_json::Value) {
+        let config_path = self.config.config_path().to_path_buf();
+        let json = std::fs::read_to_string(&config_path).unwrap();
+        let mut config_json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        config_json[key] = value;
+        std::fs::write(
+            &config_path,
+            serde_json::to_string_pretty(&config_json).unwrap(),
+        )
+        .unwrap();
+        self.config = Config::load(self.config.root(), None).await.unwrap();
+    }
+
     /// Gets the current state of the TestSheet associated with this environment.
     pub fn get_state(&self) -> TestSheetState {
         let test_sheet = TestSheet::new(self.config.spreadsheet_id());
@@ -120,6 +153,7 @@ impl TestEnv {
             transactions,
             categories,
             auto_cats,
+            ..Default::default()
         };
 
         self.config.db().save_tiller_data(&data).await.unwrap();
@@ -177,6 +211,7 @@ impl TestEnv {
             transactions,
             categories,
             auto_cats,
+            ..Default::default()
         };
 
         self.config.db().save_tiller_data(&data).await.unwrap();
@@ -221,8 +256,158 @@ impl TestEnv {
             transactions,
             categories,
             auto_cats,
+            ..Default::default()
         };
 
         self.config.db().save_tiller_data(&data).await.unwrap();
     }
 }
+
+/// Round-trips `rows` of the `tab` tab through the whole pipeline and asserts that the rows
+/// written back to the sheet equal `rows`. The first row holds the headers. The other tabs keep
+/// their seed data, so transactions and AutoCat rules must use the seed categories.
+///
+/// `sync down` ingests the rows into the model and saves them to the database, then `sync up`
+/// reads them back from the database and writes them to the `TestSheet`.
+///
+/// Along the way, every header that is not listed in `custom_headers` must be stored in a typed
+/// field of the model rather than in `other_fields`; a misspelled column constant would otherwise
+/// round-trip unnoticed while the typed field stays empty.
+///
+/// The written rows are compared after these documented normalizations of `rows`:
+/// - Rows shorter than the header row are padded with empty cells.
+/// - Transaction amounts are written in the default format, e.g. `-$1,234.50`, so input amounts
+///   should already be in that format.
+/// - AutoCat amounts are stored as plain decimals, e.g. `$10.00` is written as `10`, so input
+///   amounts should already be plain decimals.
+pub(crate) async fn assert_round_trip(tab: &str, rows: &[Vec<&str>], custom_headers: &[&str]) {
+    let env = TestEnv::new().await;
+    let config = env.config();
+
+    let test_sheet = TestSheet::new_with_seed_data(config.spreadsheet_id());
+    let mut state = test_sheet.get_state();
+    let input: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| row.iter().map(|s| s.to_string()).collect())
+        .collect();
+    state.data.insert(tab.to_string(), input.clone());
+    state.formulas.remove(tab);
+    state.formatted.remove(tab);
+    test_sheet.set_state(state);
+
+    sync_down(config.clone(), Mode::Testing).await.unwrap();
+
+    let data = config.db().get_tiller_data().await.unwrap();
+    let custom: BTreeSet<&str> = custom_headers.iter().copied().collect();
+    match tab {
+        "Transactions" => assert_typed_fields(data.transactions.data(), &custom),
+        "Categories" => assert_typed_fields(data.categories.data(), &custom),
+        "AutoCat" => assert_typed_fields(data.auto_cats.data(), &custom),
+        other => panic!("No round trip for the '{other}' tab"),
+    }
+
+    test_sheet.clear_history();
+    sync_up(
+        config.clone(),
+        Mode::Testing,
+        false,
+        FormulasMode::Ignore,
+        SyncUpStrategy::Replace,
+        Vec::new(),
+        false,
+    )
+    .await
+    .unwrap();
+
+    let (_, written) = written_range(&test_sheet.call_history(), tab);
+
+    let width = input.first().map(Vec::len).unwrap_or_default();
+    let expected: Vec<Vec<String>> = input
+        .into_iter()
+        .map(|mut row| {
+            row.resize(width, String::new());
+            row
+        })
+        .collect();
+    assert_eq!(written, expected, "The '{tab}' tab did not round-trip");
+}
+
+/// Returns the first range written to the `tab` tab in `history`, along with the rows written to
+/// it. Panics if nothing was written to the tab.
+pub(crate) fn written_range(history: &[SheetCall], tab: &str) -> (String, Vec<Vec<String>>) {
+    let prefix = format!("{tab}!");
+    history
+        .iter()
+        .find_map(|call| match call {
+            SheetCall::WriteRanges { ranges } => ranges
+                .iter()
+                .find(|(range, _)| range.starts_with(&prefix))
+                .cloned(),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("Nothing was written to the '{tab}' tab"))
+}
+
+/// Asserts that the only headers stored in the `other_fields` of `items` are in `custom`.
+fn assert_typed_fields<T: Serialize>(items: &[T], custom: &BTreeSet<&str>) {
+    for item in items {
+        let value = serde_json::to_value(item).unwrap();
+        let other_fields = value
+            .get("other_fields")
+            .and_then(serde_json::Value::as_object)
+            .map(|o| o.keys().cloned().collect::<Vec<_>>())
+            .unwrap_or_default();
+        for header in other_fields {
+            assert!(
+                custom.contains(header.as_str()),
+                "'{header}' was stored in other_fields rather than in a typed field"
+            );
+        }
+    }
+}
+
+/// A writer that collects log output in memory, so that tests can check what was logged.
+#[derive(Clone, Default)]
+pub(crate) struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl LogBuffer {
+    /// Logs INFO and above, as plain text, to this buffer until the returned guard is dropped.
+    /// This only applies to the current thread, which is the only one a `#[tokio::test]` uses.
+    pub(crate) fn capture(&self) -> DefaultGuard {
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::INFO)
+            .with_ansi(false)
+            .with_writer(self.clone())
+            .finish();
+        tracing::subscriber::set_default(subscriber)
+    }
+
+    /// The lines that have been logged so far.
+    pub(crate) fn lines(&self) -> Vec<String> {
+        let bytes = self.0.lock().unwrap().clone();
+        String::from_utf8(bytes)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+impl Write for LogBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogBuffer {
+    type Writer = LogBuffer;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
@@ -1,5 +1,6 @@
 use crate::error::Res;
 use anyhow::{anyhow, Context};
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime};
 use serde::de::DeserializeOwned;
 use std::path::{Path, PathBuf};
 use tokio::fs::ReadDir;
@@ -19,6 +20,13 @@ pub(crate) async fn read(path: &Path) -> Res<String> {
         .with_context(|| format!("Failed to read file at {}", path.display()))
 }
 
+/// Read a file to a `Vec<u8>`.
+pub(crate) async fn read_bytes(path: &Path) -> Res<Vec<u8>> {
+    tokio::fs::read(path)
+        .await
+        .with_context(|| format!("Failed to read file at {}", path.display()))
+}
+
 /// Deserialize a JSON file into type `T`.
 pub(crate) async fn deserialize<T>(path: &Path) -> Res<T>
 where
@@ -73,6 +81,17 @@ pub(crate) async fn copy(from: impl AsRef<Path>, to: impl AsRef<Path>) -> Res<u6
         })
 }
 
+/// Returns the size of a file in bytes.
+pub(crate) async fn file_size(path: impl AsRef<Path>) -> Res<u64> {
+    let metadata = tokio::fs::metadata(path.as_ref()).await.with_context(|| {
+        format!(
+            "Unable to read metadata of '{}'",
+            path.as_ref().to_string_lossy()
+        )
+    })?;
+    Ok(metadata.len())
+}
+
 /// Remove a file.
 pub(crate) async fn remove(path: impl AsRef<Path>) -> Res<()> {
     tokio::fs::remove_file(path.as_ref())
@@ -85,6 +104,18 @@ pub(crate) async fn remove(path: impl AsRef<Path>) -> Res<()> {
         })
 }
 
+/// Removes a directory and everything in it.
+pub(crate) async fn remove_dir_all(path: impl AsRef<Path>) -> Res<()> {
+    tokio::fs::remove_dir_all(path.as_ref())
+        .await
+        .with_context(|| {
+            format!(
+                "Unable to remove directory at '{}'",
+                path.as_ref().to_string_lossy()
+            )
+        })
+}
+
 /// Parses update strings in "FIELD=VALUE" format into `("FIELD", "VALUE")`.
 pub(crate) fn parse_key_val(key_val: &str) -> Res<(String, String)> {
     key_val
@@ -99,6 +130,48 @@ pub(crate) fn parse_amount(s: &str) -> Res<crate::model::Amount> {
         .with_context(|| format!("Invalid amount format: '{}'", s))
 }
 
+/// Parses a date as it appears in a Tiller sheet, e.g. `10/20/2025` or `10/1/25`. ISO 8601 dates
+/// like `2025-10-20` are also accepted.
+pub(crate) fn parse_date(s: &str) -> Res<NaiveDate> {
+    let s = s.trim();
+    let format = match s.rsplit_once('/') {
+        Some((_, year)) if year.len() == 2 => "%m/%d/%y",
+        Some(_) => "%m/%d/%Y",
+        None => "%Y-%m-%d",
+    };
+    NaiveDate::parse_from_str(s, format).with_context(|| format!("Invalid date: '{s}'"))
+}
+
+/// Formats a date the way Tiller writes its Month, Week and Date Added columns, e.g. `10/1/25`.
+pub(crate) fn tiller_date(date: NaiveDate) -> String {
+    format!("{}/{}/{:02}", date.month(), date.day(), date.year() % 100)
+}
+
+/// Formats a time the way Tiller writes its Categorized Date column, e.g.
+/// `10/21/2025 9:15:30 AM`.
+pub(crate) fn tiller_timestamp(time: NaiveDateTime) -> String {
+    time.format("%-m/%-d/%Y %-I:%M:%S %p").to_string()
+}
+
+/// Returns the Month column value that Tiller derives from a date: the first day of its month,
+/// e.g. `10/1/25` for `10/20/2025`.
+pub(crate) fn tiller_month(date: NaiveDate) -> String {
+    tiller_date(date.with_day(1).unwrap_or(date))
+}
+
+/// Returns the Week column value that Tiller derives from a date: the Sunday that starts its
+/// week, e.g. `10/19/25` for `10/20/2025`.
+pub(crate) fn tiller_week(date: NaiveDate) -> String {
+    let days_since_sunday = u64::from(date.weekday().num_days_from_sunday());
+    tiller_date(
+        date.checked_sub_days(Days::new(days_since_sunday))
+            .unwrap_or(date),
+    )
+}
+
+/// The prefix of the IDs of locally-created transactions.
+pub(crate) const USER_TRANSACTION_PREFIX: &str = "user-";
+
 /// Generates a unique transaction ID for locally-created transactions.
 ///
 /// The ID format is `user-` followed by a truncated UUIDv4 (dashes removed, truncated to 19
@@ -109,5 +182,53 @@ pub(crate) fn parse_amount(s: &str) -> Res<crate::model::Amount> {
 pub fn generate_transaction_id() -> String {
     let uuid = uuid::Uuid::new_v4();
     let hex = uuid.as_simple().to_string(); // 32 hex chars, no dashes
-    format!("user-{}", &hex[..19])
+    format!("{USER_TRANSACTION_PREFIX}{}", &hex[..19])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_date() {
+        let expected = NaiveDate::from_ymd_opt(2025, 10, 1).unwrap();
+        assert_eq!(parse_date("10/1/2025").unwrap(), expected);
+        assert_eq!(parse_date("10/01/2025").unwrap(), expected);
+        assert_eq!(parse_date("10/1/25").unwrap(), expected);
+        assert_eq!(parse_date("2025-10-01").unwrap(), expected);
+        assert!(parse_date("").is_err());
+        assert!(parse_date("October 1").is_err());
+    }
+
+    #[test]
+    fn test_tiller_month_and_week() {
+        // The first rows of the seed data: Month and Week as Tiller wrote them
+        let date = NaiveDate::from_ymd_opt(2025, 10, 20).unwrap();
+        assert_eq!(tiller_month(date), "10/1/25");
+        assert_eq!(tiller_week(date), "10/19/25");
+        let date = NaiveDate::from_ymd_opt(2025, 10, 18).unwrap();
+        assert_eq!(tiller_week(date), "10/12/25");
+
+        // A Sunday starts its own week, and weeks can start in the previous month or year
+        let date = NaiveDate::from_ymd_opt(2025, 10, 19).unwrap();
+        assert_eq!(tiller_week(date), "10/19/25");
+        let date = NaiveDate::from_ymd_opt(2026, 1, 2).unwrap();
+        assert_eq!(tiller_month(date), "1/1/26");
+        assert_eq!(tiller_week(date), "12/28/25");
+    }
+
+    #[test]
+    fn test_tiller_timestamp() {
+        let date = NaiveDate::from_ymd_opt(2025, 10, 21).unwrap();
+        let morning = date.and_hms_opt(9, 15, 30).unwrap();
+        assert_eq!(tiller_timestamp(morning), "10/21/2025 9:15:30 AM");
+        let evening = date.and_hms_opt(21, 5, 0).unwrap();
+        assert_eq!(tiller_timestamp(evening), "10/21/2025 9:05:00 PM");
+        let midnight = NaiveDate::from_ymd_opt(2026, 1, 2)
+            .unwrap()
+            .and_hms_opt(0, 0, 1)
+            .unwrap();
+        assert_eq!(tiller_timestamp(midnight), "1/2/2026 12:00:01 AM");
+        assert_eq!(tiller_date(date), "10/21/25");
+    }
 }